@@ -58,8 +58,10 @@ fn build_window_options(display_id: DisplayId, bounds: Bounds<Pixels>) -> Window
         focus: false,
         show: true,
         kind: WindowKind::PopUp,
+        layer_shell: None,
         is_movable: false,
         app_id: None,
+        icon: None,
         window_min_size: None,
         window_decorations: None,
     }
@@ -33,11 +33,11 @@ use crate::{
     current_platform, hash, init_app_menus, Action, ActionBuildError, ActionRegistry, Any, AnyView,
     AnyWindowHandle, AppContext, Asset, AssetSource, BackgroundExecutor, Bounds, ClipboardItem,
     DispatchPhase, DisplayId, EventEmitter, FocusHandle, FocusMap, ForegroundExecutor, Global,
-    KeyBinding, Keymap, Keystroke, LayoutId, Menu, MenuItem, OwnedMenu, PathPromptOptions, Pixels,
-    Platform, PlatformDisplay, Point, PromptBuilder, PromptHandle, PromptLevel, Render,
-    RenderablePromptHandle, Reservation, ScreenCaptureSource, SharedString, SubscriberSet,
-    Subscription, SvgRenderer, Task, TextSystem, Window, WindowAppearance, WindowHandle, WindowId,
-    WindowInvalidator,
+    GlobalShortcut, KeyBinding, Keymap, Keystroke, LayoutId, Menu, MenuItem, Notification,
+    OwnedMenu, PathPromptOptions, Pixels, Platform, PlatformDisplay, Point, PromptBuilder,
+    PromptHandle, PromptLevel, Render, RenderablePromptHandle, Reservation, ScreenCaptureSource,
+    SharedString, SubscriberSet, Subscription, SvgRenderer, Task, TextSystem, Window,
+    WindowAppearance, WindowHandle, WindowId, WindowInvalidator,
 };
 
 mod async_context;
@@ -124,9 +124,12 @@ impl Application {
         ))
     }
 
-    /// Build an app in headless mode. This prevents opening windows,
-    /// but makes it possible to run an application in an context like
-    /// SSH, where GUI applications are not allowed.
+    /// Build an app in headless mode. This makes it possible to run an application
+    /// in a context like SSH, where GUI applications are not allowed.
+    ///
+    /// On Linux, windows can still be opened: they are rendered offscreen, at the
+    /// scale factor given by the `GPUI_HEADLESS_SCALE_FACTOR` environment variable,
+    /// or 1 if it isn't set. Other platforms prevent opening windows.
     pub fn headless() -> Self {
         Self(App::new_app(
             current_platform(true),
@@ -135,6 +138,17 @@ impl Application {
         ))
     }
 
+    /// Build an app in headless mode, whose windows are rendered offscreen at the given
+    /// scale factor, which must be positive. (Linux)
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    pub fn headless_with_scale_factor(scale_factor: f32) -> Self {
+        Self(App::new_app(
+            Rc::new(crate::HeadlessClient::with_scale_factor(scale_factor)),
+            Arc::new(()),
+            Arc::new(NullHttpClient),
+        ))
+    }
+
     /// Assign
     pub fn with_assets(self, asset_source: impl AssetSource) -> Self {
         let mut context_lock = self.0.borrow_mut();
@@ -153,6 +167,17 @@ impl Application {
         self
     }
 
+    /// Keeps a single instance of the application with the given `app_id` running.
+    /// Launching it again forwards the command line's URLs and paths to the running
+    /// instance, which receives them through [`Application::on_open_urls`] and
+    /// activates one of its windows, and exits without calling the `run` callback.
+    /// Only launches by the same user are forwarded, and the application runs as usual
+    /// when `$XDG_RUNTIME_DIR` isn't set. (Linux)
+    pub fn single_instance(self, app_id: &str) -> Self {
+        self.0.borrow().platform.set_single_instance(app_id);
+        self
+    }
+
     /// Start the application. The provided callback will be called once the
     /// app is fully launched.
     pub fn run<F>(self, on_finish_launching: F)
@@ -1434,6 +1459,57 @@ impl App {
         self.platform.add_recent_document(path);
     }
 
+    /// Shows a notification on the desktop, outside of the application's windows.
+    /// When the user clicks it or one of its buttons, the corresponding action is
+    /// dispatched as with [`App::dispatch_action`].
+    /// Whether the notification could be shown is relayed asynchronously via the
+    /// returned oneshot channel. Only implemented on Linux, through the XDG Desktop
+    /// Portal or the desktop's notification server.
+    pub fn show_notification(&self, notification: Notification) -> oneshot::Receiver<Result<()>> {
+        let app = self.this.clone();
+        self.platform.show_notification(
+            notification,
+            Box::new(move |action| {
+                if let Some(app) = app.upgrade() {
+                    app.borrow_mut().dispatch_action(action);
+                }
+            }),
+        )
+    }
+
+    /// Registers shortcuts that are triggered even when none of the application's windows
+    /// is focused, replacing the ones registered before. The actions bound to them in the
+    /// keymap are dispatched as with [`App::dispatch_action`], see [`GlobalShortcut`].
+    /// The desktop may ask the user to confirm the shortcuts, or to pick other keys for them.
+    /// Whether the shortcuts could be registered is relayed asynchronously via the
+    /// returned oneshot channel. Only implemented on Linux, through the XDG Desktop Portal.
+    pub fn register_global_shortcuts(
+        &self,
+        shortcuts: impl IntoIterator<Item = GlobalShortcut>,
+    ) -> oneshot::Receiver<Result<()>> {
+        let app = self.this.clone();
+        self.platform.register_global_shortcuts(
+            shortcuts.into_iter().collect(),
+            Box::new(move |shortcut, released| {
+                if let Some(app) = app.upgrade() {
+                    let mut cx = app.borrow_mut();
+                    let action = shortcut.action(&cx.keymap.borrow(), released);
+                    if let Some(action) = action {
+                        cx.dispatch_action(action.as_ref());
+                    }
+                }
+            }),
+        )
+    }
+
+    /// Returns the recent documents of the application, most recent first.
+    /// On Linux, these are read from the desktop's history of recently used files,
+    /// which also lists the files opened with this application by other means.
+    /// Other platforms return an empty list.
+    pub fn recent_documents(&self) -> Task<Result<Vec<PathBuf>>> {
+        self.platform.recent_documents()
+    }
+
     /// Dispatch an action to the currently active window or global action handler
     /// See [`crate::Action`] for more information on how actions work
     pub fn dispatch_action(&mut self, action: &dyn Action) {
@@ -1816,7 +1892,7 @@ pub struct KeystrokeEvent {
     pub action: Option<Box<dyn Action>>,
 }
 
-struct NullHttpClient;
+pub(crate) struct NullHttpClient;
 
 impl HttpClient for NullHttpClient {
     fn send(
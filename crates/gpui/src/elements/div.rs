@@ -17,11 +17,12 @@
 
 use crate::{
     point, px, size, Action, AnyDrag, AnyElement, AnyTooltip, AnyView, App, Bounds, ClickEvent,
-    DispatchPhase, Element, ElementId, Entity, FocusHandle, Global, GlobalElementId, Hitbox,
-    HitboxId, IntoElement, IsZero, KeyContext, KeyDownEvent, KeyUpEvent, LayoutId,
-    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
-    ParentElement, Pixels, Point, Render, ScrollWheelEvent, SharedString, Size, Style,
-    StyleRefinement, Styled, Task, TooltipId, Visibility, Window,
+    DispatchPhase, Element, ElementId, Entity, ExternalDrag, FocusHandle, Global, GlobalElementId,
+    Hitbox, HitboxId, HoldEvent, IntoElement, IsZero, KeyContext, KeyDownEvent, KeyUpEvent,
+    LayoutId, ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
+    ParentElement, PinchEvent, Pixels, Point, RelativeMouseMoveEvent, Render, ScrollWheelEvent,
+    SharedString, Size, Style, StyleRefinement, Styled, StylusEvent, StylusPhase, SwipeEvent, Task,
+    TooltipId, TouchEvent, TouchPhase, Visibility, Window,
 };
 use collections::HashMap;
 use refineable::Refineable;
@@ -239,6 +240,24 @@ impl Interactivity {
             }));
     }
 
+    /// Bind the given callback to relative mouse movements over this element, during the bubble phase.
+    /// These are only sent while the pointer is locked or confined, see [`Window::lock_pointer`].
+    /// The imperative API equivalent to [`InteractiveElement::on_relative_mouse_move`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_relative_mouse_move(
+        &mut self,
+        listener: impl Fn(&RelativeMouseMoveEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.relative_mouse_move_listeners.push(Box::new(
+            move |event, phase, hitbox, window, cx| {
+                if phase == DispatchPhase::Bubble && hitbox.is_hovered(window) {
+                    (listener)(event, window, cx);
+                }
+            },
+        ));
+    }
+
     /// Bind the given callback to the mouse drag event of the given type. Note that this
     /// will be called for all move events, inside or outside of this element, as long as the
     /// drag was started with this element under the mouse. Useful for implementing draggable
@@ -289,6 +308,175 @@ impl Interactivity {
             }));
     }
 
+    /// Bind the given callback to a touch starting on this element, during the bubble phase.
+    /// Call [`Window::prevent_default`] to keep a tap from also being dispatched as a click.
+    /// The imperative API equivalent to [`InteractiveElement::on_touch_start`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_touch_start(
+        &mut self,
+        listener: impl Fn(&TouchEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.on_touch_phase(TouchPhase::Started, listener);
+    }
+
+    /// Bind the given callback to a touch moving over this element, during the bubble phase.
+    /// The imperative API equivalent to [`InteractiveElement::on_touch_move`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_touch_move(
+        &mut self,
+        listener: impl Fn(&TouchEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.on_touch_phase(TouchPhase::Moved, listener);
+    }
+
+    /// Bind the given callback to a touch ending over this element, during the bubble phase.
+    /// The imperative API equivalent to [`InteractiveElement::on_touch_end`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_touch_end(
+        &mut self,
+        listener: impl Fn(&TouchEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.on_touch_phase(TouchPhase::Ended, listener);
+    }
+
+    /// Bind the given callback to a touch being cancelled, during the bubble phase. This is called
+    /// wherever the touch is, so that any state tracked for it can be reset.
+    /// The imperative API equivalent to [`InteractiveElement::on_touch_cancel`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_touch_cancel(
+        &mut self,
+        listener: impl Fn(&TouchEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.touch_listeners
+            .push(Box::new(move |event, phase, _, window, cx| {
+                if phase == DispatchPhase::Bubble && event.phase == TouchPhase::Cancelled {
+                    (listener)(event, window, cx);
+                }
+            }));
+    }
+
+    /// Bind the given callback to touchpad pinch gestures over this element, during the bubble phase
+    /// The imperative API equivalent to [`InteractiveElement::on_pinch`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_pinch(&mut self, listener: impl Fn(&PinchEvent, &mut Window, &mut App) + 'static) {
+        self.pinch_listeners
+            .push(Box::new(move |event, phase, hitbox, window, cx| {
+                if phase == DispatchPhase::Bubble && hitbox.is_hovered(window) {
+                    (listener)(event, window, cx);
+                }
+            }));
+    }
+
+    /// Bind the given callback to touchpad swipe gestures over this element, during the bubble phase
+    /// The imperative API equivalent to [`InteractiveElement::on_swipe`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_swipe(&mut self, listener: impl Fn(&SwipeEvent, &mut Window, &mut App) + 'static) {
+        self.swipe_listeners
+            .push(Box::new(move |event, phase, hitbox, window, cx| {
+                if phase == DispatchPhase::Bubble && hitbox.is_hovered(window) {
+                    (listener)(event, window, cx);
+                }
+            }));
+    }
+
+    /// Bind the given callback to touchpad hold gestures over this element, during the bubble phase
+    /// The imperative API equivalent to [`InteractiveElement::on_hold`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_hold(&mut self, listener: impl Fn(&HoldEvent, &mut Window, &mut App) + 'static) {
+        self.hold_listeners
+            .push(Box::new(move |event, phase, hitbox, window, cx| {
+                if phase == DispatchPhase::Bubble && hitbox.is_hovered(window) {
+                    (listener)(event, window, cx);
+                }
+            }));
+    }
+
+    /// Bind the given callback to every stylus event over this element, during the bubble phase
+    /// The imperative API equivalent to [`InteractiveElement::on_stylus`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_stylus(&mut self, listener: impl Fn(&StylusEvent, &mut Window, &mut App) + 'static) {
+        self.stylus_listeners
+            .push(Box::new(move |event, phase, hitbox, window, cx| {
+                if phase == DispatchPhase::Bubble && hitbox.is_hovered(window) {
+                    (listener)(event, window, cx);
+                }
+            }));
+    }
+
+    /// Bind the given callback to the tip of a stylus touching the tablet over this element, during the bubble phase
+    /// The imperative API equivalent to [`InteractiveElement::on_stylus_down`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_stylus_down(
+        &mut self,
+        listener: impl Fn(&StylusEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.on_stylus_phase(StylusPhase::Down, listener);
+    }
+
+    /// Bind the given callback to a stylus moving over this element, during the bubble phase.
+    /// This is also called when the pressure, tilt or rotation of the stylus changes.
+    /// The imperative API equivalent to [`InteractiveElement::on_stylus_move`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_stylus_move(
+        &mut self,
+        listener: impl Fn(&StylusEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.on_stylus_phase(StylusPhase::Moved, listener);
+    }
+
+    /// Bind the given callback to the tip of a stylus being lifted over this element, during the bubble phase
+    /// The imperative API equivalent to [`InteractiveElement::on_stylus_up`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_stylus_up(
+        &mut self,
+        listener: impl Fn(&StylusEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.on_stylus_phase(StylusPhase::Up, listener);
+    }
+
+    fn on_stylus_phase(
+        &mut self,
+        stylus_phase: StylusPhase,
+        listener: impl Fn(&StylusEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.stylus_listeners
+            .push(Box::new(move |event, phase, hitbox, window, cx| {
+                if phase == DispatchPhase::Bubble
+                    && event.phase == stylus_phase
+                    && hitbox.is_hovered(window)
+                {
+                    (listener)(event, window, cx);
+                }
+            }));
+    }
+
+    fn on_touch_phase(
+        &mut self,
+        touch_phase: TouchPhase,
+        listener: impl Fn(&TouchEvent, &mut Window, &mut App) + 'static,
+    ) {
+        self.touch_listeners
+            .push(Box::new(move |event, phase, hitbox, window, cx| {
+                if phase == DispatchPhase::Bubble
+                    && event.phase == touch_phase
+                    && hitbox.is_hovered(window)
+                {
+                    (listener)(event, window, cx);
+                }
+            }));
+    }
+
     /// Bind the given callback to an action dispatch during the capture phase
     /// The imperative API equivalent to [`InteractiveElement::capture_action`]
     ///
@@ -486,6 +674,24 @@ impl Interactivity {
         ));
     }
 
+    /// On drag initiation, this callback will be used to create a drag that can leave the window and be
+    /// dropped onto other applications, see [`Window::start_external_drag`].
+    /// The imperative API equivalent to [`StatefulInteractiveElement::on_external_drag`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    pub fn on_external_drag(
+        &mut self,
+        constructor: impl Fn(&mut Window, &mut App) -> ExternalDrag + 'static,
+    ) where
+        Self: Sized,
+    {
+        debug_assert!(
+            self.external_drag_listener.is_none(),
+            "calling on_external_drag more than once on the same element is not supported"
+        );
+        self.external_drag_listener = Some(Box::new(constructor));
+    }
+
     /// Bind the given callback on the hover start and end events of this element. Note that the boolean
     /// passed to the callback is true when the hover starts and false when it ends.
     /// The imperative API equivalent to [`StatefulInteractiveElement::on_drag`]
@@ -727,6 +933,18 @@ pub trait InteractiveElement: Sized {
         self
     }
 
+    /// Bind the given callback to relative mouse movements over this element, during the bubble phase.
+    /// The fluent API equivalent to [`Interactivity::on_relative_mouse_move`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_relative_mouse_move(
+        mut self,
+        listener: impl Fn(&RelativeMouseMoveEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.interactivity().on_relative_mouse_move(listener);
+        self
+    }
+
     /// Bind the given callback to the mouse drag event of the given type. Note that this
     /// will be called for all move events, inside or outside of this element, as long as the
     /// drag was started with this element under the mouse. Useful for implementing draggable
@@ -754,6 +972,129 @@ pub trait InteractiveElement: Sized {
         self
     }
 
+    /// Bind the given callback to a touch starting on this element, during the bubble phase.
+    /// The fluent API equivalent to [`Interactivity::on_touch_start`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_touch_start(
+        mut self,
+        listener: impl Fn(&TouchEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.interactivity().on_touch_start(listener);
+        self
+    }
+
+    /// Bind the given callback to a touch moving over this element, during the bubble phase.
+    /// The fluent API equivalent to [`Interactivity::on_touch_move`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_touch_move(
+        mut self,
+        listener: impl Fn(&TouchEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.interactivity().on_touch_move(listener);
+        self
+    }
+
+    /// Bind the given callback to a touch ending over this element, during the bubble phase.
+    /// The fluent API equivalent to [`Interactivity::on_touch_end`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_touch_end(
+        mut self,
+        listener: impl Fn(&TouchEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.interactivity().on_touch_end(listener);
+        self
+    }
+
+    /// Bind the given callback to a touch being cancelled, during the bubble phase.
+    /// The fluent API equivalent to [`Interactivity::on_touch_cancel`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_touch_cancel(
+        mut self,
+        listener: impl Fn(&TouchEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.interactivity().on_touch_cancel(listener);
+        self
+    }
+
+    /// Bind the given callback to touchpad pinch gestures over this element, during the bubble phase
+    /// The fluent API equivalent to [`Interactivity::on_pinch`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_pinch(mut self, listener: impl Fn(&PinchEvent, &mut Window, &mut App) + 'static) -> Self {
+        self.interactivity().on_pinch(listener);
+        self
+    }
+
+    /// Bind the given callback to touchpad swipe gestures over this element, during the bubble phase
+    /// The fluent API equivalent to [`Interactivity::on_swipe`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_swipe(mut self, listener: impl Fn(&SwipeEvent, &mut Window, &mut App) + 'static) -> Self {
+        self.interactivity().on_swipe(listener);
+        self
+    }
+
+    /// Bind the given callback to touchpad hold gestures over this element, during the bubble phase
+    /// The fluent API equivalent to [`Interactivity::on_hold`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_hold(mut self, listener: impl Fn(&HoldEvent, &mut Window, &mut App) + 'static) -> Self {
+        self.interactivity().on_hold(listener);
+        self
+    }
+
+    /// Bind the given callback to every stylus event over this element, during the bubble phase
+    /// The fluent API equivalent to [`Interactivity::on_stylus`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_stylus(
+        mut self,
+        listener: impl Fn(&StylusEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.interactivity().on_stylus(listener);
+        self
+    }
+
+    /// Bind the given callback to the tip of a stylus touching the tablet over this element, during the bubble phase
+    /// The fluent API equivalent to [`Interactivity::on_stylus_down`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_stylus_down(
+        mut self,
+        listener: impl Fn(&StylusEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.interactivity().on_stylus_down(listener);
+        self
+    }
+
+    /// Bind the given callback to a stylus moving over this element, during the bubble phase.
+    /// The fluent API equivalent to [`Interactivity::on_stylus_move`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_stylus_move(
+        mut self,
+        listener: impl Fn(&StylusEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.interactivity().on_stylus_move(listener);
+        self
+    }
+
+    /// Bind the given callback to the tip of a stylus being lifted over this element, during the bubble phase
+    /// The fluent API equivalent to [`Interactivity::on_stylus_up`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_stylus_up(
+        mut self,
+        listener: impl Fn(&StylusEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.interactivity().on_stylus_up(listener);
+        self
+    }
+
     /// Capture the given action, before normal action dispatch can fire
     /// The fluent API equivalent to [`Interactivity::on_scroll_wheel`]
     ///
@@ -1022,6 +1363,22 @@ pub trait StatefulInteractiveElement: InteractiveElement {
         self
     }
 
+    /// On drag initiation, this callback will be used to create a drag that can leave the window and be
+    /// dropped onto other applications, see [`Window::start_external_drag`].
+    /// The fluent API equivalent to [`Interactivity::on_external_drag`]
+    ///
+    /// See [`Context::listener`](crate::Context::listener) to get access to a view's state from this callback.
+    fn on_external_drag(
+        mut self,
+        constructor: impl Fn(&mut Window, &mut App) -> ExternalDrag + 'static,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        self.interactivity().on_external_drag(constructor);
+        self
+    }
+
     /// Bind the given callback on the hover start and end events of this element. Note that the boolean
     /// passed to the callback is true when the hover starts and false when it ends.
     /// The fluent API equivalent to [`Interactivity::on_hover`]
@@ -1092,11 +1449,31 @@ pub(crate) type MouseMoveListener =
 pub(crate) type ScrollWheelListener =
     Box<dyn Fn(&ScrollWheelEvent, DispatchPhase, &Hitbox, &mut Window, &mut App) + 'static>;
 
+pub(crate) type TouchListener =
+    Box<dyn Fn(&TouchEvent, DispatchPhase, &Hitbox, &mut Window, &mut App) + 'static>;
+
+pub(crate) type PinchListener =
+    Box<dyn Fn(&PinchEvent, DispatchPhase, &Hitbox, &mut Window, &mut App) + 'static>;
+
+pub(crate) type SwipeListener =
+    Box<dyn Fn(&SwipeEvent, DispatchPhase, &Hitbox, &mut Window, &mut App) + 'static>;
+
+pub(crate) type HoldListener =
+    Box<dyn Fn(&HoldEvent, DispatchPhase, &Hitbox, &mut Window, &mut App) + 'static>;
+
+pub(crate) type RelativeMouseMoveListener =
+    Box<dyn Fn(&RelativeMouseMoveEvent, DispatchPhase, &Hitbox, &mut Window, &mut App) + 'static>;
+
+pub(crate) type StylusListener =
+    Box<dyn Fn(&StylusEvent, DispatchPhase, &Hitbox, &mut Window, &mut App) + 'static>;
+
 pub(crate) type ClickListener = Box<dyn Fn(&ClickEvent, &mut Window, &mut App) + 'static>;
 
 pub(crate) type DragListener =
     Box<dyn Fn(&dyn Any, Point<Pixels>, &mut Window, &mut App) -> AnyView + 'static>;
 
+pub(crate) type ExternalDragListener = Box<dyn Fn(&mut Window, &mut App) -> ExternalDrag + 'static>;
+
 type DropListener = Box<dyn Fn(&dyn Any, &mut Window, &mut App) + 'static>;
 
 type CanDropPredicate = Box<dyn Fn(&dyn Any, &mut Window, &mut App) -> bool + 'static>;
@@ -1360,7 +1737,13 @@ pub struct Interactivity {
     pub(crate) mouse_down_listeners: Vec<MouseDownListener>,
     pub(crate) mouse_up_listeners: Vec<MouseUpListener>,
     pub(crate) mouse_move_listeners: Vec<MouseMoveListener>,
+    pub(crate) relative_mouse_move_listeners: Vec<RelativeMouseMoveListener>,
     pub(crate) scroll_wheel_listeners: Vec<ScrollWheelListener>,
+    pub(crate) touch_listeners: Vec<TouchListener>,
+    pub(crate) pinch_listeners: Vec<PinchListener>,
+    pub(crate) swipe_listeners: Vec<SwipeListener>,
+    pub(crate) hold_listeners: Vec<HoldListener>,
+    pub(crate) stylus_listeners: Vec<StylusListener>,
     pub(crate) key_down_listeners: Vec<KeyDownListener>,
     pub(crate) key_up_listeners: Vec<KeyUpListener>,
     pub(crate) modifiers_changed_listeners: Vec<ModifiersChangedListener>,
@@ -1369,6 +1752,7 @@ pub struct Interactivity {
     pub(crate) can_drop_predicate: Option<CanDropPredicate>,
     pub(crate) click_listeners: Vec<ClickListener>,
     pub(crate) drag_listener: Option<(Arc<dyn Any>, DragListener)>,
+    pub(crate) external_drag_listener: Option<ExternalDragListener>,
     pub(crate) hover_listener: Option<Box<dyn Fn(&bool, &mut Window, &mut App)>>,
     pub(crate) tooltip_builder: Option<TooltipBuilder>,
     pub(crate) occlude_mouse: bool,
@@ -1514,7 +1898,14 @@ impl Interactivity {
             || !self.mouse_move_listeners.is_empty()
             || !self.click_listeners.is_empty()
             || !self.scroll_wheel_listeners.is_empty()
+            || !self.touch_listeners.is_empty()
+            || !self.pinch_listeners.is_empty()
+            || !self.swipe_listeners.is_empty()
+            || !self.hold_listeners.is_empty()
+            || !self.stylus_listeners.is_empty()
+            || !self.relative_mouse_move_listeners.is_empty()
             || self.drag_listener.is_some()
+            || self.external_drag_listener.is_some()
             || !self.drop_listeners.is_empty()
             || self.tooltip_builder.is_some()
     }
@@ -1615,7 +2006,7 @@ impl Interactivity {
                                         );
 
                                         if !cx.has_active_drag() {
-                                            if let Some(mouse_cursor) = style.mouse_cursor {
+                                            if let Some(mouse_cursor) = style.mouse_cursor.clone() {
                                                 window.set_cursor_style(mouse_cursor, hitbox);
                                             }
                                         }
@@ -1821,6 +2212,48 @@ impl Interactivity {
             })
         }
 
+        for listener in self.touch_listeners.drain(..) {
+            let hitbox = hitbox.clone();
+            window.on_mouse_event(move |event: &TouchEvent, phase, window, cx| {
+                listener(event, phase, &hitbox, window, cx);
+            })
+        }
+
+        for listener in self.pinch_listeners.drain(..) {
+            let hitbox = hitbox.clone();
+            window.on_mouse_event(move |event: &PinchEvent, phase, window, cx| {
+                listener(event, phase, &hitbox, window, cx);
+            })
+        }
+
+        for listener in self.swipe_listeners.drain(..) {
+            let hitbox = hitbox.clone();
+            window.on_mouse_event(move |event: &SwipeEvent, phase, window, cx| {
+                listener(event, phase, &hitbox, window, cx);
+            })
+        }
+
+        for listener in self.hold_listeners.drain(..) {
+            let hitbox = hitbox.clone();
+            window.on_mouse_event(move |event: &HoldEvent, phase, window, cx| {
+                listener(event, phase, &hitbox, window, cx);
+            })
+        }
+
+        for listener in self.relative_mouse_move_listeners.drain(..) {
+            let hitbox = hitbox.clone();
+            window.on_mouse_event(move |event: &RelativeMouseMoveEvent, phase, window, cx| {
+                listener(event, phase, &hitbox, window, cx);
+            })
+        }
+
+        for listener in self.stylus_listeners.drain(..) {
+            let hitbox = hitbox.clone();
+            window.on_mouse_event(move |event: &StylusEvent, phase, window, cx| {
+                listener(event, phase, &hitbox, window, cx);
+            })
+        }
+
         if self.hover_style.is_some()
             || self.base_style.mouse_cursor.is_some()
             || cx.active_drag.is_some() && !self.drag_over_styles.is_empty()
@@ -1836,6 +2269,7 @@ impl Interactivity {
         }
 
         let mut drag_listener = mem::take(&mut self.drag_listener);
+        let mut external_drag_listener = mem::take(&mut self.external_drag_listener);
         let drop_listeners = mem::take(&mut self.drop_listeners);
         let click_listeners = mem::take(&mut self.click_listeners);
         let can_drop_predicate = mem::take(&mut self.can_drop_predicate);
@@ -1873,7 +2307,10 @@ impl Interactivity {
         }
 
         if let Some(element_state) = element_state {
-            if !click_listeners.is_empty() || drag_listener.is_some() {
+            if !click_listeners.is_empty()
+                || drag_listener.is_some()
+                || external_drag_listener.is_some()
+            {
                 let pending_mouse_down = element_state
                     .pending_mouse_down
                     .get_or_insert_with(Default::default)
@@ -1929,6 +2366,15 @@ impl Interactivity {
                                     pending_mouse_down.take();
                                     window.refresh();
                                     cx.stop_propagation();
+                                } else if let Some(external_drag_listener) =
+                                    external_drag_listener.take()
+                                {
+                                    *clicked_state.borrow_mut() = ElementClickedState::default();
+                                    let drag = (external_drag_listener)(window, cx);
+                                    window.start_external_drag(drag, cx).log_err();
+                                    pending_mouse_down.take();
+                                    window.refresh();
+                                    cx.stop_propagation();
                                 }
                             }
                         }
@@ -88,6 +88,11 @@ pub mod prelude;
 mod scene;
 mod shared_string;
 mod shared_uri;
+#[cfg(all(
+    feature = "software-renderer",
+    any(target_os = "linux", target_os = "freebsd")
+))]
+mod snapshot;
 mod style;
 mod styled;
 mod subscription;
@@ -141,6 +146,11 @@ pub use scene::*;
 pub use shared_string::*;
 pub use shared_uri::*;
 pub use smol::Timer;
+#[cfg(all(
+    feature = "software-renderer",
+    any(target_os = "linux", target_os = "freebsd")
+))]
+pub use snapshot::*;
 pub use style::*;
 pub use styled::*;
 pub use subscription::*;
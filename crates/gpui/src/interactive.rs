@@ -1,9 +1,9 @@
 use crate::{
-    point, seal::Sealed, Context, Empty, IntoElement, Keystroke, Modifiers, Pixels, Point, Render,
-    Window,
+    point, seal::Sealed, AnyView, App, Context, Empty, Entity, IntoElement, Keystroke, Modifiers,
+    Pixels, Point, Render, Size, Window,
 };
 use smallvec::SmallVec;
-use std::{any::Any, fmt::Debug, ops::Deref, path::PathBuf};
+use std::{any::Any, fmt::Debug, ops::Deref, path::PathBuf, sync::Arc};
 
 /// An event from a platform input source.
 pub trait InputEvent: Sealed + 'static {
@@ -75,7 +75,7 @@ impl Deref for ModifiersChangedEvent {
 
 /// The phase of a touch motion event.
 /// Based on the winit enum of the same name.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum TouchPhase {
     /// The touch started.
     Started,
@@ -84,8 +84,225 @@ pub enum TouchPhase {
     Moved,
     /// The touch phase has ended
     Ended,
+    /// The touch was taken over by the system, e.g. for a gesture,
+    /// and won't produce any further events.
+    Cancelled,
 }
 
+/// A touch event from the platform, for a single point of contact on a touch screen.
+#[derive(Clone, Debug, Default)]
+pub struct TouchEvent {
+    /// Identifies the point of contact across its events. Only unique among the points
+    /// that are currently touching the screen, ids are reused once a touch ends.
+    pub id: u64,
+
+    /// Whether the touch started, moved, ended or was cancelled.
+    pub phase: TouchPhase,
+
+    /// The position of the touch on the window.
+    pub position: Point<Pixels>,
+
+    /// The modifiers that were held down at the time of the touch.
+    pub modifiers: Modifiers,
+}
+
+impl Sealed for TouchEvent {}
+impl InputEvent for TouchEvent {
+    fn to_platform_input(self) -> PlatformInput {
+        PlatformInput::Touch(self)
+    }
+}
+impl MouseEvent for TouchEvent {}
+
+/// A touchpad pinch gesture from the platform, as used for zooming and rotating.
+#[derive(Clone, Debug, Default)]
+pub struct PinchEvent {
+    /// The position of the mouse on the window.
+    pub position: Point<Pixels>,
+
+    /// Whether the gesture started, was updated, ended or was cancelled.
+    pub phase: TouchPhase,
+
+    /// The distance between the fingers relative to when the gesture started,
+    /// e.g. `2.0` when the fingers moved twice as far apart.
+    pub scale: f32,
+
+    /// How far the fingers have rotated since the gesture started, in degrees clockwise.
+    pub rotation: f32,
+
+    /// How far the center of the fingers moved since the previous event.
+    pub delta: Point<Pixels>,
+
+    /// The number of fingers used for the gesture.
+    pub fingers: u32,
+
+    /// The modifiers that were held down during the gesture.
+    pub modifiers: Modifiers,
+}
+
+impl Sealed for PinchEvent {}
+impl InputEvent for PinchEvent {
+    fn to_platform_input(self) -> PlatformInput {
+        PlatformInput::Pinch(self)
+    }
+}
+impl MouseEvent for PinchEvent {}
+
+/// A multi-finger touchpad swipe gesture from the platform, as used for navigation.
+#[derive(Clone, Debug, Default)]
+pub struct SwipeEvent {
+    /// The position of the mouse on the window.
+    pub position: Point<Pixels>,
+
+    /// Whether the gesture started, was updated, ended or was cancelled.
+    pub phase: TouchPhase,
+
+    /// How far the center of the fingers moved since the previous event.
+    pub delta: Point<Pixels>,
+
+    /// The number of fingers used for the gesture.
+    pub fingers: u32,
+
+    /// The modifiers that were held down during the gesture.
+    pub modifiers: Modifiers,
+}
+
+impl Sealed for SwipeEvent {}
+impl InputEvent for SwipeEvent {
+    fn to_platform_input(self) -> PlatformInput {
+        PlatformInput::Swipe(self)
+    }
+}
+impl MouseEvent for SwipeEvent {}
+
+/// A touchpad hold gesture from the platform, generated when fingers rest on the touchpad
+/// without moving, e.g. to stop kinetic scrolling.
+#[derive(Clone, Debug, Default)]
+pub struct HoldEvent {
+    /// The position of the mouse on the window.
+    pub position: Point<Pixels>,
+
+    /// Whether the gesture started, ended or was cancelled, e.g. because the fingers started
+    /// moving. Hold gestures are never updated.
+    pub phase: TouchPhase,
+
+    /// The number of fingers used for the gesture.
+    pub fingers: u32,
+
+    /// The modifiers that were held down during the gesture.
+    pub modifiers: Modifiers,
+}
+
+impl Sealed for HoldEvent {}
+impl InputEvent for HoldEvent {
+    fn to_platform_input(self) -> PlatformInput {
+        PlatformInput::Hold(self)
+    }
+}
+impl MouseEvent for HoldEvent {}
+
+/// The kind of tool used on a drawing tablet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StylusTool {
+    /// The tip of a pen.
+    #[default]
+    Pen,
+    /// The eraser end of a pen.
+    Eraser,
+    /// A paint brush.
+    Brush,
+    /// A pencil.
+    Pencil,
+    /// An airbrush.
+    Airbrush,
+    /// A mouse or lens cursor that is used on the tablet.
+    Mouse,
+}
+
+/// A button on the barrel of a stylus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StylusButton {
+    /// The button closest to the tip.
+    Primary,
+    /// The second button from the tip.
+    Secondary,
+    /// The third button from the tip.
+    Tertiary,
+    /// Any other button, identified by its Linux input event code.
+    Other(u32),
+}
+
+/// What happened to the stylus in a [`StylusEvent`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StylusPhase {
+    /// The stylus came within range of the tablet, over the window.
+    ProximityIn,
+    /// The tip of the stylus touched the tablet.
+    Down,
+    /// The stylus moved, or its pressure, distance, tilt or rotation changed.
+    #[default]
+    Moved,
+    /// The tip of the stylus was lifted from the tablet.
+    Up,
+    /// A button on the stylus was pressed.
+    ButtonDown(StylusButton),
+    /// A button on the stylus was released.
+    ButtonUp(StylusButton),
+    /// The stylus went out of range of the tablet, or left the window.
+    ProximityOut,
+}
+
+/// A stylus event from a drawing tablet.
+///
+/// Unless the event is handled with [`Window::prevent_default`](crate::Window::prevent_default),
+/// the stylus is also dispatched as the left mouse button, so that it can be used with elements
+/// that only listen to the mouse.
+#[derive(Clone, Debug, Default)]
+pub struct StylusEvent {
+    /// What happened to the stylus.
+    pub phase: StylusPhase,
+
+    /// The kind of tool that was used.
+    pub tool: StylusTool,
+
+    /// Identifies the physical tool, where the tablet supports telling tools apart.
+    pub tool_id: u64,
+
+    /// The position of the stylus on the window.
+    pub position: Point<Pixels>,
+
+    /// Whether the tip of the stylus is touching the tablet.
+    pub in_contact: bool,
+
+    /// How hard the tip of the stylus is pressed against the tablet, from `0.0` to `1.0`.
+    pub pressure: f32,
+
+    /// How far the tip of the stylus is from the tablet, from `0.0` to `1.0`.
+    /// The physical distance this corresponds to depends on the tablet.
+    pub distance: f32,
+
+    /// The tilt of the stylus away from the perpendicular in degrees, along each axis.
+    /// Positive values tilt towards the right and the bottom of the tablet.
+    pub tilt: Point<f32>,
+
+    /// The rotation of the stylus around its own axis in degrees, clockwise from its natural position.
+    pub rotation: f32,
+
+    /// The buttons on the stylus that are currently held down.
+    pub pressed_buttons: SmallVec<[StylusButton; 2]>,
+
+    /// The modifiers that were held down at the time of the event.
+    pub modifiers: Modifiers,
+}
+
+impl Sealed for StylusEvent {}
+impl InputEvent for StylusEvent {
+    fn to_platform_input(self) -> PlatformInput {
+        PlatformInput::Stylus(self)
+    }
+}
+impl MouseEvent for StylusEvent {}
+
 /// A mouse down event from the platform
 #[derive(Clone, Debug, Default)]
 pub struct MouseDownEvent {
@@ -240,6 +457,39 @@ impl MouseMoveEvent {
     }
 }
 
+/// A relative mouse movement from the platform, sent while the pointer is locked or confined
+/// to the window with [`Window::lock_pointer`](crate::Window::lock_pointer) or
+/// [`Window::confine_pointer`](crate::Window::confine_pointer).
+///
+/// Unlike [`MouseMoveEvent`]s, these are still sent when the pointer can't move any further,
+/// e.g. because it is locked in place or has reached the edge of the screen.
+#[derive(Clone, Debug, Default)]
+pub struct RelativeMouseMoveEvent {
+    /// The position of the mouse on the window, which doesn't change while the pointer is locked.
+    pub position: Point<Pixels>,
+
+    /// How far the mouse moved, with pointer acceleration applied.
+    pub delta: Point<Pixels>,
+
+    /// How far the mouse moved, without pointer acceleration. This is what the device
+    /// reported, so it is best suited for things like rotating a camera.
+    pub unaccelerated_delta: Point<Pixels>,
+
+    /// The mouse button that was pressed, if any.
+    pub pressed_button: Option<MouseButton>,
+
+    /// The modifiers that were held down when the mouse was moved.
+    pub modifiers: Modifiers,
+}
+
+impl Sealed for RelativeMouseMoveEvent {}
+impl InputEvent for RelativeMouseMoveEvent {
+    fn to_platform_input(self) -> PlatformInput {
+        PlatformInput::RelativeMouseMove(self)
+    }
+}
+impl MouseEvent for RelativeMouseMoveEvent {}
+
 /// A mouse wheel event from the platform
 #[derive(Clone, Debug, Default)]
 pub struct ScrollWheelEvent {
@@ -424,6 +674,123 @@ impl InputEvent for FileDropEvent {
 }
 impl MouseEvent for FileDropEvent {}
 
+/// What the target of an [`ExternalDrag`] does with the dragged data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DragAction {
+    /// The data is copied to the target.
+    Copy,
+    /// The data is moved to the target, so the source should remove it once the drag has finished.
+    Move,
+    /// The target asks the user what to do with the data once it's dropped.
+    Ask,
+}
+
+/// A representation of the data offered by an [`ExternalDrag`].
+#[derive(Clone, Debug)]
+pub(crate) enum ExternalDragEntry {
+    Paths(SmallVec<[PathBuf; 2]>),
+    Text(String),
+    Data { mime_type: String, bytes: Arc<[u8]> },
+}
+
+/// The progress of an [`ExternalDrag`], as reported by the target it's dragged onto.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExternalDragEvent {
+    /// The target under the pointer accepts the data in the given MIME type,
+    /// or doesn't accept any of the offered data if it's `None`.
+    Accepted {
+        /// The MIME type the target would receive the data in.
+        mime_type: Option<String>,
+    },
+    /// The action that would be performed if the data was dropped now has changed.
+    ActionChanged {
+        /// The action negotiated with the target, if any.
+        action: Option<DragAction>,
+    },
+    /// The data was dropped onto the target, which is now receiving it.
+    Dropped,
+    /// The target has finished receiving the data. This ends the drag.
+    Finished {
+        /// The action the target performed.
+        action: DragAction,
+    },
+    /// The drag ended without the data being dropped, or the target didn't accept it.
+    Cancelled,
+}
+
+/// A drag that can leave the window, offering its data to other applications.
+/// Started with [`Window::start_external_drag`], or from an element with
+/// [`StatefulInteractiveElement::on_external_drag`](crate::StatefulInteractiveElement::on_external_drag).
+///
+/// Only supported on Wayland for now.
+pub struct ExternalDrag {
+    pub(crate) entries: Vec<ExternalDragEntry>,
+    pub(crate) actions: SmallVec<[DragAction; 3]>,
+    pub(crate) icon: Option<(Size<Pixels>, AnyView)>,
+    pub(crate) listener: Option<Box<dyn FnMut(&ExternalDragEvent, &mut Window, &mut App)>>,
+}
+
+impl Default for ExternalDrag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExternalDrag {
+    /// Creates a drag without any data, that can only be copied.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            actions: SmallVec::from_slice(&[DragAction::Copy]),
+            icon: None,
+            listener: None,
+        }
+    }
+
+    /// Offers the given files, e.g. to a file manager.
+    pub fn paths(mut self, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.entries
+            .push(ExternalDragEntry::Paths(paths.into_iter().collect()));
+        self
+    }
+
+    /// Offers the given text.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.entries.push(ExternalDragEntry::Text(text.into()));
+        self
+    }
+
+    /// Offers the given data with a custom MIME type.
+    pub fn data(mut self, mime_type: impl Into<String>, bytes: impl Into<Arc<[u8]>>) -> Self {
+        self.entries.push(ExternalDragEntry::Data {
+            mime_type: mime_type.into(),
+            bytes: bytes.into(),
+        });
+        self
+    }
+
+    /// Sets the actions the target can choose from, in order of preference.
+    pub fn actions(mut self, actions: impl IntoIterator<Item = DragAction>) -> Self {
+        self.actions = actions.into_iter().collect();
+        self
+    }
+
+    /// Renders the given view under the pointer while dragging.
+    pub fn icon<V: 'static + Render>(mut self, size: Size<Pixels>, view: Entity<V>) -> Self {
+        self.icon = Some((size, view.into()));
+        self
+    }
+
+    /// Calls the given listener as the drag progresses.
+    pub fn on_event(
+        mut self,
+        listener: impl FnMut(&ExternalDragEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.listener = Some(Box::new(listener));
+        self
+    }
+}
+
 /// An enum corresponding to all kinds of platform input events.
 #[derive(Clone, Debug)]
 pub enum PlatformInput {
@@ -445,6 +812,18 @@ pub enum PlatformInput {
     ScrollWheel(ScrollWheelEvent),
     /// Files were dragged and dropped onto the window.
     FileDrop(FileDropEvent),
+    /// The touch screen was touched.
+    Touch(TouchEvent),
+    /// A pinch gesture was performed on the touchpad.
+    Pinch(PinchEvent),
+    /// A swipe gesture was performed on the touchpad.
+    Swipe(SwipeEvent),
+    /// A hold gesture was performed on the touchpad.
+    Hold(HoldEvent),
+    /// A stylus was used on a drawing tablet.
+    Stylus(StylusEvent),
+    /// The mouse was moved while the pointer is locked or confined to the window.
+    RelativeMouseMove(RelativeMouseMoveEvent),
 }
 
 impl PlatformInput {
@@ -459,6 +838,12 @@ impl PlatformInput {
             PlatformInput::MouseExited(event) => Some(event),
             PlatformInput::ScrollWheel(event) => Some(event),
             PlatformInput::FileDrop(event) => Some(event),
+            PlatformInput::Touch(event) => Some(event),
+            PlatformInput::Pinch(event) => Some(event),
+            PlatformInput::Swipe(event) => Some(event),
+            PlatformInput::Hold(event) => Some(event),
+            PlatformInput::Stylus(event) => Some(event),
+            PlatformInput::RelativeMouseMove(event) => Some(event),
         }
     }
 
@@ -473,6 +858,12 @@ impl PlatformInput {
             PlatformInput::MouseExited(_) => None,
             PlatformInput::ScrollWheel(_) => None,
             PlatformInput::FileDrop(_) => None,
+            PlatformInput::Touch(_) => None,
+            PlatformInput::Pinch(_) => None,
+            PlatformInput::Swipe(_) => None,
+            PlatformInput::Hold(_) => None,
+            PlatformInput::Stylus(_) => None,
+            PlatformInput::RelativeMouseMove(_) => None,
         }
     }
 }
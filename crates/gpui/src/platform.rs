@@ -2,7 +2,10 @@
 #![cfg_attr(windows, allow(dead_code))]
 
 mod app_menu;
+mod global_shortcut;
 mod keystroke;
+pub mod layer_shell;
+mod notification;
 
 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
 mod linux;
@@ -20,6 +23,13 @@ mod mac;
 ))]
 mod blade;
 
+#[cfg(any(
+    feature = "software-renderer",
+    target_os = "linux",
+    target_os = "freebsd"
+))]
+mod software;
+
 #[cfg(any(test, feature = "test-support"))]
 mod test;
 
@@ -27,11 +37,12 @@ mod test;
 mod windows;
 
 use crate::{
-    point, Action, AnyWindowHandle, App, AsyncWindowContext, BackgroundExecutor, Bounds,
-    DevicePixels, DispatchEventResult, Font, FontId, FontMetrics, FontRun, ForegroundExecutor,
-    GlyphId, GpuSpecs, ImageSource, Keymap, LineLayout, Pixels, PlatformInput, Point,
-    RenderGlyphParams, RenderImage, RenderImageParams, RenderSvgParams, ScaledPixels, Scene,
-    SharedString, Size, SvgRenderer, SvgSize, Task, TaskLabel, Window, DEFAULT_WINDOW_SIZE,
+    point, px, size, Action, AnyWindowHandle, App, AsyncWindowContext, BackgroundExecutor, Bounds,
+    Corner, Damage, DevicePixels, DispatchEventResult, ExternalDrag, ExternalDragEvent, Font,
+    FontId, FontMetrics, FontRun, ForegroundExecutor, GlyphId, GlyphOutlineCommand, GpuSpecs,
+    ImageSource, Keymap, LineLayout, Pixels, PlatformInput, Point, RenderGlyphParams, RenderImage,
+    RenderImageParams, RenderSvgParams, ScaledPixels, Scene, SharedString, Size, SvgRenderer,
+    SvgSize, Task, TaskLabel, Window, DEFAULT_WINDOW_SIZE,
 };
 use anyhow::{anyhow, Result};
 use async_task::Runnable;
@@ -53,19 +64,30 @@ use std::{
     ops::Range,
     path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc,
+    },
 };
 use strum::EnumIter;
 use uuid::Uuid;
 
 pub use app_menu::*;
+pub use global_shortcut::*;
 pub use keystroke::*;
+pub use notification::*;
 
 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
 pub(crate) use linux::*;
 #[cfg(target_os = "macos")]
 pub(crate) use mac::*;
 pub use semantic_version::SemanticVersion;
+#[cfg(any(
+    feature = "software-renderer",
+    target_os = "linux",
+    target_os = "freebsd"
+))]
+pub(crate) use software::*;
 #[cfg(any(test, feature = "test-support"))]
 pub(crate) use test::*;
 #[cfg(target_os = "windows")]
@@ -169,6 +191,7 @@ pub(crate) trait Platform: 'static {
     fn open_url(&self, url: &str);
     fn on_open_urls(&self, callback: Box<dyn FnMut(Vec<String>)>);
     fn register_url_scheme(&self, url: &str) -> Task<Result<()>>;
+    fn set_single_instance(&self, _app_id: &str) {}
 
     fn prompt_for_paths(
         &self,
@@ -190,6 +213,35 @@ pub(crate) trait Platform: 'static {
 
     fn set_dock_menu(&self, menu: Vec<MenuItem>, keymap: &Keymap);
     fn add_recent_document(&self, _path: &Path) {}
+    fn recent_documents(&self) -> Task<Result<Vec<PathBuf>>> {
+        Task::ready(Ok(Vec::new()))
+    }
+    fn show_notification(
+        &self,
+        _notification: Notification,
+        _on_action: Box<dyn FnOnce(&dyn Action)>,
+    ) -> oneshot::Receiver<Result<()>> {
+        let (done_tx, done_rx) = oneshot::channel();
+        done_tx
+            .send(Err(anyhow!(
+                "notifications are not supported on this platform"
+            )))
+            .ok();
+        done_rx
+    }
+    fn register_global_shortcuts(
+        &self,
+        _shortcuts: Vec<GlobalShortcut>,
+        _on_trigger: Box<dyn FnMut(&GlobalShortcut, bool)>,
+    ) -> oneshot::Receiver<Result<()>> {
+        let (done_tx, done_rx) = oneshot::channel();
+        done_tx
+            .send(Err(anyhow!(
+                "global shortcuts are not supported on this platform"
+            )))
+            .ok();
+        done_rx
+    }
     fn on_app_menu_action(&self, callback: Box<dyn FnMut(&dyn Action)>);
     fn on_will_open_app_menu(&self, callback: Box<dyn FnMut()>);
     fn on_validate_app_menu_command(&self, callback: Box<dyn FnMut(&dyn Action) -> bool>);
@@ -409,9 +461,38 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn on_should_close(&self, callback: Box<dyn FnMut() -> bool>);
     fn on_close(&self, callback: Box<dyn FnOnce()>);
     fn on_appearance_changed(&self, callback: Box<dyn FnMut()>);
-    fn draw(&self, scene: &Scene);
+    /// Renders the scene. Only the damaged regions differ from the previously drawn scene,
+    /// which lets the platform skip recompositing the rest of the window.
+    ///
+    /// The software renderer only redraws the damaged regions. The GPU renderer still redraws
+    /// the whole frame, as its swapchain images don't keep the previous frame's contents.
+    fn draw(&self, scene: &Scene, damage: &Damage);
     fn completed_frame(&self) {}
     fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas>;
+    /// Queues an event to be delivered through `on_input` on a later turn of the event loop.
+    /// Only windows without a real display server behind them support this.
+    fn inject_input(&self, _input: PlatformInput) {}
+    /// Queues a request to close the window, as if the user had clicked its close button,
+    /// on a later turn of the event loop. The `on_should_close` callback can keep it open.
+    /// Only windows without a real display server behind them support this.
+    fn inject_close_request(&self) {}
+    /// Returns the pixels of the last drawn frame, for windows that render on the CPU.
+    #[cfg(feature = "software-renderer")]
+    fn read_frame(&self) -> Option<image::RgbaImage> {
+        None
+    }
+
+    /// Starts a drag to other applications from this window, using the given window as its icon.
+    fn start_drag(
+        &self,
+        _drag: ExternalDrag,
+        _icon: Option<AnyWindowHandle>,
+        _on_event: Box<dyn FnMut(ExternalDragEvent)>,
+    ) -> Result<()> {
+        Err(anyhow!(
+            "dragging to other applications is not supported on this platform"
+        ))
+    }
 
     // macOS specific methods
     fn set_edited(&mut self, _edited: bool) {}
@@ -428,10 +509,16 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn show_window_menu(&self, _position: Point<Pixels>) {}
     fn start_window_move(&self) {}
     fn start_window_resize(&self, _edge: ResizeEdge) {}
+    fn set_pointer_constraint(&self, _constraint: Option<PointerConstraint>) {}
+    fn set_idle_inhibited(&self, _inhibited: bool) {}
+    fn set_input_region(&self, _region: Option<&[Bounds<Pixels>]>) {}
+    fn set_keyboard_shortcuts_inhibited(&self, _inhibited: bool) {}
+    fn on_keyboard_shortcuts_inhibit_change(&self, _callback: Box<dyn FnMut(bool)>) {}
     fn window_decorations(&self) -> Decorations {
         Decorations::Server
     }
     fn set_app_id(&mut self, _app_id: &str) {}
+    fn set_icon(&mut self, _icon: Option<&WindowIcon>) {}
     fn map_window(&mut self) -> anyhow::Result<()> {
         Ok(())
     }
@@ -483,6 +570,12 @@ pub(crate) trait PlatformTextSystem: Send + Sync {
         params: &RenderGlyphParams,
         raster_bounds: Bounds<DevicePixels>,
     ) -> Result<(Size<DevicePixels>, Vec<u8>)>;
+    fn glyph_outline(
+        &self,
+        font_id: FontId,
+        glyph_id: GlyphId,
+        font_size: Pixels,
+    ) -> Result<Vec<GlyphOutlineCommand>>;
     fn layout_line(&self, text: &str, font_size: Pixels, runs: &[FontRun]) -> LineLayout;
 }
 
@@ -536,6 +629,15 @@ impl PlatformTextSystem for NoopTextSystem {
         Err(anyhow!("No font found for {:?}", params))
     }
 
+    fn glyph_outline(
+        &self,
+        font_id: FontId,
+        _glyph_id: GlyphId,
+        _font_size: Pixels,
+    ) -> Result<Vec<GlyphOutlineCommand>> {
+        Err(anyhow!("No font found for {:?}", font_id))
+    }
+
     fn layout_line(&self, _text: &str, _font_size: Pixels, _runs: &[FontRun]) -> LineLayout {
         unimplemented!()
     }
@@ -930,6 +1032,10 @@ pub struct WindowOptions {
     /// The kind of window to create
     pub kind: WindowKind,
 
+    /// The configuration of a [`WindowKind::LayerShell`] window, the defaults are used when this
+    /// is `None`. Ignored for other kinds of windows.
+    pub layer_shell: Option<layer_shell::LayerShellOptions>,
+
     /// Whether the window should be movable by the user
     pub is_movable: bool,
 
@@ -943,6 +1049,10 @@ pub struct WindowOptions {
     /// Application identifier of the window. Can by used by desktop environments to group applications together.
     pub app_id: Option<String>,
 
+    /// The icon of the window, for desktop environments that don't look it up from the `app_id`.
+    /// Linux only
+    pub icon: Option<WindowIcon>,
+
     /// Window minimum size
     pub window_min_size: Option<Size<Pixels>>,
 
@@ -971,6 +1081,10 @@ pub(crate) struct WindowParams {
     #[cfg_attr(any(target_os = "linux", target_os = "freebsd"), allow(dead_code))]
     pub kind: WindowKind,
 
+    /// The configuration of a [`WindowKind::LayerShell`] window
+    #[cfg_attr(not(feature = "wayland"), allow(dead_code))]
+    pub layer_shell: Option<layer_shell::LayerShellOptions>,
+
     /// Whether the window should be movable by the user
     #[cfg_attr(any(target_os = "linux", target_os = "freebsd"), allow(dead_code))]
     pub is_movable: bool,
@@ -1029,16 +1143,58 @@ impl Default for WindowOptions {
             focus: true,
             show: true,
             kind: WindowKind::Normal,
+            layer_shell: None,
             is_movable: true,
             display_id: None,
             window_background: WindowBackgroundAppearance::default(),
             app_id: None,
+            icon: None,
             window_min_size: None,
             window_decorations: None,
         }
     }
 }
 
+/// An icon representing a window in task bars and window switchers, see [`WindowOptions::icon`].
+#[derive(Clone, Debug, Default)]
+pub struct WindowIcon {
+    name: Option<SharedString>,
+    images: Vec<Arc<image::RgbaImage>>,
+}
+
+impl WindowIcon {
+    /// Creates an icon looked up by name in the icon theme. Images added with
+    /// [`WindowIcon::image`] are used if the theme doesn't have it.
+    pub fn named(name: impl Into<SharedString>) -> Self {
+        Self {
+            name: Some(name.into()),
+            images: Vec::new(),
+        }
+    }
+
+    /// Creates an icon from an encoded image, such as a PNG.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::default().image(image::load_from_memory(bytes)?.into_rgba8()))
+    }
+
+    /// Adds a size of this icon. Providing several sizes lets the platform pick the best one
+    /// for where the icon is shown.
+    pub fn image(mut self, image: image::RgbaImage) -> Self {
+        self.images.push(Arc::new(image));
+        self
+    }
+
+    #[cfg(feature = "wayland")]
+    pub(crate) fn name(&self) -> Option<&SharedString> {
+        self.name.as_ref()
+    }
+
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    pub(crate) fn images(&self) -> &[Arc<image::RgbaImage>] {
+        &self.images
+    }
+}
+
 /// The options that can be configured for a window's titlebar
 #[derive(Debug, Default)]
 pub struct TitlebarOptions {
@@ -1053,6 +1209,16 @@ pub struct TitlebarOptions {
     pub traffic_light_position: Option<Point<Pixels>>,
 }
 
+/// How the pointer is held by a window, see [`Window::lock_pointer`](crate::Window::lock_pointer)
+/// and [`Window::confine_pointer`](crate::Window::confine_pointer).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointerConstraint {
+    /// The pointer is held in place.
+    Locked,
+    /// The pointer can't leave the window.
+    Confined,
+}
+
 /// The kind of window to create
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum WindowKind {
@@ -1062,6 +1228,92 @@ pub enum WindowKind {
     /// A window that appears above all other windows, usually used for alerts or popups
     /// use sparingly!
     PopUp,
+
+    /// A window that is part of the desktop shell, such as a panel, a dock or a launcher.
+    ///
+    /// Only supported on Wayland compositors that implement the `wlr-layer-shell` protocol,
+    /// opening it anywhere else fails. It's configured by [`WindowOptions::layer_shell`].
+    LayerShell,
+
+    /// A short-lived window attached to another window, such as a context menu or a tooltip,
+    /// that can extend past the bounds of its parent.
+    ///
+    /// Only supported on Wayland for now, opening it anywhere else fails.
+    Popover(PopoverOptions),
+
+    /// The icon that follows the pointer while dragging to another application.
+    ///
+    /// These are opened by [`Window::start_external_drag`](crate::Window::start_external_drag),
+    /// which also closes them once the drag ends.
+    DragIcon,
+}
+
+/// The placement of a [`WindowKind::Popover`] window relative to its parent.
+///
+/// The size of the popover is taken from the window's bounds, while their origin is ignored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PopoverOptions {
+    /// The window the popover is attached to, which must still be open.
+    pub parent: AnyWindowHandle,
+
+    /// The area of the parent window the popover is positioned against, e.g. the bounds
+    /// of the element that opened it.
+    pub anchor_bounds: Bounds<Pixels>,
+
+    /// The corner of `anchor_bounds` the popover is attached to.
+    pub anchor: Corner,
+
+    /// The corner of the popover that is placed on the anchor, so that the popover
+    /// extends away from it.
+    pub attach: Corner,
+
+    /// An additional offset of the popover from its anchor.
+    pub offset: Point<Pixels>,
+
+    /// How the popover is moved when it would otherwise not fit on the screen.
+    pub adjustment: PopoverAdjustment,
+
+    /// Whether the popover takes all input until it's dismissed by clicking outside of it,
+    /// like a menu. Should be `false` for tooltips and other popovers that don't expect input.
+    pub grab: bool,
+}
+
+impl PopoverOptions {
+    /// A popover below `anchor_bounds`, aligned with its left edge, like a dropdown menu.
+    pub fn below(parent: AnyWindowHandle, anchor_bounds: Bounds<Pixels>) -> Self {
+        Self {
+            parent,
+            anchor_bounds,
+            anchor: Corner::BottomLeft,
+            attach: Corner::TopLeft,
+            offset: Point::default(),
+            adjustment: PopoverAdjustment::default(),
+            grab: true,
+        }
+    }
+}
+
+/// How a [`WindowKind::Popover`] window is moved when it would otherwise not fit on the screen.
+///
+/// Each adjustment is tried in order, until the popover fits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PopoverAdjustment {
+    /// Flip the popover to the other side of its anchor.
+    pub flip: bool,
+    /// Slide the popover along the anchor until it fits.
+    pub slide: bool,
+    /// Shrink the popover until it fits.
+    pub resize: bool,
+}
+
+impl Default for PopoverAdjustment {
+    fn default() -> Self {
+        Self {
+            flip: true,
+            slide: true,
+            resize: false,
+        }
+    }
 }
 
 /// The appearance of the window, as defined by the operating system.
@@ -1143,7 +1395,7 @@ pub enum PromptLevel {
 }
 
 /// The style of the cursor (pointer)
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum CursorStyle {
     /// The default cursor
     Arrow,
@@ -1227,6 +1479,10 @@ pub enum CursorStyle {
     /// A cursor indicating that the operation will result in a context menu
     /// corresponds to the CSS cursor value `context-menu`
     ContextualMenu,
+
+    /// A custom cursor drawn from an image
+    /// corresponds to the CSS cursor value `url(...)`
+    Image(CursorImage),
 }
 
 impl Default for CursorStyle {
@@ -1235,6 +1491,172 @@ impl Default for CursorStyle {
     }
 }
 
+/// An image to be used as the cursor, along with its hotspot.
+///
+/// The image is uploaded once per scale factor by the platform, so it's cheap to clone
+/// and to set repeatedly. Two cursor images are equal only if one is a clone of the other.
+#[derive(Clone)]
+pub struct CursorImage(Arc<CursorImageInner>);
+
+struct CursorImageInner {
+    id: usize,
+    rgba: Arc<[u8]>,
+    pixel_size: Size<u32>,
+    size: Size<Pixels>,
+    hotspot: Point<Pixels>,
+}
+
+impl CursorImage {
+    /// Create a cursor image from non-premultiplied RGBA8 pixels.
+    ///
+    /// The image is displayed at one logical pixel per image pixel, use [`Self::with_size`]
+    /// to change that. The hotspot is given in logical pixels from the top left corner.
+    ///
+    /// Returns an error if the image is empty or `rgba` doesn't hold `width * height` pixels.
+    pub fn from_rgba(
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+        hotspot: Point<Pixels>,
+    ) -> Result<Self> {
+        if width == 0 || height == 0 {
+            return Err(anyhow!("cursor image is empty"));
+        }
+        let expected_len = width as usize * height as usize * 4;
+        if rgba.len() != expected_len {
+            return Err(anyhow!(
+                "cursor image data is {} bytes long, expected {expected_len} for {width}x{height} pixels",
+                rgba.len()
+            ));
+        }
+        Ok(Self(Arc::new(CursorImageInner {
+            id: next_cursor_image_id(),
+            rgba: rgba.into(),
+            pixel_size: size(width, height),
+            size: size(px(width as f32), px(height as f32)),
+            hotspot,
+        })))
+    }
+
+    /// Create a cursor image from the first frame of a [`RenderImage`].
+    pub fn from_render_image(image: &RenderImage, hotspot: Point<Pixels>) -> Result<Self> {
+        let image_size = image.size(0);
+        let mut rgba = image
+            .as_bytes(0)
+            .ok_or_else(|| anyhow!("render image has no frames"))?
+            .to_vec();
+        // Render images are stored as BGRA.
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        Self::from_rgba(
+            image_size.width.0 as u32,
+            image_size.height.0 as u32,
+            rgba,
+            hotspot,
+        )
+    }
+
+    /// Display this image at the given logical size, resampling it for each scale factor.
+    pub fn with_size(self, size: Size<Pixels>) -> Self {
+        Self(Arc::new(CursorImageInner {
+            id: next_cursor_image_id(),
+            rgba: self.0.rgba.clone(),
+            pixel_size: self.0.pixel_size,
+            size,
+            hotspot: self.0.hotspot,
+        }))
+    }
+
+    /// The logical size at which this cursor is displayed.
+    pub fn size(&self) -> Size<Pixels> {
+        self.0.size
+    }
+
+    /// The hotspot of this cursor, in logical pixels from the top left corner.
+    pub fn hotspot(&self) -> Point<Pixels> {
+        self.0.hotspot
+    }
+
+    #[cfg(feature = "wayland")]
+    pub(crate) fn id(&self) -> usize {
+        self.0.id
+    }
+
+    /// Render this image for the given scale factor, as premultiplied BGRA8 pixels
+    /// (`ARGB8888` in little endian), along with their dimensions and the hotspot in
+    /// device pixels.
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    pub(crate) fn to_premultiplied_bgra(&self, scale: f32) -> (Vec<u8>, Size<u32>, Point<u32>) {
+        let width = ((self.0.size.width.0 * scale).round() as u32).max(1);
+        let height = ((self.0.size.height.0 * scale).round() as u32).max(1);
+        let source = image::RgbaImage::from_raw(
+            self.0.pixel_size.width,
+            self.0.pixel_size.height,
+            self.0.rgba.to_vec(),
+        )
+        .expect("cursor image data matches its dimensions");
+        let mut bytes = if (width, height) == source.dimensions() {
+            source.into_raw()
+        } else {
+            image::imageops::resize(
+                &source,
+                width,
+                height,
+                image::imageops::FilterType::Triangle,
+            )
+            .into_raw()
+        };
+        rgba_to_premultiplied_bgra(&mut bytes);
+        let hotspot = point(
+            ((self.0.hotspot.x.0 * scale).round() as u32).min(width - 1),
+            ((self.0.hotspot.y.0 * scale).round() as u32).min(height - 1),
+        );
+        (bytes, size(width, height), hotspot)
+    }
+}
+
+/// Converts non-premultiplied RGBA8 pixels to premultiplied BGRA8 in place.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub(crate) fn rgba_to_premultiplied_bgra(bytes: &mut [u8]) {
+    for pixel in bytes.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u16;
+        let (r, g, b) = (pixel[0] as u16, pixel[1] as u16, pixel[2] as u16);
+        pixel[0] = ((b * alpha + 127) / 255) as u8;
+        pixel[1] = ((g * alpha + 127) / 255) as u8;
+        pixel[2] = ((r * alpha + 127) / 255) as u8;
+    }
+}
+
+fn next_cursor_image_id() -> usize {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    NEXT_ID.fetch_add(1, SeqCst)
+}
+
+impl PartialEq for CursorImage {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id == other.0.id
+    }
+}
+
+impl Eq for CursorImage {}
+
+impl Hash for CursorImage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.id.hash(state);
+    }
+}
+
+impl Debug for CursorImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CursorImage")
+            .field("id", &self.0.id)
+            .field("size", &self.0.size)
+            .field("hotspot", &self.0.hotspot)
+            .finish()
+    }
+}
+
 /// A clipboard item that should be copied to the clipboard
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ClipboardItem {
@@ -1494,3 +1916,47 @@ impl ClipboardString {
         hasher.finish()
     }
 }
+
+#[cfg(all(test, any(feature = "wayland", feature = "x11")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgba_to_premultiplied_bgra() {
+        let mut bytes = vec![
+            10, 20, 30, 255, // opaque
+            255, 128, 0, 128, // translucent
+            200, 100, 50, 0, // transparent
+        ];
+        rgba_to_premultiplied_bgra(&mut bytes);
+        assert_eq!(bytes, [30, 20, 10, 255, 0, 64, 128, 128, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_cursor_image_to_premultiplied_bgra() {
+        let image = CursorImage::from_rgba(
+            2,
+            1,
+            vec![255, 0, 0, 255, 0, 0, 255, 128],
+            point(px(1.), px(0.)),
+        )
+        .unwrap();
+
+        let (bytes, size, hotspot) = image.to_premultiplied_bgra(1.);
+        assert_eq!(bytes, [0, 0, 255, 255, 128, 0, 0, 128]);
+        assert_eq!(size, Size::new(2, 1));
+        assert_eq!(hotspot, point(1, 0));
+
+        // Scaled images keep their hotspot over the same pixel.
+        let (bytes, size, hotspot) = image.to_premultiplied_bgra(2.);
+        assert_eq!(bytes.len(), 4 * 2 * 4);
+        assert_eq!(size, Size::new(4, 2));
+        assert_eq!(hotspot, point(2, 0));
+    }
+
+    #[test]
+    fn test_cursor_image_from_invalid_rgba() {
+        assert!(CursorImage::from_rgba(2, 2, vec![0; 12], point(px(0.), px(0.))).is_err());
+        assert!(CursorImage::from_rgba(0, 0, Vec::new(), point(px(0.), px(0.))).is_err());
+    }
+}
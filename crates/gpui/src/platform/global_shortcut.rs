@@ -0,0 +1,117 @@
+use std::slice;
+
+use anyhow::Result;
+
+use crate::{Action, KeyContext, Keymap, Keystroke, SharedString};
+
+/// A shortcut that is triggered even when none of the application's windows is focused,
+/// see [`App::register_global_shortcuts`](crate::App::register_global_shortcuts).
+///
+/// Its actions are looked up in the keymap like those of key bindings, in the `GlobalShortcut`
+/// context. Bindings that also require `released` are dispatched when the keystroke is released,
+/// e.g. to implement push-to-talk:
+///
+/// ```ignore
+/// cx.bind_keys([
+///     KeyBinding::new("ctrl-alt-space", StartTalking, Some("GlobalShortcut")),
+///     KeyBinding::new("ctrl-alt-space", StopTalking, Some("GlobalShortcut && released")),
+/// ]);
+/// cx.register_global_shortcuts([GlobalShortcut::new("ctrl-alt-space", "Push to talk")?]);
+/// ```
+pub struct GlobalShortcut {
+    pub(crate) keystroke: Keystroke,
+    pub(crate) description: SharedString,
+}
+
+impl GlobalShortcut {
+    /// Creates a shortcut that is triggered when `keystroke` is pressed. The keystroke uses the
+    /// same syntax as key bindings, e.g. `ctrl-alt-space`, and the description is shown to the
+    /// user by the desktop.
+    pub fn new(keystroke: &str, description: impl Into<SharedString>) -> Result<Self> {
+        Ok(Self {
+            keystroke: Keystroke::parse(keystroke)?,
+            description: description.into(),
+        })
+    }
+
+    /// The keystroke that triggers this shortcut.
+    pub fn keystroke(&self) -> &Keystroke {
+        &self.keystroke
+    }
+
+    /// Returns the action bound to this shortcut's keystroke for when it's pressed or released.
+    pub(crate) fn action(&self, keymap: &Keymap, released: bool) -> Option<Box<dyn Action>> {
+        let mut context = KeyContext::default();
+        context.add("GlobalShortcut");
+        let pressed_context = [context.clone()];
+        if !released {
+            let (bindings, _) =
+                keymap.bindings_for_input(slice::from_ref(&self.keystroke), &pressed_context);
+            return bindings
+                .into_iter()
+                .next()
+                .map(|binding| binding.action.boxed_clone());
+        }
+
+        context.add("released");
+        let (bindings, _) = keymap.bindings_for_input(slice::from_ref(&self.keystroke), &[context]);
+        // Bindings that also match the pressed keystroke, such as the ones without a context,
+        // were already dispatched when it was pressed.
+        bindings
+            .into_iter()
+            .find(|binding| {
+                binding
+                    .predicate()
+                    .is_some_and(|predicate| !predicate.eval(&pressed_context))
+            })
+            .map(|binding| binding.action.boxed_clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as gpui;
+    use gpui::{actions, KeyBinding};
+
+    actions!(
+        global_shortcut_test,
+        [StartTalking, StopTalking, QuickCapture]
+    );
+
+    #[test]
+    fn test_action() {
+        let keymap = Keymap::new(vec![
+            KeyBinding::new("ctrl-alt-space", StartTalking, Some("GlobalShortcut")),
+            KeyBinding::new(
+                "ctrl-alt-space",
+                StopTalking,
+                Some("GlobalShortcut && released"),
+            ),
+            KeyBinding::new("ctrl-alt-c", QuickCapture, None),
+            KeyBinding::new("ctrl-alt-x", QuickCapture, Some("Editor")),
+        ]);
+        let action = |keystroke, released| {
+            GlobalShortcut::new(keystroke, "")
+                .unwrap()
+                .action(&keymap, released)
+        };
+
+        assert!(action("ctrl-alt-space", false)
+            .unwrap()
+            .partial_eq(&StartTalking));
+        assert!(action("ctrl-alt-space", true)
+            .unwrap()
+            .partial_eq(&StopTalking));
+        assert!(action("ctrl-alt-c", false)
+            .unwrap()
+            .partial_eq(&QuickCapture));
+        assert!(action("ctrl-alt-c", true).is_none());
+        assert!(action("ctrl-alt-x", false).is_none());
+    }
+
+    #[test]
+    fn test_invalid_keystroke() {
+        assert!(GlobalShortcut::new("ctrl-a-b", "").is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Options for windows that are shown as part of the desktop shell rather than as
+//! application windows, such as panels, docks, launchers and lock screens.
+//!
+//! These windows are created with [`WindowKind::LayerShell`](crate::WindowKind::LayerShell)
+//! and are backed by the `wlr-layer-shell` protocol, so they are only available on Wayland
+//! compositors that implement it.
+
+use crate::{Edges, Pixels};
+
+/// The layer of the desktop shell a window is placed in, from the bottom to the top.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Layer {
+    /// Below everything else, e.g. a wallpaper.
+    Background,
+    /// Below application windows, e.g. desktop icons.
+    Bottom,
+    /// Above application windows, e.g. a panel or a status bar.
+    #[default]
+    Top,
+    /// Above everything else, including fullscreen windows, e.g. a lock screen or an on-screen display.
+    Overlay,
+}
+
+/// The edges of the output a layer shell window is anchored to.
+///
+/// A window anchored to two opposite edges is stretched between them, and a window
+/// that isn't anchored to any edge is centered on the output.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Anchor {
+    /// Whether the window is anchored to the top edge
+    pub top: bool,
+    /// Whether the window is anchored to the bottom edge
+    pub bottom: bool,
+    /// Whether the window is anchored to the left edge
+    pub left: bool,
+    /// Whether the window is anchored to the right edge
+    pub right: bool,
+}
+
+impl Anchor {
+    /// Anchors the window to every edge, so it covers the whole output.
+    pub fn all() -> Self {
+        Self {
+            top: true,
+            bottom: true,
+            left: true,
+            right: true,
+        }
+    }
+
+    /// Anchors the window along the top edge, stretched across the output, like a status bar.
+    pub fn top_edge() -> Self {
+        Self {
+            top: true,
+            left: true,
+            right: true,
+            ..Self::default()
+        }
+    }
+
+    /// Anchors the window along the bottom edge, stretched across the output, like a dock.
+    pub fn bottom_edge() -> Self {
+        Self {
+            bottom: true,
+            left: true,
+            right: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// How a layer shell window interacts with the space reserved by other windows.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ExclusiveZone {
+    /// Stay clear of the space reserved by other windows, without reserving any.
+    #[default]
+    Avoid,
+    /// Reserve this much space along the anchored edge, so that application windows and
+    /// other layer shell windows don't cover this window. Only applies to windows anchored
+    /// to a single edge, or to one edge and both of its neighbours.
+    Reserve(Pixels),
+    /// Extend under the space reserved by other windows, e.g. for a wallpaper.
+    Ignore,
+}
+
+/// Whether a layer shell window can receive keyboard input.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum KeyboardInteractivity {
+    /// The window never receives keyboard focus.
+    #[default]
+    None,
+    /// The window takes keyboard focus away from every other window while it's shown,
+    /// e.g. for a launcher or a lock screen.
+    Exclusive,
+    /// The window is focused like a regular window, e.g. when it's clicked.
+    /// Falls back to [`KeyboardInteractivity::Exclusive`] on compositors that don't support it.
+    OnDemand,
+}
+
+/// The configuration of a [`WindowKind::LayerShell`](crate::WindowKind::LayerShell) window,
+/// passed in [`WindowOptions::layer_shell`](crate::WindowOptions::layer_shell).
+///
+/// The size of the window is taken from its bounds, except along the axes it is stretched
+/// across by its [`Anchor`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LayerShellOptions {
+    /// A name for the kind of window, e.g. `"panel"`, that compositors can use to apply rules to it.
+    pub namespace: String,
+    /// The layer the window is placed in.
+    pub layer: Layer,
+    /// The edges of the output the window is anchored to.
+    pub anchor: Anchor,
+    /// How the window interacts with the space reserved by other windows.
+    pub exclusive_zone: ExclusiveZone,
+    /// The distance from the anchored edges, ignored for edges the window isn't anchored to.
+    pub margin: Edges<Pixels>,
+    /// Whether the window can receive keyboard input.
+    pub keyboard_interactivity: KeyboardInteractivity,
+}
@@ -1,8 +1,15 @@
 mod dispatcher;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+mod global_shortcuts;
 mod headless;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+mod notification;
 mod platform;
+mod recent_documents;
+mod single_instance;
 #[cfg(any(feature = "wayland", feature = "x11"))]
 mod text_system;
+mod url_scheme;
 #[cfg(feature = "wayland")]
 mod wayland;
 #[cfg(feature = "x11")]
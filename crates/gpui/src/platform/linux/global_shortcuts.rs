@@ -0,0 +1,175 @@
+//! Registers shortcuts with the desktop through the GlobalShortcuts portal, so that they're
+//! triggered even when none of the application's windows is focused.
+//!
+//! See the [GlobalShortcuts](https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.GlobalShortcuts.html)
+//! portal, whose triggers are described in the
+//! [Shortcuts](https://specifications.freedesktop.org/shortcuts-spec/latest/) specification.
+
+use anyhow::Result;
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+use futures::{channel::oneshot, stream, StreamExt as _};
+use util::ResultExt as _;
+use xkbcommon::xkb::{self, Keysym};
+
+use crate::{GlobalShortcut, Keystroke};
+
+const SHORTCUT_ID_PREFIX: &str = "shortcut-";
+
+enum Event {
+    Activated(String),
+    Deactivated(String),
+    Unbind,
+}
+
+/// Binds `shortcuts` in a new session, relaying through `bound` whether it succeeded, and calls
+/// `on_trigger` with the shortcuts that are pressed or released until `unbind` resolves or is
+/// dropped.
+pub(super) async fn bind_global_shortcuts(
+    shortcuts: Vec<GlobalShortcut>,
+    bound: oneshot::Sender<Result<()>>,
+    unbind: oneshot::Receiver<()>,
+    mut on_trigger: Box<dyn FnMut(&GlobalShortcut, bool)>,
+) {
+    let new_shortcuts = shortcuts
+        .iter()
+        .enumerate()
+        .map(|(ix, shortcut)| {
+            NewShortcut::new(
+                format!("{SHORTCUT_ID_PREFIX}{ix}"),
+                shortcut.description.as_ref(),
+            )
+            .preferred_trigger(preferred_trigger(&shortcut.keystroke).as_deref())
+        })
+        .collect::<Vec<_>>();
+
+    let session = async {
+        let proxy = GlobalShortcuts::new().await?;
+        let session = proxy.create_session().await?;
+        proxy
+            .bind_shortcuts(&session, &new_shortcuts, None)
+            .await?
+            .response()?;
+        anyhow::Ok((proxy, session))
+    }
+    .await;
+    let (proxy, session) = match session {
+        Ok(session) => session,
+        Err(error) => {
+            bound.send(Err(error)).ok();
+            return;
+        }
+    };
+
+    let events = async {
+        let activated = proxy
+            .receive_activated()
+            .await?
+            .map(|activated| Event::Activated(activated.shortcut_id().to_string()));
+        let deactivated = proxy
+            .receive_deactivated()
+            .await?
+            .map(|deactivated| Event::Deactivated(deactivated.shortcut_id().to_string()));
+        anyhow::Ok(stream::select(activated, deactivated))
+    }
+    .await;
+    let events = match events {
+        Ok(events) => events,
+        Err(error) => {
+            session.close().await.log_err();
+            bound.send(Err(error)).ok();
+            return;
+        }
+    };
+    bound.send(Ok(())).ok();
+
+    let mut events = stream::select(events, stream::once(unbind).map(|_| Event::Unbind));
+    while let Some(event) = events.next().await {
+        let (id, released) = match event {
+            Event::Activated(id) => (id, false),
+            Event::Deactivated(id) => (id, true),
+            Event::Unbind => break,
+        };
+        let Some(shortcut) = id
+            .strip_prefix(SHORTCUT_ID_PREFIX)
+            .and_then(|ix| ix.parse::<usize>().ok())
+            .and_then(|ix| shortcuts.get(ix))
+        else {
+            continue;
+        };
+        on_trigger(shortcut, released);
+    }
+    session.close().await.log_err();
+}
+
+/// Describes a keystroke as a trigger, e.g. `CTRL+ALT+space`. The desktop uses it as a
+/// suggestion, and may let the user pick other keys.
+fn preferred_trigger(keystroke: &Keystroke) -> Option<String> {
+    let key = match keystroke.key.as_str() {
+        "space" => "space".to_string(),
+        "enter" => "Return".to_string(),
+        "escape" => "Escape".to_string(),
+        "tab" => "Tab".to_string(),
+        "backspace" => "BackSpace".to_string(),
+        "delete" => "Delete".to_string(),
+        "insert" => "Insert".to_string(),
+        "home" => "Home".to_string(),
+        "end" => "End".to_string(),
+        "pageup" => "Page_Up".to_string(),
+        "pagedown" => "Page_Down".to_string(),
+        "up" => "Up".to_string(),
+        "down" => "Down".to_string(),
+        "left" => "Left".to_string(),
+        "right" => "Right".to_string(),
+        key if key.len() > 1 && key.starts_with('f') && key[1..].parse::<u8>().is_ok() => {
+            key.to_ascii_uppercase()
+        }
+        key => {
+            let mut chars = key.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                log::warn!("no global shortcut trigger for the key {key:?}");
+                return None;
+            };
+            if c.is_ascii_alphanumeric() {
+                c.to_string()
+            } else {
+                let keysym = xkb::utf32_to_keysym(c as u32);
+                if keysym == Keysym::NoSymbol {
+                    return None;
+                }
+                xkb::keysym_get_name(keysym)
+            }
+        }
+    };
+
+    let modifiers = &keystroke.modifiers;
+    let mut trigger = String::new();
+    for (pressed, name) in [
+        (modifiers.control, "CTRL"),
+        (modifiers.alt, "ALT"),
+        (modifiers.shift, "SHIFT"),
+        (modifiers.platform, "LOGO"),
+    ] {
+        if pressed {
+            trigger.push_str(name);
+            trigger.push('+');
+        }
+    }
+    trigger.push_str(&key);
+    Some(trigger)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferred_trigger() {
+        let trigger = |source| preferred_trigger(&Keystroke::parse(source).unwrap());
+        assert_eq!(trigger("ctrl-alt-space").as_deref(), Some("CTRL+ALT+space"));
+        assert_eq!(
+            trigger("super-shift-f12").as_deref(),
+            Some("SHIFT+LOGO+F12")
+        );
+        assert_eq!(trigger("ctrl-k").as_deref(), Some("CTRL+k"));
+    }
+}
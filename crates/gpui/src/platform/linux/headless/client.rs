@@ -0,0 +1,525 @@
+use std::{
+    cell::RefCell,
+    path::PathBuf,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+#[cfg(test)]
+use std::{sync::Arc, time::Instant};
+
+use calloop::{
+    channel::Sender,
+    timer::{TimeoutAction, Timer},
+    EventLoop, LoopHandle,
+};
+use util::ResultExt;
+
+use crate::platform::linux::{
+    listen_for_forwarded_launches, single_instance::SingleInstanceListener, LinuxClient,
+};
+use crate::platform::{LinuxCommon, PlatformWindow};
+use crate::{
+    AnyWindowHandle, ClipboardItem, CursorStyle, DisplayId, PlatformDisplay, PlatformInput,
+    WindowId, WindowParams,
+};
+
+use super::{HeadlessDisplay, HeadlessWindow, HeadlessWindowStatePtr};
+#[cfg(test)]
+use crate::{App, AppCell, NullHttpClient};
+
+/// How often offscreen windows are asked to produce a frame.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// The scale factor of offscreen windows when none is given explicitly, which defaults to 1.
+const SCALE_FACTOR_ENV_VAR: &str = "GPUI_HEADLESS_SCALE_FACTOR";
+
+pub struct HeadlessClientState {
+    pub(crate) loop_handle: LoopHandle<'static, HeadlessClientStatePtr>,
+    pub(crate) event_loop: Option<EventLoop<'static, HeadlessClientStatePtr>>,
+    pub(crate) common: LinuxCommon,
+    /// Open windows, from back to front.
+    windows: Vec<HeadlessWindowStatePtr>,
+    active_window: Option<HeadlessWindowStatePtr>,
+    input_sender: Sender<(WindowId, PlatformInput)>,
+    scale_factor: f32,
+    clipboard: Option<ClipboardItem>,
+    primary: Option<ClipboardItem>,
+}
+
+#[derive(Clone)]
+pub struct HeadlessClientStatePtr(Weak<RefCell<HeadlessClientState>>);
+
+impl HeadlessClientStatePtr {
+    pub fn get_client(&self) -> Rc<RefCell<HeadlessClientState>> {
+        self.0
+            .upgrade()
+            .expect("The pointer should always be valid when dispatching in headless")
+    }
+
+    pub fn activate_window(&self, window: &HeadlessWindowStatePtr) {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let Some(ix) = state.windows.iter().position(|w| w.ptr_eq(window)) else {
+            return;
+        };
+        let raised = state.windows.remove(ix);
+        state.windows.push(raised);
+        let previous = state.active_window.replace(window.clone());
+        drop(state);
+
+        if let Some(previous) = previous {
+            if !previous.ptr_eq(window) {
+                previous.set_focused(false);
+            }
+        }
+        window.set_focused(true);
+    }
+
+    pub fn drop_window(&self, window_id: WindowId) {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let Some(ix) = state
+            .windows
+            .iter()
+            .position(|window| window.handle().window_id() == window_id)
+        else {
+            return;
+        };
+        let closed_window = state.windows.remove(ix);
+        if let Some(window) = state.active_window.take() {
+            if !window.ptr_eq(&closed_window) {
+                state.active_window = Some(window);
+            }
+        }
+        if state.windows.is_empty() {
+            state.common.signal.stop();
+        }
+    }
+
+    fn window(&self, window_id: WindowId) -> Option<HeadlessWindowStatePtr> {
+        self.get_client()
+            .borrow()
+            .windows
+            .iter()
+            .find(|window| window.handle().window_id() == window_id)
+            .cloned()
+    }
+}
+
+/// A platform client that needs no display server. Windows are rendered offscreen,
+/// which makes it possible to run views in server-side jobs and integration tests.
+#[derive(Clone)]
+pub(crate) struct HeadlessClient(Rc<RefCell<HeadlessClientState>>);
+
+impl HeadlessClient {
+    pub(crate) fn new() -> Self {
+        let scale_factor = std::env::var(SCALE_FACTOR_ENV_VAR)
+            .ok()
+            .and_then(|value| value.parse::<f32>().ok())
+            .filter(|scale_factor| *scale_factor > 0.)
+            .unwrap_or(1.0);
+        Self::with_scale_factor(scale_factor)
+    }
+
+    /// Creates a client whose windows are rendered at the given scale factor.
+    pub fn with_scale_factor(scale_factor: f32) -> Self {
+        let event_loop = EventLoop::try_new().unwrap();
+
+        let (common, main_receiver) = LinuxCommon::new(event_loop.get_signal());
+
+        let handle = event_loop.handle();
+
+        handle
+            .insert_source(main_receiver, |event, _, _: &mut HeadlessClientStatePtr| {
+                if let calloop::channel::Event::Msg(runnable) = event {
+                    runnable.run();
+                }
+            })
+            .unwrap();
+
+        let (input_sender, input_receiver) = calloop::channel::channel();
+        handle
+            .insert_source(input_receiver, |event, _, this| {
+                if let calloop::channel::Event::Msg((window_id, input)) = event {
+                    if let Some(window) = this.window(window_id) {
+                        window.handle_input(input);
+                    }
+                }
+            })
+            .unwrap();
+
+        handle
+            .insert_source(Timer::from_duration(FRAME_INTERVAL), |_, _, this| {
+                let windows = this.get_client().borrow().windows.clone();
+                for window in windows {
+                    window.frame();
+                }
+                TimeoutAction::ToDuration(FRAME_INTERVAL)
+            })
+            .unwrap();
+
+        HeadlessClient(Rc::new(RefCell::new(HeadlessClientState {
+            loop_handle: handle,
+            event_loop: Some(event_loop),
+            common,
+            windows: Vec::new(),
+            active_window: None,
+            input_sender,
+            scale_factor,
+            clipboard: None,
+            primary: None,
+        })))
+    }
+}
+
+impl LinuxClient for HeadlessClient {
+    fn with_common<R>(&self, f: impl FnOnce(&mut LinuxCommon) -> R) -> R {
+        f(&mut self.0.borrow_mut().common)
+    }
+
+    fn keyboard_layout(&self) -> String {
+        "unknown".to_string()
+    }
+
+    fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
+        vec![Rc::new(HeadlessDisplay::new())]
+    }
+
+    fn primary_display(&self) -> Option<Rc<dyn PlatformDisplay>> {
+        Some(Rc::new(HeadlessDisplay::new()))
+    }
+
+    fn display(&self, id: DisplayId) -> Option<Rc<dyn PlatformDisplay>> {
+        let display = HeadlessDisplay::new();
+        (display.id == id).then(|| Rc::new(display) as Rc<dyn PlatformDisplay>)
+    }
+
+    fn active_window(&self) -> Option<AnyWindowHandle> {
+        self.0
+            .borrow()
+            .active_window
+            .as_ref()
+            .map(|window| window.handle())
+    }
+
+    fn window_stack(&self) -> Option<Vec<AnyWindowHandle>> {
+        Some(
+            self.0
+                .borrow()
+                .windows
+                .iter()
+                .rev()
+                .map(|window| window.handle())
+                .collect(),
+        )
+    }
+
+    fn listen_for_other_instances(&self, listener: SingleInstanceListener) {
+        let loop_handle = self.0.borrow().loop_handle.clone();
+        listen_for_forwarded_launches(&loop_handle, listener, |client, launch| {
+            let client = client.get_client();
+            LinuxCommon::open_urls(|f| f(&mut client.borrow_mut().common), launch.urls());
+        });
+    }
+
+    fn open_window(
+        &self,
+        handle: AnyWindowHandle,
+        params: WindowParams,
+    ) -> anyhow::Result<Box<dyn PlatformWindow>> {
+        let mut state = self.0.borrow_mut();
+        let focus = params.focus;
+
+        let window = HeadlessWindow::new(
+            handle,
+            HeadlessClientStatePtr(Rc::downgrade(&self.0)),
+            params,
+            state.scale_factor,
+            state.common.appearance,
+            state.input_sender.clone(),
+            state.common.foreground_executor.clone(),
+        );
+        state.windows.push(window.0.clone());
+        if focus {
+            // Activate the window once its callbacks are registered, like a compositor would
+            // after mapping it.
+            let client = HeadlessClientStatePtr(Rc::downgrade(&self.0));
+            let window = window.0.clone();
+            state
+                .common
+                .foreground_executor
+                .spawn(async move { client.activate_window(&window) })
+                .detach();
+        }
+
+        Ok(Box::new(window))
+    }
+
+    fn compositor_name(&self) -> &'static str {
+        "headless"
+    }
+
+    fn set_cursor_style(&self, _style: CursorStyle) {}
+
+    fn open_uri(&self, _uri: &str) {}
+
+    fn reveal_path(&self, _path: PathBuf) {}
+
+    fn write_to_primary(&self, item: ClipboardItem) {
+        self.0.borrow_mut().primary = Some(item);
+    }
+
+    fn write_to_clipboard(&self, item: ClipboardItem) {
+        self.0.borrow_mut().clipboard = Some(item);
+    }
+
+    fn read_from_primary(&self) -> Option<ClipboardItem> {
+        self.0.borrow().primary.clone()
+    }
+
+    fn read_from_clipboard(&self) -> Option<ClipboardItem> {
+        self.0.borrow().clipboard.clone()
+    }
+
+    fn run(&self) {
+        let mut event_loop = self
+            .0
+            .borrow_mut()
+            .event_loop
+            .take()
+            .expect("App is already running");
+
+        event_loop
+            .run(
+                None,
+                &mut HeadlessClientStatePtr(Rc::downgrade(&self.0)),
+                |_| {},
+            )
+            .log_err();
+    }
+}
+
+#[cfg(test)]
+impl HeadlessClient {
+    /// Creates an app whose windows are opened by this client.
+    pub(crate) fn new_app(&self) -> Rc<AppCell> {
+        App::new_app(
+            Rc::new(self.clone()),
+            Arc::new(()),
+            Arc::new(NullHttpClient),
+        )
+    }
+
+    /// Dispatches events until `condition` holds, failing after a few seconds.
+    #[track_caller]
+    pub(crate) fn run_until(&self, mut condition: impl FnMut() -> bool) {
+        let mut event_loop = self
+            .0
+            .borrow_mut()
+            .event_loop
+            .take()
+            .expect("App is already running");
+        let mut client = HeadlessClientStatePtr(Rc::downgrade(&self.0));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out dispatching events");
+            event_loop
+                .dispatch(Some(FRAME_INTERVAL), &mut client)
+                .unwrap();
+        }
+        self.0.borrow_mut().event_loop = Some(event_loop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{
+        div, point, px, size, AppContext as _, Bounds, Context, InteractiveElement as _,
+        IntoElement, Modifiers, MouseButton, MouseDownEvent, MouseUpEvent, Render, Styled as _,
+        Window, WindowBounds, WindowOptions,
+    };
+
+    struct TestView {
+        events: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl Render for TestView {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            let down_events = self.events.clone();
+            let up_events = self.events.clone();
+            div()
+                .size_full()
+                .on_mouse_down(MouseButton::Left, move |_, _, _| {
+                    down_events.borrow_mut().push("down")
+                })
+                .on_mouse_up(MouseButton::Left, move |_, _, _| {
+                    up_events.borrow_mut().push("up")
+                })
+        }
+    }
+
+    fn open_window(app: &Rc<AppCell>, events: Rc<RefCell<Vec<&'static str>>>) -> AnyWindowHandle {
+        let options = WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(Bounds {
+                origin: point(px(10.), px(20.)),
+                size: size(px(200.), px(100.)),
+            })),
+            ..Default::default()
+        };
+        app.borrow_mut()
+            .open_window(options, |_, cx| cx.new(|_| TestView { events }))
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn test_open_window() {
+        let client = HeadlessClient::with_scale_factor(2.);
+        let app = client.new_app();
+        let window = open_window(&app, Rc::default());
+
+        assert_eq!(client.window_stack(), Some(vec![window]));
+        window
+            .update(&mut *app.borrow_mut(), |_, window, _| {
+                assert_eq!(window.viewport_size(), size(px(200.), px(100.)));
+                assert_eq!(window.scale_factor(), 2.);
+            })
+            .unwrap();
+
+        // The window is activated once its callbacks are registered.
+        client.run_until(|| client.active_window() == Some(window));
+        window
+            .update(&mut *app.borrow_mut(), |_, window, _| {
+                assert!(window.is_window_active())
+            })
+            .unwrap();
+
+        app.borrow_mut().shutdown();
+    }
+
+    #[test]
+    fn test_inject_input() {
+        let client = HeadlessClient::with_scale_factor(1.);
+        let app = client.new_app();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let window = open_window(&app, events.clone());
+
+        let position = point(px(50.), px(50.));
+        window
+            .update(&mut *app.borrow_mut(), |_, window, cx| {
+                // Draw the hitboxes the events are dispatched to.
+                window.draw(cx);
+                window.inject_input(PlatformInput::MouseDown(MouseDownEvent {
+                    button: MouseButton::Left,
+                    position,
+                    modifiers: Modifiers::default(),
+                    click_count: 1,
+                    first_mouse: false,
+                }));
+                window.inject_input(PlatformInput::MouseUp(MouseUpEvent {
+                    button: MouseButton::Left,
+                    position,
+                    modifiers: Modifiers::default(),
+                    click_count: 1,
+                }));
+            })
+            .unwrap();
+        assert!(events.borrow().is_empty());
+
+        client.run_until(|| events.borrow().len() == 2);
+        assert_eq!(*events.borrow(), ["down", "up"]);
+
+        app.borrow_mut().shutdown();
+    }
+
+    #[test]
+    fn test_request_frame_timer() {
+        let client = HeadlessClient::with_scale_factor(1.);
+        let app = client.new_app();
+        let window = open_window(&app, Rc::default());
+
+        let frames = Rc::new(RefCell::new(Vec::new()));
+        for _ in 0..2 {
+            let frame_count = frames.borrow().len();
+            let next_frames = frames.clone();
+            window
+                .update(&mut *app.borrow_mut(), |_, window, _| {
+                    window.on_next_frame(move |_, _| next_frames.borrow_mut().push(Instant::now()))
+                })
+                .unwrap();
+            client.run_until(|| frames.borrow().len() > frame_count);
+        }
+
+        let frames = frames.borrow();
+        let interval = frames[1] - frames[0];
+        assert!(
+            interval >= FRAME_INTERVAL - Duration::from_millis(1),
+            "frames were requested {interval:?} apart"
+        );
+
+        app.borrow_mut().shutdown();
+    }
+
+    #[test]
+    fn test_zoom_resizes_window() {
+        let client = HeadlessClient::with_scale_factor(1.);
+        let app = client.new_app();
+        let window = open_window(&app, Rc::default());
+        let viewport_size = || {
+            window
+                .update(&mut *app.borrow_mut(), |_, window, _| {
+                    window.viewport_size()
+                })
+                .unwrap()
+        };
+
+        window
+            .update(&mut *app.borrow_mut(), |_, window, _| window.zoom_window())
+            .unwrap();
+        client.run_until(|| viewport_size() == HeadlessDisplay::new().bounds.size);
+
+        window
+            .update(&mut *app.borrow_mut(), |_, window, _| window.zoom_window())
+            .unwrap();
+        client.run_until(|| viewport_size() == size(px(200.), px(100.)));
+
+        app.borrow_mut().shutdown();
+    }
+
+    #[test]
+    fn test_close_request() {
+        let client = HeadlessClient::with_scale_factor(1.);
+        let app = client.new_app();
+        let window = open_window(&app, Rc::default());
+
+        let close_requests = Rc::new(Cell::new(0));
+        let allow_close = Rc::new(Cell::new(false));
+        window
+            .update(&mut *app.borrow_mut(), |_, window, cx| {
+                let close_requests = close_requests.clone();
+                let allow_close = allow_close.clone();
+                window.on_window_should_close(cx, move |_, _| {
+                    close_requests.set(close_requests.get() + 1);
+                    allow_close.get()
+                });
+                window.inject_close_request();
+            })
+            .unwrap();
+        client.run_until(|| close_requests.get() == 1);
+        assert_eq!(app.borrow().windows(), vec![window]);
+
+        allow_close.set(true);
+        window
+            .update(&mut *app.borrow_mut(), |_, window, _| {
+                window.inject_close_request()
+            })
+            .unwrap();
+        client.run_until(|| app.borrow().windows().is_empty());
+        assert_eq!(close_requests.get(), 2);
+
+        app.borrow_mut().shutdown();
+    }
+}
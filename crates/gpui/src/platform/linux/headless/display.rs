@@ -0,0 +1,38 @@
+use uuid::Uuid;
+
+use crate::{point, px, size, Bounds, DisplayId, Pixels, PlatformDisplay};
+
+/// The size of the virtual display that offscreen windows are placed on.
+const HEADLESS_DISPLAY_SIZE: (f32, f32) = (1920., 1080.);
+
+#[derive(Debug, Clone)]
+pub(crate) struct HeadlessDisplay {
+    pub id: DisplayId,
+    pub bounds: Bounds<Pixels>,
+}
+
+impl HeadlessDisplay {
+    pub fn new() -> Self {
+        Self {
+            id: DisplayId(1),
+            bounds: Bounds {
+                origin: point(px(0.), px(0.)),
+                size: size(px(HEADLESS_DISPLAY_SIZE.0), px(HEADLESS_DISPLAY_SIZE.1)),
+            },
+        }
+    }
+}
+
+impl PlatformDisplay for HeadlessDisplay {
+    fn id(&self) -> DisplayId {
+        self.id
+    }
+
+    fn uuid(&self) -> anyhow::Result<Uuid> {
+        Ok(Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"headless"))
+    }
+
+    fn bounds(&self) -> Bounds<Pixels> {
+        self.bounds
+    }
+}
@@ -0,0 +1,589 @@
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    rc::Rc,
+    sync::Arc,
+};
+
+use calloop::channel::Sender;
+use futures::channel::oneshot::Receiver;
+use raw_window_handle as rwh;
+
+use crate::platform::{PlatformAtlas, PlatformInputHandler, PlatformWindow};
+use crate::scene::{Damage, Scene};
+use crate::{
+    AnyWindowHandle, Bounds, DispatchEventResult, ExternalDrag, ExternalDragEvent,
+    ForegroundExecutor, GpuSpecs, Modifiers, Pixels, PlatformDisplay, PlatformInput, Point,
+    PromptLevel, RequestFrameOptions, ScaledPixels, Size, WindowAppearance,
+    WindowBackgroundAppearance, WindowBounds, WindowId, WindowParams,
+};
+
+use super::{HeadlessClientStatePtr, HeadlessDisplay};
+
+#[derive(Default)]
+pub(crate) struct Callbacks {
+    request_frame: Option<Box<dyn FnMut(RequestFrameOptions)>>,
+    input: Option<Box<dyn FnMut(PlatformInput) -> DispatchEventResult>>,
+    active_status_change: Option<Box<dyn FnMut(bool)>>,
+    hover_status_change: Option<Box<dyn FnMut(bool)>>,
+    resize: Option<Box<dyn FnMut(Size<Pixels>, f32)>>,
+    moved: Option<Box<dyn FnMut()>>,
+    should_close: Option<Box<dyn FnMut() -> bool>>,
+    close: Option<Box<dyn FnOnce()>>,
+    appearance_changed: Option<Box<dyn FnMut()>>,
+    keyboard_shortcuts_inhibit_change: Option<Box<dyn FnMut(bool)>>,
+}
+
+pub struct HeadlessWindowState {
+    handle: AnyWindowHandle,
+    bounds: Bounds<Pixels>,
+    /// The bounds to restore when the window leaves the maximized or fullscreen state.
+    windowed_bounds: Bounds<Pixels>,
+    scale: f32,
+    appearance: WindowAppearance,
+    background_appearance: WindowBackgroundAppearance,
+    active: bool,
+    hovered: bool,
+    fullscreen: bool,
+    maximized: bool,
+    mouse_position: Point<Pixels>,
+    modifiers: Modifiers,
+    input_handler: Option<PlatformInputHandler>,
+    renderer: HeadlessRenderer,
+    input_sender: Sender<(WindowId, PlatformInput)>,
+    executor: ForegroundExecutor,
+    client: HeadlessClientStatePtr,
+}
+
+#[derive(Clone)]
+pub(crate) struct HeadlessWindowStatePtr {
+    state: Rc<RefCell<HeadlessWindowState>>,
+    callbacks: Rc<RefCell<Callbacks>>,
+}
+
+/// A window that is never shown on screen. Frames are requested on a timer by the
+/// [`HeadlessClient`](super::HeadlessClient) and, with the `software-renderer` feature,
+/// rasterized into an in-memory buffer.
+pub(crate) struct HeadlessWindow(pub HeadlessWindowStatePtr);
+
+impl Drop for HeadlessWindow {
+    fn drop(&mut self) {
+        let mut state = self.0.state.borrow_mut();
+        let window_id = state.handle.window_id();
+        let client = state.client.clone();
+
+        state.renderer.destroy();
+
+        let state_ptr = self.0.clone();
+        state
+            .executor
+            .spawn(async move {
+                state_ptr.closed();
+                client.drop_window(window_id)
+            })
+            .detach();
+        drop(state);
+    }
+}
+
+impl HeadlessWindow {
+    fn borrow(&self) -> Ref<HeadlessWindowState> {
+        self.0.state.borrow()
+    }
+
+    fn borrow_mut(&self) -> RefMut<HeadlessWindowState> {
+        self.0.state.borrow_mut()
+    }
+
+    pub fn new(
+        handle: AnyWindowHandle,
+        client: HeadlessClientStatePtr,
+        params: WindowParams,
+        scale: f32,
+        appearance: WindowAppearance,
+        input_sender: Sender<(WindowId, PlatformInput)>,
+        executor: ForegroundExecutor,
+    ) -> Self {
+        let renderer = HeadlessRenderer::new(
+            params.bounds.to_device_pixels(scale).size,
+            // Matches the default background appearance below.
+            false,
+        );
+
+        let state = HeadlessWindowState {
+            handle,
+            bounds: params.bounds,
+            windowed_bounds: params.bounds,
+            scale,
+            appearance,
+            background_appearance: WindowBackgroundAppearance::Opaque,
+            // Focused windows are activated by the client once they're open.
+            active: false,
+            hovered: false,
+            fullscreen: false,
+            maximized: false,
+            mouse_position: Point::default(),
+            modifiers: Modifiers::default(),
+            input_handler: None,
+            renderer,
+            input_sender,
+            executor,
+            client,
+        };
+
+        Self(HeadlessWindowStatePtr {
+            state: Rc::new(RefCell::new(state)),
+            callbacks: Rc::new(RefCell::new(Callbacks::default())),
+        })
+    }
+}
+
+impl HeadlessWindowStatePtr {
+    pub fn handle(&self) -> AnyWindowHandle {
+        self.state.borrow().handle
+    }
+
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+
+    pub fn frame(&self) {
+        let mut cb = self.callbacks.borrow_mut();
+        if let Some(fun) = cb.request_frame.as_mut() {
+            fun(Default::default());
+        }
+    }
+
+    /// Closes the window as if the user had clicked its close button, unless the
+    /// `should_close` callback keeps it open.
+    pub fn close(&self) {
+        let should_close = self.callbacks.borrow_mut().should_close.take();
+        if let Some(mut should_close) = should_close {
+            let close = should_close();
+            self.callbacks.borrow_mut().should_close = Some(should_close);
+            if !close {
+                return;
+            }
+        }
+        self.closed();
+    }
+
+    fn keyboard_shortcuts_inhibit_changed(&self, inhibited: bool) {
+        if let Some(fun) = self
+            .callbacks
+            .borrow_mut()
+            .keyboard_shortcuts_inhibit_change
+            .as_mut()
+        {
+            fun(inhibited);
+        }
+    }
+
+    fn closed(&self) {
+        let close = self.callbacks.borrow_mut().close.take();
+        if let Some(fun) = close {
+            fun()
+        }
+    }
+
+    /// Moves and resizes the window, as a compositor would when it's maximized or made
+    /// fullscreen.
+    pub fn set_bounds(&self, bounds: Bounds<Pixels>) {
+        let (moved, resized, scale) = {
+            let mut state = self.state.borrow_mut();
+            let moved = state.bounds.origin != bounds.origin;
+            let resized = state.bounds.size != bounds.size;
+            state.bounds = bounds;
+            let scale = state.scale;
+            if resized {
+                state
+                    .renderer
+                    .update_drawable_size(bounds.to_device_pixels(scale).size);
+            }
+            (moved, resized, scale)
+        };
+
+        let mut callbacks = self.callbacks.borrow_mut();
+        if resized {
+            if let Some(fun) = callbacks.resize.as_mut() {
+                fun(bounds.size, scale);
+            }
+        }
+        if moved {
+            if let Some(fun) = callbacks.moved.as_mut() {
+                fun();
+            }
+        }
+    }
+
+    /// Applies the bounds of the maximized or fullscreen state on a later turn of the event
+    /// loop, after the window's callbacks have returned.
+    fn update_bounds(&self) {
+        let bounds = {
+            let state = self.state.borrow();
+            if state.fullscreen || state.maximized {
+                HeadlessDisplay::new().bounds
+            } else {
+                state.windowed_bounds
+            }
+        };
+        let this = self.clone();
+        self.state
+            .borrow()
+            .executor
+            .spawn(async move { this.set_bounds(bounds) })
+            .detach();
+    }
+
+    pub fn handle_input(&self, input: PlatformInput) {
+        let hovered = {
+            let mut state = self.state.borrow_mut();
+            let was_hovered = state.hovered;
+            match &input {
+                PlatformInput::MouseMove(event) => {
+                    state.hovered = true;
+                    state.mouse_position = event.position;
+                    state.modifiers = event.modifiers;
+                }
+                PlatformInput::MouseDown(event) => {
+                    state.mouse_position = event.position;
+                    state.modifiers = event.modifiers;
+                }
+                PlatformInput::MouseUp(event) => {
+                    state.mouse_position = event.position;
+                    state.modifiers = event.modifiers;
+                }
+                PlatformInput::ScrollWheel(event) => {
+                    state.mouse_position = event.position;
+                    state.modifiers = event.modifiers;
+                }
+                PlatformInput::Touch(event) => {
+                    state.mouse_position = event.position;
+                    state.modifiers = event.modifiers;
+                }
+                PlatformInput::Stylus(event) => {
+                    state.mouse_position = event.position;
+                    state.modifiers = event.modifiers;
+                }
+                PlatformInput::ModifiersChanged(event) => {
+                    state.modifiers = event.modifiers;
+                }
+                PlatformInput::MouseExited(event) => {
+                    state.hovered = false;
+                    state.mouse_position = event.position;
+                    state.modifiers = event.modifiers;
+                }
+                PlatformInput::KeyDown(event) => {
+                    state.modifiers = event.keystroke.modifiers;
+                }
+                _ => {}
+            }
+            (state.hovered != was_hovered).then_some(state.hovered)
+        };
+        if let Some(hovered) = hovered {
+            if let Some(ref mut fun) = self.callbacks.borrow_mut().hover_status_change {
+                fun(hovered);
+            }
+        }
+
+        if let Some(ref mut fun) = self.callbacks.borrow_mut().input {
+            if !fun(input.clone()).propagate {
+                return;
+            }
+        }
+        if let PlatformInput::KeyDown(event) = input {
+            if let Some(key_char) = &event.keystroke.key_char {
+                let mut state = self.state.borrow_mut();
+                if let Some(mut input_handler) = state.input_handler.take() {
+                    drop(state);
+                    input_handler.replace_text_in_range(None, key_char);
+                    self.state.borrow_mut().input_handler = Some(input_handler);
+                }
+            }
+        }
+    }
+
+    pub fn set_focused(&self, focus: bool) {
+        {
+            let mut state = self.state.borrow_mut();
+            if state.active == focus {
+                return;
+            }
+            state.active = focus;
+        }
+        if let Some(ref mut fun) = self.callbacks.borrow_mut().active_status_change {
+            fun(focus);
+        }
+    }
+}
+
+impl rwh::HasWindowHandle for HeadlessWindow {
+    fn window_handle(&self) -> Result<rwh::WindowHandle<'_>, rwh::HandleError> {
+        Err(rwh::HandleError::Unavailable)
+    }
+}
+impl rwh::HasDisplayHandle for HeadlessWindow {
+    fn display_handle(&self) -> Result<rwh::DisplayHandle<'_>, rwh::HandleError> {
+        Err(rwh::HandleError::Unavailable)
+    }
+}
+
+impl PlatformWindow for HeadlessWindow {
+    fn bounds(&self) -> Bounds<Pixels> {
+        self.borrow().bounds
+    }
+
+    fn is_maximized(&self) -> bool {
+        self.borrow().maximized
+    }
+
+    fn window_bounds(&self) -> WindowBounds {
+        let state = self.borrow();
+        if state.fullscreen {
+            WindowBounds::Fullscreen(state.windowed_bounds)
+        } else if state.maximized {
+            WindowBounds::Maximized(state.windowed_bounds)
+        } else {
+            WindowBounds::Windowed(state.bounds)
+        }
+    }
+
+    fn content_size(&self) -> Size<Pixels> {
+        self.borrow().bounds.size
+    }
+
+    fn scale_factor(&self) -> f32 {
+        self.borrow().scale
+    }
+
+    fn appearance(&self) -> WindowAppearance {
+        self.borrow().appearance
+    }
+
+    fn display(&self) -> Option<Rc<dyn PlatformDisplay>> {
+        Some(Rc::new(HeadlessDisplay::new()))
+    }
+
+    fn mouse_position(&self) -> Point<Pixels> {
+        self.borrow().mouse_position
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        self.borrow().modifiers
+    }
+
+    fn set_input_handler(&mut self, input_handler: PlatformInputHandler) {
+        self.borrow_mut().input_handler = Some(input_handler);
+    }
+
+    fn take_input_handler(&mut self) -> Option<PlatformInputHandler> {
+        self.borrow_mut().input_handler.take()
+    }
+
+    fn prompt(
+        &self,
+        _level: PromptLevel,
+        _msg: &str,
+        _detail: Option<&str>,
+        _answers: &[&str],
+    ) -> Option<Receiver<usize>> {
+        None
+    }
+
+    fn activate(&self) {
+        let client = self.borrow().client.clone();
+        client.activate_window(&self.0);
+    }
+
+    fn is_active(&self) -> bool {
+        self.borrow().active
+    }
+
+    fn is_hovered(&self) -> bool {
+        self.borrow().hovered
+    }
+
+    fn set_title(&mut self, _title: &str) {}
+
+    fn set_background_appearance(&self, background_appearance: WindowBackgroundAppearance) {
+        let mut state = self.borrow_mut();
+        state.background_appearance = background_appearance;
+        state
+            .renderer
+            .update_transparency(background_appearance != WindowBackgroundAppearance::Opaque);
+    }
+
+    fn minimize(&self) {}
+
+    fn zoom(&self) {
+        {
+            let mut state = self.borrow_mut();
+            if !state.fullscreen && !state.maximized {
+                state.windowed_bounds = state.bounds;
+            }
+            state.maximized = !state.maximized;
+        }
+        self.0.update_bounds();
+    }
+
+    fn toggle_fullscreen(&self) {
+        {
+            let mut state = self.borrow_mut();
+            if !state.fullscreen && !state.maximized {
+                state.windowed_bounds = state.bounds;
+            }
+            state.fullscreen = !state.fullscreen;
+        }
+        self.0.update_bounds();
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.borrow().fullscreen
+    }
+
+    fn on_request_frame(&self, callback: Box<dyn FnMut(RequestFrameOptions)>) {
+        self.0.callbacks.borrow_mut().request_frame = Some(callback);
+    }
+
+    fn on_input(&self, callback: Box<dyn FnMut(PlatformInput) -> DispatchEventResult>) {
+        self.0.callbacks.borrow_mut().input = Some(callback);
+    }
+
+    fn on_active_status_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0.callbacks.borrow_mut().active_status_change = Some(callback);
+    }
+
+    fn on_hover_status_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0.callbacks.borrow_mut().hover_status_change = Some(callback);
+    }
+
+    fn on_resize(&self, callback: Box<dyn FnMut(Size<Pixels>, f32)>) {
+        self.0.callbacks.borrow_mut().resize = Some(callback);
+    }
+
+    fn on_moved(&self, callback: Box<dyn FnMut()>) {
+        self.0.callbacks.borrow_mut().moved = Some(callback);
+    }
+
+    fn on_should_close(&self, callback: Box<dyn FnMut() -> bool>) {
+        self.0.callbacks.borrow_mut().should_close = Some(callback);
+    }
+
+    fn on_close(&self, callback: Box<dyn FnOnce()>) {
+        self.0.callbacks.borrow_mut().close = Some(callback);
+    }
+
+    fn on_appearance_changed(&self, callback: Box<dyn FnMut()>) {
+        self.0.callbacks.borrow_mut().appearance_changed = Some(callback);
+    }
+
+    fn set_keyboard_shortcuts_inhibited(&self, inhibited: bool) {
+        // There's no compositor to ask, so the request is granted right away.
+        let state_ptr = self.0.clone();
+        self.borrow()
+            .executor
+            .spawn(async move { state_ptr.keyboard_shortcuts_inhibit_changed(inhibited) })
+            .detach();
+    }
+
+    fn on_keyboard_shortcuts_inhibit_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0
+            .callbacks
+            .borrow_mut()
+            .keyboard_shortcuts_inhibit_change = Some(callback);
+    }
+
+    fn draw(&self, scene: &Scene, damage: &Damage) {
+        self.borrow_mut().renderer.draw(scene, damage);
+    }
+
+    fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
+        let state = self.borrow();
+        state.renderer.sprite_atlas().clone()
+    }
+
+    fn inject_input(&self, input: PlatformInput) {
+        let state = self.borrow();
+        state
+            .input_sender
+            .send((state.handle.window_id(), input))
+            .ok();
+    }
+
+    fn inject_close_request(&self) {
+        let state_ptr = self.0.clone();
+        self.borrow()
+            .executor
+            .spawn(async move { state_ptr.close() })
+            .detach();
+    }
+
+    fn start_drag(
+        &self,
+        _drag: ExternalDrag,
+        _icon: Option<AnyWindowHandle>,
+        mut on_event: Box<dyn FnMut(ExternalDragEvent)>,
+    ) -> anyhow::Result<()> {
+        // There are no other applications to drop onto, so the drag ends right away.
+        self.borrow()
+            .executor
+            .spawn(async move { on_event(ExternalDragEvent::Cancelled) })
+            .detach();
+        Ok(())
+    }
+
+    #[cfg(feature = "software-renderer")]
+    fn read_frame(&self) -> Option<image::RgbaImage> {
+        Some(self.borrow().renderer.read_image())
+    }
+
+    fn update_ime_position(&self, _bounds: Bounds<ScaledPixels>) {}
+
+    fn gpu_specs(&self) -> Option<GpuSpecs> {
+        self.borrow().renderer.gpu_specs().into()
+    }
+}
+
+#[cfg(feature = "software-renderer")]
+type HeadlessRenderer = crate::platform::SoftwareRenderer;
+#[cfg(not(feature = "software-renderer"))]
+type HeadlessRenderer = NoopRenderer;
+
+/// Stands in for the [`SoftwareRenderer`](crate::platform::SoftwareRenderer) when the
+/// `software-renderer` feature is disabled. Scenes are still built, so layout and text
+/// shaping run as usual, but nothing is rasterized.
+#[cfg(not(feature = "software-renderer"))]
+pub(crate) struct NoopRenderer {
+    atlas: Arc<crate::platform::SoftwareAtlas>,
+}
+
+#[cfg(not(feature = "software-renderer"))]
+impl NoopRenderer {
+    fn new(_size: Size<crate::DevicePixels>, _transparent: bool) -> Self {
+        Self {
+            atlas: Arc::new(crate::platform::SoftwareAtlas::new()),
+        }
+    }
+
+    fn sprite_atlas(&self) -> &Arc<crate::platform::SoftwareAtlas> {
+        &self.atlas
+    }
+
+    fn update_drawable_size(&mut self, _size: Size<crate::DevicePixels>) {}
+
+    fn update_transparency(&mut self, _transparent: bool) {}
+
+    fn draw(&mut self, _scene: &Scene, _damage: &Damage) {}
+
+    fn gpu_specs(&self) -> GpuSpecs {
+        GpuSpecs {
+            is_software_emulated: true,
+            device_name: "none".to_string(),
+            driver_name: "gpui headless".to_string(),
+            driver_info: String::new(),
+        }
+    }
+
+    fn destroy(&mut self) {
+        self.atlas.clear();
+    }
+}
@@ -0,0 +1,319 @@
+//! Shows desktop notifications through the XDG Desktop Portal, or through the notification
+//! server when the portal isn't available, as is common outside of sandboxes.
+//!
+//! See the [Desktop Notifications](https://specifications.freedesktop.org/notification-spec/latest/)
+//! specification for the latter.
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    future::Future,
+    pin::pin,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::Result;
+use ashpd::{
+    desktop::{
+        notification::{self as portal, NotificationProxy, Priority},
+        Icon,
+    },
+    zbus::{self, zvariant::Value},
+};
+use futures::{channel::oneshot, future, StreamExt as _};
+
+use super::application_name;
+use crate::{Notification, NotificationResponse, NotificationUrgency};
+
+const DEFAULT_ACTION: &str = "default";
+const BUTTON_ACTION_PREFIX: &str = "button-";
+
+/// Relays whether a notification could be shown, until it's taken by the first attempt that
+/// shows it.
+type Shown = Cell<Option<oneshot::Sender<Result<()>>>>;
+
+/// Shows `notification`, relaying through `shown` whether it could be shown, and resolves with
+/// the user's response, if any.
+pub(super) async fn show_notification(
+    notification: &Notification,
+    shown: oneshot::Sender<Result<()>>,
+) -> Option<NotificationResponse> {
+    let shown = Cell::new(Some(shown));
+    show_with_fallback(
+        &shown,
+        show_with_portal(notification, &shown),
+        show_with_notification_server(notification, &shown),
+    )
+    .await
+}
+
+/// Awaits `portal`, falling back to `notification_server` if the portal failed before showing
+/// the notification. The fallback isn't polled otherwise.
+async fn show_with_fallback(
+    shown: &Shown,
+    portal: impl Future<Output = Result<Option<NotificationResponse>>>,
+    notification_server: impl Future<Output = Result<Option<NotificationResponse>>>,
+) -> Option<NotificationResponse> {
+    let result = match portal.await {
+        Ok(response) => Ok(response),
+        Err(error) => match shown.take() {
+            Some(sender) => {
+                shown.set(Some(sender));
+                log::info!("showing the notification without the portal: {error}");
+                notification_server.await
+            }
+            // Showing it again would duplicate the notification.
+            None => Err(error),
+        },
+    };
+    match result {
+        Ok(response) => response,
+        Err(error) => {
+            if let Some(shown) = shown.take() {
+                shown.send(Err(error)).ok();
+            } else {
+                log::error!("failed to receive the response to a notification: {error:?}");
+            }
+            None
+        }
+    }
+}
+
+async fn show_with_portal(
+    notification: &Notification,
+    shown: &Shown,
+) -> Result<Option<NotificationResponse>> {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    let id = format!("gpui-{}", NEXT_ID.fetch_add(1, Ordering::SeqCst));
+
+    let mut request = portal::Notification::new(notification.title.as_ref()).priority(
+        match notification.urgency {
+            NotificationUrgency::Low => Priority::Low,
+            NotificationUrgency::Normal => Priority::Normal,
+            NotificationUrgency::Critical => Priority::Urgent,
+        },
+    );
+    if let Some(body) = &notification.body {
+        request = request.body(body.as_ref());
+    }
+    if let Some(icon) = &notification.icon {
+        request = request.icon(Icon::with_names([icon.as_ref()]));
+    }
+    if notification.default_action.is_some() {
+        request = request.default_action(DEFAULT_ACTION);
+    }
+    for (ix, button) in notification.buttons.iter().enumerate() {
+        request = request.button(portal::Button::new(
+            button.label.as_ref(),
+            &format!("{BUTTON_ACTION_PREFIX}{ix}"),
+        ));
+    }
+
+    let proxy = NotificationProxy::new().await?;
+    // Listen before showing the notification, so that no response can be missed.
+    let mut actions = pin!(proxy.receive_action_invoked().await?);
+    proxy.add_notification(&id, request).await?;
+    notify_shown(shown);
+
+    while let Some(action) = actions.next().await {
+        if action.id() == id {
+            return Ok(parse_response(action.name()));
+        }
+    }
+    Ok(None)
+}
+
+async fn show_with_notification_server(
+    notification: &Notification,
+    shown: &Shown,
+) -> Result<Option<NotificationResponse>> {
+    enum Signal {
+        ActionInvoked(u32, String),
+        NotificationClosed(u32),
+    }
+
+    let connection = zbus::Connection::session().await?;
+    let proxy = zbus::Proxy::new(
+        &connection,
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+    )
+    .await?;
+    let action_invoked = proxy
+        .receive_signal("ActionInvoked")
+        .await?
+        .filter_map(|message| {
+            let body = message.body();
+            let signal = body
+                .deserialize::<(u32, String)>()
+                .ok()
+                .map(|(id, action)| Signal::ActionInvoked(id, action));
+            future::ready(signal)
+        });
+    let notification_closed = proxy
+        .receive_signal("NotificationClosed")
+        .await?
+        .filter_map(|message| {
+            let body = message.body();
+            let signal = body
+                .deserialize::<(u32, u32)>()
+                .ok()
+                .map(|(id, _reason)| Signal::NotificationClosed(id));
+            future::ready(signal)
+        });
+    let mut signals = pin!(futures::stream::select(action_invoked, notification_closed));
+
+    // Actions are listed as pairs of identifiers and labels.
+    let mut actions = Vec::new();
+    if notification.default_action.is_some() {
+        actions.push(DEFAULT_ACTION.to_string());
+        actions.push(String::new());
+    }
+    for (ix, button) in notification.buttons.iter().enumerate() {
+        actions.push(format!("{BUTTON_ACTION_PREFIX}{ix}"));
+        actions.push(button.label.to_string());
+    }
+    let urgency: u8 = match notification.urgency {
+        NotificationUrgency::Low => 0,
+        NotificationUrgency::Normal => 1,
+        NotificationUrgency::Critical => 2,
+    };
+    let hints = HashMap::from([("urgency", Value::from(urgency))]);
+    let id: u32 = proxy
+        .call(
+            "Notify",
+            &(
+                application_name(),
+                0u32,
+                notification.icon.as_ref().map_or("", |icon| icon.as_ref()),
+                notification.title.as_ref(),
+                notification.body.as_ref().map_or("", |body| body.as_ref()),
+                actions,
+                hints,
+                // Let the server decide when the notification expires.
+                -1i32,
+            ),
+        )
+        .await?;
+    notify_shown(shown);
+
+    while let Some(signal) = signals.next().await {
+        match signal {
+            Signal::ActionInvoked(signal_id, action) if signal_id == id => {
+                return Ok(parse_response(&action));
+            }
+            Signal::NotificationClosed(signal_id) if signal_id == id => return Ok(None),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+fn notify_shown(shown: &Shown) {
+    if let Some(shown) = shown.take() {
+        shown.send(Ok(())).ok();
+    }
+}
+
+fn parse_response(action: &str) -> Option<NotificationResponse> {
+    if action == DEFAULT_ACTION {
+        return Some(NotificationResponse::Default);
+    }
+    action
+        .strip_prefix(BUTTON_ACTION_PREFIX)?
+        .parse()
+        .ok()
+        .map(NotificationResponse::Button)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_show_with_portal() {
+        let (shown_tx, mut shown_rx) = oneshot::channel();
+        let shown = Cell::new(Some(shown_tx));
+        let fell_back = Cell::new(false);
+        let response = block_on(show_with_fallback(
+            &shown,
+            async {
+                notify_shown(&shown);
+                Ok(Some(NotificationResponse::Button(1)))
+            },
+            async {
+                fell_back.set(true);
+                Ok(None)
+            },
+        ));
+        assert_eq!(response, Some(NotificationResponse::Button(1)));
+        assert!(shown_rx.try_recv().unwrap().unwrap().is_ok());
+        assert!(!fell_back.get());
+    }
+
+    #[test]
+    fn test_fall_back_to_notification_server() {
+        let (shown_tx, mut shown_rx) = oneshot::channel();
+        let shown = Cell::new(Some(shown_tx));
+        let response = block_on(show_with_fallback(
+            &shown,
+            async { Err(anyhow!("no portal")) },
+            async {
+                notify_shown(&shown);
+                Ok(Some(NotificationResponse::Default))
+            },
+        ));
+        assert_eq!(response, Some(NotificationResponse::Default));
+        assert!(shown_rx.try_recv().unwrap().unwrap().is_ok());
+
+        // When neither can show the notification, the error is relayed.
+        let (shown_tx, mut shown_rx) = oneshot::channel();
+        let shown = Cell::new(Some(shown_tx));
+        let response = block_on(show_with_fallback(
+            &shown,
+            async { Err(anyhow!("no portal")) },
+            async { Err(anyhow!("no notification server")) },
+        ));
+        assert_eq!(response, None);
+        let error = shown_rx.try_recv().unwrap().unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "no notification server");
+    }
+
+    #[test]
+    fn test_no_fallback_once_shown() {
+        let (shown_tx, mut shown_rx) = oneshot::channel();
+        let shown = Cell::new(Some(shown_tx));
+        let fell_back = Cell::new(false);
+        let response = block_on(show_with_fallback(
+            &shown,
+            async {
+                notify_shown(&shown);
+                Err(anyhow!("portal went away"))
+            },
+            async {
+                fell_back.set(true);
+                Ok(None)
+            },
+        ));
+        assert_eq!(response, None);
+        assert!(shown_rx.try_recv().unwrap().unwrap().is_ok());
+        assert!(!fell_back.get());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(
+            parse_response(DEFAULT_ACTION),
+            Some(NotificationResponse::Default)
+        );
+        assert_eq!(
+            parse_response("button-2"),
+            Some(NotificationResponse::Button(2))
+        );
+        assert_eq!(parse_response("button-"), None);
+        assert_eq!(parse_response("other"), None);
+    }
+}
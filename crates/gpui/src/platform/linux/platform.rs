@@ -1,5 +1,9 @@
 use std::{
     env,
+    os::unix::{
+        ffi::{OsStrExt as _, OsStringExt as _},
+        net::UnixStream,
+    },
     path::{Path, PathBuf},
     process::Command,
     rc::Rc,
@@ -16,17 +20,24 @@ use std::{
 
 use anyhow::{anyhow, Context as _};
 use async_task::Runnable;
-use calloop::{channel::Channel, LoopSignal};
+use calloop::{
+    channel::Channel, generic::Generic, Interest, LoopHandle, LoopSignal, Mode, PostAction,
+};
 use futures::channel::oneshot;
 use util::ResultExt as _;
 #[cfg(any(feature = "wayland", feature = "x11"))]
 use xkbcommon::xkb::{self, Keycode, Keysym, State};
 
+use super::{
+    recent_documents,
+    single_instance::{self, ForwardedLaunch, Instance, SingleInstanceListener},
+    url_scheme,
+};
 use crate::{
     px, Action, AnyWindowHandle, BackgroundExecutor, ClipboardItem, CursorStyle, DisplayId,
-    ForegroundExecutor, Keymap, LinuxDispatcher, Menu, MenuItem, OwnedMenu, PathPromptOptions,
-    Pixels, Platform, PlatformDisplay, PlatformTextSystem, PlatformWindow, Point, Result,
-    ScreenCaptureSource, Task, WindowAppearance, WindowParams,
+    ForegroundExecutor, GlobalShortcut, Keymap, LinuxDispatcher, Menu, MenuItem, Notification,
+    OwnedMenu, PathPromptOptions, Pixels, Platform, PlatformDisplay, PlatformTextSystem,
+    PlatformWindow, Point, Result, ScreenCaptureSource, Task, WindowAppearance, WindowParams,
 };
 #[cfg(any(feature = "wayland", feature = "x11"))]
 pub(crate) const SCROLL_LINES: f32 = 3.0;
@@ -65,6 +76,7 @@ pub trait LinuxClient {
     fn read_from_clipboard(&self) -> Option<ClipboardItem>;
     fn active_window(&self) -> Option<AnyWindowHandle>;
     fn window_stack(&self) -> Option<Vec<AnyWindowHandle>>;
+    fn listen_for_other_instances(&self, listener: SingleInstanceListener);
     fn run(&self);
 }
 
@@ -88,6 +100,9 @@ pub(crate) struct LinuxCommon {
     pub(crate) callbacks: PlatformHandlers,
     pub(crate) signal: LoopSignal,
     pub(crate) menus: Vec<OwnedMenu>,
+    pub(crate) single_instance_app_id: Option<String>,
+    /// Ends the session of the registered global shortcuts when dropped.
+    pub(crate) global_shortcuts: Option<oneshot::Sender<()>>,
 }
 
 impl LinuxCommon {
@@ -114,10 +129,69 @@ impl LinuxCommon {
             callbacks,
             signal,
             menus: Vec::new(),
+            single_instance_app_id: None,
+            global_shortcuts: None,
         };
 
         (common, main_receiver)
     }
+
+    /// Calls the `open_urls` callback with `urls`. The callback can use the platform, so it's
+    /// taken out of the common state, which `with_common` gives access to, while it's called.
+    pub(crate) fn open_urls(
+        with_common: impl Fn(&mut dyn FnMut(&mut LinuxCommon)),
+        urls: Vec<String>,
+    ) {
+        let mut open_urls = None;
+        with_common(&mut |common| open_urls = common.callbacks.open_urls.take());
+        if let Some(mut fun) = open_urls {
+            fun(urls);
+            let mut fun = Some(fun);
+            with_common(&mut |common| common.callbacks.open_urls = fun.take());
+        }
+    }
+}
+
+/// Calls `on_launch` on the event loop for each launch forwarded by another instance. Each
+/// connection is read as its data arrives, so that a slow launch never blocks the event loop.
+pub(crate) fn listen_for_forwarded_launches<Data: 'static>(
+    loop_handle: &LoopHandle<'static, Data>,
+    listener: SingleInstanceListener,
+    on_launch: fn(&mut Data, ForwardedLaunch),
+) {
+    let connection_handle = loop_handle.clone();
+    loop_handle
+        .insert_source(
+            Generic::new(listener, Interest::READ, Mode::Level),
+            move |_, listener, _| {
+                for connection in listener.as_ref().accept() {
+                    read_forwarded_launch(&connection_handle, connection, on_launch);
+                }
+                Ok(PostAction::Continue)
+            },
+        )
+        .log_err();
+}
+
+fn read_forwarded_launch<Data: 'static>(
+    loop_handle: &LoopHandle<'static, Data>,
+    connection: UnixStream,
+    on_launch: fn(&mut Data, ForwardedLaunch),
+) {
+    let mut buffer = Vec::new();
+    loop_handle
+        .insert_source(
+            Generic::new(connection, Interest::READ, Mode::Level),
+            move |_, connection, data| {
+                match single_instance::read_launch(connection.as_ref(), &mut buffer) {
+                    Ok(None) => return Ok(PostAction::Continue),
+                    Ok(Some(launch)) => on_launch(data, launch),
+                    Err(error) => log::error!("failed to read a forwarded launch: {error:?}"),
+                }
+                Ok(PostAction::Remove)
+            },
+        )
+        .log_err();
 }
 
 impl<P: LinuxClient + 'static> Platform for P {
@@ -142,8 +216,34 @@ impl<P: LinuxClient + 'static> Platform for P {
     }
 
     fn run(&self, on_finish_launching: Box<dyn FnOnce()>) {
+        let app_id = self.with_common(|common| common.single_instance_app_id.clone());
+        let single_instance_listener = match app_id.map(|app_id| {
+            single_instance::connect_or_listen(&app_id)
+                .with_context(|| format!("failed to check for a running instance of {app_id}"))
+        }) {
+            Some(Ok(Instance::Primary(listener))) => Some(listener),
+            Some(Ok(Instance::Secondary)) => {
+                log::info!("forwarded the launch to the running instance");
+                return;
+            }
+            Some(Err(error)) => {
+                log::error!("{error:?}");
+                None
+            }
+            None => None,
+        };
+
         on_finish_launching();
 
+        let launch_urls = url_scheme::launch_urls();
+        if !launch_urls.is_empty() {
+            LinuxCommon::open_urls(|f| self.with_common(|common| f(common)), launch_urls);
+        }
+
+        if let Some(listener) = single_instance_listener {
+            self.listen_for_other_instances(listener);
+        }
+
         LinuxClient::run(self);
 
         let quit = self.with_common(|common| common.callbacks.quit.take());
@@ -262,6 +362,10 @@ impl<P: LinuxClient + 'static> Platform for P {
         self.with_common(|common| common.callbacks.open_urls = Some(callback));
     }
 
+    fn set_single_instance(&self, app_id: &str) {
+        self.with_common(|common| common.single_instance_app_id = Some(app_id.to_string()));
+    }
+
     fn prompt_for_paths(
         &self,
         options: PathPromptOptions,
@@ -518,8 +622,10 @@ impl<P: LinuxClient + 'static> Platform for P {
         self.with_common(|common| common.appearance)
     }
 
-    fn register_url_scheme(&self, _: &str) -> Task<anyhow::Result<()>> {
-        Task::ready(Err(anyhow!("register_url_scheme unimplemented")))
+    fn register_url_scheme(&self, scheme: &str) -> Task<anyhow::Result<()>> {
+        let scheme = scheme.to_string();
+        self.background_executor()
+            .spawn(async move { url_scheme::register_url_scheme(&scheme) })
     }
 
     fn write_to_primary(&self, item: ClipboardItem) {
@@ -538,7 +644,84 @@ impl<P: LinuxClient + 'static> Platform for P {
         self.read_from_clipboard()
     }
 
-    fn add_recent_document(&self, _path: &Path) {}
+    fn add_recent_document(&self, path: &Path) {
+        let path = path.to_path_buf();
+        self.background_executor()
+            .spawn(async move {
+                recent_documents::add_recent_document(&path).log_err();
+            })
+            .detach();
+    }
+
+    fn recent_documents(&self) -> Task<Result<Vec<PathBuf>>> {
+        self.background_executor()
+            .spawn(async { recent_documents::recent_documents() })
+    }
+
+    fn show_notification(
+        &self,
+        notification: Notification,
+        on_action: Box<dyn FnOnce(&dyn Action)>,
+    ) -> oneshot::Receiver<Result<()>> {
+        let (shown_tx, shown_rx) = oneshot::channel();
+
+        #[cfg(not(any(feature = "wayland", feature = "x11")))]
+        let _ = (
+            shown_tx.send(Err(anyhow!(
+                "notifications need the wayland or x11 feature"
+            ))),
+            notification,
+            on_action,
+        );
+
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        self.foreground_executor()
+            .spawn(async move {
+                let response =
+                    super::notification::show_notification(&notification, shown_tx).await;
+                if let Some(action) =
+                    response.and_then(|response| notification.action_for(response))
+                {
+                    on_action(action);
+                }
+            })
+            .detach();
+
+        shown_rx
+    }
+
+    fn register_global_shortcuts(
+        &self,
+        shortcuts: Vec<GlobalShortcut>,
+        on_trigger: Box<dyn FnMut(&GlobalShortcut, bool)>,
+    ) -> oneshot::Receiver<Result<()>> {
+        let (bound_tx, bound_rx) = oneshot::channel();
+        let (unbind_tx, unbind_rx) = oneshot::channel();
+        // Dropping the previous sender ends the session of the shortcuts registered before.
+        self.with_common(|common| common.global_shortcuts = Some(unbind_tx));
+        if shortcuts.is_empty() {
+            bound_tx.send(Ok(())).ok();
+            return bound_rx;
+        }
+
+        #[cfg(not(any(feature = "wayland", feature = "x11")))]
+        let _ = (
+            bound_tx.send(Err(anyhow!(
+                "global shortcuts need the wayland or x11 feature"
+            ))),
+            unbind_rx,
+            on_trigger,
+        );
+
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        self.foreground_executor()
+            .spawn(super::global_shortcuts::bind_global_shortcuts(
+                shortcuts, bound_tx, unbind_rx, on_trigger,
+            ))
+            .detach();
+
+        bound_rx
+    }
 }
 
 #[cfg(any(feature = "wayland", feature = "x11"))]
@@ -602,6 +785,115 @@ pub(super) fn reveal_path_internal(
         .detach();
 }
 
+/// Keeps the session from going idle through the XDG Desktop Portal, until it's dropped.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub(super) struct PortalIdleInhibitor {
+    _release: oneshot::Sender<()>,
+}
+
+#[cfg(any(feature = "wayland", feature = "x11"))]
+impl PortalIdleInhibitor {
+    pub(super) fn new(executor: &ForegroundExecutor) -> Self {
+        let (release_tx, release_rx) = oneshot::channel();
+        executor
+            .spawn(async move {
+                let inhibit = async {
+                    let proxy = ashpd::desktop::inhibit::InhibitProxy::new().await?;
+                    let request = proxy
+                        .inhibit(
+                            None,
+                            ashpd::desktop::inhibit::InhibitFlags::Idle.into(),
+                            "Content is playing",
+                        )
+                        .await?;
+                    // Resolves once the inhibitor is dropped.
+                    release_rx.await.ok();
+                    request.close().await?;
+                    anyhow::Ok(())
+                };
+                inhibit.await.log_err();
+            })
+            .detach();
+        Self {
+            _release: release_tx,
+        }
+    }
+}
+
+/// The base directory for user data files, `$XDG_DATA_HOME` or `~/.local/share`.
+pub(super) fn xdg_data_home() -> Result<PathBuf> {
+    xdg_base_directory("XDG_DATA_HOME", ".local/share")
+}
+
+/// The base directory for user configuration files, `$XDG_CONFIG_HOME` or `~/.config`.
+pub(super) fn xdg_config_home() -> Result<PathBuf> {
+    xdg_base_directory("XDG_CONFIG_HOME", ".config")
+}
+
+/// The name this application is known by to the desktop, which is its executable's name.
+pub(super) fn application_name() -> String {
+    env::current_exe()
+        .ok()
+        .and_then(|executable| {
+            executable
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "gpui".to_string())
+}
+
+/// Formats an absolute path as a `file://` URL, percent-encoding its reserved bytes.
+pub(super) fn file_url(path: &Path) -> String {
+    let mut url = String::from("file://");
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    url
+}
+
+/// The path of a local `file://` URL.
+pub(super) fn path_from_file_url(url: &str) -> Option<PathBuf> {
+    let encoded = url
+        .strip_prefix("file://")
+        .map(|rest| rest.strip_prefix("localhost").unwrap_or(rest))
+        .filter(|path| path.starts_with('/'))?;
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+fn xdg_base_directory(variable: &str, default_in_home: &str) -> Result<PathBuf> {
+    // Relative paths are invalid and must be ignored, as per the XDG Base Directory spec.
+    if let Some(dir) = env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+    {
+        return Ok(dir);
+    }
+    let home = env::var_os("HOME").context("$HOME is not set")?;
+    Ok(PathBuf::from(home).join(default_in_home))
+}
+
 #[allow(unused)]
 pub(super) fn is_within_click_distance(a: Point<Pixels>, b: Point<Pixels>) -> bool {
     let diff = a - b;
@@ -666,6 +958,7 @@ impl CursorStyle {
             CursorStyle::DragLink => "alias",
             CursorStyle::DragCopy => "copy",
             CursorStyle::ContextualMenu => "context-menu",
+            CursorStyle::Image(_) => "left_ptr",
         }
         .to_string()
     }
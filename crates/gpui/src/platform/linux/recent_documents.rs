@@ -0,0 +1,380 @@
+//! Keeps the history of recently used files in `recently-used.xbel`, which file choosers and
+//! desktop shells show as the recent files.
+//!
+//! See the [Desktop Bookmark](https://www.freedesktop.org/wiki/Specifications/desktop-bookmark-spec/)
+//! specification. The file is edited as text, so that the entries of other applications are
+//! kept as they are.
+
+use std::{
+    env,
+    fs::{self, File},
+    io::ErrorKind,
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context as _, Result};
+
+use super::{application_name, file_url, path_from_file_url, xdg_data_home};
+
+const FILE_NAME: &str = "recently-used.xbel";
+
+/// How many times the history is re-read when it's changed by another application while it's
+/// being updated.
+const MAX_WRITE_ATTEMPTS: usize = 3;
+
+const EMPTY_XBEL: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <xbel version=\"1.0\"\n      \
+    xmlns:bookmark=\"http://www.freedesktop.org/standards/desktop-bookmarks\"\n      \
+    xmlns:mime=\"http://www.freedesktop.org/standards/shared-mime-info\"\n>\n\
+    </xbel>\n";
+
+struct Bookmark<'a> {
+    href: &'a str,
+    mime_type: &'a str,
+    application: &'a str,
+    exec: &'a str,
+    timestamp: &'a str,
+}
+
+/// Adds `path` to the recent files, or moves it to the top if it's already there.
+pub(super) fn add_recent_document(path: &Path) -> Result<()> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("failed to resolve {path:?}"))?;
+    let mime_type = if path.is_dir() {
+        "inode/directory".to_string()
+    } else {
+        mime_guess::from_path(&path)
+            .first_or_octet_stream()
+            .to_string()
+    };
+    let executable = env::current_exe()?;
+    let bookmark = Bookmark {
+        href: &file_url(&path),
+        mime_type: &mime_type,
+        application: &application_name(),
+        exec: &format!("'{} %u'", executable.display()),
+        timestamp: &timestamp(SystemTime::now()),
+    };
+
+    let data_home = xdg_data_home()?;
+    fs::create_dir_all(&data_home)?;
+    let xbel_path = data_home.join(FILE_NAME);
+    let _lock = FileLock::acquire(&data_home.join(format!("{FILE_NAME}.lock")))?;
+
+    // Write to a temporary file first, so that readers never see a partially written history.
+    // Other toolkits don't take the lock, so the history is read again right before replacing
+    // it, and the entry is added again if it changed in the meantime.
+    let temp_path = data_home.join(format!(".{FILE_NAME}.{}", std::process::id()));
+    let mut xbel = read_xbel(&xbel_path)?;
+    for _ in 0..MAX_WRITE_ATTEMPTS {
+        fs::write(&temp_path, add_bookmark(&xbel, &bookmark))
+            .with_context(|| format!("failed to write {temp_path:?}"))?;
+        let current = read_xbel(&xbel_path)?;
+        if current == xbel {
+            break;
+        }
+        xbel = current;
+    }
+    fs::rename(&temp_path, &xbel_path).with_context(|| format!("failed to write {xbel_path:?}"))
+}
+
+/// The recent files that were used with this application, most recent first.
+pub(super) fn recent_documents() -> Result<Vec<PathBuf>> {
+    let xbel = read_xbel(&xdg_data_home()?.join(FILE_NAME))?;
+    Ok(recent_documents_in(&xbel, &application_name()))
+}
+
+/// Reads the history, which is empty if it doesn't exist yet.
+fn read_xbel(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(xbel) => Ok(xbel),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(error) => Err(error).with_context(|| format!("failed to read {path:?}")),
+    }
+}
+
+/// An exclusive lock on the history, which is released when dropped.
+///
+/// This lock file is specific to gpui: GTK and other toolkits write the history without taking
+/// any lock, so it only keeps gpui applications from losing each other's entries. A write by
+/// another toolkit is only lost if it lands between the last read and the rename in
+/// [`add_recent_document`].
+struct FileLock(File);
+
+impl FileLock {
+    fn acquire(path: &Path) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("failed to create {path:?}"))?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(anyhow!(
+                "failed to lock {path:?}: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(Self(file))
+    }
+}
+
+fn add_bookmark(xbel: &str, bookmark: &Bookmark) -> String {
+    let xbel = if xbel.contains("</xbel>") {
+        xbel
+    } else {
+        EMPTY_XBEL
+    };
+    let href = escape(bookmark.href);
+    let application = escape(bookmark.application);
+
+    if let Some(range) = bookmark_range(xbel, &href) {
+        let element = &xbel[range.clone()];
+        if let Some(element) = touch_bookmark(element, &application, bookmark) {
+            return format!("{}{element}{}", &xbel[..range.start], &xbel[range.end..]);
+        }
+        // The entry is malformed, so replace it.
+        let element = new_bookmark(bookmark);
+        return format!("{}{element}{}", &xbel[..range.start], &xbel[range.end..]);
+    }
+
+    let end = xbel.rfind("</xbel>").unwrap_or(xbel.len());
+    format!(
+        "{}{}\n{}",
+        &xbel[..end],
+        new_bookmark(bookmark),
+        &xbel[end..]
+    )
+}
+
+/// Updates the timestamps of an existing entry, and adds this application to it.
+fn touch_bookmark(element: &str, application: &str, bookmark: &Bookmark) -> Option<String> {
+    let tag_end = element.find('>')? + 1;
+    let mut tag = element[..tag_end].to_string();
+    tag = set_attribute(&tag, "modified", bookmark.timestamp);
+    tag = set_attribute(&tag, "visited", bookmark.timestamp);
+    let body = &element[tag_end..];
+
+    let application_start = format!("<bookmark:application name=\"{application}\"");
+    let body = if let Some(start) = body.find(&application_start) {
+        let end = start + body[start..].find("/>")? + 2;
+        let previous = &body[start..end];
+        let count = attribute(previous, "count")
+            .and_then(|count| count.parse::<u32>().ok())
+            .unwrap_or(0);
+        let updated = set_attribute(previous, "modified", bookmark.timestamp);
+        let updated = set_attribute(&updated, "count", &(count + 1).to_string());
+        format!("{}{updated}{}", &body[..start], &body[end..])
+    } else {
+        let end = body.find("</bookmark:applications>")?;
+        format!(
+            "{}  {}\n        {}",
+            &body[..end],
+            application_element(bookmark),
+            &body[end..]
+        )
+    };
+    Some(tag + &body)
+}
+
+fn new_bookmark(bookmark: &Bookmark) -> String {
+    let timestamp = bookmark.timestamp;
+    format!(
+        "  <bookmark href=\"{}\" added=\"{timestamp}\" modified=\"{timestamp}\" visited=\"{timestamp}\">\n    \
+        <info>\n      \
+        <metadata owner=\"http://freedesktop.org\">\n        \
+        <mime:mime-type type=\"{}\"/>\n        \
+        <bookmark:applications>\n          \
+        {}\n        \
+        </bookmark:applications>\n      \
+        </metadata>\n    \
+        </info>\n  \
+        </bookmark>",
+        escape(bookmark.href),
+        escape(bookmark.mime_type),
+        application_element(bookmark),
+    )
+}
+
+fn application_element(bookmark: &Bookmark) -> String {
+    format!(
+        "<bookmark:application name=\"{}\" exec=\"{}\" modified=\"{}\" count=\"1\"/>",
+        escape(bookmark.application),
+        escape(bookmark.exec),
+        bookmark.timestamp
+    )
+}
+
+fn recent_documents_in(xbel: &str, application: &str) -> Vec<PathBuf> {
+    let application_start = format!("<bookmark:application name=\"{}\"", escape(application));
+    let mut documents = Vec::new();
+    let mut rest = xbel;
+    while let Some(start) = rest.find("<bookmark ") {
+        let Some(length) = rest[start..].find("</bookmark>") else {
+            break;
+        };
+        let element = &rest[start..start + length];
+        rest = &rest[start + length..];
+
+        let Some(href) = element
+            .find('>')
+            .and_then(|tag_end| attribute(&element[..tag_end], "href"))
+        else {
+            continue;
+        };
+        let Some(application) = element.find(&application_start).and_then(|start| {
+            let end = start + element[start..].find("/>")?;
+            Some(&element[start..end])
+        }) else {
+            continue;
+        };
+        if let Some(path) = path_from_file_url(&unescape(href)) {
+            let modified = attribute(application, "modified").unwrap_or_default();
+            documents.push((modified, path));
+        }
+    }
+    // The timestamps are all in UTC, so they sort in chronological order.
+    documents.sort_by(|(a, _), (b, _)| b.cmp(a));
+    documents.into_iter().map(|(_, path)| path).collect()
+}
+
+/// The byte range of the entry for `href`, which is already escaped.
+fn bookmark_range(xbel: &str, href: &str) -> Option<std::ops::Range<usize>> {
+    let start = xbel.find(&format!("<bookmark href=\"{href}\""))?;
+    let end = start + xbel[start..].find("</bookmark>")? + "</bookmark>".len();
+    Some(start..end)
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let prefix = format!(" {name}=\"");
+    let start = tag.find(&prefix)? + prefix.len();
+    let end = start + tag[start..].find('"')?;
+    Some(&tag[start..end])
+}
+
+fn set_attribute(tag: &str, name: &str, value: &str) -> String {
+    let prefix = format!(" {name}=\"");
+    if let Some(start) = tag.find(&prefix).map(|start| start + prefix.len()) {
+        if let Some(end) = tag[start..].find('"').map(|end| start + end) {
+            return format!("{}{value}{}", &tag[..start], &tag[end..]);
+        }
+    }
+    let end = tag.rfind("/>").unwrap_or_else(|| tag.len() - 1);
+    format!("{}{prefix}{value}\"{}", &tag[..end], &tag[end..])
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Formats a time as in the history, e.g. `2023-11-14T22:13:20.000000Z`.
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let time_of_day = seconds % 86400;
+
+    // Converts days since the epoch to a civil date, as in http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:06}Z",
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60,
+        since_epoch.subsec_micros()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn bookmark<'a>(href: &'a str, application: &'a str, timestamp: &'a str) -> Bookmark<'a> {
+        Bookmark {
+            href,
+            mime_type: "text/plain",
+            application,
+            exec: "'zed %u'",
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(
+            timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            "2023-11-14T22:13:20.000000Z"
+        );
+        assert_eq!(
+            timestamp(UNIX_EPOCH + Duration::from_micros(951_782_400_000_001)),
+            "2000-02-29T00:00:00.000001Z"
+        );
+    }
+
+    #[test]
+    fn test_add_bookmark() {
+        let xbel = add_bookmark(
+            "",
+            &bookmark("file:///a.txt", "zed", "2024-01-01T00:00:00.000000Z"),
+        );
+        let xbel = add_bookmark(
+            &xbel,
+            &bookmark("file:///b&c.txt", "zed", "2024-01-02T00:00:00.000000Z"),
+        );
+        let xbel = add_bookmark(
+            &xbel,
+            &bookmark("file:///b&c.txt", "gedit", "2024-01-03T00:00:00.000000Z"),
+        );
+        let xbel = add_bookmark(
+            &xbel,
+            &bookmark("file:///a.txt", "zed", "2024-01-04T00:00:00.000000Z"),
+        );
+
+        assert_eq!(xbel.matches("<bookmark ").count(), 2);
+        assert!(xbel.contains("<bookmark href=\"file:///b&amp;c.txt\""));
+        assert!(xbel.contains(
+            "<bookmark:application name=\"zed\" exec=\"&apos;zed %u&apos;\" \
+            modified=\"2024-01-04T00:00:00.000000Z\" count=\"2\"/>"
+        ));
+        assert_eq!(
+            recent_documents_in(&xbel, "zed"),
+            [PathBuf::from("/a.txt"), PathBuf::from("/b&c.txt")]
+        );
+        assert_eq!(
+            recent_documents_in(&xbel, "gedit"),
+            [PathBuf::from("/b&c.txt")]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Keeps a single instance of an application running. The first launch listens on a Unix
+//! socket in `$XDG_RUNTIME_DIR`, and later launches forward their command line to it and exit.
+//!
+//! The runtime directory is only accessible to its user, and both ends of a connection check
+//! that the other one is run by the same user, so that other users can neither receive nor
+//! inject launches.
+
+use std::{
+    env, fs,
+    io::{self, ErrorKind, Read as _},
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd},
+        unix::net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use super::file_url;
+
+/// The longest command line a launch can forward, so that a client can't make the running
+/// instance buffer without limit.
+const MAX_LAUNCH_LEN: usize = 1024 * 1024;
+
+pub(super) enum Instance {
+    /// No other instance is running, so this one listens for the later launches.
+    Primary(SingleInstanceListener),
+    /// The launch was forwarded to the running instance.
+    Secondary,
+}
+
+/// The command line of a launch, as forwarded to the running instance.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct ForwardedLaunch {
+    pub(super) args: Vec<String>,
+    pub(super) cwd: PathBuf,
+    /// The token to activate a window with, passed by the launcher through
+    /// `XDG_ACTIVATION_TOKEN` or `DESKTOP_STARTUP_ID`.
+    pub(super) activation_token: Option<String>,
+}
+
+impl ForwardedLaunch {
+    fn current() -> Self {
+        Self {
+            args: env::args().skip(1).collect(),
+            cwd: env::current_dir().unwrap_or_default(),
+            activation_token: env::var("XDG_ACTIVATION_TOKEN")
+                .or_else(|_| env::var("DESKTOP_STARTUP_ID"))
+                .ok(),
+        }
+    }
+
+    /// The URLs to open for this launch: URLs are passed as they are, and paths as `file://`
+    /// URLs resolved against the launch's working directory. Flags are ignored.
+    pub(super) fn urls(&self) -> Vec<String> {
+        self.args
+            .iter()
+            .filter(|arg| !arg.is_empty() && !arg.starts_with('-'))
+            .map(|arg| {
+                if is_url(arg) {
+                    arg.clone()
+                } else {
+                    file_url(&self.cwd.join(arg))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Listens for the launches of other instances, and removes its socket when dropped.
+pub(crate) struct SingleInstanceListener {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl SingleInstanceListener {
+    /// Returns the connections of the launches since the last call, without blocking. Their
+    /// launches are read with [`read_launch`] as their data arrives.
+    pub(super) fn accept(&self) -> Vec<UnixStream> {
+        let mut connections = Vec::new();
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    match check_peer(&stream).and_then(|()| Ok(stream.set_nonblocking(true)?)) {
+                        Ok(()) => connections.push(stream),
+                        Err(error) => log::error!("failed to accept a forwarded launch: {error:?}"),
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    log::error!("failed to accept a forwarded launch: {error}");
+                    break;
+                }
+            }
+        }
+        connections
+    }
+}
+
+impl AsFd for SingleInstanceListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.listener.as_fd()
+    }
+}
+
+impl Drop for SingleInstanceListener {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+/// Forwards this launch to the running instance of `app_id` if there is one, or starts
+/// listening for the launches of other instances otherwise.
+pub(super) fn connect_or_listen(app_id: &str) -> Result<Instance> {
+    let path = socket_path(app_id)?;
+    match UnixStream::connect(&path) {
+        Ok(stream) => {
+            check_peer(&stream).context("failed to check the running instance")?;
+            serde_json::to_writer(stream, &ForwardedLaunch::current())
+                .context("failed to forward the launch")?;
+            return Ok(Instance::Secondary);
+        }
+        // The socket is left over from an instance that didn't exit cleanly.
+        Err(error) if error.kind() == ErrorKind::ConnectionRefused => {
+            fs::remove_file(&path).ok();
+        }
+        Err(_) => {}
+    }
+
+    let listener =
+        UnixListener::bind(&path).with_context(|| format!("failed to listen on {path:?}"))?;
+    listener.set_nonblocking(true)?;
+    Ok(Instance::Primary(SingleInstanceListener { listener, path }))
+}
+
+/// Reads what's available of a launch from a non-blocking connection into `buffer`, and
+/// returns the launch once the other instance has sent all of it and closed the connection.
+pub(super) fn read_launch(
+    stream: &UnixStream,
+    buffer: &mut Vec<u8>,
+) -> Result<Option<ForwardedLaunch>> {
+    let mut chunk = [0; 4096];
+    loop {
+        match (&*stream).read(&mut chunk) {
+            Ok(0) => return Ok(Some(serde_json::from_slice(buffer)?)),
+            Ok(len) => {
+                buffer.extend_from_slice(&chunk[..len]);
+                if buffer.len() > MAX_LAUNCH_LEN {
+                    return Err(anyhow!(
+                        "forwarded launch is longer than {MAX_LAUNCH_LEN} bytes"
+                    ));
+                }
+            }
+            Err(error) if error.kind() == ErrorKind::WouldBlock => return Ok(None),
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+/// The socket in `$XDG_RUNTIME_DIR`. There's no fallback, as other directories, such as the
+/// temporary directory, can be shared with other users.
+fn socket_path(app_id: &str) -> Result<PathBuf> {
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .context("$XDG_RUNTIME_DIR isn't set")?;
+    Ok(runtime_dir.join(format!("{}.sock", app_id.replace('/', "_"))))
+}
+
+/// Checks that the other end of `stream` is run by the current user.
+fn check_peer(stream: &UnixStream) -> Result<()> {
+    let uid = peer_uid(stream).context("failed to get the credentials of the other instance")?;
+    if uid != unsafe { libc::getuid() } {
+        return Err(anyhow!("the other instance is run by another user ({uid})"));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn peer_uid(stream: &UnixStream) -> io::Result<libc::uid_t> {
+    let mut credentials = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut credentials as *mut libc::ucred).cast(),
+            &mut len,
+        )
+    };
+    if result == 0 {
+        Ok(credentials.uid)
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(target_os = "freebsd")]
+fn peer_uid(stream: &UnixStream) -> io::Result<libc::uid_t> {
+    let (mut uid, mut gid) = (0, 0);
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } == 0 {
+        Ok(uid)
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+fn is_url(arg: &str) -> bool {
+    arg.split_once(':').map_or(false, |(scheme, _)| {
+        // Schemes are at least two characters long, to tell them apart from drive letters.
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+
+    #[test]
+    fn test_forwarded_launch_urls() {
+        let launch = ForwardedLaunch {
+            args: vec![
+                "--new-window".into(),
+                "notes/todo list.md".into(),
+                "/etc/hosts".into(),
+                "zed://channel/1".into(),
+                "mailto:someone@example.com".into(),
+            ],
+            cwd: PathBuf::from("/home/me"),
+            activation_token: None,
+        };
+        assert_eq!(
+            launch.urls(),
+            [
+                "file:///home/me/notes/todo%20list.md",
+                "file:///etc/hosts",
+                "zed://channel/1",
+                "mailto:someone@example.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_launch_in_parts() {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        server.set_nonblocking(true).unwrap();
+        let message = serde_json::to_vec(&ForwardedLaunch {
+            args: vec!["zed://channel/1".into()],
+            cwd: PathBuf::from("/home/me"),
+            activation_token: Some("token".into()),
+        })
+        .unwrap();
+        let mut buffer = Vec::new();
+
+        // Nothing is read until the launch arrives, and reading never blocks.
+        assert!(read_launch(&server, &mut buffer).unwrap().is_none());
+        let (first, second) = message.split_at(message.len() / 2);
+        client.write_all(first).unwrap();
+        assert!(read_launch(&server, &mut buffer).unwrap().is_none());
+        client.write_all(second).unwrap();
+        assert!(read_launch(&server, &mut buffer).unwrap().is_none());
+
+        drop(client);
+        let launch = read_launch(&server, &mut buffer).unwrap().unwrap();
+        assert_eq!(launch.args, ["zed://channel/1"]);
+        assert_eq!(launch.activation_token.as_deref(), Some("token"));
+    }
+
+    #[test]
+    fn test_check_peer() {
+        let (client, server) = UnixStream::pair().unwrap();
+        assert_eq!(peer_uid(&server).unwrap(), unsafe { libc::getuid() });
+        check_peer(&server).unwrap();
+        check_peer(&client).unwrap();
+    }
+}
@@ -1,13 +1,13 @@
 use crate::{
-    point, size, Bounds, DevicePixels, Font, FontFeatures, FontId, FontMetrics, FontRun, FontStyle,
-    FontWeight, GlyphId, LineLayout, Pixels, PlatformTextSystem, Point, RenderGlyphParams,
-    ShapedGlyph, SharedString, Size, SUBPIXEL_VARIANTS,
+    point, px, size, Bounds, DevicePixels, Font, FontFeatures, FontId, FontMetrics, FontRun,
+    FontStyle, FontWeight, GlyphId, GlyphOutlineCommand, LineLayout, Pixels, PlatformTextSystem,
+    Point, RenderGlyphParams, ShapedGlyph, SharedString, Size, SUBPIXEL_VARIANTS,
 };
 use anyhow::{anyhow, Context as _, Ok, Result};
 use collections::HashMap;
 use cosmic_text::{
-    Attrs, AttrsList, CacheKey, Family, Font as CosmicTextFont, FontSystem, ShapeBuffer, ShapeLine,
-    SwashCache,
+    Attrs, AttrsList, CacheKey, Command, Family, Font as CosmicTextFont, FontSystem, ShapeBuffer,
+    ShapeLine, SwashCache,
 };
 
 use itertools::Itertools;
@@ -165,6 +165,15 @@ impl PlatformTextSystem for CosmicTextSystem {
         self.0.write().rasterize_glyph(params, raster_bounds)
     }
 
+    fn glyph_outline(
+        &self,
+        font_id: FontId,
+        glyph_id: GlyphId,
+        font_size: Pixels,
+    ) -> Result<Vec<GlyphOutlineCommand>> {
+        self.0.write().glyph_outline(font_id, glyph_id, font_size)
+    }
+
     fn layout_line(&self, text: &str, font_size: Pixels, runs: &[FontRun]) -> LineLayout {
         self.0.write().layout_line(text, font_size, runs)
     }
@@ -333,6 +342,49 @@ impl CosmicTextSystemState {
         }
     }
 
+    fn glyph_outline(
+        &mut self,
+        font_id: FontId,
+        glyph_id: GlyphId,
+        font_size: Pixels,
+    ) -> Result<Vec<GlyphOutlineCommand>> {
+        let font = &self.loaded_fonts_store[font_id.0];
+        let commands = self
+            .swash_cache
+            .get_outline_commands(
+                &mut self.font_system,
+                CacheKey::new(
+                    font.id(),
+                    glyph_id.0 as u16,
+                    font_size.into(),
+                    (0.0, 0.0),
+                    cosmic_text::CacheKeyFlags::empty(),
+                )
+                .0,
+            )
+            .with_context(|| format!("no outline for glyph {glyph_id:?} in font {font:?}"))?;
+
+        // Swash outlines are y-up, so flip them to match the rest of GPUI.
+        let point = |x: f32, y: f32| point(px(x), px(-y));
+        Ok(commands
+            .iter()
+            .map(|command| match command {
+                Command::MoveTo(to) => GlyphOutlineCommand::MoveTo(point(to.x, to.y)),
+                Command::LineTo(to) => GlyphOutlineCommand::LineTo(point(to.x, to.y)),
+                Command::QuadTo(control, to) => GlyphOutlineCommand::QuadTo {
+                    control: point(control.x, control.y),
+                    to: point(to.x, to.y),
+                },
+                Command::CurveTo(control_a, control_b, to) => GlyphOutlineCommand::CurveTo {
+                    control_a: point(control_a.x, control_a.y),
+                    control_b: point(control_b.x, control_b.y),
+                    to: point(to.x, to.y),
+                },
+                Command::Close => GlyphOutlineCommand::Close,
+            })
+            .collect())
+    }
+
     fn font_id_for_cosmic_id(&mut self, id: cosmic_text::fontdb::ID) -> FontId {
         if let Some(ix) = self
             .loaded_fonts_store
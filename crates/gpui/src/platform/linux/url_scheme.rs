@@ -0,0 +1,268 @@
+//! Registers the application as the handler of URL schemes, through a desktop entry and the
+//! default applications listed in `mimeapps.list`.
+//!
+//! See the [Desktop Entry](https://specifications.freedesktop.org/desktop-entry-spec/latest/)
+//! and [MIME Applications](https://specifications.freedesktop.org/mime-apps-spec/latest/)
+//! specifications.
+
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Context as _, Result};
+
+use super::{xdg_config_home, xdg_data_home};
+
+const DEFAULT_APPLICATIONS_SECTION: &str = "[Default Applications]";
+const SCHEME_HANDLER_MIME_PREFIX: &str = "x-scheme-handler/";
+
+/// Makes this executable the default handler of `scheme` URLs, which it's then launched with.
+pub(super) fn register_url_scheme(scheme: &str) -> Result<()> {
+    let scheme = scheme.to_ascii_lowercase();
+    let is_valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !is_valid {
+        return Err(anyhow!("invalid URL scheme: {scheme:?}"));
+    }
+
+    let executable = env::current_exe()?;
+    let desktop_id = desktop_entry_id(&executable);
+    let applications_dir = xdg_data_home()?.join("applications");
+    fs::create_dir_all(&applications_dir)?;
+
+    let entry_path = applications_dir.join(&desktop_id);
+    let existing_entry = read_if_exists(&entry_path)?;
+    let mut schemes = schemes_in_desktop_entry(&existing_entry);
+    if !schemes.contains(&scheme) {
+        schemes.push(scheme.clone());
+    }
+    write_atomically(&entry_path, &desktop_entry(&executable, &schemes))?;
+
+    let mimeapps_path = xdg_config_home()?.join("mimeapps.list");
+    let mimeapps = read_if_exists(&mimeapps_path)?;
+    let mime_type = format!("{SCHEME_HANDLER_MIME_PREFIX}{scheme}");
+    if let Some(parent) = mimeapps_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomically(
+        &mimeapps_path,
+        &set_default_application(&mimeapps, &mime_type, &desktop_id),
+    )?;
+
+    // Some desktops look up handlers in the MIME cache rather than in mimeapps.list, so refresh
+    // it if the tool is available.
+    match Command::new("update-desktop-database")
+        .arg(&applications_dir)
+        .status()
+    {
+        Ok(status) if !status.success() => {
+            log::warn!("update-desktop-database exited with {status}");
+        }
+        Ok(_) => {}
+        Err(error) => log::info!("could not run update-desktop-database: {error}"),
+    }
+    Ok(())
+}
+
+/// The URLs this process was launched with for one of the schemes it handles, e.g. when a
+/// link was opened in a browser.
+pub(super) fn launch_urls() -> Vec<String> {
+    let Some(entry) = env::current_exe().ok().and_then(|executable| {
+        let path = xdg_data_home()
+            .ok()?
+            .join("applications")
+            .join(desktop_entry_id(&executable));
+        fs::read_to_string(path).ok()
+    }) else {
+        return Vec::new();
+    };
+    let schemes = schemes_in_desktop_entry(&entry);
+    env::args()
+        .skip(1)
+        .filter(|arg| {
+            arg.split_once(':').map_or(false, |(scheme, _)| {
+                schemes
+                    .iter()
+                    .any(|registered| registered.eq_ignore_ascii_case(scheme))
+            })
+        })
+        .collect()
+}
+
+/// Reads a file that's empty if it doesn't exist yet. Any other error is returned, so that
+/// the user's associations are never overwritten because they couldn't be read.
+fn read_if_exists(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(error) => Err(error).with_context(|| format!("failed to read {path:?}")),
+    }
+}
+
+/// Replaces a file through a temporary file in the same directory, so that it's never left
+/// partially written. Symlinks are followed, so that e.g. a `mimeapps.list` kept with the
+/// user's dotfiles stays a symlink.
+fn write_atomically(path: &Path, contents: &str) -> Result<()> {
+    let path = match fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(error) if error.kind() == ErrorKind::NotFound => path.to_path_buf(),
+        Err(error) => return Err(error).with_context(|| format!("failed to resolve {path:?}")),
+    };
+    let temp_path = temp_path_for(&path);
+    fs::write(&temp_path, contents).with_context(|| format!("failed to write {temp_path:?}"))?;
+    fs::rename(&temp_path, &path).with_context(|| format!("failed to write {path:?}"))
+}
+
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{file_name}.{}", std::process::id()))
+}
+
+fn desktop_entry_id(executable: &Path) -> String {
+    let name = executable
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "gpui".to_string());
+    format!("{name}-url-handler.desktop")
+}
+
+fn desktop_entry(executable: &Path, schemes: &[String]) -> String {
+    let name = executable
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mime_types = schemes
+        .iter()
+        .map(|scheme| format!("{SCHEME_HANDLER_MIME_PREFIX}{scheme};"))
+        .collect::<String>();
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={name}\n\
+         Exec={} %u\n\
+         NoDisplay=true\n\
+         MimeType={mime_types}\n",
+        quote_exec_argument(executable)
+    )
+}
+
+/// Quotes an argument of the `Exec` key. Reserved characters are escaped once for the command
+/// line, and the resulting backslashes once more for the desktop entry's string value.
+fn quote_exec_argument(argument: &Path) -> String {
+    let mut quoted = String::from("\"");
+    for c in argument.to_string_lossy().chars() {
+        match c {
+            '"' | '`' | '$' => {
+                quoted.push_str("\\\\");
+                quoted.push(c);
+            }
+            '\\' => quoted.push_str("\\\\\\\\"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn schemes_in_desktop_entry(entry: &str) -> Vec<String> {
+    entry
+        .lines()
+        .filter_map(|line| line.strip_prefix("MimeType="))
+        .flat_map(|mime_types| mime_types.split(';'))
+        .filter_map(|mime_type| mime_type.trim().strip_prefix(SCHEME_HANDLER_MIME_PREFIX))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Sets the default application for `mime_type` in the contents of a `mimeapps.list`, keeping
+/// everything else as it is.
+fn set_default_application(mimeapps: &str, mime_type: &str, desktop_id: &str) -> String {
+    let entry = format!("{mime_type}={desktop_id};");
+    let mut lines = mimeapps.lines().map(str::to_string).collect::<Vec<_>>();
+
+    let Some(section_start) = lines
+        .iter()
+        .position(|line| line.trim() == DEFAULT_APPLICATIONS_SECTION)
+    else {
+        if lines.last().map_or(false, |line| !line.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(DEFAULT_APPLICATIONS_SECTION.to_string());
+        lines.push(entry);
+        return lines.join("\n") + "\n";
+    };
+
+    let section_end = lines[section_start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |offset| section_start + 1 + offset);
+    let existing = lines[section_start + 1..section_end]
+        .iter()
+        .position(|line| {
+            line.split_once('=')
+                .map_or(false, |(key, _)| key.trim() == mime_type)
+        });
+    match existing {
+        Some(offset) => lines[section_start + 1 + offset] = entry,
+        None => lines.insert(section_start + 1, entry),
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_default_application() {
+        assert_eq!(
+            set_default_application("", "x-scheme-handler/zed", "zed-url-handler.desktop"),
+            "[Default Applications]\nx-scheme-handler/zed=zed-url-handler.desktop;\n"
+        );
+
+        let mimeapps = "[Added Associations]\ntext/plain=editor.desktop;\n\n\
+            [Default Applications]\nx-scheme-handler/zed=other.desktop;\ntext/html=browser.desktop;\n";
+        assert_eq!(
+            set_default_application(mimeapps, "x-scheme-handler/zed", "zed-url-handler.desktop"),
+            "[Added Associations]\ntext/plain=editor.desktop;\n\n\
+            [Default Applications]\nx-scheme-handler/zed=zed-url-handler.desktop;\ntext/html=browser.desktop;\n"
+        );
+    }
+
+    #[test]
+    fn test_desktop_entry_schemes() {
+        let executable = PathBuf::from("/opt/my app/zed");
+        let entry = desktop_entry(&executable, &["zed".to_string(), "zed-dev".to_string()]);
+        assert!(entry.contains("Exec=\"/opt/my app/zed\" %u\n"));
+        assert_eq!(schemes_in_desktop_entry(&entry), ["zed", "zed-dev"]);
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let directory = env::temp_dir().join(format!("gpui-url-scheme-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let target = directory.join("dotfiles-mimeapps.list");
+        let link = directory.join("mimeapps.list");
+        assert_eq!(read_if_exists(&link).unwrap(), "");
+        fs::write(&target, "[Default Applications]\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_atomically(&link, "[Added Associations]\n").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(read_if_exists(&target).unwrap(), "[Added Associations]\n");
+
+        // Errors other than a missing file aren't mistaken for an empty file.
+        assert!(read_if_exists(&directory).is_err());
+
+        fs::remove_dir_all(&directory).ok();
+    }
+}
@@ -7,9 +7,17 @@ mod window;
 
 pub(crate) use client::*;
 
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use wayland_client::protocol::{wl_buffer::WlBuffer, wl_shm, wl_shm::WlShm};
+use wayland_client::QueueHandle;
 use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
 
-use crate::CursorStyle;
+use crate::{CursorStyle, Size};
 
 impl CursorStyle {
     pub(super) fn to_shape(&self) -> Shape {
@@ -35,6 +43,49 @@ impl CursorStyle {
             CursorStyle::DragLink => Shape::Alias,
             CursorStyle::DragCopy => Shape::Copy,
             CursorStyle::ContextualMenu => Shape::ContextMenu,
+            // Only used where a custom surface can't be attached, e.g. for tablet tools.
+            CursorStyle::Image(_) => Shape::Default,
         }
     }
 }
+
+/// Creates a buffer holding premultiplied BGRA pixels (`ARGB8888` in little endian) in memory
+/// shared with the compositor.
+fn create_shm_buffer(
+    shm: &WlShm,
+    qh: &QueueHandle<WaylandClientStatePtr>,
+    size: Size<u32>,
+    bytes: &[u8],
+) -> Result<WlBuffer> {
+    let mut file = anonymous_file()?;
+    file.write_all(bytes)?;
+
+    let pool = shm.create_pool(file.as_fd(), bytes.len() as i32, qh, ());
+    let buffer = pool.create_buffer(
+        0,
+        size.width as i32,
+        size.height as i32,
+        size.width as i32 * 4,
+        wl_shm::Format::Argb8888,
+        qh,
+        (),
+    );
+    // The buffer keeps the memory mapped by the compositor alive.
+    pool.destroy();
+    Ok(buffer)
+}
+
+/// Create a file that can be shared with the compositor and is removed as soon as it's closed.
+fn anonymous_file() -> Result<File> {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    let path = dir.join(format!("gpui-shm-{}", uuid::Uuid::new_v4()));
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    std::fs::remove_file(&path)?;
+    Ok(file)
+}
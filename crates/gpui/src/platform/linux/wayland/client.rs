@@ -1,12 +1,15 @@
 use std::{
     cell::{RefCell, RefMut},
     hash::Hash,
+    mem,
     os::fd::{AsRawFd, BorrowedFd},
     path::PathBuf,
     rc::{Rc, Weak},
+    sync::Arc,
     time::{Duration, Instant},
 };
 
+use ashpd::url::Url;
 use calloop::{
     timer::{TimeoutAction, Timer},
     EventLoop, LoopHandle,
@@ -32,7 +35,7 @@ use wayland_client::{
     delegate_noop,
     protocol::{
         wl_buffer, wl_compositor, wl_keyboard, wl_pointer, wl_registry, wl_seat, wl_shm,
-        wl_shm_pool, wl_surface,
+        wl_shm_pool, wl_surface, wl_touch,
     },
     Connection, Dispatch, Proxy, QueueHandle,
 };
@@ -42,6 +45,19 @@ use wayland_protocols::wp::cursor_shape::v1::client::{
 use wayland_protocols::wp::fractional_scale::v1::client::{
     wp_fractional_scale_manager_v1, wp_fractional_scale_v1,
 };
+use wayland_protocols::wp::idle_inhibit::zv1::client::{
+    zwp_idle_inhibit_manager_v1, zwp_idle_inhibitor_v1,
+};
+use wayland_protocols::wp::keyboard_shortcuts_inhibit::zv1::client::{
+    zwp_keyboard_shortcuts_inhibit_manager_v1, zwp_keyboard_shortcuts_inhibitor_v1,
+};
+use wayland_protocols::wp::pointer_constraints::zv1::client::{
+    zwp_confined_pointer_v1, zwp_locked_pointer_v1, zwp_pointer_constraints_v1,
+};
+use wayland_protocols::wp::pointer_gestures::zv1::client::{
+    zwp_pointer_gesture_hold_v1, zwp_pointer_gesture_pinch_v1, zwp_pointer_gesture_swipe_v1,
+    zwp_pointer_gestures_v1,
+};
 use wayland_protocols::wp::primary_selection::zv1::client::zwp_primary_selection_offer_v1::{
     self, ZwpPrimarySelectionOfferV1,
 };
@@ -49,6 +65,14 @@ use wayland_protocols::wp::primary_selection::zv1::client::{
     zwp_primary_selection_device_manager_v1, zwp_primary_selection_device_v1,
     zwp_primary_selection_source_v1,
 };
+use wayland_protocols::wp::relative_pointer::zv1::client::{
+    zwp_relative_pointer_manager_v1, zwp_relative_pointer_v1,
+};
+use wayland_protocols::wp::tablet::zv2::client::{
+    zwp_tablet_manager_v2, zwp_tablet_pad_group_v2, zwp_tablet_pad_ring_v2,
+    zwp_tablet_pad_strip_v2, zwp_tablet_pad_v2, zwp_tablet_seat_v2, zwp_tablet_tool_v2,
+    zwp_tablet_v2,
+};
 use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
     ContentHint, ContentPurpose,
 };
@@ -60,17 +84,24 @@ use wayland_protocols::xdg::activation::v1::client::{xdg_activation_token_v1, xd
 use wayland_protocols::xdg::decoration::zv1::client::{
     zxdg_decoration_manager_v1, zxdg_toplevel_decoration_v1,
 };
-use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+use wayland_protocols::xdg::shell::client::{
+    xdg_popup, xdg_positioner, xdg_surface, xdg_toplevel, xdg_wm_base,
+};
+use wayland_protocols::xdg::toplevel_icon::v1::client::{
+    xdg_toplevel_icon_manager_v1, xdg_toplevel_icon_v1,
+};
 use wayland_protocols_plasma::blur::client::{org_kde_kwin_blur, org_kde_kwin_blur_manager};
+use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 use xkbcommon::xkb::ffi::XKB_KEYMAP_FORMAT_TEXT_V1;
 use xkbcommon::xkb::{self, Keycode, KEYMAP_COMPILE_NO_FLAGS};
 
 use super::display::WaylandDisplay;
-use super::window::{ImeInput, WaylandWindowStatePtr};
+use super::window::{ImeInput, PopoverParent, WaylandWindowStatePtr};
 
 use crate::platform::linux::{
-    get_xkb_compose_state, is_within_click_distance, open_uri_internal, read_fd,
-    reveal_path_internal,
+    get_xkb_compose_state, is_within_click_distance, listen_for_forwarded_launches,
+    open_uri_internal, read_fd, reveal_path_internal,
+    single_instance::{ForwardedLaunch, SingleInstanceListener},
     wayland::{
         clipboard::{Clipboard, DataOffer, FILE_LIST_MIME_TYPE, TEXT_MIME_TYPE},
         cursor::Cursor,
@@ -82,12 +113,14 @@ use crate::platform::linux::{
 };
 use crate::platform::{blade::BladeContext, PlatformWindow};
 use crate::{
-    point, px, size, AnyWindowHandle, Bounds, CursorStyle, DevicePixels, DisplayId, FileDropEvent,
-    ForegroundExecutor, KeyDownEvent, KeyUpEvent, Keystroke, LinuxCommon, Modifiers,
-    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseExitEvent, MouseMoveEvent,
-    MouseUpEvent, NavigationDirection, Pixels, PlatformDisplay, PlatformInput, Point, ScaledPixels,
-    ScrollDelta, ScrollWheelEvent, Size, TouchPhase, WindowParams, DOUBLE_CLICK_INTERVAL,
-    SCROLL_LINES,
+    point, px, size, AnyWindowHandle, Bounds, CursorStyle, DevicePixels, DisplayId, DragAction,
+    ExternalDrag, ExternalDragEntry, ExternalDragEvent, FileDropEvent, ForegroundExecutor,
+    HoldEvent, KeyDownEvent, KeyUpEvent, Keystroke, LinuxCommon, Modifiers, ModifiersChangedEvent,
+    MouseButton, MouseDownEvent, MouseExitEvent, MouseMoveEvent, MouseUpEvent, NavigationDirection,
+    PinchEvent, Pixels, PlatformDisplay, PlatformInput, Point, RelativeMouseMoveEvent,
+    ScaledPixels, ScrollDelta, ScrollWheelEvent, Size, StylusButton, StylusEvent, StylusPhase,
+    StylusTool, SwipeEvent, TouchEvent, TouchPhase, WindowKind, WindowParams,
+    DOUBLE_CLICK_INTERVAL, SCROLL_LINES,
 };
 
 /// Used to convert evdev scancode to xkb scancode
@@ -110,6 +143,16 @@ pub struct Globals {
         Option<wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1>,
     pub decoration_manager: Option<zxdg_decoration_manager_v1::ZxdgDecorationManagerV1>,
     pub blur_manager: Option<org_kde_kwin_blur_manager::OrgKdeKwinBlurManager>,
+    pub pointer_gestures: Option<zwp_pointer_gestures_v1::ZwpPointerGesturesV1>,
+    pub tablet_manager: Option<zwp_tablet_manager_v2::ZwpTabletManagerV2>,
+    pub pointer_constraints: Option<zwp_pointer_constraints_v1::ZwpPointerConstraintsV1>,
+    pub relative_pointer_manager:
+        Option<zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1>,
+    pub idle_inhibit_manager: Option<zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1>,
+    pub keyboard_shortcuts_inhibit_manager:
+        Option<zwp_keyboard_shortcuts_inhibit_manager_v1::ZwpKeyboardShortcutsInhibitManagerV1>,
+    pub toplevel_icon_manager: Option<xdg_toplevel_icon_manager_v1::XdgToplevelIconManagerV1>,
+    pub layer_shell: Option<zwlr_layer_shell_v1::ZwlrLayerShellV1>,
     pub text_input_manager: Option<zwp_text_input_manager_v3::ZwpTextInputManagerV3>,
     pub executor: ForegroundExecutor,
 }
@@ -147,6 +190,14 @@ impl Globals {
             fractional_scale_manager: globals.bind(&qh, 1..=1, ()).ok(),
             decoration_manager: globals.bind(&qh, 1..=1, ()).ok(),
             blur_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            pointer_gestures: globals.bind(&qh, 1..=3, ()).ok(),
+            tablet_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            pointer_constraints: globals.bind(&qh, 1..=1, ()).ok(),
+            relative_pointer_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            idle_inhibit_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            keyboard_shortcuts_inhibit_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            toplevel_icon_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            layer_shell: globals.bind(&qh, 1..=4, ()).ok(),
             text_input_manager: globals.bind(&qh, 1..=1, ()).ok(),
             executor,
             qh,
@@ -191,6 +242,11 @@ pub(crate) struct WaylandClientState {
     wl_seat: wl_seat::WlSeat, // TODO: Multi seat support
     wl_pointer: Option<wl_pointer::WlPointer>,
     wl_keyboard: Option<wl_keyboard::WlKeyboard>,
+    wl_touch: Option<wl_touch::WlTouch>,
+    pointer_gestures: Option<PointerGestures>,
+    relative_pointer: Option<zwp_relative_pointer_v1::ZwpRelativePointerV1>,
+    tablet_seat: Option<zwp_tablet_seat_v2::ZwpTabletSeatV2>,
+    tablet_tools: HashMap<ObjectId, TabletTool>,
     cursor_shape_device: Option<wp_cursor_shape_device_v1::WpCursorShapeDeviceV1>,
     data_device: Option<wl_data_device::WlDataDevice>,
     primary_selection: Option<zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1>,
@@ -207,6 +263,8 @@ pub(crate) struct WaylandClientState {
     compose_state: Option<xkb::compose::State>,
     drag: DragState,
     click: ClickState,
+    touches: HashMap<i32, TouchPoint>,
+    gesture: GestureState,
     repeat: KeyRepeat,
     pub modifiers: Modifiers,
     axis_source: AxisSource,
@@ -225,6 +283,7 @@ pub(crate) struct WaylandClientState {
     clipboard: Clipboard,
     data_offers: Vec<DataOffer<WlDataOffer>>,
     primary_data_offer: Option<DataOffer<ZwpPrimarySelectionOfferV1>>,
+    outgoing_drag: Option<OutgoingDrag>,
     cursor: Cursor,
     pending_activation: Option<PendingActivation>,
     event_loop: Option<EventLoop<'static, WaylandClientStatePtr>>,
@@ -237,6 +296,15 @@ pub struct DragState {
     position: Point<Pixels>,
 }
 
+/// A drag started by one of our windows, which can be dropped onto other applications.
+pub struct OutgoingDrag {
+    data_source: wl_data_source::WlDataSource,
+    /// The data offered to the target, keyed by MIME type.
+    data: Vec<(String, Arc<[u8]>)>,
+    action: Option<DragAction>,
+    on_event: Rc<RefCell<Box<dyn FnMut(ExternalDragEvent)>>>,
+}
+
 pub struct ClickState {
     last_mouse_button: Option<MouseButton>,
     last_click: Instant,
@@ -244,6 +312,100 @@ pub struct ClickState {
     current_count: usize,
 }
 
+/// The touchpad gestures of the current pointer.
+pub struct PointerGestures {
+    swipe: zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1,
+    pinch: zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1,
+    hold: Option<zwp_pointer_gesture_hold_v1::ZwpPointerGestureHoldV1>,
+}
+
+impl PointerGestures {
+    fn destroy(&self) {
+        self.swipe.destroy();
+        self.pinch.destroy();
+        if let Some(hold) = &self.hold {
+            hold.destroy();
+        }
+    }
+}
+
+/// The touchpad gesture that is currently in progress. Only one gesture can be
+/// performed at a time, so they all share this state.
+#[derive(Default)]
+pub struct GestureState {
+    fingers: u32,
+    scale: f32,
+    rotation: f32,
+}
+
+impl GestureState {
+    fn begin_pinch(&mut self, fingers: u32) {
+        *self = GestureState {
+            fingers,
+            scale: 1.0,
+            rotation: 0.0,
+        };
+    }
+
+    /// Updates the pinch with an event's scale, which is relative to the start of the
+    /// pinch, and its rotation, which is relative to the previous event.
+    fn update_pinch(&mut self, scale: f64, rotation: f64) {
+        self.scale = scale as f32;
+        self.rotation += rotation as f32;
+    }
+}
+
+/// A tool of a drawing tablet, e.g. a pen. Its events are accumulated until the
+/// end of the frame, and are then dispatched with the final state of the tool.
+pub struct TabletTool {
+    tool: StylusTool,
+    hardware_serial: Option<u64>,
+    cursor_shape_device: Option<wp_cursor_shape_device_v1::WpCursorShapeDeviceV1>,
+    proximity_serial: u32,
+    window: Option<WaylandWindowStatePtr>,
+    position: Point<Pixels>,
+    in_contact: bool,
+    pressure: f32,
+    distance: f32,
+    tilt: Point<f32>,
+    rotation: f32,
+    pressed_buttons: SmallVec<[StylusButton; 2]>,
+    pending_phases: SmallVec<[StylusPhase; 4]>,
+}
+
+impl TabletTool {
+    fn new(cursor_shape_device: Option<wp_cursor_shape_device_v1::WpCursorShapeDeviceV1>) -> Self {
+        Self {
+            tool: StylusTool::default(),
+            hardware_serial: None,
+            cursor_shape_device,
+            proximity_serial: 0,
+            window: None,
+            position: Point::default(),
+            in_contact: false,
+            pressure: 0.0,
+            distance: 0.0,
+            tilt: Point::default(),
+            rotation: 0.0,
+            pressed_buttons: SmallVec::new(),
+            pending_phases: SmallVec::new(),
+        }
+    }
+
+    fn push_phase(&mut self, phase: StylusPhase) {
+        // Every axis that changed within a frame is reported by a single move.
+        if phase != StylusPhase::Moved || !self.pending_phases.contains(&StylusPhase::Moved) {
+            self.pending_phases.push(phase);
+        }
+    }
+}
+
+/// A point of contact that is currently touching one of our windows.
+pub struct TouchPoint {
+    window: WaylandWindowStatePtr,
+    position: Point<Pixels>,
+}
+
 pub(crate) struct KeyRepeat {
     characters_per_second: u32,
     delay: Duration,
@@ -272,10 +434,95 @@ impl WaylandClientStatePtr {
             .expect("The pointer should always be valid when dispatching in wayland")
     }
 
+    pub fn get_pointer(&self) -> Option<wl_pointer::WlPointer> {
+        self.0.upgrade().unwrap().borrow().wl_pointer.clone()
+    }
+
     pub fn get_serial(&self, kind: SerialKind) -> u32 {
         self.0.upgrade().unwrap().borrow().serial_tracker.get(kind)
     }
 
+    pub fn start_drag(
+        &self,
+        origin: &wl_surface::WlSurface,
+        drag: ExternalDrag,
+        icon: Option<AnyWindowHandle>,
+        on_event: Box<dyn FnMut(ExternalDragEvent)>,
+    ) -> anyhow::Result<()> {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let (Some(data_device_manager), Some(data_device)) = (
+            state.globals.data_device_manager.clone(),
+            state.data_device.clone(),
+        ) else {
+            anyhow::bail!("drag and drop is not supported by the compositor");
+        };
+
+        let data = drag_data(&drag.entries);
+        let data_source = data_device_manager.create_data_source(&state.globals.qh, ());
+        for (mime_type, _) in &data {
+            data_source.offer(mime_type.clone());
+        }
+        data_source.set_actions(
+            drag.actions
+                .iter()
+                .fold(DndAction::empty(), |actions, action| {
+                    actions | action.to_wl()
+                }),
+        );
+
+        let icon = icon.and_then(|icon| {
+            state
+                .windows
+                .values()
+                .find(|window| window.handle() == icon)
+                .map(|window| window.surface())
+        });
+        let serial = state.serial_tracker.get(SerialKind::MousePress);
+        data_device.start_drag(Some(&data_source), origin, icon.as_ref(), serial);
+
+        let previous_drag = state.outgoing_drag.replace(OutgoingDrag {
+            data_source,
+            data,
+            action: None,
+            on_event: Rc::new(RefCell::new(on_event)),
+        });
+        if let Some(previous_drag) = previous_drag {
+            previous_drag.data_source.destroy();
+            // We're called from within the app, so let it finish before notifying the listener.
+            state
+                .common
+                .foreground_executor
+                .spawn(async move {
+                    (previous_drag.on_event.borrow_mut())(ExternalDragEvent::Cancelled)
+                })
+                .detach();
+        }
+        Ok(())
+    }
+
+    /// Activates a window for a launch of another instance, and opens what it was launched
+    /// with.
+    fn handle_forwarded_launch(&self, launch: ForwardedLaunch) {
+        let client = self.get_client();
+        let state = client.borrow();
+        // Compositors only let windows be activated with the token the launcher was given.
+        if let (Some(activation), Some(token)) =
+            (&state.globals.activation, launch.activation_token.clone())
+        {
+            let window = state
+                .keyboard_focused_window
+                .clone()
+                .or_else(|| state.windows.values().next().cloned());
+            if let Some(window) = window {
+                activation.activate(token, &window.surface());
+            }
+        }
+        drop(state);
+
+        LinuxCommon::open_urls(|f| f(&mut client.borrow_mut().common), launch.urls());
+    }
+
     pub fn set_pending_activation(&self, window: ObjectId) {
         self.0.upgrade().unwrap().borrow_mut().pending_activation =
             Some(PendingActivation::Window(window));
@@ -347,6 +594,19 @@ impl WaylandClientStatePtr {
                 state.keyboard_focused_window = Some(window);
             }
         }
+        state
+            .touches
+            .retain(|_, touch| !touch.window.ptr_eq(&closed_window));
+        for tool in state.tablet_tools.values_mut() {
+            if tool
+                .window
+                .as_ref()
+                .is_some_and(|window| window.ptr_eq(&closed_window))
+            {
+                tool.window = None;
+                tool.pending_phases.clear();
+            }
+        }
         if state.windows.is_empty() {
             state.common.signal.stop();
         }
@@ -364,9 +624,24 @@ impl Drop for WaylandClient {
         if let Some(wl_pointer) = &state.wl_pointer {
             wl_pointer.release();
         }
+        if let Some(wl_touch) = &state.wl_touch {
+            wl_touch.release();
+        }
+        if let Some(pointer_gestures) = &state.pointer_gestures {
+            pointer_gestures.destroy();
+        }
+        if let Some(relative_pointer) = &state.relative_pointer {
+            relative_pointer.destroy();
+        }
+        if let Some(tablet_seat) = &state.tablet_seat {
+            tablet_seat.destroy();
+        }
         if let Some(cursor_shape_device) = &state.cursor_shape_device {
             cursor_shape_device.destroy();
         }
+        if let Some(drag) = &state.outgoing_drag {
+            drag.data_source.destroy();
+        }
         if let Some(data_device) = &state.data_device {
             data_device.release();
         }
@@ -481,6 +756,11 @@ impl WaylandClient {
             .as_ref()
             .map(|primary_selection_manager| primary_selection_manager.get_device(&seat, &qh, ()));
 
+        let tablet_seat = globals
+            .tablet_manager
+            .as_ref()
+            .map(|tablet_manager| tablet_manager.get_tablet_seat(&seat, &qh, ()));
+
         let mut cursor = Cursor::new(&conn, &globals, 24);
 
         handle
@@ -520,6 +800,11 @@ impl WaylandClient {
             wl_seat: seat,
             wl_pointer: None,
             wl_keyboard: None,
+            wl_touch: None,
+            pointer_gestures: None,
+            relative_pointer: None,
+            tablet_seat,
+            tablet_tools: HashMap::default(),
             cursor_shape_device: None,
             data_device,
             primary_selection,
@@ -538,6 +823,8 @@ impl WaylandClient {
                 window: None,
                 position: Point::default(),
             },
+            touches: HashMap::default(),
+            gesture: GestureState::default(),
             click: ClickState {
                 last_click: Instant::now(),
                 last_mouse_button: None,
@@ -573,6 +860,7 @@ impl WaylandClient {
             clipboard: Clipboard::new(conn.clone(), handle.clone()),
             data_offers: Vec::new(),
             primary_data_offer: None,
+            outgoing_drag: None,
             cursor,
             pending_activation: None,
             event_loop: Some(event_loop),
@@ -642,6 +930,24 @@ impl LinuxClient for WaylandClient {
     ) -> anyhow::Result<Box<dyn PlatformWindow>> {
         let mut state = self.0.borrow_mut();
 
+        let parent = if let WindowKind::Popover(options) = &params.kind {
+            state
+                .windows
+                .values()
+                .find(|window| window.handle() == options.parent)
+                .map(|window| PopoverParent {
+                    window: window.clone(),
+                    // Popovers are opened in response to either a click, a tap or a key press.
+                    serial: state
+                        .serial_tracker
+                        .get(SerialKind::MousePress)
+                        .max(state.serial_tracker.get(SerialKind::TouchDown))
+                        .max(state.serial_tracker.get(SerialKind::KeyPress)),
+                })
+        } else {
+            None
+        };
+
         let (window, surface_id) = WaylandWindow::new(
             handle,
             state.globals.clone(),
@@ -649,6 +955,7 @@ impl LinuxClient for WaylandClient {
             WaylandClientStatePtr(Rc::downgrade(&self.0)),
             params,
             state.common.appearance,
+            parent,
         )?;
         state.windows.insert(surface_id, window.0.clone());
 
@@ -660,24 +967,37 @@ impl LinuxClient for WaylandClient {
 
         let need_update = state
             .cursor_style
-            .map_or(true, |current_style| current_style != style);
+            .as_ref()
+            .map_or(true, |current_style| *current_style != style);
 
         if need_update {
             let serial = state.serial_tracker.get(SerialKind::MouseEnter);
-            state.cursor_style = Some(style);
-
-            if let Some(cursor_shape_device) = &state.cursor_shape_device {
-                cursor_shape_device.set_shape(serial, style.to_shape());
-            } else if let Some(focused_window) = &state.mouse_focused_window {
-                // cursor-shape-v1 isn't supported, set the cursor using a surface.
-                let wl_pointer = state
-                    .wl_pointer
-                    .clone()
-                    .expect("window is focused by pointer");
-                let scale = focused_window.primary_output_scale();
-                state
-                    .cursor
-                    .set_icon(&wl_pointer, serial, &style.to_icon_name(), scale);
+            state.cursor_style = Some(style.clone());
+
+            for tool in state.tablet_tools.values() {
+                if let (Some(cursor_shape_device), Some(_)) =
+                    (&tool.cursor_shape_device, &tool.window)
+                {
+                    cursor_shape_device.set_shape(tool.proximity_serial, style.to_shape());
+                }
+            }
+
+            match &state.cursor_shape_device {
+                Some(cursor_shape_device) if !matches!(style, CursorStyle::Image(_)) => {
+                    cursor_shape_device.set_shape(serial, style.to_shape());
+                }
+                _ => {
+                    if let Some(focused_window) = &state.mouse_focused_window {
+                        // cursor-shape-v1 isn't supported or can't show this cursor, set the
+                        // cursor using a surface.
+                        let wl_pointer = state
+                            .wl_pointer
+                            .clone()
+                            .expect("window is focused by pointer");
+                        let scale = focused_window.primary_output_scale();
+                        state.cursor.set_style(&wl_pointer, serial, &style, scale);
+                    }
+                }
             }
         }
     }
@@ -795,6 +1115,13 @@ impl LinuxClient for WaylandClient {
         None
     }
 
+    fn listen_for_other_instances(&self, listener: SingleInstanceListener) {
+        let loop_handle = self.0.borrow().loop_handle.clone();
+        listen_for_forwarded_launches(&loop_handle, listener, |client, launch| {
+            client.handle_forwarded_launch(launch)
+        });
+    }
+
     fn compositor_name(&self) -> &'static str {
         "Wayland"
     }
@@ -868,6 +1195,20 @@ delegate_noop!(WaylandClientStatePtr: ignore wl_region::WlRegion);
 delegate_noop!(WaylandClientStatePtr: ignore wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1);
 delegate_noop!(WaylandClientStatePtr: ignore zxdg_decoration_manager_v1::ZxdgDecorationManagerV1);
 delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_blur_manager::OrgKdeKwinBlurManager);
+delegate_noop!(WaylandClientStatePtr: ignore zwlr_layer_shell_v1::ZwlrLayerShellV1);
+delegate_noop!(WaylandClientStatePtr: ignore xdg_positioner::XdgPositioner);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_pointer_gestures_v1::ZwpPointerGesturesV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_tablet_manager_v2::ZwpTabletManagerV2);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_pointer_constraints_v1::ZwpPointerConstraintsV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_keyboard_shortcuts_inhibit_manager_v1::ZwpKeyboardShortcutsInhibitManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore xdg_toplevel_icon_manager_v1::XdgToplevelIconManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore xdg_toplevel_icon_v1::XdgToplevelIconV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_tablet_v2::ZwpTabletV2);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2);
 delegate_noop!(WaylandClientStatePtr: ignore zwp_text_input_manager_v3::ZwpTextInputManagerV3);
 delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_blur::OrgKdeKwinBlur);
 delegate_noop!(WaylandClientStatePtr: ignore wp_viewporter::WpViewporter);
@@ -1012,6 +1353,46 @@ impl Dispatch<xdg_toplevel::XdgToplevel, ObjectId> for WaylandClientStatePtr {
     }
 }
 
+impl Dispatch<xdg_popup::XdgPopup, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        _: &xdg_popup::XdgPopup,
+        event: xdg_popup::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+
+        drop(state);
+        window.handle_popup_event(event);
+    }
+}
+
+impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        _: &zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+        event: zwlr_layer_surface_v1::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+
+        drop(state);
+        window.handle_layer_surface_event(event);
+    }
+}
+
 impl Dispatch<xdg_wm_base::XdgWmBase, ()> for WaylandClientStatePtr {
     fn event(
         _: &mut Self,
@@ -1099,12 +1480,46 @@ impl Dispatch<wl_seat::WlSeat, ()> for WaylandClientStatePtr {
                     .as_ref()
                     .map(|cursor_shape_manager| cursor_shape_manager.get_pointer(&pointer, qh, ()));
 
+                if let Some(pointer_gestures) = state.pointer_gestures.take() {
+                    pointer_gestures.destroy();
+                }
+                state.pointer_gestures =
+                    state
+                        .globals
+                        .pointer_gestures
+                        .as_ref()
+                        .map(|pointer_gestures| PointerGestures {
+                            swipe: pointer_gestures.get_swipe_gesture(&pointer, qh, ()),
+                            pinch: pointer_gestures.get_pinch_gesture(&pointer, qh, ()),
+                            // Hold gestures were added in version 3 of the protocol.
+                            hold: (pointer_gestures.version() >= 3)
+                                .then(|| pointer_gestures.get_hold_gesture(&pointer, qh, ())),
+                        });
+
+                if let Some(relative_pointer) = state.relative_pointer.take() {
+                    relative_pointer.destroy();
+                }
+                state.relative_pointer = state.globals.relative_pointer_manager.as_ref().map(
+                    |relative_pointer_manager| {
+                        relative_pointer_manager.get_relative_pointer(&pointer, qh, ())
+                    },
+                );
+
                 if let Some(wl_pointer) = &state.wl_pointer {
                     wl_pointer.release();
                 }
 
                 state.wl_pointer = Some(pointer);
             }
+            if capabilities.contains(wl_seat::Capability::Touch) {
+                let touch = seat.get_touch(qh, ());
+
+                if let Some(wl_touch) = &state.wl_touch {
+                    wl_touch.release();
+                }
+
+                state.wl_touch = Some(touch);
+            }
         }
     }
 }
@@ -1443,6 +1858,619 @@ fn linux_button_to_gpui(button: u32) -> Option<MouseButton> {
     })
 }
 
+impl Dispatch<wl_touch::WlTouch, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        _: &wl_touch::WlTouch,
+        event: wl_touch::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+
+        match event {
+            wl_touch::Event::Down {
+                serial,
+                surface,
+                id,
+                x,
+                y,
+                ..
+            } => {
+                state.serial_tracker.update(SerialKind::TouchDown, serial);
+                let Some(window) = get_window(&mut state, &surface.id()) else {
+                    return;
+                };
+                let position = point(px(x as f32), px(y as f32));
+                state.touches.insert(
+                    id,
+                    TouchPoint {
+                        window: window.clone(),
+                        position,
+                    },
+                );
+                let input = PlatformInput::Touch(TouchEvent {
+                    id: id as u64,
+                    phase: TouchPhase::Started,
+                    position,
+                    modifiers: state.modifiers,
+                });
+                drop(state);
+                window.handle_input(input);
+            }
+            wl_touch::Event::Motion { id, x, y, .. } => {
+                let modifiers = state.modifiers;
+                let Some(touch) = state.touches.get_mut(&id) else {
+                    return;
+                };
+                touch.position = point(px(x as f32), px(y as f32));
+                let window = touch.window.clone();
+                let input = PlatformInput::Touch(TouchEvent {
+                    id: id as u64,
+                    phase: TouchPhase::Moved,
+                    position: touch.position,
+                    modifiers,
+                });
+                drop(state);
+                window.handle_input(input);
+            }
+            wl_touch::Event::Up { id, .. } => {
+                let Some(touch) = state.touches.remove(&id) else {
+                    return;
+                };
+                let input = PlatformInput::Touch(TouchEvent {
+                    id: id as u64,
+                    phase: TouchPhase::Ended,
+                    position: touch.position,
+                    modifiers: state.modifiers,
+                });
+                drop(state);
+                touch.window.handle_input(input);
+            }
+            // The compositor took over all touches, e.g. for a gesture of its own.
+            wl_touch::Event::Cancel => {
+                let modifiers = state.modifiers;
+                let touches = mem::take(&mut state.touches);
+                drop(state);
+                for (id, touch) in touches {
+                    touch.window.handle_input(PlatformInput::Touch(TouchEvent {
+                        id: id as u64,
+                        phase: TouchPhase::Cancelled,
+                        position: touch.position,
+                        modifiers,
+                    }));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1, ()>
+    for WaylandClientStatePtr
+{
+    fn event(
+        this: &mut Self,
+        _: &zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1,
+        event: zwp_pointer_gesture_pinch_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = state.mouse_focused_window.clone() else {
+            return;
+        };
+
+        let (phase, delta) = match event {
+            zwp_pointer_gesture_pinch_v1::Event::Begin { fingers, .. } => {
+                state.gesture.begin_pinch(fingers);
+                (TouchPhase::Started, Point::default())
+            }
+            zwp_pointer_gesture_pinch_v1::Event::Update {
+                dx,
+                dy,
+                scale,
+                rotation,
+                ..
+            } => {
+                state.gesture.update_pinch(scale, rotation);
+                (TouchPhase::Moved, point(px(dx as f32), px(dy as f32)))
+            }
+            zwp_pointer_gesture_pinch_v1::Event::End { cancelled, .. } => (
+                if cancelled != 0 {
+                    TouchPhase::Cancelled
+                } else {
+                    TouchPhase::Ended
+                },
+                Point::default(),
+            ),
+            _ => return,
+        };
+
+        let input = PlatformInput::Pinch(PinchEvent {
+            position: state.mouse_location.unwrap_or_default(),
+            phase,
+            scale: state.gesture.scale,
+            rotation: state.gesture.rotation,
+            delta,
+            fingers: state.gesture.fingers,
+            modifiers: state.modifiers,
+        });
+        drop(state);
+        window.handle_input(input);
+    }
+}
+
+impl Dispatch<zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1, ()>
+    for WaylandClientStatePtr
+{
+    fn event(
+        this: &mut Self,
+        _: &zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1,
+        event: zwp_pointer_gesture_swipe_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = state.mouse_focused_window.clone() else {
+            return;
+        };
+
+        let (phase, delta) = match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin { fingers, .. } => {
+                state.gesture = GestureState {
+                    fingers,
+                    ..GestureState::default()
+                };
+                (TouchPhase::Started, Point::default())
+            }
+            zwp_pointer_gesture_swipe_v1::Event::Update { dx, dy, .. } => {
+                (TouchPhase::Moved, point(px(dx as f32), px(dy as f32)))
+            }
+            zwp_pointer_gesture_swipe_v1::Event::End { cancelled, .. } => (
+                if cancelled != 0 {
+                    TouchPhase::Cancelled
+                } else {
+                    TouchPhase::Ended
+                },
+                Point::default(),
+            ),
+            _ => return,
+        };
+
+        let input = PlatformInput::Swipe(SwipeEvent {
+            position: state.mouse_location.unwrap_or_default(),
+            phase,
+            delta,
+            fingers: state.gesture.fingers,
+            modifiers: state.modifiers,
+        });
+        drop(state);
+        window.handle_input(input);
+    }
+}
+
+impl Dispatch<zwp_pointer_gesture_hold_v1::ZwpPointerGestureHoldV1, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        _: &zwp_pointer_gesture_hold_v1::ZwpPointerGestureHoldV1,
+        event: zwp_pointer_gesture_hold_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = state.mouse_focused_window.clone() else {
+            return;
+        };
+
+        let phase = match event {
+            zwp_pointer_gesture_hold_v1::Event::Begin { fingers, .. } => {
+                state.gesture = GestureState {
+                    fingers,
+                    ..GestureState::default()
+                };
+                TouchPhase::Started
+            }
+            zwp_pointer_gesture_hold_v1::Event::End { cancelled, .. } => {
+                if cancelled != 0 {
+                    TouchPhase::Cancelled
+                } else {
+                    TouchPhase::Ended
+                }
+            }
+            _ => return,
+        };
+
+        let input = PlatformInput::Hold(HoldEvent {
+            position: state.mouse_location.unwrap_or_default(),
+            phase,
+            fingers: state.gesture.fingers,
+            modifiers: state.modifiers,
+        });
+        drop(state);
+        window.handle_input(input);
+    }
+}
+
+impl Dispatch<zwp_relative_pointer_v1::ZwpRelativePointerV1, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        _: &zwp_relative_pointer_v1::ZwpRelativePointerV1,
+        event: zwp_relative_pointer_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let state = client.borrow();
+        let Some(window) = state.mouse_focused_window.clone() else {
+            return;
+        };
+
+        let Some(relative_move) = relative_mouse_move(
+            event,
+            window.is_pointer_constrained(),
+            state.mouse_location.unwrap_or_default(),
+            state.button_pressed,
+            state.modifiers,
+        ) else {
+            return;
+        };
+        drop(state);
+        window.handle_input(PlatformInput::RelativeMouseMove(relative_move));
+    }
+}
+
+/// Converts relative pointer motion into an event, which is only needed while the pointer is
+/// constrained, as regular motion events already cover an unconstrained pointer.
+fn relative_mouse_move(
+    event: zwp_relative_pointer_v1::Event,
+    pointer_constrained: bool,
+    position: Point<Pixels>,
+    pressed_button: Option<MouseButton>,
+    modifiers: Modifiers,
+) -> Option<RelativeMouseMoveEvent> {
+    let zwp_relative_pointer_v1::Event::RelativeMotion {
+        dx,
+        dy,
+        dx_unaccel,
+        dy_unaccel,
+        ..
+    } = event
+    else {
+        return None;
+    };
+    pointer_constrained.then(|| RelativeMouseMoveEvent {
+        position,
+        delta: point(px(dx as f32), px(dy as f32)),
+        unaccelerated_delta: point(px(dx_unaccel as f32), px(dy_unaccel as f32)),
+        pressed_button,
+        modifiers,
+    })
+}
+
+impl Dispatch<zwp_keyboard_shortcuts_inhibitor_v1::ZwpKeyboardShortcutsInhibitorV1, ObjectId>
+    for WaylandClientStatePtr
+{
+    fn event(
+        this: &mut Self,
+        _: &zwp_keyboard_shortcuts_inhibitor_v1::ZwpKeyboardShortcutsInhibitorV1,
+        event: zwp_keyboard_shortcuts_inhibitor_v1::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+
+        drop(state);
+        match event {
+            zwp_keyboard_shortcuts_inhibitor_v1::Event::Active => {
+                window.handle_keyboard_shortcuts_inhibit_event(true)
+            }
+            zwp_keyboard_shortcuts_inhibitor_v1::Event::Inactive => {
+                window.handle_keyboard_shortcuts_inhibit_event(false)
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_locked_pointer_v1::ZwpLockedPointerV1, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        _: &zwp_locked_pointer_v1::ZwpLockedPointerV1,
+        event: zwp_locked_pointer_v1::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+
+        drop(state);
+        match event {
+            zwp_locked_pointer_v1::Event::Locked => window.handle_pointer_constraint_event(true),
+            zwp_locked_pointer_v1::Event::Unlocked => window.handle_pointer_constraint_event(false),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_confined_pointer_v1::ZwpConfinedPointerV1, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        _: &zwp_confined_pointer_v1::ZwpConfinedPointerV1,
+        event: zwp_confined_pointer_v1::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+
+        drop(state);
+        match event {
+            zwp_confined_pointer_v1::Event::Confined => {
+                window.handle_pointer_constraint_event(true)
+            }
+            zwp_confined_pointer_v1::Event::Unconfined => {
+                window.handle_pointer_constraint_event(false)
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_tablet_seat_v2::ZwpTabletSeatV2, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        _: &zwp_tablet_seat_v2::ZwpTabletSeatV2,
+        event: zwp_tablet_seat_v2::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+
+        if let zwp_tablet_seat_v2::Event::ToolAdded { id: tool } = event {
+            let cursor_shape_device = state
+                .globals
+                .cursor_shape_manager
+                .as_ref()
+                .map(|cursor_shape_manager| cursor_shape_manager.get_tablet_tool_v2(&tool, qh, ()));
+            state
+                .tablet_tools
+                .insert(tool.id(), TabletTool::new(cursor_shape_device));
+        }
+    }
+
+    event_created_child!(WaylandClientStatePtr, zwp_tablet_seat_v2::ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (zwp_tablet_v2::ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (zwp_tablet_tool_v2::ZwpTabletToolV2, ()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (zwp_tablet_pad_v2::ZwpTabletPadV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_tool_v2::ZwpTabletToolV2, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        tablet_tool: &zwp_tablet_tool_v2::ZwpTabletToolV2,
+        event: zwp_tablet_tool_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let cursor_style = state.cursor_style.clone();
+        let modifiers = state.modifiers;
+
+        if let zwp_tablet_tool_v2::Event::ProximityIn {
+            serial, surface, ..
+        } = &event
+        {
+            let window = get_window(&mut state, &surface.id());
+            if let Some(tool) = state.tablet_tools.get_mut(&tablet_tool.id()) {
+                tool.proximity_serial = *serial;
+                tool.window = window;
+                tool.push_phase(StylusPhase::ProximityIn);
+                if let Some(cursor_shape_device) = &tool.cursor_shape_device {
+                    cursor_shape_device.set_shape(
+                        *serial,
+                        cursor_style.unwrap_or(CursorStyle::Arrow).to_shape(),
+                    );
+                }
+            }
+            return;
+        }
+
+        let Some(tool) = state.tablet_tools.get_mut(&tablet_tool.id()) else {
+            return;
+        };
+
+        match event {
+            zwp_tablet_tool_v2::Event::Type { tool_type } => {
+                tool.tool = match tool_type {
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Eraser) => StylusTool::Eraser,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Brush) => StylusTool::Brush,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Pencil) => StylusTool::Pencil,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Airbrush) => StylusTool::Airbrush,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Mouse)
+                    | WEnum::Value(zwp_tablet_tool_v2::Type::Lens) => StylusTool::Mouse,
+                    _ => StylusTool::Pen,
+                };
+            }
+            zwp_tablet_tool_v2::Event::HardwareSerial {
+                hardware_serial_hi,
+                hardware_serial_lo,
+            } => {
+                tool.hardware_serial =
+                    Some(((hardware_serial_hi as u64) << 32) | hardware_serial_lo as u64);
+            }
+            zwp_tablet_tool_v2::Event::ProximityOut => {
+                tool.push_phase(StylusPhase::ProximityOut);
+            }
+            zwp_tablet_tool_v2::Event::Down { .. } => {
+                tool.in_contact = true;
+                tool.push_phase(StylusPhase::Down);
+            }
+            zwp_tablet_tool_v2::Event::Up => {
+                tool.in_contact = false;
+                tool.push_phase(StylusPhase::Up);
+            }
+            zwp_tablet_tool_v2::Event::Motion { x, y } => {
+                tool.position = point(px(x as f32), px(y as f32));
+                tool.push_phase(StylusPhase::Moved);
+            }
+            zwp_tablet_tool_v2::Event::Pressure { pressure } => {
+                tool.pressure = pressure as f32 / 65535.0;
+                tool.push_phase(StylusPhase::Moved);
+            }
+            zwp_tablet_tool_v2::Event::Distance { distance } => {
+                tool.distance = distance as f32 / 65535.0;
+                tool.push_phase(StylusPhase::Moved);
+            }
+            zwp_tablet_tool_v2::Event::Tilt { tilt_x, tilt_y } => {
+                tool.tilt = point(tilt_x as f32, tilt_y as f32);
+                tool.push_phase(StylusPhase::Moved);
+            }
+            zwp_tablet_tool_v2::Event::Rotation { degrees } => {
+                tool.rotation = degrees as f32;
+                tool.push_phase(StylusPhase::Moved);
+            }
+            zwp_tablet_tool_v2::Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => {
+                let button = linux_button_to_stylus(button);
+                if button_state == WEnum::Value(zwp_tablet_tool_v2::ButtonState::Pressed) {
+                    if !tool.pressed_buttons.contains(&button) {
+                        tool.pressed_buttons.push(button);
+                    }
+                    tool.push_phase(StylusPhase::ButtonDown(button));
+                } else {
+                    tool.pressed_buttons.retain(|pressed| *pressed != button);
+                    tool.push_phase(StylusPhase::ButtonUp(button));
+                }
+            }
+            zwp_tablet_tool_v2::Event::Frame { .. } => {
+                let phases = mem::take(&mut tool.pending_phases);
+                let Some(window) = tool.window.clone() else {
+                    return;
+                };
+                let event = StylusEvent {
+                    phase: StylusPhase::default(),
+                    tool: tool.tool,
+                    tool_id: tool
+                        .hardware_serial
+                        .unwrap_or_else(|| tablet_tool.id().protocol_id() as u64),
+                    position: tool.position,
+                    in_contact: tool.in_contact,
+                    pressure: tool.pressure,
+                    distance: tool.distance,
+                    tilt: tool.tilt,
+                    rotation: tool.rotation,
+                    pressed_buttons: tool.pressed_buttons.clone(),
+                    modifiers,
+                };
+                if phases.contains(&StylusPhase::ProximityOut) {
+                    tool.window = None;
+                    tool.in_contact = false;
+                    tool.pressed_buttons.clear();
+                }
+                drop(state);
+                for phase in phases {
+                    window.handle_input(PlatformInput::Stylus(StylusEvent {
+                        phase,
+                        ..event.clone()
+                    }));
+                }
+            }
+            zwp_tablet_tool_v2::Event::Removed => {
+                if let Some(tool) = state.tablet_tools.remove(&tablet_tool.id()) {
+                    if let Some(cursor_shape_device) = tool.cursor_shape_device {
+                        cursor_shape_device.destroy();
+                    }
+                }
+                tablet_tool.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn linux_button_to_stylus(button: u32) -> StylusButton {
+    // Stylus button codes from linux/input-event-codes.h
+    const BTN_STYLUS: u32 = 0x14b;
+    const BTN_STYLUS2: u32 = 0x14c;
+    const BTN_STYLUS3: u32 = 0x149;
+
+    match button {
+        BTN_STYLUS => StylusButton::Primary,
+        BTN_STYLUS2 => StylusButton::Secondary,
+        BTN_STYLUS3 => StylusButton::Tertiary,
+        _ => StylusButton::Other(button),
+    }
+}
+
+// Pads (the buttons, rings and strips on the tablet itself) aren't surfaced yet,
+// but their objects still need to be created when the compositor announces them.
+impl Dispatch<zwp_tablet_pad_v2::ZwpTabletPadV2, ()> for WaylandClientStatePtr {
+    fn event(
+        _: &mut Self,
+        pad: &zwp_tablet_pad_v2::ZwpTabletPadV2,
+        event: zwp_tablet_pad_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_pad_v2::Event::Removed = event {
+            pad.destroy();
+        }
+    }
+
+    event_created_child!(WaylandClientStatePtr, zwp_tablet_pad_v2::ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, ()> for WaylandClientStatePtr {
+    fn event(
+        _: &mut Self,
+        _: &zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2,
+        _: zwp_tablet_pad_group_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+
+    event_created_child!(WaylandClientStatePtr, zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2, ()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2, ()),
+    ]);
+}
+
 impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
     fn event(
         this: &mut Self,
@@ -1473,17 +2501,17 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
                     if state.enter_token.is_some() {
                         state.enter_token = None;
                     }
-                    if let Some(style) = state.cursor_style {
-                        if let Some(cursor_shape_device) = &state.cursor_shape_device {
-                            cursor_shape_device.set_shape(serial, style.to_shape());
-                        } else {
-                            let scale = window.primary_output_scale();
-                            state.cursor.set_icon(
-                                &wl_pointer,
-                                serial,
-                                &style.to_icon_name(),
-                                scale,
-                            );
+                    if let Some(style) = state.cursor_style.clone() {
+                        match &state.cursor_shape_device {
+                            Some(cursor_shape_device)
+                                if !matches!(style, CursorStyle::Image(_)) =>
+                            {
+                                cursor_shape_device.set_shape(serial, style.to_shape());
+                            }
+                            _ => {
+                                let scale = window.primary_output_scale();
+                                state.cursor.set_style(&wl_pointer, serial, &style, scale);
+                            }
                         }
                     }
                     drop(state);
@@ -1994,6 +3022,31 @@ impl Dispatch<wl_data_source::WlDataSource, ()> for WaylandClientStatePtr {
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
+        let is_outgoing_drag = state
+            .outgoing_drag
+            .as_ref()
+            .is_some_and(|drag| drag.data_source == *data_source);
+        if is_outgoing_drag {
+            let Some(event) = outgoing_drag_event(&mut state, event) else {
+                return;
+            };
+            let on_event = state
+                .outgoing_drag
+                .as_ref()
+                .map(|drag| drag.on_event.clone());
+            if matches!(
+                event,
+                ExternalDragEvent::Finished { .. } | ExternalDragEvent::Cancelled
+            ) {
+                state.outgoing_drag = None;
+            }
+            drop(state);
+            if let Some(on_event) = on_event {
+                (on_event.borrow_mut())(event);
+            }
+            return;
+        }
+
         match event {
             wl_data_source::Event::Send { mime_type, fd } => {
                 state.clipboard.send(mime_type, fd);
@@ -2006,6 +3059,97 @@ impl Dispatch<wl_data_source::WlDataSource, ()> for WaylandClientStatePtr {
     }
 }
 
+/// Converts an event of the data source of an outgoing drag into the event reported to the app.
+fn outgoing_drag_event(
+    state: &mut WaylandClientState,
+    event: wl_data_source::Event,
+) -> Option<ExternalDragEvent> {
+    let drag = state.outgoing_drag.as_mut()?;
+    match event {
+        wl_data_source::Event::Send { mime_type, fd } => {
+            if let Some((_, bytes)) = drag.data.iter().find(|(offered, _)| *offered == mime_type) {
+                state.clipboard.send_internal(fd, bytes.to_vec());
+            }
+            None
+        }
+        wl_data_source::Event::Target { mime_type } => {
+            Some(ExternalDragEvent::Accepted { mime_type })
+        }
+        wl_data_source::Event::Action { dnd_action } => {
+            drag.action = match dnd_action {
+                WEnum::Value(dnd_action) => DragAction::from_wl(dnd_action),
+                WEnum::Unknown(_) => None,
+            };
+            Some(ExternalDragEvent::ActionChanged {
+                action: drag.action,
+            })
+        }
+        wl_data_source::Event::DndDropPerformed => Some(ExternalDragEvent::Dropped),
+        wl_data_source::Event::DndFinished => {
+            drag.data_source.destroy();
+            Some(ExternalDragEvent::Finished {
+                action: drag.action.unwrap_or(DragAction::Copy),
+            })
+        }
+        wl_data_source::Event::Cancelled => {
+            drag.data_source.destroy();
+            Some(ExternalDragEvent::Cancelled)
+        }
+        _ => None,
+    }
+}
+
+/// Encodes the data of an outgoing drag in the MIME types it's offered as.
+fn drag_data(entries: &[ExternalDragEntry]) -> Vec<(String, Arc<[u8]>)> {
+    let mut data = Vec::new();
+    for entry in entries {
+        match entry {
+            ExternalDragEntry::Paths(paths) => {
+                let uri_list = paths
+                    .iter()
+                    .filter_map(|path| Url::from_file_path(path).ok())
+                    .map(|url| format!("{url}\r\n"))
+                    .collect::<String>();
+                data.push((
+                    FILE_LIST_MIME_TYPE.to_string(),
+                    uri_list.into_bytes().into(),
+                ));
+            }
+            ExternalDragEntry::Text(text) => {
+                let bytes: Arc<[u8]> = text.as_bytes().into();
+                data.push((TEXT_MIME_TYPE.to_string(), bytes.clone()));
+                data.push(("text/plain".to_string(), bytes));
+            }
+            ExternalDragEntry::Data { mime_type, bytes } => {
+                data.push((mime_type.clone(), bytes.clone()));
+            }
+        }
+    }
+    data
+}
+
+impl DragAction {
+    fn to_wl(self) -> DndAction {
+        match self {
+            DragAction::Copy => DndAction::Copy,
+            DragAction::Move => DndAction::Move,
+            DragAction::Ask => DndAction::Ask,
+        }
+    }
+
+    fn from_wl(action: DndAction) -> Option<Self> {
+        if action.contains(DndAction::Move) {
+            Some(DragAction::Move)
+        } else if action.contains(DndAction::Copy) {
+            Some(DragAction::Copy)
+        } else if action.contains(DndAction::Ask) {
+            Some(DragAction::Ask)
+        } else {
+            None
+        }
+    }
+}
+
 impl Dispatch<zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1, ()>
     for WaylandClientStatePtr
 {
@@ -2094,3 +3238,60 @@ impl Dispatch<zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1, ()>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinch_rotation_accumulates() {
+        let mut gesture = GestureState::default();
+        gesture.begin_pinch(2);
+        assert_eq!(
+            (gesture.fingers, gesture.scale, gesture.rotation),
+            (2, 1.0, 0.0)
+        );
+
+        gesture.update_pinch(1.5, 10.0);
+        gesture.update_pinch(2.0, -4.0);
+        assert_eq!((gesture.scale, gesture.rotation), (2.0, 6.0));
+
+        // A new pinch starts from no rotation.
+        gesture.begin_pinch(3);
+        gesture.update_pinch(0.5, 2.0);
+        assert_eq!(
+            (gesture.fingers, gesture.scale, gesture.rotation),
+            (3, 0.5, 2.0)
+        );
+    }
+
+    #[test]
+    fn test_relative_mouse_move_only_while_constrained() {
+        let motion = || zwp_relative_pointer_v1::Event::RelativeMotion {
+            utime_hi: 0,
+            utime_lo: 0,
+            dx: 3.0,
+            dy: -2.0,
+            dx_unaccel: 1.5,
+            dy_unaccel: -1.0,
+        };
+        let position = point(px(10.), px(20.));
+
+        assert!(
+            relative_mouse_move(motion(), false, position, None, Modifiers::default()).is_none()
+        );
+
+        let event = relative_mouse_move(
+            motion(),
+            true,
+            position,
+            Some(MouseButton::Left),
+            Modifiers::default(),
+        )
+        .unwrap();
+        assert_eq!(event.position, position);
+        assert_eq!(event.delta, point(px(3.), px(-2.)));
+        assert_eq!(event.unaccelerated_delta, point(px(1.5), px(-1.)));
+        assert_eq!(event.pressed_button, Some(MouseButton::Left));
+    }
+}
@@ -1,9 +1,12 @@
-use crate::Globals;
+use super::create_shm_buffer;
+use crate::{CursorImage, CursorStyle, Globals, Point, Size, WaylandClientStatePtr};
+use anyhow::Result;
 use util::ResultExt;
 
+use wayland_client::protocol::wl_buffer::WlBuffer;
 use wayland_client::protocol::wl_surface::WlSurface;
 use wayland_client::protocol::{wl_pointer::WlPointer, wl_shm::WlShm};
-use wayland_client::Connection;
+use wayland_client::{Connection, QueueHandle};
 use wayland_cursor::{CursorImageBuffer, CursorTheme};
 
 pub(crate) struct Cursor {
@@ -14,11 +17,25 @@ pub(crate) struct Cursor {
     size: u32,
     shm: WlShm,
     connection: Connection,
+    qh: QueueHandle<WaylandClientStatePtr>,
+    image: Option<UploadedCursorImage>,
+}
+
+/// A custom cursor image, uploaded for a single scale factor.
+struct UploadedCursorImage {
+    id: usize,
+    scale: i32,
+    buffer: WlBuffer,
+    size: Size<u32>,
+    hotspot: Point<u32>,
 }
 
 impl Drop for Cursor {
     fn drop(&mut self) {
         self.theme.take();
+        if let Some(image) = self.image.take() {
+            image.buffer.destroy();
+        }
         self.surface.destroy();
     }
 }
@@ -32,6 +49,8 @@ impl Cursor {
             surface: globals.compositor.create_surface(&globals.qh, ()),
             shm: globals.shm.clone(),
             connection: connection.clone(),
+            qh: globals.qh.clone(),
+            image: None,
             size,
         }
     }
@@ -78,6 +97,19 @@ impl Cursor {
         self.set_theme_size(size);
     }
 
+    pub fn set_style(
+        &mut self,
+        wl_pointer: &WlPointer,
+        serial_id: u32,
+        style: &CursorStyle,
+        scale: i32,
+    ) {
+        match style {
+            CursorStyle::Image(image) => self.set_image(wl_pointer, serial_id, image, scale),
+            style => self.set_icon(wl_pointer, serial_id, &style.to_icon_name(), scale),
+        }
+    }
+
     pub fn set_icon(
         &mut self,
         wl_pointer: &WlPointer,
@@ -125,4 +157,57 @@ impl Cursor {
             log::warn!("Linux: Wayland: Unable to load cursor themes");
         }
     }
+    pub fn set_image(
+        &mut self,
+        wl_pointer: &WlPointer,
+        serial_id: u32,
+        image: &CursorImage,
+        scale: i32,
+    ) {
+        let is_uploaded = self.image.as_ref().map_or(false, |uploaded| {
+            uploaded.id == image.id() && uploaded.scale == scale
+        });
+        if !is_uploaded {
+            let Some(uploaded) = self.upload_image(image, scale).log_err() else {
+                return;
+            };
+            if let Some(previous) = self.image.replace(uploaded) {
+                previous.buffer.destroy();
+            }
+        }
+        let Some(uploaded) = &self.image else {
+            return;
+        };
+
+        self.surface.set_buffer_scale(scale);
+
+        wl_pointer.set_cursor(
+            serial_id,
+            Some(&self.surface),
+            uploaded.hotspot.x as i32 / scale,
+            uploaded.hotspot.y as i32 / scale,
+        );
+
+        self.surface.attach(Some(&uploaded.buffer), 0, 0);
+        self.surface.damage(
+            0,
+            0,
+            uploaded.size.width as i32,
+            uploaded.size.height as i32,
+        );
+        self.surface.commit();
+    }
+
+    fn upload_image(&self, image: &CursorImage, scale: i32) -> Result<UploadedCursorImage> {
+        let (bytes, size, hotspot) = image.to_premultiplied_bgra(scale as f32);
+        let buffer = create_shm_buffer(&self.shm, &self.qh, size, &bytes)?;
+
+        Ok(UploadedCursorImage {
+            id: image.id(),
+            scale,
+            buffer,
+            size,
+            hotspot,
+        })
+    }
 }
@@ -6,6 +6,7 @@ pub(crate) enum SerialKind {
     InputMethod,
     MouseEnter,
     MousePress,
+    TouchDown,
     KeyPress,
 }
 
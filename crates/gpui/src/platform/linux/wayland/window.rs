@@ -1,6 +1,7 @@
 use std::{
     cell::{Ref, RefCell, RefMut},
     ffi::c_void,
+    mem,
     ptr::NonNull,
     rc::Rc,
     sync::Arc,
@@ -11,27 +12,47 @@ use collections::HashMap;
 use futures::channel::oneshot::Receiver;
 
 use raw_window_handle as rwh;
+use util::ResultExt;
 use wayland_backend::client::ObjectId;
 use wayland_client::WEnum;
-use wayland_client::{protocol::wl_surface, Proxy};
+use wayland_client::{
+    protocol::{wl_buffer, wl_surface},
+    Proxy, QueueHandle,
+};
 use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1;
+use wayland_protocols::wp::idle_inhibit::zv1::client::{
+    zwp_idle_inhibit_manager_v1, zwp_idle_inhibitor_v1,
+};
+use wayland_protocols::wp::keyboard_shortcuts_inhibit::zv1::client::zwp_keyboard_shortcuts_inhibitor_v1;
+use wayland_protocols::wp::pointer_constraints::zv1::client::{
+    zwp_confined_pointer_v1, zwp_locked_pointer_v1, zwp_pointer_constraints_v1,
+};
 use wayland_protocols::wp::viewporter::client::wp_viewport;
 use wayland_protocols::xdg::decoration::zv1::client::zxdg_toplevel_decoration_v1;
 use wayland_protocols::xdg::shell::client::xdg_surface;
 use wayland_protocols::xdg::shell::client::xdg_toplevel::{self};
+use wayland_protocols::xdg::shell::client::{xdg_popup, xdg_positioner};
+use wayland_protocols::xdg::toplevel_icon::v1::client::xdg_toplevel_icon_v1;
 use wayland_protocols_plasma::blur::client::org_kde_kwin_blur;
+use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 
 use crate::platform::{
     blade::{BladeContext, BladeRenderer, BladeSurfaceConfig},
-    linux::wayland::{display::WaylandDisplay, serial::SerialKind},
-    PlatformAtlas, PlatformInputHandler, PlatformWindow,
+    layer_shell::{ExclusiveZone, KeyboardInteractivity, Layer, LayerShellOptions},
+    linux::{
+        wayland::{create_shm_buffer, display::WaylandDisplay, serial::SerialKind},
+        PortalIdleInhibitor,
+    },
+    rgba_to_premultiplied_bgra, PlatformAtlas, PlatformInputHandler, PlatformWindow,
 };
-use crate::scene::Scene;
+use crate::scene::{Damage, Scene};
 use crate::{
-    px, size, AnyWindowHandle, Bounds, Decorations, Globals, GpuSpecs, Modifiers, Output, Pixels,
-    PlatformDisplay, PlatformInput, Point, PromptLevel, RequestFrameOptions, ResizeEdge,
-    ScaledPixels, Size, Tiling, WaylandClientStatePtr, WindowAppearance,
-    WindowBackgroundAppearance, WindowBounds, WindowControls, WindowDecorations, WindowParams,
+    px, size, AnyWindowHandle, Bounds, Corner, Decorations, ExternalDrag, ExternalDragEvent,
+    ForegroundExecutor, Globals, GpuSpecs, Modifiers, Output, Pixels, PlatformDisplay,
+    PlatformInput, Point, PointerConstraint, PopoverOptions, PromptLevel, RequestFrameOptions,
+    ResizeEdge, ScaledPixels, Size, Tiling, WaylandClientStatePtr, WindowAppearance,
+    WindowBackgroundAppearance, WindowBounds, WindowControls, WindowDecorations, WindowIcon,
+    WindowKind, WindowParams,
 };
 
 #[derive(Default)]
@@ -45,6 +66,7 @@ pub(crate) struct Callbacks {
     should_close: Option<Box<dyn FnMut() -> bool>>,
     close: Option<Box<dyn FnOnce()>>,
     appearance_changed: Option<Box<dyn FnMut()>>,
+    keyboard_shortcuts_inhibit_change: Option<Box<dyn FnMut(bool)>>,
 }
 
 struct RawWindow {
@@ -75,20 +97,379 @@ struct InProgressConfigure {
     tiling: Tiling,
 }
 
+/// The role of a window's surface, which decides how the compositor places it.
+pub enum WaylandSurfaceState {
+    /// A regular application window.
+    Xdg {
+        xdg_surface: xdg_surface::XdgSurface,
+        toplevel: xdg_toplevel::XdgToplevel,
+        decoration: Option<zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1>,
+    },
+    /// A popover attached to another window, such as a menu or a tooltip.
+    Popup {
+        xdg_surface: xdg_surface::XdgSurface,
+        popup: xdg_popup::XdgPopup,
+    },
+    /// A part of the desktop shell, such as a panel or a launcher.
+    LayerShell {
+        layer_surface: zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+    },
+    /// The icon of a drag to another application. It's given its role when the drag starts.
+    DragIcon,
+}
+
+/// A pointer lock or confinement requested for a window. It only takes effect once the
+/// compositor activates it, and is deactivated for good when the window loses focus.
+enum PointerConstraintState {
+    Locked(zwp_locked_pointer_v1::ZwpLockedPointerV1),
+    Confined(zwp_confined_pointer_v1::ZwpConfinedPointerV1),
+}
+
+impl PointerConstraintState {
+    fn destroy(&self) {
+        match self {
+            PointerConstraintState::Locked(locked_pointer) => locked_pointer.destroy(),
+            PointerConstraintState::Confined(confined_pointer) => confined_pointer.destroy(),
+        }
+    }
+}
+
+/// Keeps the session from going idle while the window is visible, through the compositor if it
+/// supports it.
+enum IdleInhibitor {
+    Wayland(zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1),
+    Portal(PortalIdleInhibitor),
+}
+
+impl IdleInhibitor {
+    /// Creates an inhibitor for `surface` with the compositor's `manager`, falling back to the
+    /// portal when the compositor doesn't implement the idle inhibit protocol.
+    fn new(
+        manager: Option<&zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1>,
+        surface: &wl_surface::WlSurface,
+        qh: &QueueHandle<WaylandClientStatePtr>,
+        executor: &ForegroundExecutor,
+    ) -> Self {
+        match manager {
+            Some(manager) => Self::Wayland(manager.create_inhibitor(surface, qh, ())),
+            None => Self::Portal(PortalIdleInhibitor::new(executor)),
+        }
+    }
+}
+
+/// The icon set on a toplevel, along with the buffers it was created from, which have to be kept
+/// alive while it's in use.
+struct ToplevelIcon {
+    icon: xdg_toplevel_icon_v1::XdgToplevelIconV1,
+    buffers: Vec<wl_buffer::WlBuffer>,
+}
+
+impl ToplevelIcon {
+    fn destroy(&self) {
+        self.icon.destroy();
+        for buffer in &self.buffers {
+            buffer.destroy();
+        }
+    }
+}
+
+/// The window a popover is attached to, along with the serial of the input event that opened it.
+pub(crate) struct PopoverParent {
+    pub window: WaylandWindowStatePtr,
+    pub serial: u32,
+}
+
+impl WaylandSurfaceState {
+    fn new(
+        surface: &wl_surface::WlSurface,
+        globals: &Globals,
+        params: &WindowParams,
+        parent: Option<&PopoverParent>,
+    ) -> anyhow::Result<Self> {
+        if let WindowKind::Popover(options) = &params.kind {
+            let Some(parent) = parent else {
+                anyhow::bail!("the parent of a popover must be an open window");
+            };
+            if parent.window.state.borrow().surface_state.is_drag_icon() {
+                anyhow::bail!("popovers can't be attached to drag icons");
+            }
+            return Ok(Self::new_popup(
+                surface,
+                globals,
+                params.bounds.size,
+                options,
+                parent,
+            ));
+        }
+
+        if params.kind == WindowKind::DragIcon {
+            return Ok(Self::DragIcon);
+        }
+
+        if params.kind == WindowKind::LayerShell {
+            let options = params.layer_shell.clone().unwrap_or_default();
+            let Some(layer_shell) = globals.layer_shell.as_ref() else {
+                anyhow::bail!("layer shell windows are not supported by this compositor");
+            };
+            let layer_surface = layer_shell.get_layer_surface(
+                surface,
+                None,
+                options.layer.to_wlr(),
+                options.namespace.clone(),
+                &globals.qh,
+                surface.id(),
+            );
+            configure_layer_surface(&layer_surface, params.bounds.size, &options);
+            return Ok(Self::LayerShell { layer_surface });
+        }
+
+        let xdg_surface = globals
+            .wm_base
+            .get_xdg_surface(surface, &globals.qh, surface.id());
+        let toplevel = xdg_surface.get_toplevel(&globals.qh, surface.id());
+
+        if let Some(size) = params.window_min_size {
+            toplevel.set_min_size(size.width.0 as i32, size.height.0 as i32);
+        }
+
+        // Attempt to set up window decorations based on the requested configuration
+        let decoration = globals
+            .decoration_manager
+            .as_ref()
+            .map(|decoration_manager| {
+                decoration_manager.get_toplevel_decoration(&toplevel, &globals.qh, surface.id())
+            });
+
+        Ok(Self::Xdg {
+            xdg_surface,
+            toplevel,
+            decoration,
+        })
+    }
+
+    fn new_popup(
+        surface: &wl_surface::WlSurface,
+        globals: &Globals,
+        size: Size<Pixels>,
+        options: &PopoverOptions,
+        parent: &PopoverParent,
+    ) -> Self {
+        let parent_state = parent.window.state.borrow();
+
+        // The anchor is given in the parent's coordinates, but the positioner expects it
+        // relative to the parent's window geometry, which excludes the client-side decorations.
+        let geometry_origin = inset_by_tiling(
+            parent_state.bounds.map_origin(|_| px(0.0)),
+            parent_state.inset.unwrap_or(px(0.0)),
+            parent_state.tiling,
+        )
+        .origin;
+        let anchor_bounds = Bounds {
+            origin: options.anchor_bounds.origin - geometry_origin,
+            size: options.anchor_bounds.size,
+        }
+        .map(|v| v.0 as i32);
+
+        let positioner = globals.wm_base.create_positioner(&globals.qh, ());
+        positioner.set_size((size.width.0 as i32).max(1), (size.height.0 as i32).max(1));
+        positioner.set_anchor_rect(
+            anchor_bounds.origin.x,
+            anchor_bounds.origin.y,
+            anchor_bounds.size.width.max(1),
+            anchor_bounds.size.height.max(1),
+        );
+        positioner.set_anchor(options.anchor.to_xdg_anchor());
+        // The gravity is the direction the popup extends in, away from the attached corner.
+        positioner.set_gravity(options.attach.opposite_corner().to_xdg_gravity());
+        positioner.set_offset(options.offset.x.0 as i32, options.offset.y.0 as i32);
+
+        let mut constraint_adjustment = xdg_positioner::ConstraintAdjustment::empty();
+        if options.adjustment.flip {
+            constraint_adjustment |= xdg_positioner::ConstraintAdjustment::FlipX
+                | xdg_positioner::ConstraintAdjustment::FlipY;
+        }
+        if options.adjustment.slide {
+            constraint_adjustment |= xdg_positioner::ConstraintAdjustment::SlideX
+                | xdg_positioner::ConstraintAdjustment::SlideY;
+        }
+        if options.adjustment.resize {
+            constraint_adjustment |= xdg_positioner::ConstraintAdjustment::ResizeX
+                | xdg_positioner::ConstraintAdjustment::ResizeY;
+        }
+        positioner.set_constraint_adjustment(constraint_adjustment);
+
+        let xdg_surface = globals
+            .wm_base
+            .get_xdg_surface(surface, &globals.qh, surface.id());
+        let popup = match &parent_state.surface_state {
+            Self::Xdg {
+                xdg_surface: parent_surface,
+                ..
+            }
+            | Self::Popup {
+                xdg_surface: parent_surface,
+                ..
+            } => {
+                xdg_surface.get_popup(Some(parent_surface), &positioner, &globals.qh, surface.id())
+            }
+            // Layer surfaces aren't xdg surfaces, so they adopt the popup instead.
+            Self::LayerShell { layer_surface } => {
+                let popup = xdg_surface.get_popup(None, &positioner, &globals.qh, surface.id());
+                layer_surface.get_popup(&popup);
+                popup
+            }
+            Self::DragIcon => unreachable!("drag icons are rejected as popover parents"),
+        };
+        positioner.destroy();
+
+        if options.grab {
+            popup.grab(&globals.seat, parent.serial);
+        }
+
+        Self::Popup { xdg_surface, popup }
+    }
+
+    fn is_drag_icon(&self) -> bool {
+        matches!(self, Self::DragIcon)
+    }
+
+    fn toplevel(&self) -> Option<&xdg_toplevel::XdgToplevel> {
+        match self {
+            Self::Xdg { toplevel, .. } => Some(toplevel),
+            Self::Popup { .. } | Self::LayerShell { .. } | Self::DragIcon => None,
+        }
+    }
+
+    fn decoration(&self) -> Option<&zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1> {
+        match self {
+            Self::Xdg { decoration, .. } => decoration.as_ref(),
+            Self::Popup { .. } | Self::LayerShell { .. } | Self::DragIcon => None,
+        }
+    }
+
+    fn ack_configure(&self, serial: u32) {
+        match self {
+            Self::Xdg { xdg_surface, .. } | Self::Popup { xdg_surface, .. } => {
+                xdg_surface.ack_configure(serial)
+            }
+            Self::LayerShell { layer_surface } => layer_surface.ack_configure(serial),
+            Self::DragIcon => {}
+        }
+    }
+
+    fn set_geometry(&self, x: i32, y: i32, width: i32, height: i32) {
+        match self {
+            Self::Xdg { xdg_surface, .. } | Self::Popup { xdg_surface, .. } => {
+                xdg_surface.set_window_geometry(x, y, width, height)
+            }
+            // Layer surfaces are always sized by the compositor's configure events.
+            Self::LayerShell { .. } | Self::DragIcon => {}
+        }
+    }
+
+    fn destroy(&self) {
+        match self {
+            Self::Xdg {
+                xdg_surface,
+                toplevel,
+                decoration,
+            } => {
+                if let Some(decoration) = decoration {
+                    decoration.destroy();
+                }
+                toplevel.destroy();
+                xdg_surface.destroy();
+            }
+            Self::Popup { xdg_surface, popup } => {
+                popup.destroy();
+                xdg_surface.destroy();
+            }
+            Self::LayerShell { layer_surface } => layer_surface.destroy(),
+            Self::DragIcon => {}
+        }
+    }
+}
+
+fn configure_layer_surface(
+    layer_surface: &zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+    size: Size<Pixels>,
+    options: &LayerShellOptions,
+) {
+    let anchor = options.anchor;
+    let mut wlr_anchor = zwlr_layer_surface_v1::Anchor::empty();
+    if anchor.top {
+        wlr_anchor |= zwlr_layer_surface_v1::Anchor::Top;
+    }
+    if anchor.bottom {
+        wlr_anchor |= zwlr_layer_surface_v1::Anchor::Bottom;
+    }
+    if anchor.left {
+        wlr_anchor |= zwlr_layer_surface_v1::Anchor::Left;
+    }
+    if anchor.right {
+        wlr_anchor |= zwlr_layer_surface_v1::Anchor::Right;
+    }
+
+    // A size of zero lets the compositor stretch the surface between the opposite anchors,
+    // which is a protocol error along an axis that isn't anchored on both sides.
+    let width = if anchor.left && anchor.right {
+        0
+    } else {
+        size.width.0 as u32
+    };
+    let height = if anchor.top && anchor.bottom {
+        0
+    } else {
+        size.height.0 as u32
+    };
+
+    layer_surface.set_size(width, height);
+    layer_surface.set_anchor(wlr_anchor);
+    layer_surface.set_exclusive_zone(match options.exclusive_zone {
+        ExclusiveZone::Avoid => 0,
+        ExclusiveZone::Reserve(size) => size.0 as i32,
+        ExclusiveZone::Ignore => -1,
+    });
+    layer_surface.set_margin(
+        options.margin.top.0 as i32,
+        options.margin.right.0 as i32,
+        options.margin.bottom.0 as i32,
+        options.margin.left.0 as i32,
+    );
+
+    let keyboard_interactivity = match options.keyboard_interactivity {
+        KeyboardInteractivity::None => zwlr_layer_surface_v1::KeyboardInteractivity::None,
+        KeyboardInteractivity::Exclusive => zwlr_layer_surface_v1::KeyboardInteractivity::Exclusive,
+        // On-demand keyboard interactivity was added in version 4 of the protocol.
+        KeyboardInteractivity::OnDemand if layer_surface.version() >= 4 => {
+            zwlr_layer_surface_v1::KeyboardInteractivity::OnDemand
+        }
+        KeyboardInteractivity::OnDemand => {
+            log::warn!("on-demand keyboard interactivity is not supported by this compositor, falling back to exclusive");
+            zwlr_layer_surface_v1::KeyboardInteractivity::Exclusive
+        }
+    };
+    layer_surface.set_keyboard_interactivity(keyboard_interactivity);
+}
+
 pub struct WaylandWindowState {
-    xdg_surface: xdg_surface::XdgSurface,
+    surface_state: WaylandSurfaceState,
+    /// The window this popover is attached to.
+    parent: Option<WaylandWindowStatePtr>,
+    /// The popovers attached to this window, which have to be dismissed before it's destroyed.
+    popovers: Vec<WaylandWindowStatePtr>,
     acknowledged_first_configure: bool,
     pub surface: wl_surface::WlSurface,
-    decoration: Option<zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1>,
     app_id: Option<String>,
     appearance: WindowAppearance,
     blur: Option<org_kde_kwin_blur::OrgKdeKwinBlur>,
-    toplevel: xdg_toplevel::XdgToplevel,
     viewport: Option<wp_viewport::WpViewport>,
     outputs: HashMap<ObjectId, Output>,
     display: Option<(ObjectId, Output)>,
     globals: Globals,
     renderer: BladeRenderer,
+    /// Set when the next frame has to damage the whole surface, e.g. after a resize.
+    needs_full_damage: bool,
     bounds: Bounds<Pixels>,
     scale: f32,
     input_handler: Option<PlatformInputHandler>,
@@ -106,6 +487,13 @@ pub struct WaylandWindowState {
     in_progress_window_controls: Option<WindowControls>,
     window_controls: WindowControls,
     inset: Option<Pixels>,
+    pointer_constraint: Option<PointerConstraintState>,
+    pointer_constraint_active: bool,
+    idle_inhibitor: Option<IdleInhibitor>,
+    icon: Option<ToplevelIcon>,
+    keyboard_shortcuts_inhibitor:
+        Option<zwp_keyboard_shortcuts_inhibitor_v1::ZwpKeyboardShortcutsInhibitorV1>,
+    keyboard_shortcuts_inhibit_active: bool,
 }
 
 #[derive(Clone)]
@@ -119,9 +507,7 @@ impl WaylandWindowState {
     pub(crate) fn new(
         handle: AnyWindowHandle,
         surface: wl_surface::WlSurface,
-        xdg_surface: xdg_surface::XdgSurface,
-        toplevel: xdg_toplevel::XdgToplevel,
-        decoration: Option<zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1>,
+        surface_state: WaylandSurfaceState,
         appearance: WindowAppearance,
         viewport: Option<wp_viewport::WpViewport>,
         client: WaylandClientStatePtr,
@@ -150,23 +536,42 @@ impl WaylandWindowState {
             BladeRenderer::new(gpu_context, &raw_window, config)?
         };
 
+        // Popovers, layer surfaces and drag icons don't have any decorations or window controls.
+        let (decorations, window_controls) = match surface_state {
+            WaylandSurfaceState::Xdg { .. } => {
+                (WindowDecorations::Client, WindowControls::default())
+            }
+            WaylandSurfaceState::Popup { .. }
+            | WaylandSurfaceState::LayerShell { .. }
+            | WaylandSurfaceState::DragIcon => (
+                WindowDecorations::Server,
+                WindowControls {
+                    fullscreen: false,
+                    maximize: false,
+                    minimize: false,
+                    window_menu: false,
+                },
+            ),
+        };
+
         Ok(Self {
-            xdg_surface,
+            surface_state,
+            parent: None,
+            popovers: Vec::new(),
             acknowledged_first_configure: false,
             surface,
-            decoration,
             app_id: None,
             blur: None,
-            toplevel,
             viewport,
             globals,
             outputs: HashMap::default(),
             display: None,
             renderer,
+            needs_full_damage: true,
             bounds: options.bounds,
             scale: 1.0,
             input_handler: None,
-            decorations: WindowDecorations::Client,
+            decorations,
             background_appearance: WindowBackgroundAppearance::Opaque,
             fullscreen: false,
             maximized: false,
@@ -179,8 +584,14 @@ impl WaylandWindowState {
             active: false,
             hovered: false,
             in_progress_window_controls: None,
-            window_controls: WindowControls::default(),
+            window_controls,
             inset: None,
+            pointer_constraint: None,
+            pointer_constraint_active: false,
+            idle_inhibitor: None,
+            icon: None,
+            keyboard_shortcuts_inhibitor: None,
+            keyboard_shortcuts_inhibit_active: false,
         })
     }
 
@@ -221,18 +632,37 @@ impl Drop for WaylandWindow {
         let surface_id = state.surface.id();
         let client = state.client.clone();
 
-        state.renderer.destroy();
-        if let Some(decoration) = &state.decoration {
-            decoration.destroy();
+        if let Some(parent) = state.parent.take() {
+            parent
+                .state
+                .borrow_mut()
+                .popovers
+                .retain(|popover| !popover.ptr_eq(&self.0));
+        }
+        for popover in mem::take(&mut state.popovers) {
+            popover.dismiss();
         }
+
+        state.renderer.destroy();
         if let Some(blur) = &state.blur {
             blur.release();
         }
-        state.toplevel.destroy();
         if let Some(viewport) = &state.viewport {
             viewport.destroy();
         }
-        state.xdg_surface.destroy();
+        if let Some(pointer_constraint) = state.pointer_constraint.take() {
+            pointer_constraint.destroy();
+        }
+        if let Some(IdleInhibitor::Wayland(inhibitor)) = state.idle_inhibitor.take() {
+            inhibitor.destroy();
+        }
+        if let Some(inhibitor) = state.keyboard_shortcuts_inhibitor.take() {
+            inhibitor.destroy();
+        }
+        if let Some(icon) = state.icon.take() {
+            icon.destroy();
+        }
+        state.surface_state.destroy();
         state.surface.destroy();
 
         let state_ptr = self.0.clone();
@@ -257,6 +687,7 @@ impl WaylandWindow {
         self.0.state.borrow_mut()
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         handle: AnyWindowHandle,
         globals: Globals,
@@ -264,29 +695,22 @@ impl WaylandWindow {
         client: WaylandClientStatePtr,
         params: WindowParams,
         appearance: WindowAppearance,
+        parent: Option<PopoverParent>,
     ) -> anyhow::Result<(Self, ObjectId)> {
         let surface = globals.compositor.create_surface(&globals.qh, ());
-        let xdg_surface = globals
-            .wm_base
-            .get_xdg_surface(&surface, &globals.qh, surface.id());
-        let toplevel = xdg_surface.get_toplevel(&globals.qh, surface.id());
-
-        if let Some(size) = params.window_min_size {
-            toplevel.set_min_size(size.width.0 as i32, size.height.0 as i32);
-        }
+        let surface_state =
+            match WaylandSurfaceState::new(&surface, &globals, &params, parent.as_ref()) {
+                Ok(surface_state) => surface_state,
+                Err(err) => {
+                    surface.destroy();
+                    return Err(err);
+                }
+            };
 
         if let Some(fractional_scale_manager) = globals.fractional_scale_manager.as_ref() {
             fractional_scale_manager.get_fractional_scale(&surface, &globals.qh, surface.id());
         }
 
-        // Attempt to set up window decorations based on the requested configuration
-        let decoration = globals
-            .decoration_manager
-            .as_ref()
-            .map(|decoration_manager| {
-                decoration_manager.get_toplevel_decoration(&toplevel, &globals.qh, surface.id())
-            });
-
         let viewport = globals
             .viewporter
             .as_ref()
@@ -296,9 +720,7 @@ impl WaylandWindow {
             state: Rc::new(RefCell::new(WaylandWindowState::new(
                 handle,
                 surface.clone(),
-                xdg_surface,
-                toplevel,
-                decoration,
+                surface_state,
                 appearance,
                 viewport,
                 client,
@@ -309,9 +731,31 @@ impl WaylandWindow {
             callbacks: Rc::new(RefCell::new(Callbacks::default())),
         });
 
+        if let Some(parent) = parent {
+            this.borrow_mut().parent = Some(parent.window.clone());
+            parent
+                .window
+                .state
+                .borrow_mut()
+                .popovers
+                .push(this.0.clone());
+        }
+
         // Kick things off
         surface.commit();
 
+        // Drag icons are never configured, so they're drawn straight away instead,
+        // once the platform window has been set up.
+        if this.borrow().surface_state.is_drag_icon() {
+            this.borrow_mut().acknowledged_first_configure = true;
+            let state_ptr = this.0.clone();
+            this.borrow()
+                .globals
+                .executor
+                .spawn(async move { state_ptr.frame() })
+                .detach();
+        }
+
         Ok((this, surface.id()))
     }
 }
@@ -329,6 +773,24 @@ impl WaylandWindowStatePtr {
         Rc::ptr_eq(&self.state, &other.state)
     }
 
+    /// Unmaps this popover and its own popovers ahead of their parent being destroyed,
+    /// as the compositor requires popovers to be destroyed before their parent.
+    fn dismiss(&self) {
+        let mut state = self.state.borrow_mut();
+        state.parent = None;
+        for popover in mem::take(&mut state.popovers) {
+            popover.dismiss();
+        }
+        state.surface_state.destroy();
+
+        let this = self.clone();
+        state
+            .globals
+            .executor
+            .spawn(async move { this.close() })
+            .detach();
+    }
+
     pub fn frame(&self) {
         let mut state = self.state.borrow_mut();
         state.surface.frame(&state.globals.qh, state.surface.id());
@@ -383,7 +845,7 @@ impl WaylandWindowStatePtr {
                     }
                 }
                 let mut state = self.state.borrow_mut();
-                state.xdg_surface.ack_configure(serial);
+                state.surface_state.ack_configure(serial);
 
                 let window_geometry = inset_by_tiling(
                     state.bounds.map_origin(|_| px(0.0)),
@@ -393,7 +855,7 @@ impl WaylandWindowStatePtr {
                 .map(|v| v.0 as i32)
                 .map_size(|v| if v <= 0 { 1 } else { v });
 
-                state.xdg_surface.set_window_geometry(
+                state.surface_state.set_geometry(
                     window_geometry.origin.x,
                     window_geometry.origin.y,
                     window_geometry.size.width,
@@ -442,6 +904,110 @@ impl WaylandWindowStatePtr {
         }
     }
 
+    pub fn handle_popup_event(&self, event: xdg_popup::Event) {
+        match event {
+            xdg_popup::Event::Configure {
+                x: _,
+                y: _,
+                width,
+                height,
+            } => {
+                let size = if width == 0 || height == 0 {
+                    None
+                } else {
+                    Some(size(px(width as f32), px(height as f32)))
+                };
+                self.state.borrow_mut().in_progress_configure = Some(InProgressConfigure {
+                    size,
+                    fullscreen: false,
+                    maximized: false,
+                    tiling: Tiling::default(),
+                });
+            }
+            xdg_popup::Event::PopupDone => {
+                // The popover was dismissed by the compositor, e.g. by clicking outside of it.
+                self.close();
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether the pointer is currently locked or confined to this window.
+    pub fn is_pointer_constrained(&self) -> bool {
+        self.state.borrow().pointer_constraint_active
+    }
+
+    pub fn handle_pointer_constraint_event(&self, active: bool) {
+        if active {
+            self.state.borrow_mut().pointer_constraint_active = true;
+        } else {
+            // The constraint was requested as one-shot, so it can't be activated again.
+            self.release_pointer_constraint();
+        }
+    }
+
+    fn release_pointer_constraint(&self) {
+        let mut state = self.state.borrow_mut();
+        if let Some(pointer_constraint) = state.pointer_constraint.take() {
+            pointer_constraint.destroy();
+        }
+        state.pointer_constraint_active = false;
+    }
+
+    pub fn handle_keyboard_shortcuts_inhibit_event(&self, active: bool) {
+        let mut state = self.state.borrow_mut();
+        if state.keyboard_shortcuts_inhibitor.is_none()
+            || state.keyboard_shortcuts_inhibit_active == active
+        {
+            return;
+        }
+        state.keyboard_shortcuts_inhibit_active = active;
+        drop(state);
+        self.keyboard_shortcuts_inhibit_changed(active);
+    }
+
+    fn keyboard_shortcuts_inhibit_changed(&self, active: bool) {
+        let mut callbacks = self.callbacks.borrow_mut();
+        if let Some(fun) = callbacks.keyboard_shortcuts_inhibit_change.as_mut() {
+            fun(active);
+        }
+    }
+
+    pub fn handle_layer_surface_event(&self, event: zwlr_layer_surface_v1::Event) {
+        match event {
+            zwlr_layer_surface_v1::Event::Configure {
+                serial,
+                width,
+                height,
+            } => {
+                // A zero size leaves it up to us, in which case we keep the requested size.
+                if width != 0 && height != 0 {
+                    let size = size(px(width as f32), px(height as f32));
+                    self.state.borrow_mut().window_bounds = Bounds {
+                        origin: Point::default(),
+                        size,
+                    };
+                    self.resize(size);
+                }
+
+                let mut state = self.state.borrow_mut();
+                state.surface_state.ack_configure(serial);
+
+                let request_frame_callback = !state.acknowledged_first_configure;
+                if request_frame_callback {
+                    state.acknowledged_first_configure = true;
+                    drop(state);
+                    self.frame();
+                }
+            }
+            zwlr_layer_surface_v1::Event::Closed => {
+                // The compositor has already unmapped the surface, so there's nothing to ask.
+                self.close();
+            }
+            _ => {}
+        }
+    }
+
     pub fn handle_fractional_scale_event(&self, event: wp_fractional_scale_v1::Event) {
         match event {
             wp_fractional_scale_v1::Event::PreferredScale { scale } => {
@@ -663,6 +1229,7 @@ impl WaylandWindowStatePtr {
             }
             let device_bounds = state.bounds.to_device_pixels(state.scale);
             state.renderer.update_drawable_size(device_bounds.size);
+            state.needs_full_damage = true;
             (state.bounds.size, state.scale)
         };
 
@@ -713,6 +1280,9 @@ impl WaylandWindowStatePtr {
 
     pub fn set_focused(&self, focus: bool) {
         self.state.borrow_mut().active = focus;
+        if !focus {
+            self.release_pointer_constraint();
+        }
         if let Some(ref mut fun) = self.callbacks.borrow_mut().active_status_change {
             fun(focus);
         }
@@ -874,15 +1444,56 @@ impl PlatformWindow for WaylandWindow {
     }
 
     fn set_title(&mut self, title: &str) {
-        self.borrow().toplevel.set_title(title.to_string());
+        if let Some(toplevel) = self.borrow().surface_state.toplevel() {
+            toplevel.set_title(title.to_string());
+        }
     }
 
     fn set_app_id(&mut self, app_id: &str) {
         let mut state = self.borrow_mut();
-        state.toplevel.set_app_id(app_id.to_owned());
+        if let Some(toplevel) = state.surface_state.toplevel() {
+            toplevel.set_app_id(app_id.to_owned());
+        }
         state.app_id = Some(app_id.to_owned());
     }
 
+    fn set_icon(&mut self, icon: Option<&WindowIcon>) {
+        let mut state = self.borrow_mut();
+        let Some(toplevel) = state.surface_state.toplevel().cloned() else {
+            return;
+        };
+        let Some(icon_manager) = state.globals.toplevel_icon_manager.clone() else {
+            log::info!("Window icons are not supported by the compositor");
+            return;
+        };
+
+        let new_icon = icon.map(|icon| {
+            let toplevel_icon = icon_manager.create_icon(&state.globals.qh, ());
+            if let Some(name) = icon.name() {
+                toplevel_icon.set_name(name.to_string());
+            }
+            let buffers = icon
+                .images()
+                .iter()
+                .filter_map(|image| {
+                    let (bytes, size) = icon_buffer_bytes(image);
+                    create_shm_buffer(&state.globals.shm, &state.globals.qh, size, &bytes).log_err()
+                })
+                .collect::<Vec<_>>();
+            for buffer in &buffers {
+                toplevel_icon.add_buffer(buffer, 1);
+            }
+            ToplevelIcon {
+                icon: toplevel_icon,
+                buffers,
+            }
+        });
+        icon_manager.set_icon(&toplevel, new_icon.as_ref().map(|icon| &icon.icon));
+        if let Some(previous_icon) = mem::replace(&mut state.icon, new_icon) {
+            previous_icon.destroy();
+        }
+    }
+
     fn set_background_appearance(&self, background_appearance: WindowBackgroundAppearance) {
         let mut state = self.borrow_mut();
         state.background_appearance = background_appearance;
@@ -890,24 +1501,32 @@ impl PlatformWindow for WaylandWindow {
     }
 
     fn minimize(&self) {
-        self.borrow().toplevel.set_minimized();
+        if let Some(toplevel) = self.borrow().surface_state.toplevel() {
+            toplevel.set_minimized();
+        }
     }
 
     fn zoom(&self) {
         let state = self.borrow();
+        let Some(toplevel) = state.surface_state.toplevel() else {
+            return;
+        };
         if !state.maximized {
-            state.toplevel.set_maximized();
+            toplevel.set_maximized();
         } else {
-            state.toplevel.unset_maximized();
+            toplevel.unset_maximized();
         }
     }
 
     fn toggle_fullscreen(&self) {
-        let mut state = self.borrow_mut();
+        let state = self.borrow();
+        let Some(toplevel) = state.surface_state.toplevel() else {
+            return;
+        };
         if !state.fullscreen {
-            state.toplevel.set_fullscreen(None);
+            toplevel.set_fullscreen(None);
         } else {
-            state.toplevel.unset_fullscreen();
+            toplevel.unset_fullscreen();
         }
     }
 
@@ -951,8 +1570,42 @@ impl PlatformWindow for WaylandWindow {
         self.0.callbacks.borrow_mut().appearance_changed = Some(callback);
     }
 
-    fn draw(&self, scene: &Scene) {
+    fn on_keyboard_shortcuts_inhibit_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0
+            .callbacks
+            .borrow_mut()
+            .keyboard_shortcuts_inhibit_change = Some(callback);
+    }
+
+    fn draw(&self, scene: &Scene, damage: &Damage) {
         let mut state = self.borrow_mut();
+        let damage = if mem::take(&mut state.needs_full_damage) {
+            Damage::Full
+        } else {
+            damage.clone()
+        };
+        // Nothing changed, so let the compositor keep showing the attached buffer.
+        if damage.is_empty() {
+            return;
+        }
+
+        // Compositors that predate `damage_buffer` recomposite the whole surface anyway.
+        let viewport = state.bounds.to_device_pixels(state.scale).size;
+        let regions = if state.surface.version() >= wl_surface::REQ_DAMAGE_BUFFER_SINCE {
+            damage.regions(viewport)
+        } else {
+            Vec::new()
+        };
+        for region in regions {
+            state.surface.damage_buffer(
+                region.origin.x.0,
+                region.origin.y.0,
+                region.size.width.0,
+                region.size.height.0,
+            );
+        }
+        // The damage only saves the compositor's work: the renderer redraws the whole frame, as
+        // the contents of the swapchain image it's given are undefined.
         state.renderer.draw(scene);
     }
 
@@ -968,8 +1621,11 @@ impl PlatformWindow for WaylandWindow {
 
     fn show_window_menu(&self, position: Point<Pixels>) {
         let state = self.borrow();
+        let Some(toplevel) = state.surface_state.toplevel() else {
+            return;
+        };
         let serial = state.client.get_serial(SerialKind::MousePress);
-        state.toplevel.show_window_menu(
+        toplevel.show_window_menu(
             &state.globals.seat,
             serial,
             position.x.0 as i32,
@@ -979,13 +1635,144 @@ impl PlatformWindow for WaylandWindow {
 
     fn start_window_move(&self) {
         let state = self.borrow();
+        let Some(toplevel) = state.surface_state.toplevel() else {
+            return;
+        };
         let serial = state.client.get_serial(SerialKind::MousePress);
-        state.toplevel._move(&state.globals.seat, serial);
+        toplevel._move(&state.globals.seat, serial);
+    }
+
+    fn start_drag(
+        &self,
+        drag: ExternalDrag,
+        icon: Option<AnyWindowHandle>,
+        on_event: Box<dyn FnMut(ExternalDragEvent)>,
+    ) -> anyhow::Result<()> {
+        let state = self.borrow();
+        let client = state.client.clone();
+        let surface = state.surface.clone();
+        drop(state);
+        client.start_drag(&surface, drag, icon, on_event)
+    }
+
+    fn set_idle_inhibited(&self, inhibited: bool) {
+        let mut state = self.borrow_mut();
+        if inhibited == state.idle_inhibitor.is_some() {
+            return;
+        }
+        if !inhibited {
+            if let Some(IdleInhibitor::Wayland(inhibitor)) = state.idle_inhibitor.take() {
+                inhibitor.destroy();
+            }
+            return;
+        }
+        state.idle_inhibitor = Some(IdleInhibitor::new(
+            state.globals.idle_inhibit_manager.as_ref(),
+            &state.surface,
+            &state.globals.qh,
+            &state.globals.executor,
+        ));
+    }
+
+    fn set_input_region(&self, region: Option<&[Bounds<Pixels>]>) {
+        let state = self.borrow();
+        let Some(region) = region else {
+            state.surface.set_input_region(None);
+            return;
+        };
+        let wl_region = state
+            .globals
+            .compositor
+            .create_region(&state.globals.qh, ());
+        for bounds in region {
+            // Round outwards, so that input on partially covered pixels isn't lost.
+            let origin = bounds.origin.map(|v| v.0.floor() as i32);
+            let corner = bounds.bottom_right().map(|v| v.0.ceil() as i32);
+            wl_region.add(origin.x, origin.y, corner.x - origin.x, corner.y - origin.y);
+        }
+        // Applied with the next frame.
+        state.surface.set_input_region(Some(&wl_region));
+        wl_region.destroy();
+    }
+
+    fn set_keyboard_shortcuts_inhibited(&self, inhibited: bool) {
+        let mut state = self.borrow_mut();
+        if inhibited == state.keyboard_shortcuts_inhibitor.is_some() {
+            return;
+        }
+        if inhibited {
+            let Some(manager) = state.globals.keyboard_shortcuts_inhibit_manager.clone() else {
+                log::info!("Keyboard shortcuts inhibition is not supported by the compositor");
+                return;
+            };
+            let surface_id = state.surface.id();
+            state.keyboard_shortcuts_inhibitor = Some(manager.inhibit_shortcuts(
+                &state.surface,
+                &state.globals.seat,
+                &state.globals.qh,
+                surface_id,
+            ));
+        } else if let Some(inhibitor) = state.keyboard_shortcuts_inhibitor.take() {
+            inhibitor.destroy();
+            if mem::take(&mut state.keyboard_shortcuts_inhibit_active) {
+                // The window is being updated by the caller, so notify it afterwards.
+                let this = self.0.clone();
+                state
+                    .globals
+                    .executor
+                    .spawn(async move { this.keyboard_shortcuts_inhibit_changed(false) })
+                    .detach();
+            }
+        }
+    }
+
+    fn set_pointer_constraint(&self, constraint: Option<PointerConstraint>) {
+        self.0.release_pointer_constraint();
+        let Some(constraint) = constraint else {
+            return;
+        };
+
+        let mut state = self.borrow_mut();
+        let Some(pointer_constraints) = state.globals.pointer_constraints.clone() else {
+            log::info!("Pointer constraints are not supported by the compositor");
+            return;
+        };
+        let Some(pointer) = state.client.get_pointer() else {
+            return;
+        };
+        // One-shot constraints are deactivated by the compositor when the window loses focus.
+        let lifetime = zwp_pointer_constraints_v1::Lifetime::Oneshot;
+        let surface_id = state.surface.id();
+        state.pointer_constraint = Some(match constraint {
+            PointerConstraint::Locked => {
+                PointerConstraintState::Locked(pointer_constraints.lock_pointer(
+                    &state.surface,
+                    &pointer,
+                    None,
+                    lifetime,
+                    &state.globals.qh,
+                    surface_id,
+                ))
+            }
+            PointerConstraint::Confined => {
+                PointerConstraintState::Confined(pointer_constraints.confine_pointer(
+                    &state.surface,
+                    &pointer,
+                    None,
+                    lifetime,
+                    &state.globals.qh,
+                    surface_id,
+                ))
+            }
+        });
     }
 
     fn start_window_resize(&self, edge: crate::ResizeEdge) {
         let state = self.borrow();
-        state.toplevel.resize(
+        let Some(toplevel) = state.surface_state.toplevel() else {
+            return;
+        };
+        toplevel.resize(
             &state.globals.seat,
             state.client.get_serial(SerialKind::MousePress),
             edge.to_xdg(),
@@ -1004,8 +1791,11 @@ impl PlatformWindow for WaylandWindow {
 
     fn request_decorations(&self, decorations: WindowDecorations) {
         let mut state = self.borrow_mut();
+        if state.surface_state.toplevel().is_none() {
+            return;
+        }
         state.decorations = decorations;
-        if let Some(decoration) = state.decoration.as_ref() {
+        if let Some(decoration) = state.surface_state.decoration().cloned() {
             decoration.set_mode(decorations.to_xdg());
             update_window(state);
         }
@@ -1033,10 +1823,27 @@ impl PlatformWindow for WaylandWindow {
     }
 }
 
+/// Centers the image on a square canvas, as icon buffers have to be square, and converts it to
+/// premultiplied BGRA.
+fn icon_buffer_bytes(image: &image::RgbaImage) -> (Vec<u8>, Size<u32>) {
+    let side = image.width().max(image.height());
+    let mut canvas = image::RgbaImage::new(side, side);
+    image::imageops::overlay(
+        &mut canvas,
+        image,
+        ((side - image.width()) / 2) as i64,
+        ((side - image.height()) / 2) as i64,
+    );
+    let mut bytes = canvas.into_raw();
+    rgba_to_premultiplied_bgra(&mut bytes);
+    (bytes, size(side, side))
+}
+
 fn update_window(mut state: RefMut<WaylandWindowState>) {
     let opaque = !state.is_transparent();
 
     state.renderer.update_transparency(!opaque);
+    state.needs_full_damage = true;
     let mut opaque_area = state.window_bounds.map(|v| v.0 as i32);
     if let Some(inset) = state.inset {
         opaque_area.inset(inset.0 as i32);
@@ -1094,6 +1901,37 @@ impl WindowDecorations {
     }
 }
 
+impl Layer {
+    fn to_wlr(&self) -> zwlr_layer_shell_v1::Layer {
+        match self {
+            Layer::Background => zwlr_layer_shell_v1::Layer::Background,
+            Layer::Bottom => zwlr_layer_shell_v1::Layer::Bottom,
+            Layer::Top => zwlr_layer_shell_v1::Layer::Top,
+            Layer::Overlay => zwlr_layer_shell_v1::Layer::Overlay,
+        }
+    }
+}
+
+impl Corner {
+    fn to_xdg_anchor(&self) -> xdg_positioner::Anchor {
+        match self {
+            Corner::TopLeft => xdg_positioner::Anchor::TopLeft,
+            Corner::TopRight => xdg_positioner::Anchor::TopRight,
+            Corner::BottomLeft => xdg_positioner::Anchor::BottomLeft,
+            Corner::BottomRight => xdg_positioner::Anchor::BottomRight,
+        }
+    }
+
+    fn to_xdg_gravity(&self) -> xdg_positioner::Gravity {
+        match self {
+            Corner::TopLeft => xdg_positioner::Gravity::TopLeft,
+            Corner::TopRight => xdg_positioner::Gravity::TopRight,
+            Corner::BottomLeft => xdg_positioner::Gravity::BottomLeft,
+            Corner::BottomRight => xdg_positioner::Gravity::BottomRight,
+        }
+    }
+}
+
 impl ResizeEdge {
     fn to_xdg(&self) -> xdg_toplevel::ResizeEdge {
         match self {
@@ -1155,3 +1993,36 @@ fn inset_by_tiling(mut bounds: Bounds<Pixels>, inset: Pixels, tiling: Tiling) ->
 
     bounds
 }
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use wayland_client::Connection;
+
+    use super::*;
+    use crate::HeadlessClient;
+
+    #[test]
+    fn test_idle_inhibitor_falls_back_to_portal() {
+        let app = HeadlessClient::with_scale_factor(1.).new_app();
+        let executor = app.borrow().foreground_executor().clone();
+
+        // Requests on inert objects are ignored, so no compositor has to be running.
+        let (socket, _compositor) = UnixStream::pair().unwrap();
+        let connection = Connection::from_socket(socket).unwrap();
+        let queue = connection.new_event_queue::<WaylandClientStatePtr>();
+        let backend = connection.backend().downgrade();
+        let surface = wl_surface::WlSurface::inert(backend.clone());
+        let manager = zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1::inert(backend);
+
+        let inhibitor = IdleInhibitor::new(Some(&manager), &surface, &queue.handle(), &executor);
+        assert!(matches!(inhibitor, IdleInhibitor::Wayland(_)));
+
+        let inhibitor = IdleInhibitor::new(None, &surface, &queue.handle(), &executor);
+        assert!(matches!(inhibitor, IdleInhibitor::Portal(_)));
+
+        drop(inhibitor);
+        app.borrow_mut().shutdown();
+    }
+}
@@ -0,0 +1,7 @@
+mod client;
+mod clipboard;
+mod display;
+mod event;
+mod window;
+
+pub(crate) use client::*;
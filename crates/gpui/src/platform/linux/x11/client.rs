@@ -0,0 +1,1383 @@
+use std::{
+    cell::RefCell,
+    env,
+    os::fd::{AsFd, AsRawFd, BorrowedFd},
+    path::PathBuf,
+    rc::{Rc, Weak},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context as _;
+use ashpd::url::Url;
+use calloop::{
+    generic::Generic,
+    timer::{TimeoutAction, Timer},
+    EventLoop, Interest, LoopHandle, Mode, PostAction, RegistrationToken,
+};
+use collections::HashMap;
+use smallvec::SmallVec;
+use util::ResultExt;
+use x11rb::{
+    connection::Connection,
+    cursor,
+    protocol::{
+        randr::ConnectionExt as _,
+        render::{self, ConnectionExt as _},
+        xinput::{self, ConnectionExt as _},
+        xkb::{self, ConnectionExt as _},
+        xproto::{
+            self, AtomEnum, ChangeWindowAttributesAux, ClientMessageEvent, ConnectionExt as _,
+        },
+        Event,
+    },
+    resource_manager,
+    xcb_ffi::XCBConnection,
+    NONE,
+};
+use xkbcommon::xkb as xkbc;
+
+use super::{
+    clipboard::Clipboard,
+    display::X11Display,
+    event::{
+        button_or_scroll_from_event_detail, fp1616_to_f32, fp3232_to_f32, get_valuator_axis_index,
+        is_pointer_emulated, modifiers_from_xinput_info, pressed_button_from_mask, ButtonOrScroll,
+        ScrollDirection,
+    },
+    window::{X11Window, X11WindowStatePtr, XcbAtoms},
+};
+use crate::platform::linux::{
+    get_xkb_compose_state, is_within_click_distance, listen_for_forwarded_launches,
+    open_uri_internal, reveal_path_internal,
+    single_instance::{ForwardedLaunch, SingleInstanceListener},
+    xdg_desktop_portal::{Event as XDPEvent, XDPEventSource},
+    LinuxClient,
+};
+use crate::platform::{blade::BladeContext, PlatformWindow};
+use crate::{
+    point, px, size, AnyWindowHandle, CursorImage, CursorStyle, DevicePixels, DisplayId,
+    ExternalPaths, FileDropEvent, KeyDownEvent, KeyUpEvent, Keystroke, LinuxCommon, Modifiers,
+    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseExitEvent, MouseMoveEvent,
+    MouseUpEvent, Pixels, PlatformDisplay, PlatformInput, Point, ScrollDelta, ScrollWheelEvent,
+    TouchPhase, WindowKind, WindowParams, DOUBLE_CLICK_INTERVAL, SCROLL_LINES,
+};
+
+/// Queries every device, so scroll valuators of slave devices are found too.
+const XINPUT_ALL_DEVICES: xinput::DeviceId = 0;
+
+/// Used when RandR can't tell us the refresh rate of the primary output.
+const DEFAULT_REFRESH_RATE: Duration = Duration::from_micros(1_000_000 / 60);
+
+/// Lets users override the scale factor derived from `Xft.dpi`.
+const SCALE_FACTOR_ENV_VAR: &str = "GPUI_X11_SCALE_FACTOR";
+
+pub(crate) struct WindowRef {
+    window: X11WindowStatePtr,
+    refresh_event_token: RegistrationToken,
+}
+
+struct ClickState {
+    last_mouse_button: Option<MouseButton>,
+    last_click: Instant,
+    last_location: Point<Pixels>,
+    current_count: usize,
+}
+
+#[derive(Default)]
+struct ScrollAxisState {
+    valuator_number: Option<u16>,
+    /// Converts valuator units to lines.
+    multiplier: f32,
+    /// The last seen valuator value, unknown until the first motion after entering a window.
+    scroll_value: Option<f32>,
+}
+
+/// Smooth scrolling state of a single XInput2 device.
+#[derive(Default)]
+struct PointerDeviceState {
+    horizontal: ScrollAxisState,
+    vertical: ScrollAxisState,
+}
+
+/// An in-progress XDND drag over one of our windows.
+struct DragState {
+    source_window: xproto::Window,
+    target_window: xproto::Window,
+    offers_file_list: bool,
+    requested: bool,
+    entered: bool,
+    position: Point<Pixels>,
+}
+
+pub(crate) struct X11ClientState {
+    xcb_connection: Rc<XCBConnection>,
+    x_root_index: usize,
+    atoms: XcbAtoms,
+    scale_factor: f32,
+    refresh_rate: Duration,
+    gpu_context: BladeContext,
+    windows: HashMap<xproto::Window, WindowRef>,
+    mouse_focused_window: Option<xproto::Window>,
+    keyboard_focused_window: Option<xproto::Window>,
+    xkb: xkbc::State,
+    xkb_device_id: i32,
+    compose_state: Option<xkbc::compose::State>,
+    pre_edit_text: Option<String>,
+    last_keycode_pressed: Option<xkbc::Keycode>,
+    modifiers: Modifiers,
+    pointer_device_states: HashMap<xinput::DeviceId, PointerDeviceState>,
+    last_location: Point<Pixels>,
+    click: ClickState,
+    drag: Option<DragState>,
+    cursor_handle: cursor::Handle,
+    cursor_style: Option<CursorStyle>,
+    cursor_cache: HashMap<CursorStyle, xproto::Cursor>,
+    image_cursor: Option<(CursorImage, xproto::Cursor)>,
+    clipboard: Clipboard,
+    loop_handle: LoopHandle<'static, X11ClientStatePtr>,
+    event_loop: Option<EventLoop<'static, X11ClientStatePtr>>,
+    common: LinuxCommon,
+}
+
+#[derive(Clone)]
+pub struct X11ClientStatePtr(pub Weak<RefCell<X11ClientState>>);
+
+impl X11ClientStatePtr {
+    pub fn get_client(&self) -> Rc<RefCell<X11ClientState>> {
+        self.0
+            .upgrade()
+            .expect("The pointer should always be valid when dispatching in X11")
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.get_client().borrow().modifiers
+    }
+
+    pub fn drop_window(&self, x_window: xproto::Window) {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window_ref) = state.windows.remove(&x_window) else {
+            return;
+        };
+        state.loop_handle.remove(window_ref.refresh_event_token);
+        if state.mouse_focused_window == Some(x_window) {
+            state.mouse_focused_window = None;
+        }
+        if state.keyboard_focused_window == Some(x_window) {
+            state.keyboard_focused_window = None;
+        }
+        if state
+            .drag
+            .as_ref()
+            .is_some_and(|drag| drag.target_window == x_window)
+        {
+            state.drag = None;
+        }
+        if state.windows.is_empty() {
+            state.common.signal.stop();
+        }
+    }
+
+    fn get_window(&self, x_window: xproto::Window) -> Option<X11WindowStatePtr> {
+        let client = self.get_client();
+        let state = client.borrow();
+        state
+            .windows
+            .get(&x_window)
+            .map(|window_ref| window_ref.window.clone())
+    }
+
+    /// Activates a window for a launch of another instance, and opens what it was launched
+    /// with.
+    fn handle_forwarded_launch(&self, launch: ForwardedLaunch) {
+        let client = self.get_client();
+        let state = client.borrow();
+        let window = state
+            .keyboard_focused_window
+            .and_then(|x_window| state.windows.get(&x_window))
+            .or_else(|| state.windows.values().next())
+            .map(|window_ref| window_ref.window.clone());
+        drop(state);
+        if let Some(window) = window {
+            window.activate();
+        }
+
+        LinuxCommon::open_urls(|f| f(&mut client.borrow_mut().common), launch.urls());
+    }
+
+    fn process_x11_events(&self) {
+        let xcb_connection = self.get_client().borrow().xcb_connection.clone();
+        loop {
+            match xcb_connection.poll_for_event() {
+                Ok(Some(event)) => self.handle_event(event),
+                Ok(None) => break,
+                Err(error) => {
+                    log::error!("lost the connection to the X server: {error}");
+                    self.get_client().borrow().common.signal.stop();
+                    break;
+                }
+            }
+        }
+        xcb_connection.flush().log_err();
+    }
+
+    fn handle_event(&self, event: Event) {
+        match event {
+            Event::ClientMessage(event) => self.handle_client_message(event),
+            Event::ConfigureNotify(event) => {
+                let window = self.get_window(event.window);
+                if let Some(window) = window {
+                    window.configure(size(
+                        DevicePixels(event.width as i32),
+                        DevicePixels(event.height as i32),
+                    ));
+                }
+            }
+            Event::Expose(event) if event.count == 0 => {
+                if let Some(window) = self.get_window(event.window) {
+                    window.expose();
+                }
+            }
+            Event::PropertyNotify(event) => {
+                let client = self.get_client();
+                let atoms = client.borrow().atoms;
+                if event.atom == atoms._NET_WM_STATE {
+                    if let Some(window) = self.get_window(event.window) {
+                        window.update_wm_state();
+                    }
+                }
+            }
+            Event::SelectionNotify(event) => self.handle_xdnd_selection(event),
+            Event::FocusIn(event) => {
+                let Some(window) = self.get_window(event.event) else {
+                    return;
+                };
+                self.get_client().borrow_mut().keyboard_focused_window = Some(event.event);
+                window.set_active(true);
+            }
+            Event::FocusOut(event) => {
+                let Some(window) = self.get_window(event.event) else {
+                    return;
+                };
+                let client = self.get_client();
+                let mut state = client.borrow_mut();
+                if state.keyboard_focused_window == Some(event.event) {
+                    state.keyboard_focused_window = None;
+                }
+                state.last_keycode_pressed = None;
+                if let Some(compose) = state.compose_state.as_mut() {
+                    compose.reset();
+                }
+                let had_pre_edit = state.pre_edit_text.take().is_some();
+                drop(state);
+                if had_pre_edit {
+                    window.delete_marked_text();
+                }
+                window.set_active(false);
+            }
+            Event::XkbStateNotify(event) => {
+                let client = self.get_client();
+                let mut state = client.borrow_mut();
+                let old_layout = state.xkb.serialize_layout(xkbc::STATE_LAYOUT_EFFECTIVE);
+                state.xkb.update_mask(
+                    event.base_mods.into(),
+                    event.latched_mods.into(),
+                    event.locked_mods.into(),
+                    event.base_group as u32,
+                    event.latched_group as u32,
+                    event.locked_group.into(),
+                );
+                let new_layout = state.xkb.serialize_layout(xkbc::STATE_LAYOUT_EFFECTIVE);
+                let modifiers = Modifiers::from_xkb(&state.xkb);
+                let modifiers_changed = modifiers != state.modifiers;
+                state.modifiers = modifiers;
+                let focused_window = state.keyboard_focused_window;
+                drop(state);
+
+                if old_layout != new_layout {
+                    self.keyboard_layout_changed();
+                }
+                if modifiers_changed {
+                    if let Some(window) = focused_window.and_then(|id| self.get_window(id)) {
+                        window.handle_input(PlatformInput::ModifiersChanged(
+                            ModifiersChangedEvent { modifiers },
+                        ));
+                    }
+                }
+            }
+            Event::XkbMapNotify(_) | Event::XkbNewKeyboardNotify(_) => {
+                let client = self.get_client();
+                let mut state = client.borrow_mut();
+                let xkb_context = xkbc::Context::new(xkbc::CONTEXT_NO_FLAGS);
+                let keymap = xkbc::x11::keymap_new_from_device(
+                    &xkb_context,
+                    state.xcb_connection.as_ref(),
+                    state.xkb_device_id,
+                    xkbc::KEYMAP_COMPILE_NO_FLAGS,
+                );
+                state.xkb = xkbc::x11::state_new_from_device(
+                    &keymap,
+                    state.xcb_connection.as_ref(),
+                    state.xkb_device_id,
+                );
+                state.compose_state = get_xkb_compose_state(&xkb_context);
+                drop(state);
+                self.keyboard_layout_changed();
+            }
+            Event::KeyPress(event) => self.handle_key(event.event, event.detail, true),
+            Event::KeyRelease(event) => self.handle_key(event.event, event.detail, false),
+            Event::XinputButtonPress(event) => self.handle_button(event, true),
+            Event::XinputButtonRelease(event) => self.handle_button(event, false),
+            Event::XinputMotion(event) => self.handle_motion(event),
+            Event::XinputEnter(event) => {
+                let Some(window) = self.get_window(event.event) else {
+                    return;
+                };
+                let client = self.get_client();
+                let mut state = client.borrow_mut();
+                state.mouse_focused_window = Some(event.event);
+                // Valuators keep counting while the pointer is elsewhere.
+                for device in state.pointer_device_states.values_mut() {
+                    device.horizontal.scroll_value = None;
+                    device.vertical.scroll_value = None;
+                }
+                if let Some(style) = state.cursor_style.clone() {
+                    state.apply_cursor_style(event.event, &style);
+                }
+                drop(state);
+                window.set_hovered(true);
+            }
+            Event::XinputLeave(event) => {
+                let Some(window) = self.get_window(event.event) else {
+                    return;
+                };
+                let client = self.get_client();
+                let mut state = client.borrow_mut();
+                if state.mouse_focused_window == Some(event.event) {
+                    state.mouse_focused_window = None;
+                }
+                let scale_factor = window.scale_factor();
+                let input = PlatformInput::MouseExited(MouseExitEvent {
+                    position: point(
+                        px(fp1616_to_f32(event.event_x) / scale_factor),
+                        px(fp1616_to_f32(event.event_y) / scale_factor),
+                    ),
+                    pressed_button: pressed_button_from_mask(
+                        event.buttons.first().copied().unwrap_or_default(),
+                    ),
+                    modifiers: modifiers_from_xinput_info(event.mods),
+                });
+                drop(state);
+                window.handle_input(input);
+                window.set_hovered(false);
+            }
+            Event::XinputHierarchy(_) | Event::XinputDeviceChanged(_) => {
+                let client = self.get_client();
+                let mut state = client.borrow_mut();
+                state.pointer_device_states = pointer_device_states(&state.xcb_connection);
+            }
+            Event::Error(error) => {
+                log::warn!("X11 protocol error: {error:?}");
+            }
+            _ => {}
+        }
+    }
+
+    fn keyboard_layout_changed(&self) {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        if let Some(mut callback) = state.common.callbacks.keyboard_layout_change.take() {
+            drop(state);
+            callback();
+            state = client.borrow_mut();
+            state.common.callbacks.keyboard_layout_change = Some(callback);
+        }
+    }
+
+    fn handle_key(&self, x_window: xproto::Window, detail: u8, pressed: bool) {
+        let Some(window) = self.get_window(x_window) else {
+            return;
+        };
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let keycode = xkbc::Keycode::from(detail);
+        let keysym = state.xkb.key_get_one_sym(keycode);
+        if keysym.is_modifier_key() {
+            return;
+        }
+        let mut keystroke = Keystroke::from_xkb(&state.xkb, state.modifiers, keycode);
+
+        if !pressed {
+            if state.last_keycode_pressed == Some(keycode) {
+                state.last_keycode_pressed = None;
+            }
+            drop(state);
+            window.handle_input(PlatformInput::KeyUp(KeyUpEvent { keystroke }));
+            return;
+        }
+
+        // With detectable autorepeat, a held key sends presses without releases in between.
+        let is_held = state.last_keycode_pressed == Some(keycode);
+        state.last_keycode_pressed = Some(keycode);
+
+        if let Some(mut compose) = state.compose_state.take() {
+            compose.feed(keysym);
+            match compose.status() {
+                xkbc::Status::Composing => {
+                    keystroke.key_char = None;
+                    state.pre_edit_text = compose.utf8().or(Keystroke::underlying_dead_key(keysym));
+                    let pre_edit = state.pre_edit_text.clone().unwrap_or_default();
+                    drop(state);
+                    window.set_marked_text(&pre_edit);
+                    state = client.borrow_mut();
+                }
+                xkbc::Status::Composed => {
+                    state.pre_edit_text.take();
+                    keystroke.key_char = compose.utf8();
+                    if let Some(keysym) = compose.keysym() {
+                        keystroke.key = xkbc::keysym_get_name(keysym);
+                    }
+                }
+                xkbc::Status::Cancelled => {
+                    let pre_edit = state.pre_edit_text.take();
+                    let new_pre_edit = Keystroke::underlying_dead_key(keysym);
+                    state.pre_edit_text = new_pre_edit.clone();
+                    drop(state);
+                    if let Some(pre_edit) = pre_edit {
+                        window.insert_text(&pre_edit);
+                    }
+                    if let Some(current_key) = new_pre_edit {
+                        window.set_marked_text(&current_key);
+                    }
+                    compose.feed(keysym);
+                    state = client.borrow_mut();
+                }
+                _ => {}
+            }
+            state.compose_state = Some(compose);
+        }
+        drop(state);
+
+        window.handle_input(PlatformInput::KeyDown(KeyDownEvent { keystroke, is_held }));
+    }
+
+    fn handle_button(&self, event: xinput::ButtonPressEvent, pressed: bool) {
+        let Some(window) = self.get_window(event.event) else {
+            return;
+        };
+        // Wheel buttons emulated from smooth scrolling are already handled as valuator motion.
+        if is_pointer_emulated(event.flags) {
+            return;
+        }
+        let Some(button_or_scroll) = button_or_scroll_from_event_detail(event.detail) else {
+            return;
+        };
+        let scale_factor = window.scale_factor();
+        let position = point(
+            px(fp1616_to_f32(event.event_x) / scale_factor),
+            px(fp1616_to_f32(event.event_y) / scale_factor),
+        );
+        let modifiers = modifiers_from_xinput_info(event.mods);
+
+        let button = match button_or_scroll {
+            ButtonOrScroll::Button(button) => button,
+            ButtonOrScroll::Scroll(direction) => {
+                if pressed {
+                    let mut delta = match direction {
+                        ScrollDirection::Up => point(0.0, SCROLL_LINES),
+                        ScrollDirection::Down => point(0.0, -SCROLL_LINES),
+                        ScrollDirection::Left => point(SCROLL_LINES, 0.0),
+                        ScrollDirection::Right => point(-SCROLL_LINES, 0.0),
+                    };
+                    if modifiers.shift {
+                        delta = point(delta.y, delta.x);
+                    }
+                    window.handle_input(PlatformInput::ScrollWheel(ScrollWheelEvent {
+                        position,
+                        delta: ScrollDelta::Lines(delta),
+                        modifiers,
+                        touch_phase: TouchPhase::Moved,
+                    }));
+                }
+                return;
+            }
+        };
+
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        if !pressed {
+            let click_count = state.click.current_count;
+            drop(state);
+            window.handle_input(PlatformInput::MouseUp(MouseUpEvent {
+                button,
+                position,
+                modifiers,
+                click_count,
+            }));
+            return;
+        }
+
+        if let (Some(text), Some(compose)) =
+            (state.pre_edit_text.take(), state.compose_state.as_mut())
+        {
+            compose.reset();
+            drop(state);
+            window.insert_text(&text);
+            state = client.borrow_mut();
+        }
+
+        if state.click.last_click.elapsed() < DOUBLE_CLICK_INTERVAL
+            && state.click.last_mouse_button == Some(button)
+            && is_within_click_distance(state.click.last_location, position)
+        {
+            state.click.current_count += 1;
+        } else {
+            state.click.current_count = 1;
+        }
+        state.click.last_click = Instant::now();
+        state.click.last_mouse_button = Some(button);
+        state.click.last_location = position;
+        let click_count = state.click.current_count;
+        drop(state);
+
+        window.handle_input(PlatformInput::MouseDown(MouseDownEvent {
+            button,
+            position,
+            modifiers,
+            click_count,
+            first_mouse: false,
+        }));
+    }
+
+    fn handle_motion(&self, event: xinput::MotionEvent) {
+        let Some(window) = self.get_window(event.event) else {
+            return;
+        };
+        let scale_factor = window.scale_factor();
+        let position = point(
+            px(fp1616_to_f32(event.event_x) / scale_factor),
+            px(fp1616_to_f32(event.event_y) / scale_factor),
+        );
+        let modifiers = modifiers_from_xinput_info(event.mods);
+
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let moved = position != state.last_location;
+        state.last_location = position;
+
+        let mut scroll_delta = None;
+        if let Some(device) = state.pointer_device_states.get_mut(&event.sourceid) {
+            for (axis, is_vertical) in [
+                (&mut device.horizontal, false),
+                (&mut device.vertical, true),
+            ] {
+                let Some(valuator_number) = axis.valuator_number else {
+                    continue;
+                };
+                let Some(index) = get_valuator_axis_index(&event.valuator_mask, valuator_number)
+                else {
+                    continue;
+                };
+                let Some(&value) = event.axisvalues.get(index) else {
+                    continue;
+                };
+                let value = fp3232_to_f32(value);
+                if let Some(previous) = axis.scroll_value.replace(value) {
+                    let lines = (previous - value) * axis.multiplier;
+                    let delta = scroll_delta.get_or_insert(point(0.0, 0.0));
+                    if is_vertical {
+                        delta.y += lines;
+                    } else {
+                        delta.x += lines;
+                    }
+                }
+            }
+        }
+        drop(state);
+
+        if let Some(mut delta) = scroll_delta {
+            if modifiers.shift {
+                delta = point(delta.y, delta.x);
+            }
+            window.handle_input(PlatformInput::ScrollWheel(ScrollWheelEvent {
+                position,
+                delta: ScrollDelta::Lines(delta),
+                modifiers,
+                touch_phase: TouchPhase::Moved,
+            }));
+        }
+        if moved || scroll_delta.is_none() {
+            window.handle_input(PlatformInput::MouseMove(MouseMoveEvent {
+                position,
+                pressed_button: pressed_button_from_mask(
+                    event.button_mask.first().copied().unwrap_or_default(),
+                ),
+                modifiers,
+            }));
+        }
+    }
+
+    fn handle_client_message(&self, event: ClientMessageEvent) {
+        let Some(window) = self.get_window(event.window) else {
+            return;
+        };
+        let client = self.get_client();
+        let atoms = client.borrow().atoms;
+        let data = event.data.as_data32();
+
+        if event.type_ == atoms.WM_PROTOCOLS && data[0] == atoms.WM_DELETE_WINDOW {
+            if window.should_close() {
+                window.close();
+            }
+        } else if event.type_ == atoms.XdndEnter {
+            let source_window = data[0];
+            let mut types = data[2..5].to_vec();
+            // The source lists more than three types in a property instead.
+            if data[1] & 1 == 1 {
+                let state = client.borrow();
+                types = state
+                    .xcb_connection
+                    .get_property(
+                        false,
+                        source_window,
+                        atoms.XdndTypeList,
+                        AtomEnum::ATOM,
+                        0,
+                        u32::MAX,
+                    )
+                    .ok()
+                    .and_then(|cookie| cookie.reply().log_err())
+                    .and_then(|reply| reply.value32().map(|atoms| atoms.collect()))
+                    .unwrap_or_default();
+            }
+            client.borrow_mut().drag = Some(DragState {
+                source_window,
+                target_window: event.window,
+                offers_file_list: types.contains(&atoms.TEXT_URI_LIST),
+                requested: false,
+                entered: false,
+                position: Point::default(),
+            });
+        } else if event.type_ == atoms.XdndPosition {
+            let mut state = client.borrow_mut();
+            let x_root_window = state.xcb_connection.setup().roots[state.x_root_index].root;
+            let Some(translated) = state
+                .xcb_connection
+                .translate_coordinates(
+                    x_root_window,
+                    event.window,
+                    (data[2] >> 16) as i16,
+                    (data[2] & 0xffff) as i16,
+                )
+                .ok()
+                .and_then(|cookie| cookie.reply().log_err())
+            else {
+                return;
+            };
+            let scale_factor = window.scale_factor();
+            let position = point(
+                px(translated.dst_x as f32 / scale_factor),
+                px(translated.dst_y as f32 / scale_factor),
+            );
+            let Some(drag) = state.drag.as_mut() else {
+                return;
+            };
+            drag.position = position;
+            let (source_window, accept, entered) =
+                (drag.source_window, drag.offers_file_list, drag.entered);
+            if accept && !drag.requested {
+                drag.requested = true;
+                state
+                    .xcb_connection
+                    .convert_selection(
+                        event.window,
+                        atoms.XdndSelection,
+                        atoms.TEXT_URI_LIST,
+                        atoms.XdndSelection,
+                        data[3],
+                    )
+                    .log_err();
+            }
+            // Accept the drop and keep sending positions, only copying is supported.
+            let status = [
+                event.window,
+                accept as u32 | 2,
+                0,
+                0,
+                if accept { atoms.XdndActionCopy } else { NONE },
+            ];
+            state.send_xdnd_message(source_window, atoms.XdndStatus, status);
+            drop(state);
+
+            if entered {
+                window.handle_input(PlatformInput::FileDrop(FileDropEvent::Pending { position }));
+            }
+        } else if event.type_ == atoms.XdndLeave {
+            let drag = client.borrow_mut().drag.take();
+            if drag.is_some_and(|drag| drag.entered) {
+                window.handle_input(PlatformInput::FileDrop(FileDropEvent::Exited));
+            }
+        } else if event.type_ == atoms.XdndDrop {
+            let Some(drag) = client.borrow_mut().drag.take() else {
+                return;
+            };
+            let finished = [
+                event.window,
+                drag.entered as u32,
+                if drag.entered {
+                    atoms.XdndActionCopy
+                } else {
+                    NONE
+                },
+                0,
+                0,
+            ];
+            client
+                .borrow()
+                .send_xdnd_message(drag.source_window, atoms.XdndFinished, finished);
+            if drag.entered {
+                window.handle_input(PlatformInput::FileDrop(FileDropEvent::Submit {
+                    position: drag.position,
+                }));
+            }
+        }
+    }
+
+    fn handle_xdnd_selection(&self, event: xproto::SelectionNotifyEvent) {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let atoms = state.atoms;
+        if event.selection != atoms.XdndSelection || event.property == NONE {
+            return;
+        }
+        let Some(reply) = state
+            .xcb_connection
+            .get_property(
+                true,
+                event.requestor,
+                atoms.XdndSelection,
+                AtomEnum::ANY,
+                0,
+                u32::MAX,
+            )
+            .ok()
+            .and_then(|cookie| cookie.reply().log_err())
+        else {
+            return;
+        };
+        let paths = paths_from_uri_list(&String::from_utf8_lossy(&reply.value));
+        let Some(drag) = state
+            .drag
+            .as_mut()
+            .filter(|drag| drag.target_window == event.requestor)
+        else {
+            return;
+        };
+        // Prevent dropping text from other programs.
+        if paths.is_empty() {
+            return;
+        }
+        drag.entered = true;
+        let position = drag.position;
+        drop(state);
+
+        if let Some(window) = self.get_window(event.requestor) {
+            window.handle_input(PlatformInput::FileDrop(FileDropEvent::Entered {
+                position,
+                paths: ExternalPaths(paths),
+            }));
+        }
+    }
+}
+
+fn paths_from_uri_list(uri_list: &str) -> SmallVec<[PathBuf; 2]> {
+    uri_list
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|uri| Url::parse(uri).log_err())
+        .filter_map(|url| url.to_file_path().log_err())
+        .collect()
+}
+
+impl X11ClientState {
+    fn send_xdnd_message(
+        &self,
+        target: xproto::Window,
+        message_type: xproto::Atom,
+        data: [u32; 5],
+    ) {
+        let message = ClientMessageEvent::new(32, target, message_type, data);
+        self.xcb_connection
+            .send_event(false, target, xproto::EventMask::NO_EVENT, message)
+            .log_err();
+        self.xcb_connection.flush().log_err();
+    }
+
+    fn apply_cursor_style(&mut self, x_window: xproto::Window, style: &CursorStyle) {
+        let cursor = match self.cursor_cache.get(style) {
+            Some(cursor) => *cursor,
+            None => {
+                if let CursorStyle::Image(image) = style {
+                    match &self.image_cursor {
+                        Some((current, cursor)) if current == image => *cursor,
+                        _ => {
+                            let Some(cursor) = self.create_image_cursor(image).log_err() else {
+                                return;
+                            };
+                            // Only the last image cursor is kept, as they tend to be replaced
+                            // often (e.g. when resizing a brush). The server keeps a freed
+                            // cursor alive while windows still use it.
+                            if let Some((_, previous)) =
+                                self.image_cursor.replace((image.clone(), cursor))
+                            {
+                                self.xcb_connection.free_cursor(previous).log_err();
+                            }
+                            cursor
+                        }
+                    }
+                } else {
+                    let load = |name: &str| {
+                        self.cursor_handle
+                            .load_cursor(self.xcb_connection.as_ref(), name)
+                            .log_err()
+                            .filter(|cursor| *cursor != NONE)
+                    };
+                    let Some(cursor) = load(&style.to_icon_name()).or_else(|| load("left_ptr"))
+                    else {
+                        return;
+                    };
+                    self.cursor_cache.insert(style.clone(), cursor);
+                    cursor
+                }
+            }
+        };
+        self.xcb_connection
+            .change_window_attributes(x_window, &ChangeWindowAttributesAux::new().cursor(cursor))
+            .log_err();
+        self.xcb_connection.flush().log_err();
+    }
+
+    /// Creates an ARGB cursor from the image through the RENDER extension.
+    fn create_image_cursor(&self, image: &CursorImage) -> anyhow::Result<xproto::Cursor> {
+        let connection = self.xcb_connection.as_ref();
+        let root = connection.setup().roots[self.x_root_index].root;
+        let (bytes, size, hotspot) = image.to_premultiplied_bgra(self.scale_factor);
+
+        let format = connection
+            .render_query_pict_formats()?
+            .reply()?
+            .formats
+            .into_iter()
+            .find(|format| {
+                format.type_ == render::PictType::DIRECT
+                    && format.depth == 32
+                    && format.direct.alpha_shift == 24
+                    && format.direct.red_shift == 16
+                    && format.direct.green_shift == 8
+                    && format.direct.blue_shift == 0
+            })
+            .context("X server has no ARGB32 picture format")?;
+
+        let pixmap = connection.generate_id()?;
+        connection.create_pixmap(32, pixmap, root, size.width as u16, size.height as u16)?;
+        let gc = connection.generate_id()?;
+        connection.create_gc(gc, pixmap, &xproto::CreateGCAux::new())?;
+        connection.put_image(
+            xproto::ImageFormat::Z_PIXMAP,
+            pixmap,
+            gc,
+            size.width as u16,
+            size.height as u16,
+            0,
+            0,
+            0,
+            32,
+            &bytes,
+        )?;
+        let picture = connection.generate_id()?;
+        connection.render_create_picture(
+            picture,
+            pixmap,
+            format.id,
+            &render::CreatePictureAux::new(),
+        )?;
+        let cursor = connection.generate_id()?;
+        connection.render_create_cursor(cursor, picture, hotspot.x as u16, hotspot.y as u16)?;
+
+        connection.render_free_picture(picture)?;
+        connection.free_gc(gc)?;
+        connection.free_pixmap(pixmap)?;
+        Ok(cursor)
+    }
+}
+
+/// Finds the scroll valuators of every input device that has some.
+fn pointer_device_states(
+    xcb_connection: &XCBConnection,
+) -> HashMap<xinput::DeviceId, PointerDeviceState> {
+    let Some(reply) = xcb_connection
+        .xinput_xi_query_device(XINPUT_ALL_DEVICES)
+        .ok()
+        .and_then(|cookie| cookie.reply().log_err())
+    else {
+        return HashMap::default();
+    };
+
+    let mut states = HashMap::default();
+    for info in reply.infos {
+        let mut state = PointerDeviceState::default();
+        for class in info.classes {
+            let xinput::DeviceClassData::Scroll(scroll) = class.data else {
+                continue;
+            };
+            let axis = match scroll.scroll_type {
+                xinput::ScrollType::HORIZONTAL => &mut state.horizontal,
+                xinput::ScrollType::VERTICAL => &mut state.vertical,
+                _ => continue,
+            };
+            let increment = fp3232_to_f32(scroll.increment);
+            if increment != 0.0 {
+                axis.valuator_number = Some(scroll.number);
+                axis.multiplier = SCROLL_LINES / increment;
+            }
+        }
+        if state.horizontal.valuator_number.is_some() || state.vertical.valuator_number.is_some() {
+            states.insert(info.deviceid, state);
+        }
+    }
+    states
+}
+
+/// Reads the refresh rate of the primary output through RandR.
+fn refresh_rate(xcb_connection: &XCBConnection, root: xproto::Window) -> Option<Duration> {
+    let resources = xcb_connection
+        .randr_get_screen_resources_current(root)
+        .ok()?
+        .reply()
+        .ok()?;
+    let primary = xcb_connection
+        .randr_get_output_primary(root)
+        .ok()?
+        .reply()
+        .ok()?
+        .output;
+    let output_info = xcb_connection
+        .randr_get_output_info(primary, resources.config_timestamp)
+        .ok()?
+        .reply()
+        .ok()?;
+    let crtc_info = xcb_connection
+        .randr_get_crtc_info(output_info.crtc, resources.config_timestamp)
+        .ok()?
+        .reply()
+        .ok()?;
+    let mode = resources
+        .modes
+        .iter()
+        .find(|mode| mode.id == crtc_info.mode)?;
+    let dots_per_frame = mode.htotal as u64 * mode.vtotal as u64;
+    if mode.dot_clock == 0 || dots_per_frame == 0 {
+        return None;
+    }
+    Some(Duration::from_nanos(
+        dots_per_frame * 1_000_000_000 / mode.dot_clock as u64,
+    ))
+}
+
+/// Derives the scale factor from `Xft.dpi`, which desktop environments set for HiDPI screens.
+fn scale_factor(resource_database: &resource_manager::Database) -> f32 {
+    if let Some(scale_factor) = env::var(SCALE_FACTOR_ENV_VAR)
+        .ok()
+        .and_then(|value| value.parse::<f32>().ok())
+        .filter(|scale_factor| *scale_factor > 0.0)
+    {
+        return scale_factor;
+    }
+    resource_database
+        .get_value::<f32>("Xft.dpi", "Xft.dpi")
+        .ok()
+        .flatten()
+        .map_or(1.0, |dpi| dpi / 96.0)
+}
+
+struct XcbConnectionFd(Rc<XCBConnection>);
+
+impl AsFd for XcbConnectionFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        unsafe { BorrowedFd::borrow_raw(self.0.as_raw_fd()) }
+    }
+}
+
+pub(crate) struct X11Client(Rc<RefCell<X11ClientState>>);
+
+impl X11Client {
+    pub(crate) fn new() -> Self {
+        let (xcb_connection, x_root_index) =
+            XCBConnection::connect(None).expect("Failed to connect to the X server");
+        let xcb_connection = Rc::new(xcb_connection);
+
+        let xkb_extension = xcb_connection
+            .xkb_use_extension(1, 0)
+            .unwrap()
+            .reply()
+            .unwrap();
+        assert!(xkb_extension.supported, "The X server lacks XKB support");
+        // Smooth scrolling needs XInput 2.1.
+        xcb_connection
+            .xinput_xi_query_version(2, 1)
+            .unwrap()
+            .reply()
+            .expect("The X server lacks XInput2 support");
+
+        let xkb_events = xkb::EventType::STATE_NOTIFY
+            | xkb::EventType::MAP_NOTIFY
+            | xkb::EventType::NEW_KEYBOARD_NOTIFY;
+        xcb_connection
+            .xkb_select_events(
+                xkb::ID::USE_CORE_KBD.into(),
+                0u8.into(),
+                xkb_events,
+                0u8.into(),
+                0u8.into(),
+                &xkb::SelectEventsAux::new(),
+            )
+            .unwrap();
+        xcb_connection
+            .xkb_per_client_flags(
+                xkb::ID::USE_CORE_KBD.into(),
+                xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT,
+                xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT,
+                0u32.into(),
+                0u32.into(),
+                0u32.into(),
+            )
+            .unwrap()
+            .reply()
+            .log_err();
+
+        let root = xcb_connection.setup().roots[x_root_index].root;
+        xcb_connection
+            .xinput_xi_select_events(
+                root,
+                &[xinput::EventMask {
+                    deviceid: XINPUT_ALL_DEVICES,
+                    mask: vec![
+                        xinput::XIEventMask::HIERARCHY | xinput::XIEventMask::DEVICE_CHANGED,
+                    ],
+                }],
+            )
+            .unwrap();
+
+        let atoms = XcbAtoms::new(xcb_connection.as_ref())
+            .unwrap()
+            .reply()
+            .unwrap();
+
+        let xkb_context = xkbc::Context::new(xkbc::CONTEXT_NO_FLAGS);
+        let xkb_device_id = xkbc::x11::get_core_keyboard_device_id(xcb_connection.as_ref());
+        let xkb_keymap = xkbc::x11::keymap_new_from_device(
+            &xkb_context,
+            xcb_connection.as_ref(),
+            xkb_device_id,
+            xkbc::KEYMAP_COMPILE_NO_FLAGS,
+        );
+        let xkb_state =
+            xkbc::x11::state_new_from_device(&xkb_keymap, xcb_connection.as_ref(), xkb_device_id);
+        let compose_state = get_xkb_compose_state(&xkb_context);
+
+        let resource_database = resource_manager::new_from_default(xcb_connection.as_ref())
+            .expect("Failed to read X resources");
+        let scale_factor = scale_factor(&resource_database);
+        let cursor_handle =
+            cursor::Handle::new(xcb_connection.as_ref(), x_root_index, &resource_database)
+                .unwrap()
+                .reply()
+                .unwrap();
+        let refresh_rate = refresh_rate(&xcb_connection, root).unwrap_or(DEFAULT_REFRESH_RATE);
+        let pointer_device_states = pointer_device_states(&xcb_connection);
+
+        let event_loop = EventLoop::<X11ClientStatePtr>::try_new().unwrap();
+        let (common, main_receiver) = LinuxCommon::new(event_loop.get_signal());
+
+        let handle = event_loop.handle();
+        handle
+            .insert_source(main_receiver, {
+                let handle = handle.clone();
+                move |event, _, _: &mut X11ClientStatePtr| {
+                    if let calloop::channel::Event::Msg(runnable) = event {
+                        handle.insert_idle(|_| {
+                            runnable.run();
+                        });
+                    }
+                }
+            })
+            .unwrap();
+
+        handle
+            .insert_source(
+                Generic::new(
+                    XcbConnectionFd(xcb_connection.clone()),
+                    Interest::READ,
+                    Mode::Level,
+                ),
+                |_readiness, _, client| {
+                    client.process_x11_events();
+                    Ok(PostAction::Continue)
+                },
+            )
+            .unwrap();
+
+        handle
+            .insert_source(XDPEventSource::new(&common.background_executor), {
+                move |event, _, client| match event {
+                    XDPEvent::WindowAppearance(appearance) => {
+                        if let Some(client) = client.0.upgrade() {
+                            let mut client = client.borrow_mut();
+                            client.common.appearance = appearance;
+                            for window_ref in client.windows.values_mut() {
+                                window_ref.window.set_appearance(appearance);
+                            }
+                        }
+                    }
+                    // The X server loads cursor themes on its own.
+                    XDPEvent::CursorTheme(_) | XDPEvent::CursorSize(_) => {}
+                }
+            })
+            .unwrap();
+
+        let gpu_context = BladeContext::new().expect("Unable to init GPU context");
+        let clipboard = Clipboard::new().expect("Failed to set up the X11 clipboard");
+
+        xcb_connection.flush().unwrap();
+
+        Self(Rc::new(RefCell::new(X11ClientState {
+            xcb_connection,
+            x_root_index,
+            atoms,
+            scale_factor,
+            refresh_rate,
+            gpu_context,
+            windows: HashMap::default(),
+            mouse_focused_window: None,
+            keyboard_focused_window: None,
+            xkb: xkb_state,
+            xkb_device_id,
+            compose_state,
+            pre_edit_text: None,
+            last_keycode_pressed: None,
+            modifiers: Modifiers::default(),
+            pointer_device_states,
+            last_location: Point::default(),
+            click: ClickState {
+                last_mouse_button: None,
+                last_click: Instant::now(),
+                last_location: Point::default(),
+                current_count: 0,
+            },
+            drag: None,
+            cursor_handle,
+            cursor_style: None,
+            cursor_cache: HashMap::default(),
+            image_cursor: None,
+            clipboard,
+            loop_handle: handle,
+            event_loop: Some(event_loop),
+            common,
+        })))
+    }
+}
+
+impl LinuxClient for X11Client {
+    fn keyboard_layout(&self) -> String {
+        let state = self.0.borrow();
+        let layout_idx = state.xkb.serialize_layout(xkbc::STATE_LAYOUT_EFFECTIVE);
+        state
+            .xkb
+            .get_keymap()
+            .layout_get_name(layout_idx)
+            .to_string()
+    }
+
+    fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
+        let state = self.0.borrow();
+        (0..state.xcb_connection.setup().roots.len())
+            .filter_map(|ix| {
+                X11Display::new(&state.xcb_connection, state.scale_factor, ix)
+                    .log_err()
+                    .map(|display| Rc::new(display) as Rc<dyn PlatformDisplay>)
+            })
+            .collect()
+    }
+
+    fn display(&self, id: DisplayId) -> Option<Rc<dyn PlatformDisplay>> {
+        let state = self.0.borrow();
+        let display =
+            X11Display::new(&state.xcb_connection, state.scale_factor, id.0 as usize).log_err()?;
+        Some(Rc::new(display))
+    }
+
+    fn primary_display(&self) -> Option<Rc<dyn PlatformDisplay>> {
+        let state = self.0.borrow();
+        let display = X11Display::new(
+            &state.xcb_connection,
+            state.scale_factor,
+            state.x_root_index,
+        )
+        .log_err()?;
+        Some(Rc::new(display))
+    }
+
+    fn open_window(
+        &self,
+        handle: AnyWindowHandle,
+        params: WindowParams,
+    ) -> anyhow::Result<Box<dyn PlatformWindow>> {
+        match params.kind {
+            WindowKind::LayerShell => {
+                anyhow::bail!("layer shell windows are only supported on Wayland")
+            }
+            WindowKind::Popover(_) => anyhow::bail!("popovers are only supported on Wayland"),
+            WindowKind::DragIcon => {
+                anyhow::bail!("dragging to other applications is only supported on Wayland")
+            }
+            WindowKind::Normal | WindowKind::PopUp => {}
+        }
+
+        let mut state = self.0.borrow_mut();
+        let x_window = state.xcb_connection.generate_id()?;
+
+        let window = X11Window::new(
+            handle,
+            X11ClientStatePtr(Rc::downgrade(&self.0)),
+            state.common.foreground_executor.clone(),
+            &state.gpu_context,
+            params,
+            &state.xcb_connection,
+            state.x_root_index,
+            x_window,
+            &state.atoms,
+            state.scale_factor,
+            state.common.appearance,
+        )?;
+
+        let refresh_rate = state.refresh_rate;
+        let refresh_event_token = state
+            .loop_handle
+            .insert_source(Timer::from_duration(refresh_rate), {
+                let window = window.0.clone();
+                move |mut instant, (), _| {
+                    window.refresh(false);
+                    // Skip the frames we were too busy to draw.
+                    let now = Instant::now();
+                    while instant < now {
+                        instant += refresh_rate;
+                    }
+                    TimeoutAction::ToInstant(instant)
+                }
+            })
+            .map_err(|error| anyhow::anyhow!("failed to schedule frames: {error}"))?;
+
+        state.windows.insert(
+            x_window,
+            WindowRef {
+                window: window.0.clone(),
+                refresh_event_token,
+            },
+        );
+        Ok(Box::new(window))
+    }
+
+    fn set_cursor_style(&self, style: CursorStyle) {
+        let mut state = self.0.borrow_mut();
+        if state.cursor_style.as_ref() == Some(&style) {
+            return;
+        }
+        if let Some(x_window) = state.mouse_focused_window {
+            state.apply_cursor_style(x_window, &style);
+        }
+        state.cursor_style = Some(style);
+    }
+
+    fn open_uri(&self, uri: &str) {
+        let executor = self.0.borrow().common.background_executor.clone();
+        open_uri_internal(executor, uri, None);
+    }
+
+    fn reveal_path(&self, path: PathBuf) {
+        let executor = self.0.borrow().common.background_executor.clone();
+        reveal_path_internal(executor, path, None);
+    }
+
+    fn with_common<R>(&self, f: impl FnOnce(&mut LinuxCommon) -> R) -> R {
+        f(&mut self.0.borrow_mut().common)
+    }
+
+    fn run(&self) {
+        let mut event_loop = self
+            .0
+            .borrow_mut()
+            .event_loop
+            .take()
+            .expect("App is already running");
+
+        event_loop
+            .run(None, &mut X11ClientStatePtr(Rc::downgrade(&self.0)), |_| {})
+            .log_err();
+    }
+
+    fn write_to_primary(&self, item: crate::ClipboardItem) {
+        self.0.borrow().clipboard.set_primary(item);
+    }
+
+    fn write_to_clipboard(&self, item: crate::ClipboardItem) {
+        self.0.borrow().clipboard.set(item);
+    }
+
+    fn read_from_primary(&self) -> Option<crate::ClipboardItem> {
+        self.0.borrow().clipboard.read_primary()
+    }
+
+    fn read_from_clipboard(&self) -> Option<crate::ClipboardItem> {
+        self.0.borrow().clipboard.read()
+    }
+
+    fn active_window(&self) -> Option<AnyWindowHandle> {
+        let state = self.0.borrow();
+        state
+            .keyboard_focused_window
+            .and_then(|x_window| state.windows.get(&x_window))
+            .map(|window_ref| window_ref.window.handle())
+    }
+
+    fn window_stack(&self) -> Option<Vec<AnyWindowHandle>> {
+        None
+    }
+
+    fn listen_for_other_instances(&self, listener: SingleInstanceListener) {
+        let loop_handle = self.0.borrow().loop_handle.clone();
+        listen_for_forwarded_launches(&loop_handle, listener, |client, launch| {
+            client.handle_forwarded_launch(launch)
+        });
+    }
+
+    fn compositor_name(&self) -> &'static str {
+        "X11"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_from_uri_list() {
+        let paths = paths_from_uri_list(
+            "# dropped from a file manager\r\nfile:///tmp/a%20b.txt\r\nhttps://example.com/\r\nfile:///home/user/\r\n",
+        );
+        assert_eq!(
+            paths.as_slice(),
+            &[PathBuf::from("/tmp/a b.txt"), PathBuf::from("/home/user/")]
+        );
+    }
+}
@@ -0,0 +1,520 @@
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use parking_lot::Mutex;
+use strum::IntoEnumIterator;
+use util::ResultExt;
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{
+            Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt as _, CreateWindowAux,
+            EventMask, PropMode, Property, SelectionNotifyEvent, SelectionRequestEvent, Window,
+            WindowClass, SELECTION_NOTIFY_EVENT,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE,
+};
+
+use crate::{hash, ClipboardEntry, ClipboardItem, Image, ImageFormat};
+
+/// How long to wait for the selection owner before giving up on a read.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+x11rb::atom_manager! {
+    pub(crate) ClipboardAtoms: ClipboardAtomsCookie {
+        CLIPBOARD,
+        PRIMARY,
+        TARGETS,
+        INCR,
+        TEXT,
+        STRING,
+        UTF8_STRING,
+        TEXT_PLAIN_UTF8: b"text/plain;charset=utf-8",
+        TEXT_PLAIN: b"text/plain",
+        GPUI_SELECTION,
+    }
+}
+
+fn image_mime_type(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "image/png",
+        ImageFormat::Jpeg => "image/jpeg",
+        ImageFormat::Webp => "image/webp",
+        ImageFormat::Gif => "image/gif",
+        ImageFormat::Svg => "image/svg+xml",
+        ImageFormat::Bmp => "image/bmp",
+        ImageFormat::Tiff => "image/tiff",
+    }
+}
+
+/// Reads and serves the `CLIPBOARD` and `PRIMARY` selections.
+///
+/// Selections are owned by a hidden window on a dedicated connection, and requests for them
+/// are answered on a background thread, so other clients can paste while the main thread is busy.
+pub(crate) struct Clipboard {
+    reader: Reader,
+    owner: Arc<Owner>,
+}
+
+struct Reader {
+    connection: RustConnection,
+    window: Window,
+    atoms: ClipboardAtoms,
+    image_targets: Vec<(ImageFormat, Atom)>,
+}
+
+struct Owner {
+    connection: RustConnection,
+    window: Window,
+    atoms: ClipboardAtoms,
+    image_targets: Vec<(ImageFormat, Atom)>,
+    contents: Mutex<HashMap<Atom, ClipboardItem>>,
+}
+
+/// An `INCR` transfer of a selection that doesn't fit into a single request.
+struct IncrTransfer {
+    target: Atom,
+    bytes: Vec<u8>,
+    offset: usize,
+}
+
+impl IncrTransfer {
+    fn next_chunk(&mut self, chunk_size: usize) -> &[u8] {
+        let start = self.offset;
+        self.offset = (start + chunk_size).min(self.bytes.len());
+        &self.bytes[start..self.offset]
+    }
+}
+
+fn create_hidden_window(connection: &RustConnection, screen_index: usize) -> Result<Window> {
+    let root = connection.setup().roots[screen_index].root;
+    let window = connection.generate_id()?;
+    connection
+        .create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            COPY_FROM_PARENT,
+            &CreateWindowAux::new()
+                .event_mask(EventMask::PROPERTY_CHANGE | EventMask::STRUCTURE_NOTIFY),
+        )?
+        .check()?;
+    Ok(window)
+}
+
+impl Clipboard {
+    pub(crate) fn new() -> Result<Self> {
+        let (reader_connection, screen_index) =
+            x11rb::connect(None).context("connecting clipboard reader")?;
+        let (owner_connection, _) = x11rb::connect(None).context("connecting clipboard owner")?;
+
+        let atoms = ClipboardAtoms::new(&reader_connection)?.reply()?;
+        let image_targets = ImageFormat::iter()
+            .map(|format| {
+                let atom = reader_connection
+                    .intern_atom(false, image_mime_type(format).as_bytes())?
+                    .reply()?
+                    .atom;
+                Ok((format, atom))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let reader = Reader {
+            window: create_hidden_window(&reader_connection, screen_index)?,
+            connection: reader_connection,
+            atoms,
+            image_targets: image_targets.clone(),
+        };
+        let owner = Arc::new(Owner {
+            window: create_hidden_window(&owner_connection, screen_index)?,
+            connection: owner_connection,
+            atoms,
+            image_targets,
+            contents: Mutex::default(),
+        });
+
+        thread::Builder::new()
+            .name("X11Clipboard".to_string())
+            .spawn({
+                let owner = owner.clone();
+                move || owner.serve().log_err()
+            })?;
+
+        Ok(Self { reader, owner })
+    }
+
+    pub(crate) fn set(&self, item: ClipboardItem) {
+        self.set_selection(self.reader.atoms.CLIPBOARD, item)
+            .log_err();
+    }
+
+    pub(crate) fn set_primary(&self, item: ClipboardItem) {
+        self.set_selection(self.reader.atoms.PRIMARY, item)
+            .log_err();
+    }
+
+    pub(crate) fn read(&self) -> Option<ClipboardItem> {
+        self.read_selection(self.reader.atoms.CLIPBOARD)
+            .log_err()
+            .flatten()
+    }
+
+    pub(crate) fn read_primary(&self) -> Option<ClipboardItem> {
+        self.read_selection(self.reader.atoms.PRIMARY)
+            .log_err()
+            .flatten()
+    }
+
+    fn set_selection(&self, selection: Atom, item: ClipboardItem) -> Result<()> {
+        self.owner.contents.lock().insert(selection, item);
+        self.owner
+            .connection
+            .set_selection_owner(self.owner.window, selection, CURRENT_TIME)?;
+        self.owner.connection.flush()?;
+        Ok(())
+    }
+
+    fn read_selection(&self, selection: Atom) -> Result<Option<ClipboardItem>> {
+        let reader = &self.reader;
+        let owner = reader
+            .connection
+            .get_selection_owner(selection)?
+            .reply()?
+            .owner;
+        if owner == self.owner.window {
+            return Ok(self.owner.contents.lock().get(&selection).cloned());
+        }
+        if owner == NONE {
+            return Ok(None);
+        }
+
+        let atoms = &reader.atoms;
+        let targets = reader
+            .convert(selection, atoms.TARGETS)?
+            .map(|bytes| {
+                bytes
+                    .chunks_exact(4)
+                    .map(|atom| u32::from_ne_bytes([atom[0], atom[1], atom[2], atom[3]]))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        // Owners that don't answer `TARGETS` usually still understand the common text targets.
+        let offers = |target: Atom| targets.is_empty() || targets.contains(&target);
+
+        for target in [atoms.UTF8_STRING, atoms.TEXT_PLAIN_UTF8, atoms.STRING] {
+            if !offers(target) {
+                continue;
+            }
+            if let Some(bytes) = reader.convert(selection, target)? {
+                let text = match String::from_utf8(bytes) {
+                    Ok(text) => text,
+                    Err(error) => {
+                        log::error!("Failed to convert clipboard content to UTF-8: {}", error);
+                        continue;
+                    }
+                };
+                // Normalize the text to unix line endings, like the Wayland clipboard does.
+                return Ok(Some(ClipboardItem::new_string(text.replace("\r\n", "\n"))));
+            }
+        }
+
+        for &(format, target) in &reader.image_targets {
+            if targets.contains(&target) {
+                if let Some(bytes) = reader.convert(selection, target)? {
+                    let id = hash(&bytes);
+                    return Ok(Some(ClipboardItem {
+                        entries: vec![ClipboardEntry::Image(Image { format, bytes, id })],
+                    }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Drop for Clipboard {
+    fn drop(&mut self) {
+        // Destroying the owner window stops the serving thread.
+        self.owner
+            .connection
+            .destroy_window(self.owner.window)
+            .map(|_| ())
+            .log_err();
+        self.owner.connection.flush().log_err();
+    }
+}
+
+impl Reader {
+    /// Asks the selection owner to convert the selection to `target`, returning the
+    /// converted bytes, or `None` if the owner refused or didn't answer in time.
+    fn convert(&self, selection: Atom, target: Atom) -> Result<Option<Vec<u8>>> {
+        let property = self.atoms.GPUI_SELECTION;
+        self.connection.delete_property(self.window, property)?;
+        self.connection.convert_selection(
+            self.window,
+            selection,
+            target,
+            property,
+            CURRENT_TIME,
+        )?;
+        self.connection.flush()?;
+
+        let deadline = Instant::now() + READ_TIMEOUT;
+        let mut incremental_data = None;
+        loop {
+            let Some(event) = self.poll_until(deadline)? else {
+                log::warn!("timed out reading the X11 selection");
+                return Ok(None);
+            };
+            match event {
+                Event::SelectionNotify(event) if event.requestor == self.window => {
+                    if event.property == NONE {
+                        return Ok(None);
+                    }
+                    // Deleting the property tells the owner to start an `INCR` transfer.
+                    let reply = self.take_property()?;
+                    if reply.type_ == self.atoms.INCR {
+                        incremental_data = Some(Vec::new());
+                        continue;
+                    }
+                    return Ok(Some(reply.value));
+                }
+                Event::PropertyNotify(event)
+                    if event.window == self.window
+                        && event.atom == property
+                        && event.state == Property::NEW_VALUE =>
+                {
+                    let Some(data) = incremental_data.as_mut() else {
+                        continue;
+                    };
+                    let reply = self.take_property()?;
+                    if reply.value.is_empty() {
+                        return Ok(incremental_data);
+                    }
+                    data.extend(reply.value);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn take_property(&self) -> Result<x11rb::protocol::xproto::GetPropertyReply> {
+        Ok(self
+            .connection
+            .get_property(
+                true,
+                self.window,
+                self.atoms.GPUI_SELECTION,
+                AtomEnum::ANY,
+                0,
+                u32::MAX,
+            )?
+            .reply()?)
+    }
+
+    fn poll_until(&self, deadline: Instant) -> Result<Option<Event>> {
+        loop {
+            if let Some(event) = self.connection.poll_for_event()? {
+                return Ok(Some(event));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl Owner {
+    fn serve(&self) -> Result<()> {
+        let mut transfers = HashMap::<(Window, Atom), IncrTransfer>::default();
+        loop {
+            match self.connection.wait_for_event()? {
+                Event::SelectionRequest(event) => {
+                    self.handle_request(event, &mut transfers)?;
+                }
+                Event::SelectionClear(event) if event.owner == self.window => {
+                    self.contents.lock().remove(&event.selection);
+                }
+                Event::PropertyNotify(event) if event.state == Property::DELETE => {
+                    let key = (event.window, event.atom);
+                    let Some(transfer) = transfers.get_mut(&key) else {
+                        continue;
+                    };
+                    let target = transfer.target;
+                    let chunk = transfer.next_chunk(self.chunk_size());
+                    let finished = chunk.is_empty();
+                    self.connection.change_property8(
+                        PropMode::REPLACE,
+                        event.window,
+                        event.atom,
+                        target,
+                        chunk,
+                    )?;
+                    self.connection.flush()?;
+                    if finished {
+                        transfers.remove(&key);
+                    }
+                }
+                Event::DestroyNotify(event) if event.window == self.window => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn chunk_size(&self) -> usize {
+        self.connection.maximum_request_bytes() / 4
+    }
+
+    fn handle_request(
+        &self,
+        event: SelectionRequestEvent,
+        transfers: &mut HashMap<(Window, Atom), IncrTransfer>,
+    ) -> Result<()> {
+        // Obsolete clients leave the property unset and expect the target to be used instead.
+        let property = if event.property == NONE {
+            event.target
+        } else {
+            event.property
+        };
+        let item = self.contents.lock().get(&event.selection).cloned();
+        let accepted = match item {
+            Some(item) => {
+                self.send_target(&item, event.requestor, property, event.target, transfers)?
+            }
+            None => false,
+        };
+
+        self.connection.send_event(
+            false,
+            event.requestor,
+            EventMask::NO_EVENT,
+            SelectionNotifyEvent {
+                response_type: SELECTION_NOTIFY_EVENT,
+                sequence: 0,
+                time: event.time,
+                requestor: event.requestor,
+                selection: event.selection,
+                target: event.target,
+                property: if accepted { property } else { NONE },
+            },
+        )?;
+        self.connection.flush()?;
+        Ok(())
+    }
+
+    fn send_target(
+        &self,
+        item: &ClipboardItem,
+        requestor: Window,
+        property: Atom,
+        target: Atom,
+        transfers: &mut HashMap<(Window, Atom), IncrTransfer>,
+    ) -> Result<bool> {
+        let atoms = &self.atoms;
+        let text_targets = [
+            atoms.UTF8_STRING,
+            atoms.TEXT_PLAIN_UTF8,
+            atoms.TEXT_PLAIN,
+            atoms.STRING,
+            atoms.TEXT,
+        ];
+
+        if target == atoms.TARGETS {
+            let mut targets = vec![atoms.TARGETS];
+            if item.text().is_some() {
+                targets.extend(text_targets);
+            }
+            for entry in item.entries() {
+                if let ClipboardEntry::Image(image) = entry {
+                    targets.extend(self.image_target(image.format));
+                }
+            }
+            self.connection.change_property32(
+                PropMode::REPLACE,
+                requestor,
+                property,
+                AtomEnum::ATOM,
+                &targets,
+            )?;
+            return Ok(true);
+        }
+
+        let (property_type, bytes) = if text_targets.contains(&target) {
+            let Some(text) = item.text() else {
+                return Ok(false);
+            };
+            let property_type = if target == atoms.TEXT {
+                atoms.UTF8_STRING
+            } else {
+                target
+            };
+            (property_type, text.into_bytes())
+        } else {
+            let image = item.entries().iter().find_map(|entry| match entry {
+                ClipboardEntry::Image(image) if self.image_target(image.format) == Some(target) => {
+                    Some(image)
+                }
+                _ => None,
+            });
+            let Some(image) = image else {
+                return Ok(false);
+            };
+            (target, image.bytes.clone())
+        };
+
+        if bytes.len() > self.chunk_size() {
+            self.connection.change_window_attributes(
+                requestor,
+                &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+            )?;
+            self.connection.change_property32(
+                PropMode::REPLACE,
+                requestor,
+                property,
+                atoms.INCR,
+                &[bytes.len() as u32],
+            )?;
+            transfers.insert(
+                (requestor, property),
+                IncrTransfer {
+                    target: property_type,
+                    bytes,
+                    offset: 0,
+                },
+            );
+        } else {
+            self.connection.change_property8(
+                PropMode::REPLACE,
+                requestor,
+                property,
+                property_type,
+                &bytes,
+            )?;
+        }
+        Ok(true)
+    }
+
+    fn image_target(&self, format: ImageFormat) -> Option<Atom> {
+        self.image_targets
+            .iter()
+            .find_map(|&(image_format, atom)| (image_format == format).then_some(atom))
+    }
+}
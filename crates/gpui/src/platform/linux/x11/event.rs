@@ -0,0 +1,150 @@
+use x11rb::protocol::{xinput, xproto::KeyButMask};
+
+use crate::{Modifiers, MouseButton, NavigationDirection};
+
+pub(crate) enum ButtonOrScroll {
+    Button(MouseButton),
+    Scroll(ScrollDirection),
+}
+
+pub(crate) enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+pub(crate) fn button_or_scroll_from_event_detail(detail: u32) -> Option<ButtonOrScroll> {
+    Some(match detail {
+        1 => ButtonOrScroll::Button(MouseButton::Left),
+        2 => ButtonOrScroll::Button(MouseButton::Middle),
+        3 => ButtonOrScroll::Button(MouseButton::Right),
+        4 => ButtonOrScroll::Scroll(ScrollDirection::Up),
+        5 => ButtonOrScroll::Scroll(ScrollDirection::Down),
+        6 => ButtonOrScroll::Scroll(ScrollDirection::Left),
+        7 => ButtonOrScroll::Scroll(ScrollDirection::Right),
+        8 => ButtonOrScroll::Button(MouseButton::Navigate(NavigationDirection::Back)),
+        9 => ButtonOrScroll::Button(MouseButton::Navigate(NavigationDirection::Forward)),
+        _ => return None,
+    })
+}
+
+pub(crate) fn modifiers_from_state(state: KeyButMask) -> Modifiers {
+    Modifiers {
+        control: state.contains(KeyButMask::CONTROL),
+        alt: state.contains(KeyButMask::MOD1),
+        shift: state.contains(KeyButMask::SHIFT),
+        platform: state.contains(KeyButMask::MOD4),
+        function: false,
+    }
+}
+
+pub(crate) fn modifiers_from_xinput_info(modifier_info: xinput::ModifierInfo) -> Modifiers {
+    modifiers_from_state(KeyButMask::from(modifier_info.effective as u16))
+}
+
+/// Returns the button that is held during a pointer event, preferring the primary button.
+pub(crate) fn pressed_button_from_mask(button_mask: u32) -> Option<MouseButton> {
+    Some(if button_mask & 2 == 2 {
+        MouseButton::Left
+    } else if button_mask & 4 == 4 {
+        MouseButton::Middle
+    } else if button_mask & 8 == 8 {
+        MouseButton::Right
+    } else {
+        return None;
+    })
+}
+
+pub(crate) fn fp1616_to_f32(value: xinput::Fp1616) -> f32 {
+    value as f32 / 65_536.0
+}
+
+pub(crate) fn fp3232_to_f32(value: xinput::Fp3232) -> f32 {
+    value.integral as f32 + value.frac as f32 / u32::MAX as f32
+}
+
+/// Returns the value of the valuator with the given number, if the event carries it.
+///
+/// XInput2 only sends the valuators that changed, packed in mask order.
+pub(crate) fn get_valuator_axis_index(
+    valuator_mask: &[u32],
+    valuator_number: u16,
+) -> Option<usize> {
+    let bit = valuator_number as usize;
+    let word = *valuator_mask.get(bit / 32)?;
+    if word & (1 << (bit % 32)) == 0 {
+        return None;
+    }
+    let preceding_words: u32 = valuator_mask[..bit / 32]
+        .iter()
+        .map(|word| word.count_ones())
+        .sum();
+    let preceding_bits = (word & ((1u64 << (bit % 32)) - 1) as u32).count_ones();
+    Some((preceding_words + preceding_bits) as usize)
+}
+
+pub(crate) fn is_pointer_emulated(flags: xinput::PointerEventFlags) -> bool {
+    flags.contains(xinput::PointerEventFlags::POINTER_EMULATED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_button_or_scroll_from_event_detail() {
+        assert!(matches!(
+            button_or_scroll_from_event_detail(1),
+            Some(ButtonOrScroll::Button(MouseButton::Left))
+        ));
+        assert!(matches!(
+            button_or_scroll_from_event_detail(5),
+            Some(ButtonOrScroll::Scroll(ScrollDirection::Down))
+        ));
+        assert!(matches!(
+            button_or_scroll_from_event_detail(9),
+            Some(ButtonOrScroll::Button(MouseButton::Navigate(
+                NavigationDirection::Forward
+            )))
+        ));
+        assert!(button_or_scroll_from_event_detail(12).is_none());
+    }
+
+    #[test]
+    fn test_modifiers_from_state() {
+        let modifiers = modifiers_from_state(KeyButMask::CONTROL | KeyButMask::MOD4);
+        assert!(modifiers.control);
+        assert!(modifiers.platform);
+        assert!(!modifiers.shift);
+        assert!(!modifiers.alt);
+    }
+
+    #[test]
+    fn test_pressed_button_from_mask() {
+        assert_eq!(pressed_button_from_mask(0), None);
+        assert_eq!(pressed_button_from_mask(8), Some(MouseButton::Right));
+        assert_eq!(pressed_button_from_mask(2 | 8), Some(MouseButton::Left));
+    }
+
+    #[test]
+    fn test_valuator_axis_index() {
+        let mask = [0b1011, 0b1];
+        assert_eq!(get_valuator_axis_index(&mask, 0), Some(0));
+        assert_eq!(get_valuator_axis_index(&mask, 2), None);
+        assert_eq!(get_valuator_axis_index(&mask, 3), Some(2));
+        assert_eq!(get_valuator_axis_index(&mask, 32), Some(3));
+        assert_eq!(get_valuator_axis_index(&mask, 64), None);
+    }
+
+    #[test]
+    fn test_fixed_point_conversion() {
+        assert_eq!(fp1616_to_f32(3 << 16), 3.0);
+        assert_eq!(fp1616_to_f32(-(1 << 15)), -0.5);
+        let value = xinput::Fp3232 {
+            integral: 2,
+            frac: u32::MAX / 2,
+        };
+        assert!((fp3232_to_f32(value) - 2.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,1140 @@
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    ffi::c_void,
+    mem,
+    num::NonZeroU32,
+    ptr::NonNull,
+    rc::Rc,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context as _};
+use as_raw_xcb_connection::AsRawXcbConnection;
+use blade_graphics as gpu;
+use futures::channel::oneshot::Receiver;
+use raw_window_handle as rwh;
+use util::ResultExt;
+use x11rb::{
+    connection::Connection,
+    properties::{WmSizeHints, WmSizeHintsSpecification},
+    protocol::{
+        xinput::{self, ConnectionExt as _},
+        xproto::{
+            self, AtomEnum, ClientMessageEvent, ColormapAlloc, ConnectionExt as _, CreateWindowAux,
+            EventMask, PropMode, VisualClass,
+        },
+    },
+    wrapper::ConnectionExt as _,
+    xcb_ffi::XCBConnection,
+    CURRENT_TIME,
+};
+
+use super::{display::X11Display, X11ClientStatePtr};
+use crate::platform::{
+    blade::{BladeContext, BladeRenderer, BladeSurfaceConfig},
+    linux::PortalIdleInhibitor,
+    PlatformAtlas, PlatformInputHandler, PlatformWindow,
+};
+use crate::scene::{Damage, Scene};
+use crate::{
+    point, px, size, AnyWindowHandle, Bounds, Decorations, DevicePixels, ForegroundExecutor,
+    GpuSpecs, Modifiers, Pixels, PlatformDisplay, PlatformInput, Point, PromptLevel,
+    RequestFrameOptions, ResizeEdge, ScaledPixels, Size, Tiling, WindowAppearance,
+    WindowBackgroundAppearance, WindowBounds, WindowDecorations, WindowIcon, WindowKind,
+    WindowParams,
+};
+
+x11rb::atom_manager! {
+    pub(crate) XcbAtoms: XcbAtomsCookie {
+        UTF8_STRING,
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
+        WM_CHANGE_STATE,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_ICON,
+        _NET_WM_STATE,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_NOTIFICATION,
+        _NET_ACTIVE_WINDOW,
+        _NET_WM_MOVERESIZE,
+        _MOTIF_WM_HINTS,
+        _GTK_FRAME_EXTENTS,
+        _GTK_SHOW_WINDOW_MENU,
+        _KDE_NET_WM_BLUR_BEHIND_REGION,
+        XdndAware,
+        XdndEnter,
+        XdndPosition,
+        XdndStatus,
+        XdndLeave,
+        XdndDrop,
+        XdndFinished,
+        XdndSelection,
+        XdndTypeList,
+        XdndActionCopy,
+        TEXT_URI_LIST: b"text/uri-list",
+    }
+}
+
+/// The XDND protocol version we implement.
+pub(crate) const XDND_VERSION: u32 = 5;
+
+const NET_WM_STATE_REMOVE: u32 = 0;
+const NET_WM_STATE_ADD: u32 = 1;
+const NET_WM_STATE_TOGGLE: u32 = 2;
+const NET_WM_MOVERESIZE_MOVE: u32 = 8;
+const ICONIC_STATE: u32 = 3;
+
+#[derive(Default)]
+pub(crate) struct Callbacks {
+    request_frame: Option<Box<dyn FnMut(RequestFrameOptions)>>,
+    input: Option<Box<dyn FnMut(crate::PlatformInput) -> crate::DispatchEventResult>>,
+    active_status_change: Option<Box<dyn FnMut(bool)>>,
+    hover_status_change: Option<Box<dyn FnMut(bool)>>,
+    resize: Option<Box<dyn FnMut(Size<Pixels>, f32)>>,
+    moved: Option<Box<dyn FnMut()>>,
+    should_close: Option<Box<dyn FnMut() -> bool>>,
+    close: Option<Box<dyn FnOnce()>>,
+    appearance_changed: Option<Box<dyn FnMut()>>,
+}
+
+struct RawWindow {
+    connection: *mut c_void,
+    screen_id: usize,
+    window_id: u32,
+    visual_id: u32,
+}
+
+impl rwh::HasWindowHandle for RawWindow {
+    fn window_handle(&self) -> Result<rwh::WindowHandle<'_>, rwh::HandleError> {
+        let non_zero = NonZeroU32::new(self.window_id).unwrap();
+        let mut handle = rwh::XcbWindowHandle::new(non_zero);
+        handle.visual_id = NonZeroU32::new(self.visual_id);
+        Ok(unsafe { rwh::WindowHandle::borrow_raw(handle.into()) })
+    }
+}
+impl rwh::HasDisplayHandle for RawWindow {
+    fn display_handle(&self) -> Result<rwh::DisplayHandle<'_>, rwh::HandleError> {
+        let non_zero = NonNull::new(self.connection).unwrap();
+        let handle = rwh::XcbDisplayHandle::new(Some(non_zero), self.screen_id as i32);
+        Ok(unsafe { rwh::DisplayHandle::borrow_raw(handle.into()) })
+    }
+}
+
+pub struct X11WindowState {
+    xcb: Rc<XCBConnection>,
+    atoms: XcbAtoms,
+    x_root_window: xproto::Window,
+    x_screen_index: usize,
+    x_window: xproto::Window,
+    visual_id: xproto::Visualid,
+    executor: ForegroundExecutor,
+    client: X11ClientStatePtr,
+    handle: AnyWindowHandle,
+    renderer: BladeRenderer,
+    /// Set when the next frame has to be presented in full, e.g. after a resize or an expose.
+    needs_full_damage: bool,
+    bounds: Bounds<Pixels>,
+    scale_factor: f32,
+    show: bool,
+    appearance: WindowAppearance,
+    background_appearance: WindowBackgroundAppearance,
+    decorations: WindowDecorations,
+    input_handler: Option<PlatformInputHandler>,
+    active: bool,
+    hovered: bool,
+    fullscreen: bool,
+    maximized_vertical: bool,
+    maximized_horizontal: bool,
+    hidden: bool,
+    inset: Option<Pixels>,
+    idle_inhibitor: Option<PortalIdleInhibitor>,
+}
+
+#[derive(Clone)]
+pub(crate) struct X11WindowStatePtr {
+    pub(crate) state: Rc<RefCell<X11WindowState>>,
+    callbacks: Rc<RefCell<Callbacks>>,
+    xcb: Rc<XCBConnection>,
+    pub(crate) x_window: xproto::Window,
+}
+
+/// Finds a 32-bit true color visual, which lets the compositor blend transparent windows.
+fn find_argb_visual(screen: &xproto::Screen) -> Option<xproto::Visualid> {
+    screen
+        .allowed_depths
+        .iter()
+        .filter(|depth| depth.depth == 32)
+        .flat_map(|depth| depth.visuals.iter())
+        .find(|visual| visual.class == VisualClass::TRUE_COLOR)
+        .map(|visual| visual.visual_id)
+}
+
+impl X11WindowState {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        handle: AnyWindowHandle,
+        client: X11ClientStatePtr,
+        executor: ForegroundExecutor,
+        gpu_context: &BladeContext,
+        params: WindowParams,
+        xcb: &Rc<XCBConnection>,
+        x_screen_index: usize,
+        x_window: xproto::Window,
+        atoms: &XcbAtoms,
+        scale_factor: f32,
+        appearance: WindowAppearance,
+    ) -> anyhow::Result<Self> {
+        let screen = xcb
+            .setup()
+            .roots
+            .get(x_screen_index)
+            .ok_or_else(|| anyhow!("no X11 screen with index {x_screen_index}"))?;
+        let x_root_window = screen.root;
+
+        let (depth, visual_id, colormap) = match find_argb_visual(screen) {
+            Some(visual_id) => {
+                let colormap = xcb.generate_id()?;
+                xcb.create_colormap(ColormapAlloc::NONE, colormap, x_root_window, visual_id)?
+                    .check()
+                    .context("creating colormap")?;
+                (32, visual_id, colormap)
+            }
+            None => (
+                screen.root_depth,
+                screen.root_visual,
+                screen.default_colormap,
+            ),
+        };
+
+        let device_bounds = params.bounds.to_device_pixels(scale_factor);
+        let width = device_bounds.size.width.0.max(1) as u16;
+        let height = device_bounds.size.height.0.max(1) as u16;
+        xcb.create_window(
+            depth,
+            x_window,
+            x_root_window,
+            device_bounds.origin.x.0 as i16,
+            device_bounds.origin.y.0 as i16,
+            width,
+            height,
+            0,
+            xproto::WindowClass::INPUT_OUTPUT,
+            visual_id,
+            &CreateWindowAux::new()
+                .background_pixel(0)
+                .border_pixel(0)
+                .colormap(colormap)
+                .event_mask(
+                    EventMask::EXPOSURE
+                        | EventMask::STRUCTURE_NOTIFY
+                        | EventMask::FOCUS_CHANGE
+                        | EventMask::KEY_PRESS
+                        | EventMask::KEY_RELEASE
+                        | EventMask::PROPERTY_CHANGE,
+                ),
+        )?
+        .check()
+        .context("creating window")?;
+
+        xcb.xinput_xi_select_events(
+            x_window,
+            &[xinput::EventMask {
+                deviceid: XINPUT_ALL_MASTER_DEVICES,
+                mask: vec![
+                    xinput::XIEventMask::MOTION
+                        | xinput::XIEventMask::BUTTON_PRESS
+                        | xinput::XIEventMask::BUTTON_RELEASE
+                        | xinput::XIEventMask::ENTER
+                        | xinput::XIEventMask::LEAVE,
+                ],
+            }],
+        )?;
+
+        xcb.change_property32(
+            PropMode::REPLACE,
+            x_window,
+            atoms.WM_PROTOCOLS,
+            AtomEnum::ATOM,
+            &[atoms.WM_DELETE_WINDOW],
+        )?;
+        xcb.change_property32(
+            PropMode::REPLACE,
+            x_window,
+            atoms._NET_WM_PID,
+            AtomEnum::CARDINAL,
+            &[std::process::id()],
+        )?;
+        xcb.change_property32(
+            PropMode::REPLACE,
+            x_window,
+            atoms.XdndAware,
+            AtomEnum::ATOM,
+            &[XDND_VERSION],
+        )?;
+
+        if params.kind == WindowKind::PopUp {
+            xcb.change_property32(
+                PropMode::REPLACE,
+                x_window,
+                atoms._NET_WM_WINDOW_TYPE,
+                AtomEnum::ATOM,
+                &[atoms._NET_WM_WINDOW_TYPE_NOTIFICATION],
+            )?;
+            xcb.change_property32(
+                PropMode::REPLACE,
+                x_window,
+                atoms._NET_WM_STATE,
+                AtomEnum::ATOM,
+                &[atoms._NET_WM_STATE_ABOVE],
+            )?;
+        }
+
+        let mut size_hints = WmSizeHints::new();
+        size_hints.position = Some((
+            WmSizeHintsSpecification::ProgramSpecified,
+            device_bounds.origin.x.0,
+            device_bounds.origin.y.0,
+        ));
+        if let Some(min_size) = params.window_min_size {
+            size_hints.min_size = Some((
+                (min_size.width.0 * scale_factor) as i32,
+                (min_size.height.0 * scale_factor) as i32,
+            ));
+        }
+        size_hints.set_normal_hints(xcb.as_ref(), x_window)?;
+        xcb.flush()?;
+
+        let renderer = {
+            let raw_window = RawWindow {
+                connection: xcb.as_raw_xcb_connection() as *mut c_void,
+                screen_id: x_screen_index,
+                window_id: x_window,
+                visual_id,
+            };
+            let config = BladeSurfaceConfig {
+                size: gpu::Extent {
+                    width: width as u32,
+                    height: height as u32,
+                    depth: 1,
+                },
+                transparent: depth == 32,
+            };
+            BladeRenderer::new(gpu_context, &raw_window, config)?
+        };
+
+        Ok(Self {
+            xcb: xcb.clone(),
+            atoms: *atoms,
+            x_root_window,
+            x_screen_index,
+            x_window,
+            visual_id,
+            executor,
+            client,
+            handle,
+            renderer,
+            needs_full_damage: true,
+            bounds: params.bounds,
+            scale_factor,
+            show: params.show,
+            appearance,
+            background_appearance: WindowBackgroundAppearance::Opaque,
+            decorations: WindowDecorations::Server,
+            input_handler: None,
+            active: false,
+            hovered: false,
+            fullscreen: false,
+            maximized_vertical: false,
+            maximized_horizontal: false,
+            hidden: false,
+            inset: None,
+            idle_inhibitor: None,
+        })
+    }
+
+    fn is_transparent(&self) -> bool {
+        self.decorations == WindowDecorations::Client
+            || self.background_appearance != WindowBackgroundAppearance::Opaque
+    }
+
+    /// Sends a client message about this window to the window manager.
+    fn send_wm_message(&self, message_type: xproto::Atom, data: [u32; 5]) -> anyhow::Result<()> {
+        let message = ClientMessageEvent::new(32, self.x_window, message_type, data);
+        self.xcb.send_event(
+            false,
+            self.x_root_window,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            message,
+        )?;
+        self.xcb.flush()?;
+        Ok(())
+    }
+
+    fn set_wm_state(
+        &self,
+        action: u32,
+        first: xproto::Atom,
+        second: xproto::Atom,
+    ) -> anyhow::Result<()> {
+        // The fourth value marks the request as coming from a normal application.
+        self.send_wm_message(self.atoms._NET_WM_STATE, [action, first, second, 1, 0])
+    }
+
+    fn root_pointer_position(&self) -> anyhow::Result<(i16, i16)> {
+        let reply = self.xcb.query_pointer(self.x_root_window)?.reply()?;
+        Ok((reply.root_x, reply.root_y))
+    }
+}
+
+/// Selects events from every master device rather than from a specific pointer or keyboard.
+pub(crate) const XINPUT_ALL_MASTER_DEVICES: xinput::DeviceId = 1;
+
+pub(crate) struct X11Window(pub X11WindowStatePtr);
+
+impl Drop for X11Window {
+    fn drop(&mut self) {
+        let mut state = self.0.state.borrow_mut();
+        state.renderer.destroy();
+
+        self.0.xcb.destroy_window(self.0.x_window).log_err();
+        self.0.xcb.flush().log_err();
+
+        let this_ptr = self.0.clone();
+        let client_ptr = state.client.clone();
+        state
+            .executor
+            .spawn(async move {
+                this_ptr.close();
+                client_ptr.drop_window(this_ptr.x_window);
+            })
+            .detach();
+        drop(state);
+    }
+}
+
+impl X11Window {
+    fn borrow(&self) -> Ref<X11WindowState> {
+        self.0.state.borrow()
+    }
+
+    fn borrow_mut(&self) -> RefMut<X11WindowState> {
+        self.0.state.borrow_mut()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        handle: AnyWindowHandle,
+        client: X11ClientStatePtr,
+        executor: ForegroundExecutor,
+        gpu_context: &BladeContext,
+        params: WindowParams,
+        xcb: &Rc<XCBConnection>,
+        x_screen_index: usize,
+        x_window: xproto::Window,
+        atoms: &XcbAtoms,
+        scale_factor: f32,
+        appearance: WindowAppearance,
+    ) -> anyhow::Result<Self> {
+        Ok(Self(X11WindowStatePtr {
+            state: Rc::new(RefCell::new(X11WindowState::new(
+                handle,
+                client,
+                executor,
+                gpu_context,
+                params,
+                xcb,
+                x_screen_index,
+                x_window,
+                atoms,
+                scale_factor,
+                appearance,
+            )?)),
+            callbacks: Rc::new(RefCell::new(Callbacks::default())),
+            xcb: xcb.clone(),
+            x_window,
+        }))
+    }
+
+    fn state_ptr(&self) -> &X11WindowStatePtr {
+        &self.0
+    }
+}
+
+impl X11WindowStatePtr {
+    pub fn handle(&self) -> AnyWindowHandle {
+        self.state.borrow().handle
+    }
+
+    pub fn activate(&self) {
+        let state = self.state.borrow();
+        // The first value marks the request as coming from a normal application.
+        state
+            .send_wm_message(state.atoms._NET_ACTIVE_WINDOW, [1, CURRENT_TIME, 0, 0, 0])
+            .log_err();
+    }
+
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.state.borrow().scale_factor
+    }
+
+    pub fn refresh(&self, require_presentation: bool) {
+        let mut callbacks = self.callbacks.borrow_mut();
+        if let Some(ref mut fun) = callbacks.request_frame {
+            fun(RequestFrameOptions {
+                require_presentation,
+            });
+        }
+    }
+
+    /// Handles an `Expose` event: the X server discarded (part of) the window contents.
+    pub fn expose(&self) {
+        self.state.borrow_mut().needs_full_damage = true;
+        self.refresh(true);
+    }
+
+    pub fn handle_input(&self, input: PlatformInput) {
+        if let Some(ref mut fun) = self.callbacks.borrow_mut().input {
+            if !fun(input.clone()).propagate {
+                return;
+            }
+        }
+        if let PlatformInput::KeyDown(event) = input {
+            if let Some(key_char) = &event.keystroke.key_char {
+                self.insert_text(key_char);
+            }
+        }
+    }
+
+    pub fn insert_text(&self, text: &str) {
+        let mut state = self.state.borrow_mut();
+        if let Some(mut input_handler) = state.input_handler.take() {
+            drop(state);
+            input_handler.replace_text_in_range(None, text);
+            self.state.borrow_mut().input_handler = Some(input_handler);
+        }
+    }
+
+    pub fn set_marked_text(&self, text: &str) {
+        let mut state = self.state.borrow_mut();
+        if let Some(mut input_handler) = state.input_handler.take() {
+            drop(state);
+            input_handler.replace_and_mark_text_in_range(None, text, None);
+            self.state.borrow_mut().input_handler = Some(input_handler);
+        }
+    }
+
+    pub fn delete_marked_text(&self) {
+        let mut state = self.state.borrow_mut();
+        if let Some(mut input_handler) = state.input_handler.take() {
+            drop(state);
+            if let Some(marked) = input_handler.marked_text_range() {
+                input_handler.replace_text_in_range(Some(marked), "");
+            }
+            self.state.borrow_mut().input_handler = Some(input_handler);
+        }
+    }
+
+    /// Handles a `ConfigureNotify` event with the window's new size in device pixels.
+    pub fn configure(&self, device_size: Size<DevicePixels>) {
+        let origin = self
+            .xcb
+            .translate_coordinates(self.x_window, self.state.borrow().x_root_window, 0, 0)
+            .ok()
+            .and_then(|cookie| cookie.reply().log_err());
+
+        let mut state = self.state.borrow_mut();
+        let scale_factor = state.scale_factor;
+        let new_size = size(
+            px(device_size.width.0 as f32 / scale_factor),
+            px(device_size.height.0 as f32 / scale_factor),
+        );
+        let new_origin = origin.map_or(state.bounds.origin, |origin| {
+            point(
+                px(origin.dst_x as f32 / scale_factor),
+                px(origin.dst_y as f32 / scale_factor),
+            )
+        });
+
+        let resized = new_size != state.bounds.size;
+        let moved = new_origin != state.bounds.origin;
+        state.bounds = Bounds {
+            origin: new_origin,
+            size: new_size,
+        };
+        if resized {
+            state.renderer.update_drawable_size(device_size);
+            state.needs_full_damage = true;
+        }
+        drop(state);
+
+        let mut callbacks = self.callbacks.borrow_mut();
+        if resized {
+            if let Some(ref mut fun) = callbacks.resize {
+                fun(new_size, scale_factor);
+            }
+        }
+        if moved {
+            if let Some(ref mut fun) = callbacks.moved {
+                fun();
+            }
+        }
+    }
+
+    /// Re-reads `_NET_WM_STATE` after the window manager changed it.
+    pub fn update_wm_state(&self) {
+        let mut state = self.state.borrow_mut();
+        let atoms = state.atoms;
+        let Some(reply) = self
+            .xcb
+            .get_property(
+                false,
+                self.x_window,
+                atoms._NET_WM_STATE,
+                AtomEnum::ATOM,
+                0,
+                u32::MAX,
+            )
+            .ok()
+            .and_then(|cookie| cookie.reply().log_err())
+        else {
+            return;
+        };
+        let wm_state = reply
+            .value32()
+            .map(|atoms| atoms.collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        state.fullscreen = wm_state.contains(&atoms._NET_WM_STATE_FULLSCREEN);
+        state.maximized_vertical = wm_state.contains(&atoms._NET_WM_STATE_MAXIMIZED_VERT);
+        state.maximized_horizontal = wm_state.contains(&atoms._NET_WM_STATE_MAXIMIZED_HORZ);
+        let hidden = wm_state.contains(&atoms._NET_WM_STATE_HIDDEN);
+        if state.hidden && !hidden {
+            state.needs_full_damage = true;
+        }
+        state.hidden = hidden;
+    }
+
+    pub fn should_close(&self) -> bool {
+        let mut callbacks = self.callbacks.borrow_mut();
+        callbacks.should_close.as_mut().map_or(true, |fun| fun())
+    }
+
+    pub fn close(&self) {
+        let mut callbacks = self.callbacks.borrow_mut();
+        if let Some(fun) = callbacks.close.take() {
+            fun()
+        }
+    }
+
+    pub fn set_active(&self, focus: bool) {
+        self.state.borrow_mut().active = focus;
+        if let Some(ref mut fun) = self.callbacks.borrow_mut().active_status_change {
+            fun(focus);
+        }
+    }
+
+    pub fn set_hovered(&self, hovered: bool) {
+        self.state.borrow_mut().hovered = hovered;
+        if let Some(ref mut fun) = self.callbacks.borrow_mut().hover_status_change {
+            fun(hovered);
+        }
+    }
+
+    pub fn set_appearance(&mut self, appearance: WindowAppearance) {
+        self.state.borrow_mut().appearance = appearance;
+
+        let mut callbacks = self.callbacks.borrow_mut();
+        if let Some(ref mut fun) = callbacks.appearance_changed {
+            (fun)()
+        }
+    }
+}
+
+impl rwh::HasWindowHandle for X11Window {
+    fn window_handle(&self) -> Result<rwh::WindowHandle<'_>, rwh::HandleError> {
+        let non_zero = NonZeroU32::new(self.0.x_window).ok_or(rwh::HandleError::Unavailable)?;
+        let mut handle = rwh::XcbWindowHandle::new(non_zero);
+        handle.visual_id = NonZeroU32::new(self.0.state.borrow().visual_id);
+        // The window is only destroyed when this is dropped.
+        Ok(unsafe { rwh::WindowHandle::borrow_raw(handle.into()) })
+    }
+}
+impl rwh::HasDisplayHandle for X11Window {
+    fn display_handle(&self) -> Result<rwh::DisplayHandle<'_>, rwh::HandleError> {
+        let connection = NonNull::new(self.0.xcb.as_raw_xcb_connection() as *mut c_void)
+            .ok_or(rwh::HandleError::Unavailable)?;
+        let screen = self.0.state.borrow().x_screen_index as i32;
+        let handle = rwh::XcbDisplayHandle::new(Some(connection), screen);
+        // The connection is kept open by this window.
+        Ok(unsafe { rwh::DisplayHandle::borrow_raw(handle.into()) })
+    }
+}
+
+impl PlatformWindow for X11Window {
+    fn bounds(&self) -> Bounds<Pixels> {
+        self.borrow().bounds
+    }
+
+    fn is_maximized(&self) -> bool {
+        let state = self.borrow();
+        state.maximized_vertical && state.maximized_horizontal
+    }
+
+    fn window_bounds(&self) -> WindowBounds {
+        let state = self.borrow();
+        if state.fullscreen {
+            WindowBounds::Fullscreen(state.bounds)
+        } else if state.maximized_vertical && state.maximized_horizontal {
+            WindowBounds::Maximized(state.bounds)
+        } else {
+            WindowBounds::Windowed(state.bounds)
+        }
+    }
+
+    fn inner_window_bounds(&self) -> WindowBounds {
+        let state = self.borrow();
+        let inset = state.inset.unwrap_or(px(0.));
+        drop(state);
+        match self.window_bounds() {
+            WindowBounds::Windowed(bounds) => WindowBounds::Windowed(bounds.inset(inset)),
+            window_bounds => window_bounds,
+        }
+    }
+
+    fn content_size(&self) -> Size<Pixels> {
+        self.borrow().bounds.size
+    }
+
+    fn scale_factor(&self) -> f32 {
+        self.borrow().scale_factor
+    }
+
+    fn appearance(&self) -> WindowAppearance {
+        self.borrow().appearance
+    }
+
+    fn display(&self) -> Option<Rc<dyn PlatformDisplay>> {
+        let state = self.borrow();
+        let display =
+            X11Display::new(&state.xcb, state.scale_factor, state.x_screen_index).log_err()?;
+        Some(Rc::new(display))
+    }
+
+    fn mouse_position(&self) -> Point<Pixels> {
+        let state = self.borrow();
+        state
+            .xcb
+            .query_pointer(self.0.x_window)
+            .ok()
+            .and_then(|cookie| cookie.reply().log_err())
+            .map_or(Point::default(), |reply| {
+                point(
+                    px(reply.win_x as f32 / state.scale_factor),
+                    px(reply.win_y as f32 / state.scale_factor),
+                )
+            })
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        self.borrow().client.modifiers()
+    }
+
+    fn set_input_handler(&mut self, input_handler: PlatformInputHandler) {
+        self.borrow_mut().input_handler = Some(input_handler);
+    }
+
+    fn take_input_handler(&mut self) -> Option<PlatformInputHandler> {
+        self.borrow_mut().input_handler.take()
+    }
+
+    fn prompt(
+        &self,
+        _level: PromptLevel,
+        _msg: &str,
+        _detail: Option<&str>,
+        _answers: &[&str],
+    ) -> Option<Receiver<usize>> {
+        None
+    }
+
+    fn activate(&self) {
+        self.0.activate();
+    }
+
+    fn is_active(&self) -> bool {
+        self.borrow().active
+    }
+
+    fn is_hovered(&self) -> bool {
+        self.borrow().hovered
+    }
+
+    fn set_title(&mut self, title: &str) {
+        let state = self.borrow();
+        state
+            .xcb
+            .change_property8(
+                PropMode::REPLACE,
+                self.0.x_window,
+                state.atoms._NET_WM_NAME,
+                state.atoms.UTF8_STRING,
+                title.as_bytes(),
+            )
+            .log_err();
+        state
+            .xcb
+            .change_property8(
+                PropMode::REPLACE,
+                self.0.x_window,
+                AtomEnum::WM_NAME,
+                AtomEnum::STRING,
+                title.as_bytes(),
+            )
+            .log_err();
+        state.xcb.flush().log_err();
+    }
+
+    fn set_app_id(&mut self, app_id: &str) {
+        // WM_CLASS holds the instance name followed by the class name, both nul-terminated.
+        let mut class = app_id.as_bytes().to_vec();
+        class.push(0);
+        class.extend_from_slice(app_id.as_bytes());
+        class.push(0);
+
+        let state = self.borrow();
+        state
+            .xcb
+            .change_property8(
+                PropMode::REPLACE,
+                self.0.x_window,
+                AtomEnum::WM_CLASS,
+                AtomEnum::STRING,
+                &class,
+            )
+            .log_err();
+        state.xcb.flush().log_err();
+    }
+
+    fn set_icon(&mut self, icon: Option<&WindowIcon>) {
+        let state = self.borrow();
+        // Icons from the icon theme can only be found by the window manager through WM_CLASS.
+        let Some(icon) = icon.filter(|icon| !icon.images().is_empty()) else {
+            state
+                .xcb
+                .delete_property(self.0.x_window, state.atoms._NET_WM_ICON)
+                .log_err();
+            state.xcb.flush().log_err();
+            return;
+        };
+
+        // _NET_WM_ICON holds the width and height of each image, followed by its pixels as
+        // non-premultiplied ARGB.
+        let mut data = Vec::new();
+        for image in icon.images() {
+            data.push(image.width());
+            data.push(image.height());
+            data.extend(image.pixels().map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                u32::from_be_bytes([a, r, g, b])
+            }));
+        }
+        state
+            .xcb
+            .change_property32(
+                PropMode::REPLACE,
+                self.0.x_window,
+                state.atoms._NET_WM_ICON,
+                AtomEnum::CARDINAL,
+                &data,
+            )
+            .log_err();
+        state.xcb.flush().log_err();
+    }
+
+    fn map_window(&mut self) -> anyhow::Result<()> {
+        let state = self.borrow();
+        if state.show {
+            state.xcb.map_window(self.0.x_window)?;
+            state.xcb.flush()?;
+        }
+        Ok(())
+    }
+
+    fn set_background_appearance(&self, background_appearance: WindowBackgroundAppearance) {
+        let mut state = self.borrow_mut();
+        state.background_appearance = background_appearance;
+        update_window(self.state_ptr(), state);
+    }
+
+    fn minimize(&self) {
+        let state = self.borrow();
+        state
+            .send_wm_message(state.atoms.WM_CHANGE_STATE, [ICONIC_STATE, 0, 0, 0, 0])
+            .log_err();
+    }
+
+    fn zoom(&self) {
+        let state = self.borrow();
+        state
+            .set_wm_state(
+                NET_WM_STATE_TOGGLE,
+                state.atoms._NET_WM_STATE_MAXIMIZED_VERT,
+                state.atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+            )
+            .log_err();
+    }
+
+    fn toggle_fullscreen(&self) {
+        let state = self.borrow();
+        let action = if state.fullscreen {
+            NET_WM_STATE_REMOVE
+        } else {
+            NET_WM_STATE_ADD
+        };
+        state
+            .set_wm_state(action, state.atoms._NET_WM_STATE_FULLSCREEN, 0)
+            .log_err();
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.borrow().fullscreen
+    }
+
+    fn on_request_frame(&self, callback: Box<dyn FnMut(RequestFrameOptions)>) {
+        self.0.callbacks.borrow_mut().request_frame = Some(callback);
+    }
+
+    fn on_input(&self, callback: Box<dyn FnMut(PlatformInput) -> crate::DispatchEventResult>) {
+        self.0.callbacks.borrow_mut().input = Some(callback);
+    }
+
+    fn on_active_status_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0.callbacks.borrow_mut().active_status_change = Some(callback);
+    }
+
+    fn on_hover_status_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0.callbacks.borrow_mut().hover_status_change = Some(callback);
+    }
+
+    fn on_resize(&self, callback: Box<dyn FnMut(Size<Pixels>, f32)>) {
+        self.0.callbacks.borrow_mut().resize = Some(callback);
+    }
+
+    fn on_moved(&self, callback: Box<dyn FnMut()>) {
+        self.0.callbacks.borrow_mut().moved = Some(callback);
+    }
+
+    fn on_should_close(&self, callback: Box<dyn FnMut() -> bool>) {
+        self.0.callbacks.borrow_mut().should_close = Some(callback);
+    }
+
+    fn on_close(&self, callback: Box<dyn FnOnce()>) {
+        self.0.callbacks.borrow_mut().close = Some(callback);
+    }
+
+    fn on_appearance_changed(&self, callback: Box<dyn FnMut()>) {
+        self.0.callbacks.borrow_mut().appearance_changed = Some(callback);
+    }
+
+    fn draw(&self, scene: &Scene, damage: &Damage) {
+        let mut state = self.borrow_mut();
+        if state.hidden {
+            return;
+        }
+        // X11 has no notion of buffer damage, so the most we can do is to skip presenting
+        // frames that didn't change anything.
+        if !mem::take(&mut state.needs_full_damage) && damage.is_empty() {
+            return;
+        }
+        state.renderer.draw(scene);
+    }
+
+    fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
+        let state = self.borrow();
+        state.renderer.sprite_atlas().clone()
+    }
+
+    fn show_window_menu(&self, position: Point<Pixels>) {
+        let state = self.borrow();
+        let Some(origin) = self
+            .0
+            .xcb
+            .translate_coordinates(
+                self.0.x_window,
+                state.x_root_window,
+                (position.x.0 * state.scale_factor) as i16,
+                (position.y.0 * state.scale_factor) as i16,
+            )
+            .ok()
+            .and_then(|cookie| cookie.reply().log_err())
+        else {
+            return;
+        };
+        // The window manager can't open the menu while we hold the implicit pointer grab.
+        self.0.xcb.ungrab_pointer(CURRENT_TIME).log_err();
+        state
+            .send_wm_message(
+                state.atoms._GTK_SHOW_WINDOW_MENU,
+                [0, origin.dst_x as u32, origin.dst_y as u32, 0, 0],
+            )
+            .log_err();
+    }
+
+    fn start_window_move(&self) {
+        let state = self.borrow();
+        start_move_resize(&state, NET_WM_MOVERESIZE_MOVE);
+    }
+
+    fn start_window_resize(&self, edge: ResizeEdge) {
+        let state = self.borrow();
+        start_move_resize(&state, edge.to_moveresize());
+    }
+
+    fn set_idle_inhibited(&self, inhibited: bool) {
+        let mut state = self.borrow_mut();
+        if inhibited == state.idle_inhibitor.is_some() {
+            return;
+        }
+        state.idle_inhibitor = inhibited.then(|| PortalIdleInhibitor::new(&state.executor));
+    }
+
+    fn window_decorations(&self) -> Decorations {
+        let state = self.borrow();
+        match state.decorations {
+            WindowDecorations::Server => Decorations::Server,
+            WindowDecorations::Client => Decorations::Client {
+                tiling: if state.fullscreen
+                    || (state.maximized_vertical && state.maximized_horizontal)
+                {
+                    Tiling::tiled()
+                } else {
+                    Tiling::default()
+                },
+            },
+        }
+    }
+
+    fn request_decorations(&self, decorations: WindowDecorations) {
+        let mut state = self.borrow_mut();
+        state.decorations = decorations;
+        // _MOTIF_WM_HINTS: flags (decorations only), functions, decorations, input mode, status.
+        let server_decorations = (decorations == WindowDecorations::Server) as u32;
+        state
+            .xcb
+            .change_property32(
+                PropMode::REPLACE,
+                self.0.x_window,
+                state.atoms._MOTIF_WM_HINTS,
+                state.atoms._MOTIF_WM_HINTS,
+                &[2, 0, server_decorations, 0, 0],
+            )
+            .log_err();
+        update_window(self.state_ptr(), state);
+    }
+
+    fn set_client_inset(&self, inset: Pixels) {
+        let mut state = self.borrow_mut();
+        if Some(inset) != state.inset {
+            state.inset = Some(inset);
+            update_window(self.state_ptr(), state);
+        }
+    }
+
+    fn update_ime_position(&self, _bounds: Bounds<ScaledPixels>) {
+        // Only compose sequences are supported, which don't need a candidate window.
+    }
+
+    fn gpu_specs(&self) -> Option<GpuSpecs> {
+        self.borrow().renderer.gpu_specs().into()
+    }
+}
+
+fn start_move_resize(state: &X11WindowState, direction: u32) {
+    let Some((root_x, root_y)) = state.root_pointer_position().log_err() else {
+        return;
+    };
+    // The window manager takes over the pointer, so release the implicit grab first.
+    state.xcb.ungrab_pointer(CURRENT_TIME).log_err();
+    state
+        .send_wm_message(
+            state.atoms._NET_WM_MOVERESIZE,
+            [root_x as u32, root_y as u32, direction, 1, 1],
+        )
+        .log_err();
+}
+
+/// Pushes decoration, inset and blur state to the window manager and compositor.
+fn update_window(window: &X11WindowStatePtr, mut state: RefMut<X11WindowState>) {
+    let transparent = state.is_transparent();
+    state.renderer.update_transparency(transparent);
+    state.needs_full_damage = true;
+
+    let atoms = state.atoms;
+    let x_window = window.x_window;
+    match state.inset {
+        Some(inset) if state.decorations == WindowDecorations::Client => {
+            let inset = (inset.0 * state.scale_factor) as u32;
+            state
+                .xcb
+                .change_property32(
+                    PropMode::REPLACE,
+                    x_window,
+                    atoms._GTK_FRAME_EXTENTS,
+                    AtomEnum::CARDINAL,
+                    &[inset, inset, inset, inset],
+                )
+                .log_err();
+        }
+        _ => {
+            state
+                .xcb
+                .delete_property(x_window, atoms._GTK_FRAME_EXTENTS)
+                .log_err();
+        }
+    }
+
+    if state.background_appearance == WindowBackgroundAppearance::Blurred {
+        // An empty region asks KWin to blur behind the whole window.
+        state
+            .xcb
+            .change_property32(
+                PropMode::REPLACE,
+                x_window,
+                atoms._KDE_NET_WM_BLUR_BEHIND_REGION,
+                AtomEnum::CARDINAL,
+                &[],
+            )
+            .log_err();
+    } else {
+        state
+            .xcb
+            .delete_property(x_window, atoms._KDE_NET_WM_BLUR_BEHIND_REGION)
+            .log_err();
+    }
+    state.xcb.flush().log_err();
+}
+
+impl ResizeEdge {
+    fn to_moveresize(&self) -> u32 {
+        match self {
+            ResizeEdge::TopLeft => 0,
+            ResizeEdge::Top => 1,
+            ResizeEdge::TopRight => 2,
+            ResizeEdge::Right => 3,
+            ResizeEdge::BottomRight => 4,
+            ResizeEdge::Bottom => 5,
+            ResizeEdge::BottomLeft => 6,
+            ResizeEdge::Left => 7,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{Action, SharedString};
+
+/// How urgent a notification is.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NotificationUrgency {
+    /// A notification that can be shown discreetly, e.g. a finished download.
+    Low,
+    /// A regular notification.
+    #[default]
+    Normal,
+    /// A notification that needs the user's attention right away. Desktops usually show
+    /// these even when notifications are muted, and keep them on screen until dismissed.
+    Critical,
+}
+
+/// A notification shown by the desktop outside of the application's windows,
+/// see [`App::show_notification`](crate::App::show_notification).
+pub struct Notification {
+    pub(crate) title: SharedString,
+    pub(crate) body: Option<SharedString>,
+    pub(crate) icon: Option<SharedString>,
+    pub(crate) urgency: NotificationUrgency,
+    pub(crate) default_action: Option<Box<dyn Action>>,
+    pub(crate) buttons: Vec<NotificationButton>,
+}
+
+/// A button shown in a notification.
+pub(crate) struct NotificationButton {
+    pub(crate) label: SharedString,
+    pub(crate) action: Box<dyn Action>,
+}
+
+/// How the user responded to a notification.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum NotificationResponse {
+    /// The notification itself was clicked.
+    Default,
+    /// The button at this index was clicked.
+    Button(usize),
+}
+
+impl Notification {
+    /// Creates a notification with the given title.
+    pub fn new(title: impl Into<SharedString>) -> Self {
+        Self {
+            title: title.into(),
+            body: None,
+            icon: None,
+            urgency: NotificationUrgency::default(),
+            default_action: None,
+            buttons: Vec::new(),
+        }
+    }
+
+    /// Sets the text shown below the title.
+    pub fn body(mut self, body: impl Into<SharedString>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Sets the icon, by its name in the desktop's icon theme, e.g. `dialog-information`.
+    pub fn icon(mut self, name: impl Into<SharedString>) -> Self {
+        self.icon = Some(name.into());
+        self
+    }
+
+    /// Sets how urgent the notification is.
+    pub fn urgency(mut self, urgency: NotificationUrgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+
+    /// Sets the action dispatched when the notification itself is clicked.
+    pub fn on_click(mut self, action: impl Action) -> Self {
+        self.default_action = Some(Box::new(action));
+        self
+    }
+
+    /// Adds a button with the given label, which dispatches `action` when clicked.
+    pub fn button(mut self, label: impl Into<SharedString>, action: impl Action) -> Self {
+        self.buttons.push(NotificationButton {
+            label: label.into(),
+            action: Box::new(action),
+        });
+        self
+    }
+
+    /// The action to dispatch for a response.
+    pub(crate) fn action_for(&self, response: NotificationResponse) -> Option<&dyn Action> {
+        match response {
+            NotificationResponse::Default => self.default_action.as_deref(),
+            NotificationResponse::Button(ix) => {
+                self.buttons.get(ix).map(|button| button.action.as_ref())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as gpui;
+    use gpui::actions;
+
+    actions!(notification_test, [Open, Reply, Archive]);
+
+    #[test]
+    fn test_action_for() {
+        let notification = Notification::new("New message")
+            .on_click(Open)
+            .button("Reply", Reply)
+            .button("Archive", Archive);
+
+        let action = |response| notification.action_for(response);
+        assert!(action(NotificationResponse::Default)
+            .unwrap()
+            .partial_eq(&Open));
+        assert!(action(NotificationResponse::Button(0))
+            .unwrap()
+            .partial_eq(&Reply));
+        assert!(action(NotificationResponse::Button(1))
+            .unwrap()
+            .partial_eq(&Archive));
+        assert!(action(NotificationResponse::Button(2)).is_none());
+
+        let notification = Notification::new("Download finished");
+        assert!(notification
+            .action_for(NotificationResponse::Default)
+            .is_none());
+    }
+}
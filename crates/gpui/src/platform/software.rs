@@ -0,0 +1,7 @@
+mod software_atlas;
+#[cfg(feature = "software-renderer")]
+mod software_renderer;
+
+pub(crate) use software_atlas::*;
+#[cfg(feature = "software-renderer")]
+pub(crate) use software_renderer::*;
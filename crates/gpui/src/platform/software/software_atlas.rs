@@ -0,0 +1,255 @@
+use crate::{
+    platform::AtlasTextureList, AtlasKey, AtlasTextureId, AtlasTextureKind, AtlasTile, Bounds,
+    DevicePixels, PlatformAtlas, Point, Size,
+};
+use anyhow::Result;
+use collections::FxHashMap;
+use etagere::BucketedAtlasAllocator;
+use parking_lot::Mutex;
+use std::{borrow::Cow, ops};
+
+/// An atlas that keeps its textures in main memory, for windows that are not backed by a GPU.
+pub(crate) struct SoftwareAtlas(Mutex<SoftwareAtlasState>);
+
+struct SoftwareAtlasState {
+    storage: SoftwareAtlasStorage,
+    tiles_by_key: FxHashMap<AtlasKey, AtlasTile>,
+}
+
+impl SoftwareAtlas {
+    pub(crate) fn new() -> Self {
+        SoftwareAtlas(Mutex::new(SoftwareAtlasState {
+            storage: SoftwareAtlasStorage::default(),
+            tiles_by_key: Default::default(),
+        }))
+    }
+
+    /// Drops every texture in the atlas.
+    pub(crate) fn clear(&self) {
+        let mut lock = self.0.lock();
+        lock.tiles_by_key.clear();
+        lock.storage.monochrome_textures.drain();
+        lock.storage.polychrome_textures.drain();
+        lock.storage.path_textures.drain();
+    }
+
+    /// Calls `f` with the texture identified by `id`, if it still exists.
+    pub(crate) fn with_texture<R>(
+        &self,
+        id: AtlasTextureId,
+        f: impl FnOnce(&SoftwareAtlasTexture) -> R,
+    ) -> Option<R> {
+        let lock = self.0.lock();
+        let textures = &lock.storage[id.kind];
+        textures
+            .textures
+            .get(id.index as usize)
+            .and_then(|texture| texture.as_ref())
+            .map(f)
+    }
+}
+
+impl PlatformAtlas for SoftwareAtlas {
+    fn get_or_insert_with<'a>(
+        &self,
+        key: &AtlasKey,
+        build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Option<AtlasTile>> {
+        let mut lock = self.0.lock();
+        if let Some(tile) = lock.tiles_by_key.get(key) {
+            Ok(Some(tile.clone()))
+        } else {
+            profiling::scope!("new tile");
+            let Some((size, bytes)) = build()? else {
+                return Ok(None);
+            };
+            let tile = lock.allocate(size, key.texture_kind());
+            lock.upload_texture(&tile, &bytes);
+            lock.tiles_by_key.insert(key.clone(), tile.clone());
+            Ok(Some(tile))
+        }
+    }
+
+    fn remove(&self, key: &AtlasKey) {
+        let mut lock = self.0.lock();
+        let Some(id) = lock.tiles_by_key.remove(key).map(|tile| tile.texture_id) else {
+            return;
+        };
+
+        let textures = &mut lock.storage[id.kind];
+        let Some(texture_slot) = textures.textures.get_mut(id.index as usize) else {
+            return;
+        };
+
+        if let Some(mut texture) = texture_slot.take() {
+            texture.decrement_ref_count();
+            if texture.is_unreferenced() {
+                textures.free_list.push(id.index as usize);
+            } else {
+                *texture_slot = Some(texture);
+            }
+        }
+    }
+}
+
+impl SoftwareAtlasState {
+    fn allocate(&mut self, size: Size<DevicePixels>, texture_kind: AtlasTextureKind) -> AtlasTile {
+        {
+            let textures = &mut self.storage[texture_kind];
+            if let Some(tile) = textures
+                .iter_mut()
+                .rev()
+                .find_map(|texture| texture.allocate(size))
+            {
+                return tile;
+            }
+        }
+
+        let texture = self.push_texture(size, texture_kind);
+        texture.allocate(size).unwrap()
+    }
+
+    fn push_texture(
+        &mut self,
+        min_size: Size<DevicePixels>,
+        kind: AtlasTextureKind,
+    ) -> &mut SoftwareAtlasTexture {
+        const DEFAULT_ATLAS_SIZE: Size<DevicePixels> = Size {
+            width: DevicePixels(1024),
+            height: DevicePixels(1024),
+        };
+
+        let size = min_size.max(&DEFAULT_ATLAS_SIZE);
+        let bytes_per_pixel = kind.bytes_per_pixel();
+        let texture_list = &mut self.storage[kind];
+        let index = texture_list.free_list.pop();
+
+        let atlas_texture = SoftwareAtlasTexture {
+            id: AtlasTextureId {
+                index: index.unwrap_or(texture_list.textures.len()) as u32,
+                kind,
+            },
+            allocator: BucketedAtlasAllocator::new(etagere::size2(size.width.0, size.height.0)),
+            size,
+            bytes: vec![0; size.width.0 as usize * size.height.0 as usize * bytes_per_pixel],
+            live_atlas_keys: 0,
+        };
+
+        if let Some(ix) = index {
+            texture_list.textures[ix] = Some(atlas_texture);
+            texture_list.textures.get_mut(ix).unwrap().as_mut().unwrap()
+        } else {
+            texture_list.textures.push(Some(atlas_texture));
+            texture_list.textures.last_mut().unwrap().as_mut().unwrap()
+        }
+    }
+
+    fn upload_texture(&mut self, tile: &AtlasTile, bytes: &[u8]) {
+        let textures = &mut self.storage[tile.texture_id.kind];
+        let Some(texture) = textures
+            .textures
+            .get_mut(tile.texture_id.index as usize)
+            .and_then(|texture| texture.as_mut())
+        else {
+            return;
+        };
+
+        let bytes_per_pixel = tile.texture_id.kind.bytes_per_pixel();
+        let row_len = tile.bounds.size.width.0 as usize * bytes_per_pixel;
+        let texture_row_len = texture.size.width.0 as usize * bytes_per_pixel;
+        for (row, source) in bytes.chunks_exact(row_len).enumerate() {
+            let start = (tile.bounds.origin.y.0 as usize + row) * texture_row_len
+                + tile.bounds.origin.x.0 as usize * bytes_per_pixel;
+            texture.bytes[start..start + row_len].copy_from_slice(source);
+        }
+    }
+}
+
+#[derive(Default)]
+struct SoftwareAtlasStorage {
+    monochrome_textures: AtlasTextureList<SoftwareAtlasTexture>,
+    polychrome_textures: AtlasTextureList<SoftwareAtlasTexture>,
+    path_textures: AtlasTextureList<SoftwareAtlasTexture>,
+}
+
+impl ops::Index<AtlasTextureKind> for SoftwareAtlasStorage {
+    type Output = AtlasTextureList<SoftwareAtlasTexture>;
+    fn index(&self, kind: AtlasTextureKind) -> &Self::Output {
+        match kind {
+            AtlasTextureKind::Monochrome => &self.monochrome_textures,
+            AtlasTextureKind::Polychrome => &self.polychrome_textures,
+            AtlasTextureKind::Path => &self.path_textures,
+        }
+    }
+}
+
+impl ops::IndexMut<AtlasTextureKind> for SoftwareAtlasStorage {
+    fn index_mut(&mut self, kind: AtlasTextureKind) -> &mut Self::Output {
+        match kind {
+            AtlasTextureKind::Monochrome => &mut self.monochrome_textures,
+            AtlasTextureKind::Polychrome => &mut self.polychrome_textures,
+            AtlasTextureKind::Path => &mut self.path_textures,
+        }
+    }
+}
+
+/// A texture in a [`SoftwareAtlas`]. Monochrome textures store one coverage byte per
+/// pixel, polychrome textures store BGRA pixels with straight alpha.
+pub(crate) struct SoftwareAtlasTexture {
+    id: AtlasTextureId,
+    allocator: BucketedAtlasAllocator,
+    size: Size<DevicePixels>,
+    bytes: Vec<u8>,
+    live_atlas_keys: u32,
+}
+
+impl SoftwareAtlasTexture {
+    fn allocate(&mut self, size: Size<DevicePixels>) -> Option<AtlasTile> {
+        let allocation = self
+            .allocator
+            .allocate(etagere::size2(size.width.0, size.height.0))?;
+        let tile = AtlasTile {
+            texture_id: self.id,
+            tile_id: allocation.id.into(),
+            padding: 0,
+            bounds: Bounds {
+                origin: Point {
+                    x: DevicePixels(allocation.rectangle.min.x),
+                    y: DevicePixels(allocation.rectangle.min.y),
+                },
+                size,
+            },
+        };
+        self.live_atlas_keys += 1;
+        Some(tile)
+    }
+
+    pub(crate) fn size(&self) -> Size<DevicePixels> {
+        self.size
+    }
+
+    pub(crate) fn bytes_per_pixel(&self) -> usize {
+        self.id.kind.bytes_per_pixel()
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn decrement_ref_count(&mut self) {
+        self.live_atlas_keys -= 1;
+    }
+
+    fn is_unreferenced(&mut self) -> bool {
+        self.live_atlas_keys == 0
+    }
+}
+
+impl AtlasTextureKind {
+    fn bytes_per_pixel(&self) -> usize {
+        match self {
+            AtlasTextureKind::Monochrome | AtlasTextureKind::Path => 1,
+            AtlasTextureKind::Polychrome => 4,
+        }
+    }
+}
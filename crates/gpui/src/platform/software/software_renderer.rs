@@ -0,0 +1,1125 @@
+use super::{SoftwareAtlas, SoftwareAtlasTexture};
+use crate::{
+    AtlasTile, Background, BackgroundTag, Bounds, ColorSpace, ContentMask, Corners, Damage,
+    DevicePixels, GpuSpecs, Hsla, MonochromeSprite, Path, PolychromeSprite, PrimitiveBatch, Quad,
+    Rgba, ScaledPixels, Scene, Shadow, Size, TransformationMatrix, Underline,
+};
+use image::RgbaImage;
+use std::{f32::consts::PI, ops::Range, sync::Arc};
+
+/// Linear combination of the sRGB channels used to desaturate grayscale sprites.
+const GRAYSCALE_FACTORS: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Renders a [`Scene`] into a pixel buffer on the CPU.
+///
+/// The per-pixel math is a port of the fragment shaders used by the GPU renderers, and
+/// primitives are consumed in the same [`PrimitiveBatch`] order, so the output matches
+/// what a window would present up to rounding.
+pub(crate) struct SoftwareRenderer {
+    atlas: Arc<SoftwareAtlas>,
+    size: Size<DevicePixels>,
+    transparent: bool,
+    /// Premultiplied RGBA, one entry per device pixel, row-major.
+    pixels: Vec<[f32; 4]>,
+    /// The pixels primitives are currently rasterized into.
+    scissor: PixelRegion,
+    /// Set when the retained pixels no longer match the last drawn scene.
+    needs_full_redraw: bool,
+}
+
+impl SoftwareRenderer {
+    pub(crate) fn new(size: Size<DevicePixels>, transparent: bool) -> Self {
+        Self {
+            atlas: Arc::new(SoftwareAtlas::new()),
+            size,
+            transparent,
+            pixels: vec![[0.; 4]; pixel_count(size)],
+            scissor: PixelRegion::viewport(size),
+            needs_full_redraw: true,
+        }
+    }
+
+    pub(crate) fn sprite_atlas(&self) -> &Arc<SoftwareAtlas> {
+        &self.atlas
+    }
+
+    pub(crate) fn viewport_size(&self) -> Size<DevicePixels> {
+        self.size
+    }
+
+    pub(crate) fn update_drawable_size(&mut self, size: Size<DevicePixels>) {
+        if size != self.size {
+            self.size = size;
+            self.pixels = vec![[0.; 4]; pixel_count(size)];
+            self.needs_full_redraw = true;
+        }
+    }
+
+    pub(crate) fn update_transparency(&mut self, transparent: bool) {
+        if transparent != self.transparent {
+            self.transparent = transparent;
+            self.needs_full_redraw = true;
+        }
+    }
+
+    pub(crate) fn gpu_specs(&self) -> GpuSpecs {
+        GpuSpecs {
+            is_software_emulated: true,
+            device_name: "cpu".to_string(),
+            driver_name: "gpui software renderer".to_string(),
+            driver_info: String::new(),
+        }
+    }
+
+    pub(crate) fn destroy(&mut self) {
+        self.atlas.clear();
+    }
+
+    /// Draws the scene, only touching the pixels within the damaged regions. The pixels
+    /// outside of them are retained from the previous frame.
+    pub(crate) fn draw(&mut self, scene: &Scene, damage: &Damage) {
+        let regions = if self.needs_full_redraw {
+            Damage::Full.regions(self.size)
+        } else {
+            damage.regions(self.size)
+        };
+        self.needs_full_redraw = false;
+
+        for region in regions {
+            self.scissor = PixelRegion {
+                x: region.origin.x.0..(region.origin.x + region.size.width).0,
+                y: region.origin.y.0..(region.origin.y + region.size.height).0,
+            };
+            self.clear_scissor();
+            self.draw_batches(scene);
+        }
+        self.scissor = PixelRegion::viewport(self.size);
+    }
+
+    fn clear_scissor(&mut self) {
+        let clear_color = if self.transparent {
+            [0., 0., 0., 0.]
+        } else {
+            [0., 0., 0., 1.]
+        };
+        let width = self.size.width.0 as usize;
+        for y in self.scissor.y.clone() {
+            let row = y as usize * width;
+            self.pixels[row + self.scissor.x.start as usize..row + self.scissor.x.end as usize]
+                .fill(clear_color);
+        }
+    }
+
+    fn draw_batches(&mut self, scene: &Scene) {
+        for batch in scene.batches() {
+            match batch {
+                PrimitiveBatch::Shadows(shadows) => {
+                    for shadow in shadows {
+                        self.draw_shadow(shadow);
+                    }
+                }
+                PrimitiveBatch::Quads(quads) => {
+                    for quad in quads {
+                        self.draw_quad(quad);
+                    }
+                }
+                PrimitiveBatch::Paths(paths) => {
+                    for path in paths {
+                        self.draw_path(path);
+                    }
+                }
+                PrimitiveBatch::Underlines(underlines) => {
+                    for underline in underlines {
+                        self.draw_underline(underline);
+                    }
+                }
+                PrimitiveBatch::MonochromeSprites {
+                    texture_id,
+                    sprites,
+                } => {
+                    let atlas = self.atlas.clone();
+                    atlas.with_texture(texture_id, |texture| {
+                        for sprite in sprites {
+                            self.draw_monochrome_sprite(sprite, texture);
+                        }
+                    });
+                }
+                PrimitiveBatch::PolychromeSprites {
+                    texture_id,
+                    sprites,
+                } => {
+                    let atlas = self.atlas.clone();
+                    atlas.with_texture(texture_id, |texture| {
+                        for sprite in sprites {
+                            self.draw_polychrome_sprite(sprite, texture);
+                        }
+                    });
+                }
+                PrimitiveBatch::Surfaces(_) => {
+                    // Surfaces are backed by platform video buffers, which only exist on macOS.
+                }
+            }
+        }
+    }
+
+    /// Returns the last drawn frame as tightly packed RGBA bytes with straight alpha.
+    pub(crate) fn read_pixels(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 4);
+        for pixel in &self.pixels {
+            let alpha = pixel[3];
+            let unpremultiply = if alpha > 0. { 1. / alpha } else { 0. };
+            bytes.push(to_u8(pixel[0] * unpremultiply));
+            bytes.push(to_u8(pixel[1] * unpremultiply));
+            bytes.push(to_u8(pixel[2] * unpremultiply));
+            bytes.push(to_u8(alpha));
+        }
+        bytes
+    }
+
+    /// Returns the last drawn frame as an image.
+    pub(crate) fn read_image(&self) -> RgbaImage {
+        RgbaImage::from_raw(
+            self.size.width.0.max(0) as u32,
+            self.size.height.0.max(0) as u32,
+            self.read_pixels(),
+        )
+        .expect("pixel buffer should match the viewport size")
+    }
+
+    fn draw_shadow(&mut self, shadow: &Shadow) {
+        let blur_radius = shadow.blur_radius.0;
+        let margin = 3. * blur_radius;
+        let bounds = Bounds {
+            origin: shadow.bounds.origin.map(|v| v - ScaledPixels(margin)),
+            size: shadow.bounds.size.map(|v| v + ScaledPixels(2. * margin)),
+        };
+        let Some(region) = self.pixel_region(bounds, &shadow.content_mask) else {
+            return;
+        };
+
+        let rect = Rect::from(shadow.bounds);
+        let radii = shadow.corner_radii.map(|radius| radius.0);
+        let color = hsla_to_rgba(shadow.color);
+        let half_size = [rect.width / 2., rect.height / 2.];
+        let center = [rect.x + half_size[0], rect.y + half_size[1]];
+
+        for y in region.y.clone() {
+            for x in region.x.clone() {
+                let point = pixel_center(x, y);
+                let alpha = if blur_radius <= 0. {
+                    saturate(0.5 - quad_sdf(point, &rect, &radii))
+                } else {
+                    let center_to_point = [point[0] - center[0], point[1] - center[1]];
+                    let corner_radius = pick_corner_radius(center_to_point, &radii);
+
+                    // The signal is only non-zero in a limited range, so don't waste samples.
+                    let low = center_to_point[1] - half_size[1];
+                    let high = center_to_point[1] + half_size[1];
+                    let start = (-3. * blur_radius).clamp(low, high);
+                    let end = (3. * blur_radius).clamp(low, high);
+
+                    let step = (end - start) / 4.;
+                    let mut sample_y = start + step * 0.5;
+                    let mut alpha = 0.;
+                    for _ in 0..4 {
+                        let blur = blur_along_x(
+                            center_to_point[0],
+                            center_to_point[1] - sample_y,
+                            blur_radius,
+                            corner_radius,
+                            half_size,
+                        );
+                        alpha += blur * gaussian(sample_y, blur_radius) * step;
+                        sample_y += step;
+                    }
+                    alpha
+                };
+                self.blend(x, y, color, alpha);
+            }
+        }
+    }
+
+    fn draw_quad(&mut self, quad: &Quad) {
+        let Some(region) = self.pixel_region(quad.bounds, &quad.content_mask) else {
+            return;
+        };
+
+        let rect = Rect::from(quad.bounds);
+        let radii = quad.corner_radii.map(|radius| radius.0);
+        let border_widths = quad.border_widths.map(|width| width.0);
+        let border_color = hsla_to_rgba(quad.border_color);
+        let background = BackgroundShader::new(&quad.background, rect);
+
+        let is_simple = radii.top_left == 0.
+            && radii.top_right == 0.
+            && radii.bottom_right == 0.
+            && radii.bottom_left == 0.
+            && !border_widths.any(|width| *width != 0.);
+
+        let half_size = [rect.width / 2., rect.height / 2.];
+        let center = [rect.x + half_size[0], rect.y + half_size[1]];
+
+        for y in region.y.clone() {
+            for x in region.x.clone() {
+                let point = pixel_center(x, y);
+                let background_color = background.color_at(point);
+                if is_simple {
+                    self.blend(x, y, background_color, 1.);
+                    continue;
+                }
+
+                let center_to_point = [point[0] - center[0], point[1] - center[1]];
+                let corner_radius = pick_corner_radius(center_to_point, &radii);
+                let distance = rounded_rect_distance(center_to_point, half_size, corner_radius);
+
+                let vertical_border = if center_to_point[0] > 0. {
+                    border_widths.right
+                } else {
+                    border_widths.left
+                };
+                let horizontal_border = if center_to_point[1] > 0. {
+                    border_widths.bottom
+                } else {
+                    border_widths.top
+                };
+                let inset_size = [
+                    half_size[0] - corner_radius - vertical_border,
+                    half_size[1] - corner_radius - horizontal_border,
+                ];
+                let point_to_inset_corner = [
+                    center_to_point[0].abs() - inset_size[0],
+                    center_to_point[1].abs() - inset_size[1],
+                ];
+                let border_width = if point_to_inset_corner[0] < 0. && point_to_inset_corner[1] < 0.
+                {
+                    0.
+                } else if point_to_inset_corner[1] > point_to_inset_corner[0] {
+                    horizontal_border
+                } else {
+                    vertical_border
+                };
+
+                let mut color = background_color;
+                if border_width > 0. {
+                    // Blend the border on top of the background and then linearly interpolate
+                    // between the two as we slide inside the background.
+                    let inset_distance = distance + border_width;
+                    let blended_border = over(background_color, border_color);
+                    color = mix(
+                        blended_border,
+                        background_color,
+                        saturate(0.5 - inset_distance),
+                    );
+                }
+
+                self.blend(x, y, color, saturate(0.5 - distance));
+            }
+        }
+    }
+
+    fn draw_path(&mut self, path: &Path<ScaledPixels>) {
+        let Some(region) = self.pixel_region(path.bounds, &path.content_mask) else {
+            return;
+        };
+
+        // Paths are rasterized additively and clamped, like the GPU path atlas.
+        let region_width = region.x.len();
+        let mut coverage = vec![0f32; region_width * region.y.len()];
+        for triangle in path.vertices.chunks_exact(3) {
+            let Some(triangle) = PathTriangle::new(
+                [
+                    [triangle[0].xy_position.x.0, triangle[0].xy_position.y.0],
+                    [triangle[1].xy_position.x.0, triangle[1].xy_position.y.0],
+                    [triangle[2].xy_position.x.0, triangle[2].xy_position.y.0],
+                ],
+                [
+                    [triangle[0].st_position.x, triangle[0].st_position.y],
+                    [triangle[1].st_position.x, triangle[1].st_position.y],
+                    [triangle[2].st_position.x, triangle[2].st_position.y],
+                ],
+            ) else {
+                continue;
+            };
+
+            let x_range = clamp_range(triangle.pixel_range_x(), &region.x);
+            let y_range = clamp_range(triangle.pixel_range_y(), &region.y);
+            for y in y_range {
+                for x in x_range.clone() {
+                    let value = triangle.coverage_at(pixel_center(x, y));
+                    if value > 0. {
+                        let ix = (y - region.y.start) as usize * region_width
+                            + (x - region.x.start) as usize;
+                        coverage[ix] += value;
+                    }
+                }
+            }
+        }
+
+        let background = BackgroundShader::new(&path.color, Rect::from(path.bounds));
+        for y in region.y.clone() {
+            for x in region.x.clone() {
+                let ix =
+                    (y - region.y.start) as usize * region_width + (x - region.x.start) as usize;
+                let alpha = coverage[ix].min(1.);
+                if alpha > 0. {
+                    let color = background.color_at(pixel_center(x, y));
+                    self.blend(x, y, color, alpha);
+                }
+            }
+        }
+    }
+
+    fn draw_underline(&mut self, underline: &Underline) {
+        let Some(region) = self.pixel_region(underline.bounds, &underline.content_mask) else {
+            return;
+        };
+
+        let color = hsla_to_rgba(underline.color);
+        let rect = Rect::from(underline.bounds);
+        let thickness = underline.thickness.0;
+
+        for y in region.y.clone() {
+            for x in region.x.clone() {
+                if !underline.wavy {
+                    self.blend(x, y, color, 1.);
+                    continue;
+                }
+
+                let point = pixel_center(x, y);
+                let half_thickness = thickness * 0.5;
+                let st = [
+                    (point[0] - rect.x) / rect.height,
+                    (point[1] - rect.y) / rect.height - 0.5,
+                ];
+                let frequency = PI * 3. * thickness / 3.;
+                let amplitude = 1. / (4. * thickness);
+                let sine = (st[0] * frequency).sin() * amplitude;
+                let d_sine = (st[0] * frequency).cos() * amplitude * frequency;
+                let distance = (st[1] - sine) / (1. + d_sine * d_sine).sqrt();
+                let distance_in_pixels = distance * rect.height;
+                let distance_from_top_border = distance_in_pixels - half_thickness;
+                let distance_from_bottom_border = distance_in_pixels + half_thickness;
+                let alpha =
+                    saturate(0.5 - (-distance_from_bottom_border).max(distance_from_top_border));
+                self.blend(x, y, color, alpha);
+            }
+        }
+    }
+
+    fn draw_monochrome_sprite(
+        &mut self,
+        sprite: &MonochromeSprite,
+        texture: &SoftwareAtlasTexture,
+    ) {
+        let Some(inverse) = InverseTransform::new(&sprite.transformation) else {
+            return;
+        };
+        let transformed_bounds = transform_bounds(sprite.bounds, &sprite.transformation);
+        let Some(region) = self.pixel_region(transformed_bounds, &sprite.content_mask) else {
+            return;
+        };
+
+        let rect = Rect::from(sprite.bounds);
+        let color = hsla_to_rgba(sprite.color);
+        for y in region.y.clone() {
+            for x in region.x.clone() {
+                let local = inverse.apply(pixel_center(x, y));
+                let Some(uv) = rect.normalize(local) else {
+                    continue;
+                };
+                let sample = sample_tile(texture, &sprite.tile, uv);
+                self.blend(x, y, color, sample[0]);
+            }
+        }
+    }
+
+    fn draw_polychrome_sprite(
+        &mut self,
+        sprite: &PolychromeSprite,
+        texture: &SoftwareAtlasTexture,
+    ) {
+        let Some(region) = self.pixel_region(sprite.bounds, &sprite.content_mask) else {
+            return;
+        };
+
+        let rect = Rect::from(sprite.bounds);
+        let radii = sprite.corner_radii.map(|radius| radius.0);
+        for y in region.y.clone() {
+            for x in region.x.clone() {
+                let point = pixel_center(x, y);
+                let Some(uv) = rect.normalize(point) else {
+                    continue;
+                };
+                // Polychrome tiles are stored as BGRA.
+                let sample = sample_tile(texture, &sprite.tile, uv);
+                let mut color = [sample[2], sample[1], sample[0], sample[3]];
+                if sprite.grayscale {
+                    let grayscale = color[0] * GRAYSCALE_FACTORS[0]
+                        + color[1] * GRAYSCALE_FACTORS[1]
+                        + color[2] * GRAYSCALE_FACTORS[2];
+                    color = [grayscale, grayscale, grayscale, color[3]];
+                }
+                let distance = quad_sdf(point, &rect, &radii);
+                self.blend(x, y, color, sprite.opacity * saturate(0.5 - distance));
+            }
+        }
+    }
+
+    /// Returns the device pixels whose centers lie within `bounds`, the content mask and the
+    /// scissor.
+    fn pixel_region(
+        &self,
+        bounds: Bounds<ScaledPixels>,
+        content_mask: &ContentMask<ScaledPixels>,
+    ) -> Option<PixelRegion> {
+        let clipped = bounds.intersect(&content_mask.bounds);
+        let x = clamp_range(
+            pixel_span(
+                clipped.origin.x.0,
+                (clipped.origin.x + clipped.size.width).0,
+            ),
+            &self.scissor.x,
+        );
+        let y = clamp_range(
+            pixel_span(
+                clipped.origin.y.0,
+                (clipped.origin.y + clipped.size.height).0,
+            ),
+            &self.scissor.y,
+        );
+        (!x.is_empty() && !y.is_empty()).then_some(PixelRegion { x, y })
+    }
+
+    /// Composites a straight-alpha color over the pixel at the given coordinates.
+    fn blend(&mut self, x: i32, y: i32, color: [f32; 4], alpha_factor: f32) {
+        let alpha = color[3] * alpha_factor;
+        if alpha <= 0. || alpha.is_nan() {
+            return;
+        }
+        let alpha = alpha.min(1.);
+        let ix = y as usize * self.size.width.0 as usize + x as usize;
+        let destination = &mut self.pixels[ix];
+        let remaining = 1. - alpha;
+        destination[0] = color[0] * alpha + destination[0] * remaining;
+        destination[1] = color[1] * alpha + destination[1] * remaining;
+        destination[2] = color[2] * alpha + destination[2] * remaining;
+        destination[3] = alpha + destination[3] * remaining;
+    }
+}
+
+struct PixelRegion {
+    x: Range<i32>,
+    y: Range<i32>,
+}
+
+impl PixelRegion {
+    fn viewport(size: Size<DevicePixels>) -> Self {
+        Self {
+            x: 0..size.width.0.max(0),
+            y: 0..size.height.0.max(0),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Rect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl From<Bounds<ScaledPixels>> for Rect {
+    fn from(bounds: Bounds<ScaledPixels>) -> Self {
+        Rect {
+            x: bounds.origin.x.0,
+            y: bounds.origin.y.0,
+            width: bounds.size.width.0,
+            height: bounds.size.height.0,
+        }
+    }
+}
+
+impl Rect {
+    /// Maps a point inside the rect to `0..1` coordinates.
+    fn normalize(&self, point: [f32; 2]) -> Option<[f32; 2]> {
+        if self.width <= 0. || self.height <= 0. {
+            return None;
+        }
+        let u = (point[0] - self.x) / self.width;
+        let v = (point[1] - self.y) / self.height;
+        ((0. ..=1.).contains(&u) && (0. ..=1.).contains(&v)).then_some([u, v])
+    }
+}
+
+/// Evaluates a [`Background`] at a point, mirroring `gradient_color` in the shaders.
+struct BackgroundShader {
+    tag: BackgroundTag,
+    color_space: ColorSpace,
+    solid: [f32; 4],
+    colors: [[f32; 4]; 2],
+    stops: [f32; 2],
+    angle_or_height: f32,
+    rect: Rect,
+}
+
+impl BackgroundShader {
+    fn new(background: &Background, rect: Rect) -> Self {
+        let mut colors = [
+            hsla_to_rgba(background.colors[0].color),
+            hsla_to_rgba(background.colors[1].color),
+        ];
+        if background.color_space == ColorSpace::Oklab {
+            colors = colors.map(|color| linear_srgb_to_oklab(srgb_to_linear(color)));
+        }
+        Self {
+            tag: background.tag,
+            color_space: background.color_space,
+            solid: hsla_to_rgba(background.solid),
+            colors,
+            stops: [
+                background.colors[0].percentage,
+                background.colors[1].percentage,
+            ],
+            angle_or_height: background.gradient_angle_or_pattern_height,
+            rect,
+        }
+    }
+
+    fn color_at(&self, point: [f32; 2]) -> [f32; 4] {
+        let rect = self.rect;
+        match self.tag {
+            BackgroundTag::Solid => self.solid,
+            BackgroundTag::LinearGradient => {
+                // -90 degrees to match the CSS gradient angle.
+                let radians = (self.angle_or_height % 360. - 90.) * PI / 180.;
+                let mut direction = [radians.cos(), radians.sin()];
+
+                // Expand the short side to be the same as the long side
+                if rect.width > rect.height {
+                    direction[1] *= rect.height / rect.width;
+                } else {
+                    direction[0] *= rect.width / rect.height;
+                }
+
+                let half_size = [rect.width / 2., rect.height / 2.];
+                let center_to_point = [
+                    point[0] - (rect.x + half_size[0]),
+                    point[1] - (rect.y + half_size[1]),
+                ];
+                let length = (direction[0] * direction[0] + direction[1] * direction[1]).sqrt();
+                let mut t = (center_to_point[0] * direction[0] + center_to_point[1] * direction[1])
+                    / length;
+                if direction[0].abs() > direction[1].abs() {
+                    t = (t + half_size[0]) / rect.width;
+                } else {
+                    t = (t + half_size[1]) / rect.height;
+                }
+
+                // Adjust t based on the stop percentages
+                t = (t - self.stops[0]) / (self.stops[1] - self.stops[0]);
+                let t = t.clamp(0., 1.);
+
+                let color = mix(self.colors[0], self.colors[1], t);
+                match self.color_space {
+                    ColorSpace::Srgb => color,
+                    ColorSpace::Oklab => linear_to_srgb(oklab_to_linear_srgb(color)),
+                }
+            }
+            BackgroundTag::PatternSlash => {
+                let pattern_height = self.angle_or_height;
+                let stripe_angle = PI / 4.;
+                let pattern_period = pattern_height * stripe_angle.sin();
+                let relative = [point[0] - rect.x, point[1] - rect.y];
+                let rotated_x = stripe_angle.cos() * relative[0] + stripe_angle.sin() * relative[1];
+                let pattern = rotated_x % pattern_period;
+                let distance = pattern.min(pattern_period - pattern) - pattern_period / 4.;
+                let mut color = self.solid;
+                color[3] *= saturate(0.5 - distance);
+                color
+            }
+        }
+    }
+}
+
+/// A path triangle, with the quadratic curve coordinates interpolated across it.
+struct PathTriangle {
+    vertices: [[f32; 2]; 3],
+    area: f32,
+    st_origin: [f32; 2],
+    st_dx: [f32; 2],
+    st_dy: [f32; 2],
+}
+
+impl PathTriangle {
+    fn new(vertices: [[f32; 2]; 3], st: [[f32; 2]; 3]) -> Option<Self> {
+        let [a, b, c] = vertices;
+        let area = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+        if area.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        // st is affine in the position, so its derivatives are constant across the triangle.
+        let st_dx = [
+            ((st[1][0] - st[0][0]) * (c[1] - a[1]) - (st[2][0] - st[0][0]) * (b[1] - a[1])) / area,
+            ((st[1][1] - st[0][1]) * (c[1] - a[1]) - (st[2][1] - st[0][1]) * (b[1] - a[1])) / area,
+        ];
+        let st_dy = [
+            ((st[2][0] - st[0][0]) * (b[0] - a[0]) - (st[1][0] - st[0][0]) * (c[0] - a[0])) / area,
+            ((st[2][1] - st[0][1]) * (b[0] - a[0]) - (st[1][1] - st[0][1]) * (c[0] - a[0])) / area,
+        ];
+
+        Some(Self {
+            vertices,
+            area,
+            st_origin: st[0],
+            st_dx,
+            st_dy,
+        })
+    }
+
+    fn pixel_range_x(&self) -> Range<i32> {
+        let min = self.vertices.iter().map(|v| v[0]).fold(f32::MAX, f32::min);
+        let max = self.vertices.iter().map(|v| v[0]).fold(f32::MIN, f32::max);
+        pixel_span(min, max)
+    }
+
+    fn pixel_range_y(&self) -> Range<i32> {
+        let min = self.vertices.iter().map(|v| v[1]).fold(f32::MAX, f32::min);
+        let max = self.vertices.iter().map(|v| v[1]).fold(f32::MIN, f32::max);
+        pixel_span(min, max)
+    }
+
+    fn coverage_at(&self, point: [f32; 2]) -> f32 {
+        let [a, b, c] = self.vertices;
+        let edge = |p: [f32; 2], q: [f32; 2]| {
+            ((q[0] - p[0]) * (point[1] - p[1]) - (point[0] - p[0]) * (q[1] - p[1]))
+                * self.area.signum()
+        };
+        if edge(a, b) < 0. || edge(b, c) < 0. || edge(c, a) < 0. {
+            return 0.;
+        }
+
+        let offset = [point[0] - a[0], point[1] - a[1]];
+        let s = self.st_origin[0] + self.st_dx[0] * offset[0] + self.st_dy[0] * offset[1];
+        let t = self.st_origin[1] + self.st_dx[1] * offset[0] + self.st_dy[1] * offset[1];
+        let gradient = [
+            2. * s * self.st_dx[0] - self.st_dx[1],
+            2. * s * self.st_dy[0] - self.st_dy[1],
+        ];
+        let f = s * s - t;
+        let length = (gradient[0] * gradient[0] + gradient[1] * gradient[1]).sqrt();
+        if length == 0. {
+            return if f < 0. { 1. } else { 0. };
+        }
+        saturate(0.5 - f / length)
+    }
+}
+
+struct InverseTransform {
+    matrix: [[f32; 2]; 2],
+    translation: [f32; 2],
+}
+
+impl InverseTransform {
+    fn new(transformation: &TransformationMatrix) -> Option<Self> {
+        let [[a, b], [c, d]] = transformation.rotation_scale;
+        let determinant = a * d - b * c;
+        if determinant.abs() <= f32::EPSILON {
+            return None;
+        }
+        Some(Self {
+            matrix: [
+                [d / determinant, -b / determinant],
+                [-c / determinant, a / determinant],
+            ],
+            translation: transformation.translation,
+        })
+    }
+
+    fn apply(&self, point: [f32; 2]) -> [f32; 2] {
+        let x = point[0] - self.translation[0];
+        let y = point[1] - self.translation[1];
+        [
+            self.matrix[0][0] * x + self.matrix[0][1] * y,
+            self.matrix[1][0] * x + self.matrix[1][1] * y,
+        ]
+    }
+}
+
+fn transform_bounds(
+    bounds: Bounds<ScaledPixels>,
+    transformation: &TransformationMatrix,
+) -> Bounds<ScaledPixels> {
+    if *transformation == TransformationMatrix::unit() {
+        return bounds;
+    }
+
+    let [[a, b], [c, d]] = transformation.rotation_scale;
+    let rect = Rect::from(bounds);
+    let corners = [
+        [rect.x, rect.y],
+        [rect.x + rect.width, rect.y],
+        [rect.x, rect.y + rect.height],
+        [rect.x + rect.width, rect.y + rect.height],
+    ]
+    .map(|[x, y]| {
+        [
+            a * x + b * y + transformation.translation[0],
+            c * x + d * y + transformation.translation[1],
+        ]
+    });
+
+    let min_x = corners.iter().map(|p| p[0]).fold(f32::MAX, f32::min);
+    let max_x = corners.iter().map(|p| p[0]).fold(f32::MIN, f32::max);
+    let min_y = corners.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
+    let max_y = corners.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+    Bounds {
+        origin: crate::point(ScaledPixels(min_x), ScaledPixels(min_y)),
+        size: crate::size(ScaledPixels(max_x - min_x), ScaledPixels(max_y - min_y)),
+    }
+}
+
+/// Bilinearly samples a tile at normalized coordinates, returning channels in `0..1`.
+fn sample_tile(texture: &SoftwareAtlasTexture, tile: &AtlasTile, uv: [f32; 2]) -> [f32; 4] {
+    let origin = [tile.bounds.origin.x.0 as f32, tile.bounds.origin.y.0 as f32];
+    let size = [
+        tile.bounds.size.width.0 as f32,
+        tile.bounds.size.height.0 as f32,
+    ];
+    if size[0] <= 0. || size[1] <= 0. {
+        return [0.; 4];
+    }
+
+    let x = (uv[0] * size[0] - 0.5).clamp(0., size[0] - 1.);
+    let y = (uv[1] * size[1] - 0.5).clamp(0., size[1] - 1.);
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let x1 = (x0 + 1.).min(size[0] - 1.);
+    let y1 = (y0 + 1.).min(size[1] - 1.);
+    let fx = x - x0;
+    let fy = y - y0;
+
+    let texel = |tx: f32, ty: f32| -> [f32; 4] {
+        let bytes_per_pixel = texture.bytes_per_pixel();
+        let row = (origin[1] + ty) as usize;
+        let column = (origin[0] + tx) as usize;
+        let ix = (row * texture.size().width.0 as usize + column) * bytes_per_pixel;
+        let bytes = &texture.bytes()[ix..ix + bytes_per_pixel];
+        let mut texel = [0.; 4];
+        for (channel, byte) in texel.iter_mut().zip(bytes) {
+            *channel = *byte as f32 / 255.;
+        }
+        texel
+    };
+
+    let top = mix(texel(x0, y0), texel(x1, y0), fx);
+    let bottom = mix(texel(x0, y1), texel(x1, y1), fx);
+    mix(top, bottom, fy)
+}
+
+fn pixel_count(size: Size<DevicePixels>) -> usize {
+    size.width.0.max(0) as usize * size.height.0.max(0) as usize
+}
+
+fn pixel_center(x: i32, y: i32) -> [f32; 2] {
+    [x as f32 + 0.5, y as f32 + 0.5]
+}
+
+/// Returns the pixels whose centers fall between `start` and `end`.
+fn pixel_span(start: f32, end: f32) -> Range<i32> {
+    (start - 0.5).ceil() as i32..(end - 0.5).ceil() as i32
+}
+
+fn clamp_range(range: Range<i32>, limit: &Range<i32>) -> Range<i32> {
+    range.start.max(limit.start)..range.end.min(limit.end)
+}
+
+fn hsla_to_rgba(color: Hsla) -> [f32; 4] {
+    let rgba = Rgba::from(color);
+    [rgba.r, rgba.g, rgba.b, rgba.a]
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0., 1.) * 255.).round() as u8
+}
+
+fn saturate(value: f32) -> f32 {
+    value.clamp(0., 1.)
+}
+
+fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    ]
+}
+
+/// Composites two straight-alpha colors.
+fn over(below: [f32; 4], above: [f32; 4]) -> [f32; 4] {
+    let alpha = above[3] + below[3] * (1. - above[3]);
+    if alpha <= 0. {
+        return [0.; 4];
+    }
+    let channel = |i: usize| (above[i] * above[3] + below[i] * below[3] * (1. - above[3])) / alpha;
+    [channel(0), channel(1), channel(2), alpha]
+}
+
+fn pick_corner_radius(center_to_point: [f32; 2], radii: &Corners<f32>) -> f32 {
+    if center_to_point[0] < 0. {
+        if center_to_point[1] < 0. {
+            radii.top_left
+        } else {
+            radii.bottom_left
+        }
+    } else if center_to_point[1] < 0. {
+        radii.top_right
+    } else {
+        radii.bottom_right
+    }
+}
+
+fn rounded_rect_distance(
+    center_to_point: [f32; 2],
+    half_size: [f32; 2],
+    corner_radius: f32,
+) -> f32 {
+    let rounded_edge_to_point = [
+        center_to_point[0].abs() - half_size[0] + corner_radius,
+        center_to_point[1].abs() - half_size[1] + corner_radius,
+    ];
+    let outside = [
+        rounded_edge_to_point[0].max(0.),
+        rounded_edge_to_point[1].max(0.),
+    ];
+    (outside[0] * outside[0] + outside[1] * outside[1]).sqrt()
+        + rounded_edge_to_point[0]
+            .max(rounded_edge_to_point[1])
+            .min(0.)
+        - corner_radius
+}
+
+fn quad_sdf(point: [f32; 2], rect: &Rect, radii: &Corners<f32>) -> f32 {
+    let half_size = [rect.width / 2., rect.height / 2.];
+    let center_to_point = [
+        point[0] - (rect.x + half_size[0]),
+        point[1] - (rect.y + half_size[1]),
+    ];
+    let corner_radius = pick_corner_radius(center_to_point, radii);
+    rounded_rect_distance(center_to_point, half_size, corner_radius)
+}
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    (-(x * x) / (2. * sigma * sigma)).exp() / ((2. * PI).sqrt() * sigma)
+}
+
+/// A fast approximation of the error function, matching the shaders.
+fn erf(value: f32) -> f32 {
+    let sign = value.signum();
+    let a = value.abs();
+    let r1 = 1. + (0.278393 + (0.230389 + (0.000972 + 0.078108 * a) * a) * a) * a;
+    let r2 = r1 * r1;
+    sign - sign / (r2 * r2)
+}
+
+fn blur_along_x(x: f32, y: f32, sigma: f32, corner: f32, half_size: [f32; 2]) -> f32 {
+    let delta = (half_size[1] - corner - y.abs()).min(0.);
+    let curved = half_size[0] - corner + (corner * corner - delta * delta).max(0.).sqrt();
+    let scale = 0.5f32.sqrt() / sigma;
+    let start = 0.5 + 0.5 * erf((x - curved) * scale);
+    let end = 0.5 + 0.5 * erf((x + curved) * scale);
+    end - start
+}
+
+fn srgb_to_linear(color: [f32; 4]) -> [f32; 4] {
+    let convert = |c: f32| {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    [
+        convert(color[0]),
+        convert(color[1]),
+        convert(color[2]),
+        color[3],
+    ]
+}
+
+fn linear_to_srgb(color: [f32; 4]) -> [f32; 4] {
+    let convert = |c: f32| {
+        if c <= 0.0031308 {
+            c * 12.92
+        } else {
+            1.055 * c.max(0.).powf(1. / 2.4) - 0.055
+        }
+    };
+    [
+        convert(color[0]),
+        convert(color[1]),
+        convert(color[2]),
+        color[3],
+    ]
+}
+
+fn linear_srgb_to_oklab(color: [f32; 4]) -> [f32; 4] {
+    let [r, g, b, a] = color;
+    let l = 0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b;
+    let m = 0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b;
+    let s = 0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b;
+    let l = l.cbrt();
+    let m = m.cbrt();
+    let s = s.cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        a,
+    ]
+}
+
+fn oklab_to_linear_srgb(color: [f32; 4]) -> [f32; 4] {
+    let [l, a, b, alpha] = color;
+    let l_ = l + 0.3963377774 * a + 0.2158037573 * b;
+    let m_ = l - 0.1055613458 * a - 0.0638541728 * b;
+    let s_ = l - 0.0894841775 * a - 1.2914855480 * b;
+    let l = l_ * l_ * l_;
+    let m = m_ * m_ * m_;
+    let s = s_ * s_ * s_;
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        alpha,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hsla, point, px, size, Edges};
+
+    fn red() -> Hsla {
+        hsla(0., 1., 0.5, 1.)
+    }
+
+    fn scaled_bounds(x: f32, y: f32, width: f32, height: f32) -> Bounds<ScaledPixels> {
+        Bounds {
+            origin: point(ScaledPixels(x), ScaledPixels(y)),
+            size: size(ScaledPixels(width), ScaledPixels(height)),
+        }
+    }
+
+    fn quad(bounds: Bounds<ScaledPixels>, content_mask: Bounds<ScaledPixels>) -> Quad {
+        Quad {
+            order: 0,
+            pad: 0,
+            bounds,
+            content_mask: ContentMask {
+                bounds: content_mask,
+            },
+            background: red().into(),
+            border_color: Hsla::default(),
+            corner_radii: Corners::default(),
+            border_widths: Edges::default(),
+        }
+    }
+
+    fn render(scene: &mut Scene, width: i32, height: i32) -> RgbaImage {
+        scene.finish();
+        let mut renderer =
+            SoftwareRenderer::new(size(DevicePixels(width), DevicePixels(height)), true);
+        renderer.draw(scene, &Damage::Full);
+        renderer.read_image()
+    }
+
+    #[test]
+    fn test_draw_damaged_regions() {
+        let full_bounds = scaled_bounds(0., 0., 16., 16.);
+        let mut previous = Scene::default();
+        previous.insert_primitive(quad(scaled_bounds(1., 1., 4., 4.), full_bounds));
+        previous.insert_primitive(quad(scaled_bounds(10., 10., 4., 4.), full_bounds));
+        previous.finish();
+        let mut next = Scene::default();
+        next.insert_primitive(quad(scaled_bounds(1., 1., 4., 4.), full_bounds));
+        next.insert_primitive(quad(scaled_bounds(8., 10., 4., 4.), full_bounds));
+        next.finish();
+
+        let mut renderer = SoftwareRenderer::new(size(DevicePixels(16), DevicePixels(16)), true);
+        renderer.draw(&previous, &Damage::Full);
+        renderer.draw(&next, &next.damage_since(&previous));
+
+        assert_eq!(renderer.read_image(), render(&mut next, 16, 16));
+        assert_eq!(renderer.read_image().get_pixel(12, 12).0, [0, 0, 0, 0]);
+        assert_eq!(renderer.read_image().get_pixel(2, 2).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_solid_quad() {
+        let mut scene = Scene::default();
+        let bounds = scaled_bounds(2., 2., 4., 4.);
+        scene.insert_primitive(quad(bounds, bounds));
+        let image = render(&mut scene, 8, 8);
+
+        assert_eq!(image.get_pixel(2, 2).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(5, 5).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 1).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(6, 6).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_content_mask_clips_quad() {
+        let mut scene = Scene::default();
+        scene.insert_primitive(quad(
+            scaled_bounds(0., 0., 8., 8.),
+            scaled_bounds(0., 0., 4., 8.),
+        ));
+        let image = render(&mut scene, 8, 8);
+
+        assert_eq!(image.get_pixel(3, 4).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(4, 4).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_rounded_quad_with_border() {
+        let mut scene = Scene::default();
+        let bounds = scaled_bounds(0., 0., 16., 16.);
+        let mut quad = quad(bounds, bounds);
+        quad.corner_radii = Corners::all(ScaledPixels(8.));
+        quad.border_widths = Edges::all(ScaledPixels(2.));
+        quad.border_color = hsla(0.66, 1., 0.5, 1.);
+        scene.insert_primitive(quad);
+        let image = render(&mut scene, 16, 16);
+
+        // The corners are cut away, the edge is border colored and the center is filled.
+        assert_eq!(image.get_pixel(0, 0).0[3], 0);
+        let edge = image.get_pixel(8, 0).0;
+        assert!(edge[2] > 200 && edge[0] < 50, "{edge:?}");
+        assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_path_fill() {
+        let mut path = Path::new(point(px(1.), px(1.)));
+        path.line_to(point(px(7.), px(1.)));
+        path.line_to(point(px(7.), px(7.)));
+        path.line_to(point(px(1.), px(7.)));
+        path.color = red().into();
+        path.content_mask = ContentMask {
+            bounds: Bounds {
+                origin: point(px(0.), px(0.)),
+                size: size(px(8.), px(8.)),
+            },
+        };
+
+        let mut scene = Scene::default();
+        scene.insert_primitive(path.scale(1.));
+        let image = render(&mut scene, 8, 8);
+
+        assert_eq!(image.get_pixel(4, 4).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    }
+}
@@ -7,6 +7,16 @@ use crate::{
 };
 use std::{fmt::Debug, iter::Peekable, ops::Range, slice};
 
+mod damage;
+mod dump;
+mod pdf;
+mod svg;
+
+pub(crate) use damage::*;
+pub use dump::*;
+pub use pdf::*;
+pub(crate) use svg::*;
+
 #[allow(non_camel_case_types, unused)]
 pub(crate) type PathVertex_ScaledPixels = PathVertex<ScaledPixels>;
 
@@ -0,0 +1,423 @@
+use super::{PaintOperation, Primitive, Scene};
+use crate::{
+    point, size, AtlasTile, Background, Bounds, ContentMask, Corners, DevicePixels, Hsla,
+    ScaledPixels, Size, TransformationMatrix,
+};
+use collections::FxHashMap;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// Beyond this many disjoint regions, the damage is collapsed into their bounding box.
+/// Compositors and renderers handle a few large rectangles better than many small ones.
+const MAX_DAMAGE_REGIONS: usize = 8;
+
+/// The parts of a window that changed between two frames, in device pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Damage {
+    /// The whole window needs to be redrawn, e.g. because it was resized.
+    Full,
+    /// Only these regions changed. No regions means the frame is identical to the last one.
+    Regions(Vec<Bounds<DevicePixels>>),
+}
+
+impl Default for Damage {
+    fn default() -> Self {
+        Damage::Regions(Vec::new())
+    }
+}
+
+impl Damage {
+    /// Returns whether nothing changed.
+    pub(crate) fn is_empty(&self) -> bool {
+        matches!(self, Damage::Regions(regions) if regions.is_empty())
+    }
+
+    /// Extends this damage with another one.
+    pub(crate) fn union(&mut self, other: Damage) {
+        match (&mut *self, other) {
+            (Damage::Full, _) => {}
+            (_, Damage::Full) => *self = Damage::Full,
+            (Damage::Regions(regions), Damage::Regions(other)) => {
+                for region in other {
+                    add_region(regions, region);
+                }
+            }
+        }
+    }
+
+    /// Returns the damaged regions clipped to the viewport, or the entire viewport if the
+    /// damage is full.
+    pub(crate) fn regions(&self, viewport: Size<DevicePixels>) -> Vec<Bounds<DevicePixels>> {
+        let viewport = Bounds {
+            origin: point(DevicePixels(0), DevicePixels(0)),
+            size: viewport,
+        };
+        match self {
+            Damage::Full => vec![viewport],
+            Damage::Regions(regions) => regions
+                .iter()
+                .map(|region| region.intersect(&viewport))
+                .filter(|region| !region.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl Scene {
+    /// Computes the regions that differ between this scene and the previously presented one.
+    ///
+    /// Primitives are matched by value, so ones that merely moved between the two paint
+    /// operation lists, e.g. because a cached view was replayed, don't cause damage.
+    pub(crate) fn damage_since(&self, previous: &Scene) -> Damage {
+        let mut primitives = FxHashMap::<u64, (isize, Bounds<ScaledPixels>)>::default();
+        let mut damaged_bounds = Vec::new();
+        for (scene, delta) in [(self, 1), (previous, -1)] {
+            for operation in &scene.paint_operations {
+                let PaintOperation::Primitive(primitive) = operation else {
+                    continue;
+                };
+                // Surfaces are backed by video buffers that can change without the primitive
+                // changing, so they are always redrawn.
+                if let Primitive::Surface(surface) = primitive {
+                    damaged_bounds.push(surface.bounds.intersect(&surface.content_mask.bounds));
+                    continue;
+                }
+                let entry = primitives
+                    .entry(fingerprint(primitive))
+                    .or_insert_with(|| (0, painted_bounds(primitive)));
+                entry.0 += delta;
+            }
+        }
+        damaged_bounds.extend(
+            primitives
+                .into_values()
+                .filter(|(count, _)| *count != 0)
+                .map(|(_, bounds)| bounds),
+        );
+
+        let mut regions = Vec::new();
+        for bounds in damaged_bounds {
+            if !bounds.is_empty() {
+                add_region(&mut regions, to_device_pixels(bounds));
+            }
+        }
+        Damage::Regions(regions)
+    }
+}
+
+/// Returns the pixels a primitive can touch when rendered.
+fn painted_bounds(primitive: &Primitive) -> Bounds<ScaledPixels> {
+    let bounds = match primitive {
+        Primitive::Shadow(shadow) => shadow
+            .bounds
+            .dilate(ScaledPixels(3. * shadow.blur_radius.0)),
+        Primitive::MonochromeSprite(sprite) => {
+            transformed_bounds(sprite.bounds, &sprite.transformation)
+        }
+        primitive => *primitive.bounds(),
+    };
+    bounds.intersect(&primitive.content_mask().bounds)
+}
+
+fn transformed_bounds(
+    bounds: Bounds<ScaledPixels>,
+    transformation: &TransformationMatrix,
+) -> Bounds<ScaledPixels> {
+    if *transformation == TransformationMatrix::unit() {
+        return bounds;
+    }
+
+    let [[a, b], [c, d]] = transformation.rotation_scale;
+    let origin = bounds.origin;
+    let corner = bounds.bottom_right();
+    let corners = [
+        [origin.x.0, origin.y.0],
+        [corner.x.0, origin.y.0],
+        [origin.x.0, corner.y.0],
+        [corner.x.0, corner.y.0],
+    ]
+    .map(|[x, y]| {
+        [
+            a * x + b * y + transformation.translation[0],
+            c * x + d * y + transformation.translation[1],
+        ]
+    });
+
+    let min_x = corners.iter().map(|p| p[0]).fold(f32::MAX, f32::min);
+    let max_x = corners.iter().map(|p| p[0]).fold(f32::MIN, f32::max);
+    let min_y = corners.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
+    let max_y = corners.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+    Bounds {
+        origin: point(ScaledPixels(min_x), ScaledPixels(min_y)),
+        size: size(ScaledPixels(max_x - min_x), ScaledPixels(max_y - min_y)),
+    }
+}
+
+/// Rounds the bounds outwards to whole device pixels, with a pixel of slack for antialiasing.
+fn to_device_pixels(bounds: Bounds<ScaledPixels>) -> Bounds<DevicePixels> {
+    let bottom_right = bounds.bottom_right();
+    let left = bounds.origin.x.0.floor() as i32 - 1;
+    let top = bounds.origin.y.0.floor() as i32 - 1;
+    let right = bottom_right.x.0.ceil() as i32 + 1;
+    let bottom = bottom_right.y.0.ceil() as i32 + 1;
+    Bounds {
+        origin: point(DevicePixels(left), DevicePixels(top)),
+        size: size(DevicePixels(right - left), DevicePixels(bottom - top)),
+    }
+}
+
+/// Adds a region to the list, merging it with the regions it overlaps.
+fn add_region(regions: &mut Vec<Bounds<DevicePixels>>, mut region: Bounds<DevicePixels>) {
+    if region.is_empty() {
+        return;
+    }
+
+    // Merging can make the region overlap ones that were checked before, so repeat until
+    // the region is disjoint from all others.
+    while let Some(ix) = regions
+        .iter()
+        .position(|existing| existing.intersects(&region))
+    {
+        region = region.union(&regions.swap_remove(ix));
+    }
+    regions.push(region);
+
+    if regions.len() > MAX_DAMAGE_REGIONS {
+        let bounding_box = regions
+            .drain(..)
+            .reduce(|a, b| a.union(&b))
+            .expect("regions should not be empty");
+        regions.push(bounding_box);
+    }
+}
+
+/// Hashes everything that affects how a primitive is rendered, including its draw order.
+fn fingerprint(primitive: &Primitive) -> u64 {
+    let mut hasher = Fingerprint(DefaultHasher::new());
+    match primitive {
+        Primitive::Shadow(shadow) => {
+            hasher.tag(0);
+            hasher.order(shadow.order);
+            hasher.scaled(shadow.blur_radius);
+            hasher.bounds(&shadow.bounds);
+            hasher.corners(&shadow.corner_radii);
+            hasher.content_mask(&shadow.content_mask);
+            hasher.hsla(shadow.color);
+        }
+        Primitive::Quad(quad) => {
+            hasher.tag(1);
+            hasher.order(quad.order);
+            hasher.bounds(&quad.bounds);
+            hasher.content_mask(&quad.content_mask);
+            hasher.background(&quad.background);
+            hasher.hsla(quad.border_color);
+            hasher.corners(&quad.corner_radii);
+            hasher.scaled(quad.border_widths.top);
+            hasher.scaled(quad.border_widths.right);
+            hasher.scaled(quad.border_widths.bottom);
+            hasher.scaled(quad.border_widths.left);
+        }
+        Primitive::Path(path) => {
+            hasher.tag(2);
+            hasher.order(path.order);
+            hasher.bounds(&path.bounds);
+            hasher.content_mask(&path.content_mask);
+            hasher.background(&path.color);
+            for vertex in &path.vertices {
+                hasher.scaled(vertex.xy_position.x);
+                hasher.scaled(vertex.xy_position.y);
+                hasher.f32(vertex.st_position.x);
+                hasher.f32(vertex.st_position.y);
+            }
+        }
+        Primitive::Underline(underline) => {
+            hasher.tag(3);
+            hasher.order(underline.order);
+            hasher.bounds(&underline.bounds);
+            hasher.content_mask(&underline.content_mask);
+            hasher.hsla(underline.color);
+            hasher.scaled(underline.thickness);
+            hasher.tag(underline.wavy as u8);
+        }
+        Primitive::MonochromeSprite(sprite) => {
+            hasher.tag(4);
+            hasher.order(sprite.order);
+            hasher.bounds(&sprite.bounds);
+            hasher.content_mask(&sprite.content_mask);
+            hasher.hsla(sprite.color);
+            hasher.tile(&sprite.tile);
+            for value in sprite.transformation.rotation_scale.iter().flatten() {
+                hasher.f32(*value);
+            }
+            hasher.f32(sprite.transformation.translation[0]);
+            hasher.f32(sprite.transformation.translation[1]);
+        }
+        Primitive::PolychromeSprite(sprite) => {
+            hasher.tag(5);
+            hasher.order(sprite.order);
+            hasher.tag(sprite.grayscale as u8);
+            hasher.f32(sprite.opacity);
+            hasher.bounds(&sprite.bounds);
+            hasher.content_mask(&sprite.content_mask);
+            hasher.corners(&sprite.corner_radii);
+            hasher.tile(&sprite.tile);
+        }
+        Primitive::Surface(surface) => {
+            hasher.tag(6);
+            hasher.order(surface.order);
+            hasher.bounds(&surface.bounds);
+            hasher.content_mask(&surface.content_mask);
+        }
+    }
+    hasher.0.finish()
+}
+
+/// Feeds the fields of a primitive into a hasher. Floats are hashed by their bit patterns.
+struct Fingerprint(DefaultHasher);
+
+impl Fingerprint {
+    fn tag(&mut self, tag: u8) {
+        self.0.write_u8(tag);
+    }
+
+    fn order(&mut self, order: u32) {
+        self.0.write_u32(order);
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.0.write_u32(value.to_bits());
+    }
+
+    fn scaled(&mut self, value: ScaledPixels) {
+        self.f32(value.0);
+    }
+
+    fn bounds(&mut self, bounds: &Bounds<ScaledPixels>) {
+        self.scaled(bounds.origin.x);
+        self.scaled(bounds.origin.y);
+        self.scaled(bounds.size.width);
+        self.scaled(bounds.size.height);
+    }
+
+    fn content_mask(&mut self, content_mask: &ContentMask<ScaledPixels>) {
+        self.bounds(&content_mask.bounds);
+    }
+
+    fn corners(&mut self, corners: &Corners<ScaledPixels>) {
+        self.scaled(corners.top_left);
+        self.scaled(corners.top_right);
+        self.scaled(corners.bottom_right);
+        self.scaled(corners.bottom_left);
+    }
+
+    fn hsla(&mut self, color: Hsla) {
+        color.hash(&mut self.0);
+    }
+
+    fn background(&mut self, background: &Background) {
+        self.tag(background.tag as u8);
+        self.tag(background.color_space as u8);
+        self.hsla(background.solid);
+        self.f32(background.gradient_angle_or_pattern_height);
+        for stop in &background.colors {
+            self.hsla(stop.color);
+            self.f32(stop.percentage);
+        }
+    }
+
+    fn tile(&mut self, tile: &AtlasTile) {
+        tile.texture_id.hash(&mut self.0);
+        self.0.write_u32(tile.tile_id.0);
+        self.0.write_u32(tile.padding);
+        self.0.write_i32(tile.bounds.origin.x.0);
+        self.0.write_i32(tile.bounds.origin.y.0);
+        self.0.write_i32(tile.bounds.size.width.0);
+        self.0.write_i32(tile.bounds.size.height.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hsla, Quad};
+
+    fn quad(x: f32, y: f32, color: Hsla) -> Quad {
+        let bounds = Bounds {
+            origin: point(ScaledPixels(x), ScaledPixels(y)),
+            size: size(ScaledPixels(10.), ScaledPixels(10.)),
+        };
+        Quad {
+            bounds,
+            content_mask: ContentMask {
+                bounds: Bounds {
+                    origin: point(ScaledPixels(0.), ScaledPixels(0.)),
+                    size: size(ScaledPixels(1000.), ScaledPixels(1000.)),
+                },
+            },
+            background: color.into(),
+            ..Default::default()
+        }
+    }
+
+    fn scene(quads: impl IntoIterator<Item = Quad>) -> Scene {
+        let mut scene = Scene::default();
+        for quad in quads {
+            scene.insert_primitive(quad);
+        }
+        scene.finish();
+        scene
+    }
+
+    #[test]
+    fn test_damage_between_scenes() {
+        let red = hsla(0., 1., 0.5, 1.);
+        let blue = hsla(0.6, 1., 0.5, 1.);
+        let previous = scene([quad(0., 0., red), quad(100., 100., red)]);
+
+        let unchanged = scene([quad(100., 100., red), quad(0., 0., red)]);
+        assert!(unchanged.damage_since(&previous).is_empty());
+
+        let recolored = scene([quad(0., 0., red), quad(100., 100., blue)]);
+        assert_eq!(
+            recolored.damage_since(&previous),
+            Damage::Regions(vec![Bounds {
+                origin: point(DevicePixels(99), DevicePixels(99)),
+                size: size(DevicePixels(12), DevicePixels(12)),
+            }])
+        );
+
+        // The old and new position of a moved quad overlap, so they merge into one region.
+        let moved = scene([quad(5., 0., red), quad(100., 100., red)]);
+        assert_eq!(
+            moved.damage_since(&previous),
+            Damage::Regions(vec![Bounds {
+                origin: point(DevicePixels(-1), DevicePixels(-1)),
+                size: size(DevicePixels(17), DevicePixels(12)),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_damage_union() {
+        let region = |x: i32| Bounds {
+            origin: point(DevicePixels(x), DevicePixels(0)),
+            size: size(DevicePixels(10), DevicePixels(10)),
+        };
+
+        let mut damage = Damage::default();
+        damage.union(Damage::Regions(vec![region(0)]));
+        damage.union(Damage::Regions(vec![region(5), region(100)]));
+        assert_eq!(
+            damage.regions(size(DevicePixels(50), DevicePixels(50))),
+            vec![Bounds {
+                origin: point(DevicePixels(0), DevicePixels(0)),
+                size: size(DevicePixels(15), DevicePixels(10)),
+            }]
+        );
+
+        damage.union(Damage::Full);
+        assert_eq!(damage, Damage::Full);
+    }
+}
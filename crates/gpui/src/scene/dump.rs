@@ -0,0 +1,480 @@
+use std::fmt;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AtlasTextureKind, AtlasTile, Background, BackgroundTag, Bounds, ColorSpace, ContentMask,
+    Corners, Edges, Hsla, Rgba, ScaledPixels,
+};
+
+use super::{PaintOperation, Primitive, Scene};
+
+/// Scenes with more operations than this in their changed region are diffed as a single
+/// replacement, to keep [`diff_scene_dumps`] from allocating a huge table.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// A serializable copy of the primitives in a finished frame, in paint order.
+///
+/// Obtain one with [`Window::dump_scene`](crate::Window::dump_scene). Positions and sizes
+/// are in device pixels, and colors are `#rrggbbaa` strings.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneDump {
+    /// The primitives and layers of the scene, in the order they were painted.
+    pub operations: Vec<SceneDumpOperation>,
+}
+
+/// A single paint operation in a [`SceneDump`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[allow(missing_docs)]
+pub enum SceneDumpOperation {
+    StartLayer {
+        bounds: SceneDumpBounds,
+    },
+    EndLayer,
+    Shadow {
+        order: u32,
+        bounds: SceneDumpBounds,
+        content_mask: SceneDumpBounds,
+        corner_radii: [f32; 4],
+        blur_radius: f32,
+        color: String,
+    },
+    Quad {
+        order: u32,
+        bounds: SceneDumpBounds,
+        content_mask: SceneDumpBounds,
+        background: SceneDumpBackground,
+        border_color: String,
+        corner_radii: [f32; 4],
+        border_widths: [f32; 4],
+    },
+    Path {
+        order: u32,
+        bounds: SceneDumpBounds,
+        content_mask: SceneDumpBounds,
+        background: SceneDumpBackground,
+        vertices: Vec<[f32; 2]>,
+    },
+    Underline {
+        order: u32,
+        bounds: SceneDumpBounds,
+        content_mask: SceneDumpBounds,
+        color: String,
+        thickness: f32,
+        wavy: bool,
+    },
+    MonochromeSprite {
+        order: u32,
+        bounds: SceneDumpBounds,
+        content_mask: SceneDumpBounds,
+        color: String,
+        tile: SceneDumpTile,
+        transformation: [f32; 6],
+    },
+    PolychromeSprite {
+        order: u32,
+        bounds: SceneDumpBounds,
+        content_mask: SceneDumpBounds,
+        corner_radii: [f32; 4],
+        grayscale: bool,
+        opacity: f32,
+        tile: SceneDumpTile,
+    },
+    Surface {
+        order: u32,
+        bounds: SceneDumpBounds,
+        content_mask: SceneDumpBounds,
+    },
+}
+
+/// A rectangle in a [`SceneDump`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub struct SceneDumpBounds {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// The fill of a quad or path in a [`SceneDump`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[allow(missing_docs)]
+pub enum SceneDumpBackground {
+    Solid {
+        color: String,
+    },
+    LinearGradient {
+        angle: f32,
+        color_space: String,
+        /// The color and position of each gradient stop.
+        stops: [(String, f32); 2],
+    },
+    PatternSlash {
+        color: String,
+        height: f32,
+    },
+}
+
+/// The atlas location a sprite is drawn from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub struct SceneDumpTile {
+    /// Either `monochrome`, `polychrome` or `path`, followed by the texture index.
+    pub texture: String,
+    pub bounds: SceneDumpBounds,
+}
+
+impl SceneDump {
+    pub(crate) fn new(scene: &Scene) -> Self {
+        let operations = scene
+            .paint_operations
+            .iter()
+            .map(|operation| match operation {
+                PaintOperation::Primitive(primitive) => dump_primitive(primitive),
+                PaintOperation::StartLayer(bounds) => SceneDumpOperation::StartLayer {
+                    bounds: (*bounds).into(),
+                },
+                PaintOperation::EndLayer => SceneDumpOperation::EndLayer,
+            })
+            .collect();
+        Self { operations }
+    }
+
+    /// Serializes the dump as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("scene dumps are always serializable")
+    }
+
+    /// Parses a dump previously produced by [`SceneDump::to_json`].
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// The differences between two [`SceneDump`]s, as computed by [`diff_scene_dumps`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SceneDumpDiff {
+    /// The removed and added operations, in paint order.
+    pub changes: Vec<SceneDumpChange>,
+}
+
+/// A single difference between two [`SceneDump`]s.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneDumpChange {
+    /// An operation of the old dump that is missing from the new one.
+    Removed {
+        /// The index of the operation in the old dump.
+        index: usize,
+        /// The removed operation.
+        operation: SceneDumpOperation,
+    },
+    /// An operation of the new dump that is missing from the old one.
+    Added {
+        /// The index of the operation in the new dump.
+        index: usize,
+        /// The added operation.
+        operation: SceneDumpOperation,
+    },
+}
+
+impl SceneDumpDiff {
+    /// Whether both dumps contain the same operations.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for SceneDumpDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            let (sign, index, operation) = match change {
+                SceneDumpChange::Removed { index, operation } => ('-', index, operation),
+                SceneDumpChange::Added { index, operation } => ('+', index, operation),
+            };
+            let operation = serde_json::to_string(operation).map_err(|_| fmt::Error)?;
+            writeln!(f, "{sign} [{index}] {operation}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the operations that were removed from `old` and added in `new`, using a longest
+/// common subsequence so that an inserted primitive doesn't mark everything after it as changed.
+pub fn diff_scene_dumps(old: &SceneDump, new: &SceneDump) -> SceneDumpDiff {
+    let old = &old.operations;
+    let new = &new.operations;
+
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut changes = Vec::new();
+    let removed = |ix: usize| SceneDumpChange::Removed {
+        index: prefix + ix,
+        operation: old_middle[ix].clone(),
+    };
+    let added = |ix: usize| SceneDumpChange::Added {
+        index: prefix + ix,
+        operation: new_middle[ix].clone(),
+    };
+
+    if old_middle.len().saturating_mul(new_middle.len()) > MAX_DIFF_CELLS {
+        changes.extend((0..old_middle.len()).map(removed));
+        changes.extend((0..new_middle.len()).map(added));
+        return SceneDumpDiff { changes };
+    }
+
+    // lengths[i][j] is the length of the longest common subsequence of old_middle[i..] and new_middle[j..].
+    let width = new_middle.len() + 1;
+    let mut lengths = vec![0u32; (old_middle.len() + 1) * width];
+    for i in (0..old_middle.len()).rev() {
+        for j in (0..new_middle.len()).rev() {
+            lengths[i * width + j] = if old_middle[i] == new_middle[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old_middle.len() && j < new_middle.len() {
+        if old_middle[i] == new_middle[j] {
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            changes.push(removed(i));
+            i += 1;
+        } else {
+            changes.push(added(j));
+            j += 1;
+        }
+    }
+    changes.extend((i..old_middle.len()).map(removed));
+    changes.extend((j..new_middle.len()).map(added));
+
+    SceneDumpDiff { changes }
+}
+
+fn dump_primitive(primitive: &Primitive) -> SceneDumpOperation {
+    match primitive {
+        Primitive::Shadow(shadow) => SceneDumpOperation::Shadow {
+            order: shadow.order,
+            bounds: shadow.bounds.into(),
+            content_mask: dump_content_mask(&shadow.content_mask),
+            corner_radii: dump_corners(&shadow.corner_radii),
+            blur_radius: shadow.blur_radius.0,
+            color: dump_color(shadow.color),
+        },
+        Primitive::Quad(quad) => SceneDumpOperation::Quad {
+            order: quad.order,
+            bounds: quad.bounds.into(),
+            content_mask: dump_content_mask(&quad.content_mask),
+            background: quad.background.into(),
+            border_color: dump_color(quad.border_color),
+            corner_radii: dump_corners(&quad.corner_radii),
+            border_widths: dump_edges(&quad.border_widths),
+        },
+        Primitive::Path(path) => SceneDumpOperation::Path {
+            order: path.order,
+            bounds: path.bounds.into(),
+            content_mask: dump_content_mask(&path.content_mask),
+            background: path.color.into(),
+            vertices: path
+                .vertices
+                .iter()
+                .map(|vertex| [vertex.xy_position.x.0, vertex.xy_position.y.0])
+                .collect(),
+        },
+        Primitive::Underline(underline) => SceneDumpOperation::Underline {
+            order: underline.order,
+            bounds: underline.bounds.into(),
+            content_mask: dump_content_mask(&underline.content_mask),
+            color: dump_color(underline.color),
+            thickness: underline.thickness.0,
+            wavy: underline.wavy,
+        },
+        Primitive::MonochromeSprite(sprite) => {
+            let [[a, b], [c, d]] = sprite.transformation.rotation_scale;
+            let [tx, ty] = sprite.transformation.translation;
+            SceneDumpOperation::MonochromeSprite {
+                order: sprite.order,
+                bounds: sprite.bounds.into(),
+                content_mask: dump_content_mask(&sprite.content_mask),
+                color: dump_color(sprite.color),
+                tile: (&sprite.tile).into(),
+                transformation: [a, b, c, d, tx, ty],
+            }
+        }
+        Primitive::PolychromeSprite(sprite) => SceneDumpOperation::PolychromeSprite {
+            order: sprite.order,
+            bounds: sprite.bounds.into(),
+            content_mask: dump_content_mask(&sprite.content_mask),
+            corner_radii: dump_corners(&sprite.corner_radii),
+            grayscale: sprite.grayscale,
+            opacity: sprite.opacity,
+            tile: (&sprite.tile).into(),
+        },
+        Primitive::Surface(surface) => SceneDumpOperation::Surface {
+            order: surface.order,
+            bounds: surface.bounds.into(),
+            content_mask: dump_content_mask(&surface.content_mask),
+        },
+    }
+}
+
+impl From<Bounds<ScaledPixels>> for SceneDumpBounds {
+    fn from(bounds: Bounds<ScaledPixels>) -> Self {
+        Self {
+            x: bounds.origin.x.0,
+            y: bounds.origin.y.0,
+            width: bounds.size.width.0,
+            height: bounds.size.height.0,
+        }
+    }
+}
+
+impl From<Background> for SceneDumpBackground {
+    fn from(background: Background) -> Self {
+        match background.tag {
+            BackgroundTag::Solid => SceneDumpBackground::Solid {
+                color: dump_color(background.solid),
+            },
+            BackgroundTag::LinearGradient => SceneDumpBackground::LinearGradient {
+                angle: background.gradient_angle_or_pattern_height,
+                color_space: match background.color_space {
+                    ColorSpace::Srgb => "srgb",
+                    ColorSpace::Oklab => "oklab",
+                }
+                .to_string(),
+                stops: background
+                    .colors
+                    .map(|stop| (dump_color(stop.color), stop.percentage)),
+            },
+            BackgroundTag::PatternSlash => SceneDumpBackground::PatternSlash {
+                color: dump_color(background.solid),
+                height: background.gradient_angle_or_pattern_height,
+            },
+        }
+    }
+}
+
+impl From<&AtlasTile> for SceneDumpTile {
+    fn from(tile: &AtlasTile) -> Self {
+        let kind = match tile.texture_id.kind {
+            AtlasTextureKind::Monochrome => "monochrome",
+            AtlasTextureKind::Polychrome => "polychrome",
+            AtlasTextureKind::Path => "path",
+        };
+        Self {
+            texture: format!("{kind}/{}", tile.texture_id.index),
+            bounds: SceneDumpBounds {
+                x: tile.bounds.origin.x.0 as f32,
+                y: tile.bounds.origin.y.0 as f32,
+                width: tile.bounds.size.width.0 as f32,
+                height: tile.bounds.size.height.0 as f32,
+            },
+        }
+    }
+}
+
+fn dump_content_mask(content_mask: &ContentMask<ScaledPixels>) -> SceneDumpBounds {
+    content_mask.bounds.into()
+}
+
+fn dump_corners(corners: &Corners<ScaledPixels>) -> [f32; 4] {
+    [
+        corners.top_left.0,
+        corners.top_right.0,
+        corners.bottom_right.0,
+        corners.bottom_left.0,
+    ]
+}
+
+fn dump_edges(edges: &Edges<ScaledPixels>) -> [f32; 4] {
+    [edges.top.0, edges.right.0, edges.bottom.0, edges.left.0]
+}
+
+fn dump_color(color: Hsla) -> String {
+    format!("#{:08x}", u32::from(Rgba::from(color)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hsla, point, size, Quad};
+
+    fn quad(x: f32, color: Hsla) -> Quad {
+        let bounds = Bounds {
+            origin: point(ScaledPixels(x), ScaledPixels(0.)),
+            size: size(ScaledPixels(10.), ScaledPixels(10.)),
+        };
+        Quad {
+            bounds,
+            content_mask: ContentMask { bounds },
+            background: color.into(),
+            ..Default::default()
+        }
+    }
+
+    fn dump(quads: &[Quad]) -> SceneDump {
+        let mut scene = Scene::default();
+        for quad in quads {
+            scene.insert_primitive(quad.clone());
+        }
+        scene.finish();
+        SceneDump::new(&scene)
+    }
+
+    #[test]
+    fn test_scene_dump_round_trips_through_json() {
+        let dump = dump(&[quad(0., hsla(0., 1., 0.5, 1.))]);
+        assert_eq!(SceneDump::from_json(&dump.to_json()).unwrap(), dump);
+
+        let SceneDumpOperation::Quad { background, .. } = &dump.operations[0] else {
+            panic!("expected a quad, got {:?}", dump.operations[0]);
+        };
+        assert_eq!(
+            background,
+            &SceneDumpBackground::Solid {
+                color: "#ff0000ff".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_scene_dumps() {
+        let red = hsla(0., 1., 0.5, 1.);
+        let blue = hsla(0.66, 1., 0.5, 1.);
+        let old = dump(&[quad(0., red), quad(40., red)]);
+        let new = dump(&[quad(0., red), quad(20., blue), quad(40., red)]);
+
+        assert!(diff_scene_dumps(&old, &old).is_empty());
+
+        let diff = diff_scene_dumps(&old, &new);
+        assert_eq!(
+            diff.changes
+                .iter()
+                .map(|change| match change {
+                    SceneDumpChange::Removed { index, .. } => format!("-{index}"),
+                    SceneDumpChange::Added { index, .. } => format!("+{index}"),
+                })
+                .collect::<Vec<_>>(),
+            ["+1"]
+        );
+    }
+}
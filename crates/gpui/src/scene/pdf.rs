@@ -0,0 +1,888 @@
+use std::ops::Range;
+
+use collections::FxHashMap;
+use util::ResultExt as _;
+
+use crate::{
+    px, size, Background, BackgroundTag, Bounds, ContentMask, Corners, DevicePixels, Edges,
+    GlyphOutlineCommand, Hsla, Pixels, Point, Rgba, ScaledPixels, Size, SpriteSource, TextSystem,
+};
+
+use super::svg::{draw_order, Num};
+use super::{
+    MonochromeSprite, PaintOperation, Path, PolychromeSprite, Primitive, Quad, Scene, Shadow,
+    Underline,
+};
+
+/// PDF user space is measured in points, and a logical pixel is 1/96th of an inch like in CSS.
+const POINTS_PER_PIXEL: f32 = 0.75;
+
+/// The magic number for approximating a quarter circle with a cubic bezier curve.
+const KAPPA: f32 = 0.552_284_8;
+
+/// The size and margins of the pages a view is printed on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageSetup {
+    /// The size of each page.
+    pub size: Size<Pixels>,
+    /// The space between the edges of each page and the printed content.
+    pub margins: Edges<Pixels>,
+}
+
+impl PageSetup {
+    /// An A4 page, 210mm by 297mm.
+    pub fn a4() -> Self {
+        Self {
+            size: size(px(793.7), px(1122.5)),
+            margins: Edges::all(px(48.)),
+        }
+    }
+
+    /// A US letter page, 8.5in by 11in.
+    pub fn letter() -> Self {
+        Self {
+            size: size(px(816.), px(1056.)),
+            margins: Edges::all(px(48.)),
+        }
+    }
+
+    /// Sets the margins of each page.
+    pub fn margins(mut self, margins: Edges<Pixels>) -> Self {
+        self.margins = margins;
+        self
+    }
+
+    /// The size of the printable area of each page.
+    pub fn content_size(&self) -> Size<Pixels> {
+        size(
+            (self.size.width - self.margins.left - self.margins.right).max(px(1.)),
+            (self.size.height - self.margins.top - self.margins.bottom).max(px(1.)),
+        )
+    }
+}
+
+impl Default for PageSetup {
+    fn default() -> Self {
+        Self::a4()
+    }
+}
+
+/// Splits a scene that is `content_height` tall into pages and writes them as a PDF document.
+///
+/// Glyphs are drawn from their outlines and bitmaps are embedded uncompressed, reconstructed
+/// from `sprite_sources` like in [`scene_to_svg`](super::scene_to_svg).
+pub(crate) fn scene_to_pdf(
+    scene: &Scene,
+    sprite_sources: &FxHashMap<usize, SpriteSource>,
+    text_system: &TextSystem,
+    scale_factor: f32,
+    content_height: Pixels,
+    page_setup: &PageSetup,
+) -> Vec<u8> {
+    let mut primitives = scene
+        .paint_operations
+        .iter()
+        .enumerate()
+        .filter_map(|(ix, operation)| match operation {
+            PaintOperation::Primitive(primitive) => Some((ix, primitive)),
+            PaintOperation::StartLayer(_) | PaintOperation::EndLayer => None,
+        })
+        .collect::<Vec<_>>();
+    primitives.sort_by_key(|(_, primitive)| draw_order(primitive));
+
+    let text_lines = primitives
+        .iter()
+        .filter(|(ix, _)| matches!(sprite_sources.get(ix), Some(SpriteSource::Glyph { .. })))
+        .map(|(_, primitive)| {
+            let bounds = primitive.bounds();
+            bounds.origin.y.0 / scale_factor..bounds.bottom().0 / scale_factor
+        })
+        .collect::<Vec<_>>();
+    let content_size = page_setup.content_size();
+    let pages = paginate(content_height.0, content_size.height.0, &text_lines);
+
+    let mut writer = PdfWriter {
+        scale_factor,
+        sprite_sources,
+        text_system,
+        document: PdfDocument::default(),
+        content: String::new(),
+        alpha_states: FxHashMap::default(),
+        shadings: Vec::new(),
+        images: Vec::new(),
+    };
+    let catalog_id = writer.document.reserve();
+    let pages_id = writer.document.reserve();
+    let resources_id = writer.document.reserve();
+
+    let page_width = page_setup.size.width.0 * POINTS_PER_PIXEL;
+    let page_height = page_setup.size.height.0 * POINTS_PER_PIXEL;
+    let mut page_ids = Vec::new();
+    for band in pages {
+        // Flip the y axis and scale to logical pixels, then move the band into the printable area.
+        writer.content = format!(
+            "{} 0 0 {} 0 {} cm\n1 0 0 1 {} {} cm\n0 {} {} {} re W n\n",
+            Num(POINTS_PER_PIXEL),
+            Num(-POINTS_PER_PIXEL),
+            Num(page_height),
+            Num(page_setup.margins.left.0),
+            Num(page_setup.margins.top.0 - band.start),
+            Num(band.start),
+            Num(content_size.width.0),
+            Num(band.end - band.start),
+        );
+        for (operation_ix, primitive) in &primitives {
+            let bounds = primitive.bounds();
+            let bounds = match primitive {
+                Primitive::Shadow(shadow) => bounds.dilate(ScaledPixels(shadow.blur_radius.0 * 3.)),
+                _ => *bounds,
+            };
+            let (top, bottom) = (bounds.origin.y, bounds.bottom());
+            if bottom.0 / scale_factor <= band.start || top.0 / scale_factor >= band.end {
+                continue;
+            }
+            writer.write_primitive(*operation_ix, primitive);
+        }
+
+        let content = std::mem::take(&mut writer.content);
+        let content_id = writer.document.add_stream("", content.as_bytes());
+        page_ids.push(writer.document.add(format!(
+            "<< /Type /Page /Parent {pages_id} 0 R /MediaBox [0 0 {} {}] \
+            /Resources {resources_id} 0 R /Contents {content_id} 0 R >>",
+            Num(page_width),
+            Num(page_height)
+        )));
+    }
+
+    let kids = page_ids
+        .iter()
+        .map(|id| format!("{id} 0 R"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    writer.document.set(
+        pages_id,
+        format!(
+            "<< /Type /Pages /Kids [{kids}] /Count {} >>",
+            page_ids.len()
+        ),
+    );
+    let resources = writer.resources();
+    writer.document.set(resources_id, resources);
+    writer.document.set(
+        catalog_id,
+        format!("<< /Type /Catalog /Pages {pages_id} 0 R >>"),
+    );
+    writer.document.finish(catalog_id)
+}
+
+/// Splits content into page-sized bands, moving page breaks above lines of text they would
+/// cut through, as long as that leaves at least half a page.
+fn paginate(content_height: f32, page_height: f32, text_lines: &[Range<f32>]) -> Vec<Range<f32>> {
+    let mut pages = Vec::new();
+    let mut start = 0.;
+    while start + page_height < content_height {
+        let mut end = start + page_height;
+        while let Some(line) = text_lines
+            .iter()
+            .find(|line| line.start < end && line.end > end)
+        {
+            if line.start <= start + page_height / 2. {
+                break;
+            }
+            end = line.start;
+        }
+        pages.push(start..end);
+        start = end;
+    }
+    pages.push(start..content_height.max(start + 1.));
+    pages
+}
+
+struct PdfWriter<'a> {
+    scale_factor: f32,
+    sprite_sources: &'a FxHashMap<usize, SpriteSource>,
+    text_system: &'a TextSystem,
+    document: PdfDocument,
+    /// The content stream of the page being written.
+    content: String,
+    /// Names of graphics states that set the fill and stroke alpha, keyed by the alpha's bits.
+    alpha_states: FxHashMap<u32, String>,
+    shadings: Vec<(String, usize)>,
+    images: Vec<(String, usize)>,
+}
+
+impl PdfWriter<'_> {
+    fn write_primitive(&mut self, operation_ix: usize, primitive: &Primitive) {
+        match primitive {
+            Primitive::Shadow(shadow) => self.write_shadow(shadow),
+            Primitive::Quad(quad) => self.write_quad(quad),
+            Primitive::Path(path) => self.write_path(path),
+            Primitive::Underline(underline) => self.write_underline(underline),
+            Primitive::MonochromeSprite(sprite) => {
+                self.write_monochrome_sprite(operation_ix, sprite)
+            }
+            Primitive::PolychromeSprite(sprite) => {
+                self.write_polychrome_sprite(operation_ix, sprite)
+            }
+            // Surfaces are video frames owned by the compositor.
+            Primitive::Surface(_) => {}
+        }
+    }
+
+    fn write_shadow(&mut self, shadow: &Shadow) {
+        let visible_bounds = shadow
+            .bounds
+            .dilate(ScaledPixels(shadow.blur_radius.0 * 3.));
+        self.begin(visible_bounds, &shadow.content_mask);
+        let [x, y, width, height] = self.rect(shadow.bounds);
+        let radii = self.corners(&shadow.corner_radii);
+        let blur_radius = shadow.blur_radius.0 / self.scale_factor;
+
+        // PDF has no blur, so fade the shadow out with a few translucent layers instead.
+        let layers = if blur_radius > 0. { 4 } else { 1 };
+        let color = Rgba::from(shadow.color);
+        for layer in 0..layers {
+            let spread = blur_radius * (layers - layer - 1) as f32 / layers as f32;
+            self.content.push_str("q\n");
+            self.set_fill(Hsla::from(Rgba {
+                a: color.a / layers as f32,
+                ..color
+            }));
+            self.rounded_rect(
+                [
+                    x - spread,
+                    y - spread,
+                    width + spread * 2.,
+                    height + spread * 2.,
+                ],
+                radii.map(|radius| radius + spread),
+            );
+            self.content.push_str("f\nQ\n");
+        }
+        self.end();
+    }
+
+    fn write_quad(&mut self, quad: &Quad) {
+        self.begin(quad.bounds, &quad.content_mask);
+        let rect = self.rect(quad.bounds);
+        let [top_left, top_right, bottom_right, bottom_left] = self.corners(&quad.corner_radii);
+        let radii = [top_left, top_right, bottom_right, bottom_left];
+        self.fill_background(&quad.background, rect, |this| {
+            this.rounded_rect(rect, radii)
+        });
+
+        let [top, right, bottom, left] = self.edges(&quad.border_widths);
+        if quad.border_color.a > 0. && (top > 0. || right > 0. || bottom > 0. || left > 0.) {
+            let [x, y, width, height] = rect;
+            self.content.push_str("q\n");
+            self.set_fill(quad.border_color);
+            self.rounded_rect(rect, radii);
+            self.rounded_rect(
+                [
+                    x + left,
+                    y + top,
+                    (width - left - right).max(0.),
+                    (height - top - bottom).max(0.),
+                ],
+                [
+                    (top_left - top.max(left)).max(0.),
+                    (top_right - top.max(right)).max(0.),
+                    (bottom_right - bottom.max(right)).max(0.),
+                    (bottom_left - bottom.max(left)).max(0.),
+                ],
+            );
+            self.content.push_str("f*\nQ\n");
+        }
+        self.end();
+    }
+
+    fn write_path(&mut self, path: &Path<ScaledPixels>) {
+        self.begin(path.bounds, &path.content_mask);
+        let rect = self.rect(path.bounds);
+        let scale_factor = self.scale_factor;
+        self.fill_background(&path.color, rect, |this| {
+            for triangle in path.vertices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|ix| {
+                    let position = triangle[ix].xy_position;
+                    [position.x.0 / scale_factor, position.y.0 / scale_factor]
+                });
+                // Orient every triangle the same way, so that overlapping triangles are unioned
+                // under the nonzero fill rule, like the additive coverage of the path rasterizer.
+                let clockwise = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]) >= 0.;
+                let (a, c) = if clockwise { (a, c) } else { (c, a) };
+                this.move_to(a);
+                if triangle[1].st_position.x == 0.5 {
+                    this.quad_to(a, b, c);
+                } else {
+                    this.line_to(b);
+                    this.line_to(c);
+                }
+                this.content.push_str("h\n");
+            }
+        });
+        self.end();
+    }
+
+    fn write_underline(&mut self, underline: &Underline) {
+        self.begin(underline.bounds, &underline.content_mask);
+        let [x, y, width, height] = self.rect(underline.bounds);
+        if underline.wavy {
+            // Sample the same sine wave the shaders draw and stroke it.
+            let frequency = std::f32::consts::PI * underline.thickness.0;
+            let amplitude = 1. / (4. * underline.thickness.0);
+            let samples = ((width / height.max(1.)) * 8.).ceil().max(1.) as usize;
+            self.set_stroke(underline.color);
+            self.content.push_str(&format!(
+                "{} w\n",
+                Num(underline.thickness.0 / self.scale_factor)
+            ));
+            for sample in 0..=samples {
+                let dx = width * sample as f32 / samples as f32;
+                let dy = height * (0.5 + (dx / height * frequency).sin() * amplitude);
+                if sample == 0 {
+                    self.move_to([x + dx, y + dy]);
+                } else {
+                    self.line_to([x + dx, y + dy]);
+                }
+            }
+            self.content.push_str("S\n");
+        } else {
+            self.set_fill(underline.color);
+            self.content.push_str(&format!(
+                "{} {} {} {} re f\n",
+                Num(x),
+                Num(y),
+                Num(width),
+                Num(height)
+            ));
+        }
+        self.end();
+    }
+
+    fn write_monochrome_sprite(&mut self, operation_ix: usize, sprite: &MonochromeSprite) {
+        let sprite_sources = self.sprite_sources;
+        match sprite_sources.get(&operation_ix) {
+            Some(SpriteSource::Glyph {
+                font_id,
+                glyph_id,
+                font_size,
+                origin,
+            }) => {
+                let Some(outline) = self
+                    .text_system
+                    .glyph_outline(*font_id, *glyph_id, *font_size)
+                    .log_err()
+                else {
+                    return;
+                };
+                self.begin(sprite.bounds, &sprite.content_mask);
+                self.set_fill(sprite.color);
+                self.content.push_str(&format!(
+                    "1 0 0 1 {} {} cm\n",
+                    Num(origin.x.0),
+                    Num(origin.y.0)
+                ));
+                let point = |point: Point<Pixels>| [point.x.0, point.y.0];
+                let mut current = [0., 0.];
+                for command in outline {
+                    match command {
+                        GlyphOutlineCommand::MoveTo(to) => {
+                            current = point(to);
+                            self.move_to(current);
+                        }
+                        GlyphOutlineCommand::LineTo(to) => {
+                            current = point(to);
+                            self.line_to(current);
+                        }
+                        GlyphOutlineCommand::QuadTo { control, to } => {
+                            self.quad_to(current, point(control), point(to));
+                            current = point(to);
+                        }
+                        GlyphOutlineCommand::CurveTo {
+                            control_a,
+                            control_b,
+                            to,
+                        } => {
+                            self.curve_to(point(control_a), point(control_b), point(to));
+                            current = point(to);
+                        }
+                        GlyphOutlineCommand::Close => self.content.push_str("h\n"),
+                    }
+                }
+                self.content.push_str("f\n");
+                self.end();
+            }
+            Some(SpriteSource::Bitmap { size, bytes }) => {
+                let color = Rgba::from(sprite.color);
+                let rgb = bytes
+                    .iter()
+                    .flat_map(|_| [color.r, color.g, color.b].map(|c| (c * 255.).round() as u8))
+                    .collect();
+                let alpha = bytes
+                    .iter()
+                    .map(|alpha| (color.a * *alpha as f32).round() as u8)
+                    .collect();
+                let image = self.add_image(*size, rgb, alpha);
+
+                self.begin(sprite.bounds, &sprite.content_mask);
+                let transformation = &sprite.transformation;
+                let [[a, b], [c, d]] = transformation.rotation_scale;
+                let [tx, ty] = transformation.translation;
+                self.content.push_str(&format!(
+                    "{} {} {} {} {} {} cm\n",
+                    Num(a),
+                    Num(c),
+                    Num(b),
+                    Num(d),
+                    Num(tx / self.scale_factor),
+                    Num(ty / self.scale_factor)
+                ));
+                self.draw_image(&image, self.rect(sprite.bounds));
+                self.end();
+            }
+            // The sprite was painted before the export started recording sprite sources.
+            None => {}
+        }
+    }
+
+    fn write_polychrome_sprite(&mut self, operation_ix: usize, sprite: &PolychromeSprite) {
+        let sprite_sources = self.sprite_sources;
+        let Some(SpriteSource::Bitmap { size, bytes }) = sprite_sources.get(&operation_ix) else {
+            return;
+        };
+
+        let mut rgb = Vec::with_capacity(bytes.len() / 4 * 3);
+        let mut alpha = Vec::with_capacity(bytes.len() / 4);
+        for bgra in bytes.chunks_exact(4) {
+            let [b, g, r, a] = [bgra[0], bgra[1], bgra[2], bgra[3]];
+            if sprite.grayscale {
+                let luminance =
+                    (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32).round() as u8;
+                rgb.extend([luminance; 3]);
+            } else {
+                rgb.extend([r, g, b]);
+            }
+            alpha.push(a);
+        }
+        let image = self.add_image(*size, rgb, alpha);
+
+        self.begin(sprite.bounds, &sprite.content_mask);
+        let rect = self.rect(sprite.bounds);
+        let radii = self.corners(&sprite.corner_radii);
+        if radii.iter().any(|radius| *radius > 0.) {
+            self.rounded_rect(rect, radii);
+            self.content.push_str("W n\n");
+        }
+        self.set_alpha(sprite.opacity);
+        self.draw_image(&image, rect);
+        self.end();
+    }
+
+    /// Fills the path built by `path` with a background.
+    fn fill_background(
+        &mut self,
+        background: &Background,
+        [x, y, width, height]: [f32; 4],
+        path: impl Fn(&mut Self),
+    ) {
+        match background.tag {
+            BackgroundTag::Solid => {
+                if background.solid.a > 0. {
+                    self.content.push_str("q\n");
+                    self.set_fill(background.solid);
+                    path(self);
+                    self.content.push_str("f\nQ\n");
+                }
+            }
+            BackgroundTag::LinearGradient => {
+                // Mirror `gradient_color` in the shaders. PDF interpolates in sRGB,
+                // so Oklab gradients come out slightly different.
+                let radians =
+                    (background.gradient_angle_or_pattern_height % 360. - 90.).to_radians();
+                let mut direction = [radians.cos(), radians.sin()];
+                if width > height {
+                    direction[1] *= height / width;
+                } else {
+                    direction[0] *= width / height;
+                }
+                let length = direction[0].hypot(direction[1]);
+                let direction = direction.map(|component| component / length);
+                let span = if direction[0].abs() > direction[1].abs() {
+                    width
+                } else {
+                    height
+                };
+                let center = [x + width / 2., y + height / 2.];
+                let [from, to] = background.colors;
+                let point_at = |t: f32| {
+                    [
+                        center[0] + direction[0] * span * (t - 0.5),
+                        center[1] + direction[1] * span * (t - 0.5),
+                    ]
+                };
+                let start = point_at(from.percentage);
+                let end = point_at(to.percentage);
+
+                let [from_color, to_color] = [from.color, to.color].map(Rgba::from);
+                let shading = self.document.add(format!(
+                    "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [{} {} {} {}] \
+                    /Function << /FunctionType 2 /Domain [0 1] /C0 [{} {} {}] /C1 [{} {} {}] /N 1 >> \
+                    /Extend [true true] >>",
+                    Num(start[0]),
+                    Num(start[1]),
+                    Num(end[0]),
+                    Num(end[1]),
+                    Num(from_color.r),
+                    Num(from_color.g),
+                    Num(from_color.b),
+                    Num(to_color.r),
+                    Num(to_color.g),
+                    Num(to_color.b),
+                ));
+                let name = format!("Sh{}", self.shadings.len() + 1);
+                self.shadings.push((name.clone(), shading));
+
+                self.content.push_str("q\n");
+                // Shadings have no alpha, so use the average of both stops.
+                self.set_alpha((from_color.a + to_color.a) / 2.);
+                path(self);
+                self.content.push_str(&format!("W n\n/{name} sh\nQ\n"));
+            }
+            BackgroundTag::PatternSlash => {
+                // Stripes half a period wide, perpendicular to the diagonal.
+                let period = background.gradient_angle_or_pattern_height / self.scale_factor
+                    * std::f32::consts::FRAC_1_SQRT_2;
+                if period <= 0. {
+                    return;
+                }
+                self.content.push_str("q\n");
+                path(self);
+                self.content.push_str("W n\n");
+                self.set_fill(background.solid);
+                // Lines where x + y is constant are perpendicular to the diagonal.
+                let extent = width + height;
+                let stripes = (extent * std::f32::consts::FRAC_1_SQRT_2 / period).ceil() as i32;
+                for stripe in 0..=stripes {
+                    let center = stripe as f32 * period * std::f32::consts::SQRT_2;
+                    let half_width = period / 4. * std::f32::consts::SQRT_2;
+                    let [near, far] = [center - half_width, center + half_width];
+                    self.move_to([x + near + extent, y - extent]);
+                    self.line_to([x + near - extent, y + extent]);
+                    self.line_to([x + far - extent, y + extent]);
+                    self.line_to([x + far + extent, y - extent]);
+                    self.content.push_str("h\n");
+                }
+                self.content.push_str("f\nQ\n");
+            }
+        }
+    }
+
+    /// Starts drawing a primitive, clipping it to its content mask if needed.
+    fn begin(&mut self, bounds: Bounds<ScaledPixels>, content_mask: &ContentMask<ScaledPixels>) {
+        self.content.push_str("q\n");
+        if content_mask.bounds.intersect(&bounds) != bounds {
+            let [x, y, width, height] = self.rect(content_mask.bounds);
+            self.content.push_str(&format!(
+                "{} {} {} {} re W n\n",
+                Num(x),
+                Num(y),
+                Num(width),
+                Num(height)
+            ));
+        }
+    }
+
+    fn end(&mut self) {
+        self.content.push_str("Q\n");
+    }
+
+    fn set_fill(&mut self, color: Hsla) {
+        let color = Rgba::from(color);
+        self.set_alpha(color.a);
+        self.content.push_str(&format!(
+            "{} {} {} rg\n",
+            Num(color.r),
+            Num(color.g),
+            Num(color.b)
+        ));
+    }
+
+    fn set_stroke(&mut self, color: Hsla) {
+        let color = Rgba::from(color);
+        self.set_alpha(color.a);
+        self.content.push_str(&format!(
+            "{} {} {} RG\n",
+            Num(color.r),
+            Num(color.g),
+            Num(color.b)
+        ));
+    }
+
+    fn set_alpha(&mut self, alpha: f32) {
+        if alpha >= 1. {
+            return;
+        }
+        let next_name = format!("GS{}", self.alpha_states.len() + 1);
+        let name = self
+            .alpha_states
+            .entry(alpha.max(0.).to_bits())
+            .or_insert(next_name);
+        self.content.push_str(&format!("/{name} gs\n"));
+    }
+
+    fn move_to(&mut self, [x, y]: [f32; 2]) {
+        self.content.push_str(&format!("{} {} m\n", Num(x), Num(y)));
+    }
+
+    fn line_to(&mut self, [x, y]: [f32; 2]) {
+        self.content.push_str(&format!("{} {} l\n", Num(x), Num(y)));
+    }
+
+    fn curve_to(&mut self, control_a: [f32; 2], control_b: [f32; 2], to: [f32; 2]) {
+        self.content.push_str(&format!(
+            "{} {} {} {} {} {} c\n",
+            Num(control_a[0]),
+            Num(control_a[1]),
+            Num(control_b[0]),
+            Num(control_b[1]),
+            Num(to[0]),
+            Num(to[1])
+        ));
+    }
+
+    /// PDF only has cubic curves, so raise a quadratic curve starting at `from` to a cubic one.
+    fn quad_to(&mut self, from: [f32; 2], control: [f32; 2], to: [f32; 2]) {
+        let lerp = |a: [f32; 2], b: [f32; 2]| {
+            [
+                a[0] + (b[0] - a[0]) * 2. / 3.,
+                a[1] + (b[1] - a[1]) * 2. / 3.,
+            ]
+        };
+        self.curve_to(lerp(from, control), lerp(to, control), to);
+    }
+
+    /// Builds a rectangle with the given top left, top right, bottom right and bottom left
+    /// corner radii.
+    fn rounded_rect(&mut self, [x, y, width, height]: [f32; 4], radii: [f32; 4]) {
+        let max_radius = width.min(height) / 2.;
+        let [top_left, top_right, bottom_right, bottom_left] =
+            radii.map(|radius| radius.clamp(0., max_radius));
+        let right = x + width;
+        let bottom = y + height;
+
+        self.move_to([x + top_left, y]);
+        self.line_to([right - top_right, y]);
+        if top_right > 0. {
+            self.curve_to(
+                [right - top_right * (1. - KAPPA), y],
+                [right, y + top_right * (1. - KAPPA)],
+                [right, y + top_right],
+            );
+        }
+        self.line_to([right, bottom - bottom_right]);
+        if bottom_right > 0. {
+            self.curve_to(
+                [right, bottom - bottom_right * (1. - KAPPA)],
+                [right - bottom_right * (1. - KAPPA), bottom],
+                [right - bottom_right, bottom],
+            );
+        }
+        self.line_to([x + bottom_left, bottom]);
+        if bottom_left > 0. {
+            self.curve_to(
+                [x + bottom_left * (1. - KAPPA), bottom],
+                [x, bottom - bottom_left * (1. - KAPPA)],
+                [x, bottom - bottom_left],
+            );
+        }
+        self.line_to([x, y + top_left]);
+        if top_left > 0. {
+            self.curve_to(
+                [x, y + top_left * (1. - KAPPA)],
+                [x + top_left * (1. - KAPPA), y],
+                [x + top_left, y],
+            );
+        }
+        self.content.push_str("h\n");
+    }
+
+    /// Adds an image with the given RGB pixels and alpha channel, and returns its name.
+    fn add_image(&mut self, size: Size<DevicePixels>, rgb: Vec<u8>, alpha: Vec<u8>) -> String {
+        let (width, height) = (size.width.0, size.height.0);
+        let mask = self.document.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {width} /Height {height} \
+                /ColorSpace /DeviceGray /BitsPerComponent 8"
+            ),
+            &alpha,
+        );
+        let image = self.document.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {width} /Height {height} \
+                /ColorSpace /DeviceRGB /BitsPerComponent 8 /SMask {mask} 0 R"
+            ),
+            &rgb,
+        );
+        let name = format!("Im{}", self.images.len() + 1);
+        self.images.push((name.clone(), image));
+        name
+    }
+
+    fn draw_image(&mut self, name: &str, [x, y, width, height]: [f32; 4]) {
+        // Images fill the unit square upwards, so flip them back into our y-down space.
+        self.content.push_str(&format!(
+            "q\n{} 0 0 {} {} {} cm\n/{name} Do\nQ\n",
+            Num(width),
+            Num(-height),
+            Num(x),
+            Num(y + height)
+        ));
+    }
+
+    fn resources(&self) -> String {
+        let mut resources = String::from("<< /ExtGState <<");
+        for (alpha, name) in &self.alpha_states {
+            let alpha = Num(f32::from_bits(*alpha));
+            resources.push_str(&format!(" /{name} << /ca {alpha} /CA {alpha} >>"));
+        }
+        resources.push_str(" >> /Shading <<");
+        for (name, id) in &self.shadings {
+            resources.push_str(&format!(" /{name} {id} 0 R"));
+        }
+        resources.push_str(" >> /XObject <<");
+        for (name, id) in &self.images {
+            resources.push_str(&format!(" /{name} {id} 0 R"));
+        }
+        resources.push_str(" >> >>");
+        resources
+    }
+
+    /// Converts bounds to `[x, y, width, height]` in logical pixels.
+    fn rect(&self, bounds: Bounds<ScaledPixels>) -> [f32; 4] {
+        [
+            bounds.origin.x.0,
+            bounds.origin.y.0,
+            bounds.size.width.0,
+            bounds.size.height.0,
+        ]
+        .map(|value| value / self.scale_factor)
+    }
+
+    fn corners(&self, corners: &Corners<ScaledPixels>) -> [f32; 4] {
+        [
+            corners.top_left.0,
+            corners.top_right.0,
+            corners.bottom_right.0,
+            corners.bottom_left.0,
+        ]
+        .map(|value| value / self.scale_factor)
+    }
+
+    fn edges(&self, edges: &Edges<ScaledPixels>) -> [f32; 4] {
+        [edges.top.0, edges.right.0, edges.bottom.0, edges.left.0]
+            .map(|value| value / self.scale_factor)
+    }
+}
+
+/// The objects of a PDF document, numbered from 1.
+#[derive(Default)]
+struct PdfDocument {
+    objects: Vec<Option<Vec<u8>>>,
+}
+
+impl PdfDocument {
+    /// Reserves an object number, so that other objects can refer to it before it is written.
+    fn reserve(&mut self) -> usize {
+        self.objects.push(None);
+        self.objects.len()
+    }
+
+    fn set(&mut self, id: usize, object: impl Into<Vec<u8>>) {
+        self.objects[id - 1] = Some(object.into());
+    }
+
+    fn add(&mut self, object: impl Into<Vec<u8>>) -> usize {
+        let id = self.reserve();
+        self.set(id, object);
+        id
+    }
+
+    fn add_stream(&mut self, dictionary: &str, data: &[u8]) -> usize {
+        let mut object =
+            format!("<< {dictionary} /Length {} >>\nstream\n", data.len()).into_bytes();
+        object.extend_from_slice(data);
+        object.extend_from_slice(b"\nendstream");
+        self.add(object)
+    }
+
+    fn finish(self, catalog_id: usize) -> Vec<u8> {
+        // The comment with high bytes marks the file as binary.
+        let mut output = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (ix, object) in self.objects.into_iter().enumerate() {
+            offsets.push(output.len());
+            output.extend_from_slice(format!("{} 0 obj\n", ix + 1).as_bytes());
+            output.extend(object.unwrap_or_else(|| b"null".to_vec()));
+            output.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = output.len();
+        output.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).as_bytes(),
+        );
+        for offset in offsets.iter() {
+            output.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        output.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root {catalog_id} 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+                offsets.len() + 1
+            )
+            .as_bytes(),
+        );
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate_avoids_cutting_lines() {
+        assert_eq!(paginate(50., 100., &[]), vec![0. ..50.]);
+        assert_eq!(
+            paginate(250., 100., &[]),
+            vec![0. ..100., 100. ..200., 200. ..250.]
+        );
+
+        // The break at 100 would cut the line from 90 to 110, so it moves up to 90.
+        let lines = [70. ..90., 90. ..110., 110. ..130.];
+        assert_eq!(paginate(150., 100., &lines), vec![0. ..90., 90. ..150.]);
+
+        // A line taller than half a page gets cut rather than leaving the page mostly empty.
+        let lines = [20. ..120.];
+        assert_eq!(paginate(150., 100., &lines), vec![0. ..100., 100. ..150.]);
+    }
+
+    #[test]
+    fn test_document_cross_references() {
+        let mut document = PdfDocument::default();
+        let catalog = document.reserve();
+        let pages = document.add("<< /Type /Pages /Kids [] /Count 0 >>");
+        document.set(catalog, format!("<< /Type /Catalog /Pages {pages} 0 R >>"));
+        let output = document.finish(catalog);
+        let text = String::from_utf8_lossy(&output);
+
+        let second_object = text.find("2 0 obj").unwrap();
+        assert!(
+            text.contains(&format!("{second_object:010} 00000 n \n")),
+            "{text}"
+        );
+        let xref = text.find("xref\n").unwrap();
+        assert!(
+            text.ends_with(&format!("startxref\n{xref}\n%%EOF\n")),
+            "{text}"
+        );
+    }
+}
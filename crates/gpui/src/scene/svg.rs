@@ -0,0 +1,703 @@
+use std::fmt;
+
+use collections::FxHashMap;
+use util::ResultExt as _;
+
+use crate::{
+    Background, BackgroundTag, Bounds, ContentMask, Corners, DevicePixels, Edges,
+    GlyphOutlineCommand, Hsla, Pixels, Point, Rgba, ScaledPixels, Size, SpriteSource, TextSystem,
+    TransformationMatrix,
+};
+
+use super::{
+    DrawOrder, MonochromeSprite, PaintOperation, Path, PolychromeSprite, Primitive, Quad, Scene,
+    Shadow, Underline,
+};
+
+/// Translates the primitives of a finished [`Scene`] that intersect `bounds` into an SVG document.
+///
+/// Sprites only reference atlas tiles, so glyphs and bitmaps are reconstructed from
+/// `sprite_sources`, which maps paint operation indices to what was painted there.
+pub(crate) fn scene_to_svg(
+    scene: &Scene,
+    sprite_sources: &FxHashMap<usize, SpriteSource>,
+    text_system: &TextSystem,
+    scale_factor: f32,
+    bounds: Bounds<Pixels>,
+) -> String {
+    let export_bounds = bounds.scale(scale_factor);
+    let mut primitives = scene
+        .paint_operations
+        .iter()
+        .enumerate()
+        .filter_map(|(ix, operation)| match operation {
+            PaintOperation::Primitive(primitive) => Some((ix, primitive)),
+            PaintOperation::StartLayer(_) | PaintOperation::EndLayer => None,
+        })
+        .filter(|(_, primitive)| primitive.bounds().intersects(&export_bounds))
+        .collect::<Vec<_>>();
+    // Match the order in which the renderers draw batches of primitives.
+    primitives.sort_by_key(|(_, primitive)| draw_order(primitive));
+
+    let mut writer = SvgWriter {
+        scale_factor,
+        sprite_sources,
+        text_system,
+        defs: String::new(),
+        body: String::new(),
+        clip_paths: FxHashMap::default(),
+        next_id: 0,
+    };
+    for (operation_ix, primitive) in primitives {
+        match primitive {
+            Primitive::Shadow(shadow) => writer.write_shadow(shadow),
+            Primitive::Quad(quad) => writer.write_quad(quad),
+            Primitive::Path(path) => writer.write_path(path),
+            Primitive::Underline(underline) => writer.write_underline(underline),
+            Primitive::MonochromeSprite(sprite) => {
+                writer.write_monochrome_sprite(operation_ix, sprite)
+            }
+            Primitive::PolychromeSprite(sprite) => {
+                writer.write_polychrome_sprite(operation_ix, sprite)
+            }
+            // Surfaces are video frames owned by the compositor.
+            Primitive::Surface(_) => {}
+        }
+    }
+
+    let x = Num(bounds.origin.x.0);
+    let y = Num(bounds.origin.y.0);
+    let width = Num(bounds.size.width.0);
+    let height = Num(bounds.size.height.0);
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+        width=\"{width}\" height=\"{height}\" viewBox=\"{x} {y} {width} {height}\">\n\
+        <defs>\n{}</defs>\n{}</svg>\n",
+        writer.defs, writer.body
+    )
+}
+
+struct SvgWriter<'a> {
+    scale_factor: f32,
+    sprite_sources: &'a FxHashMap<usize, SpriteSource>,
+    text_system: &'a TextSystem,
+    defs: String,
+    body: String,
+    /// Clip path ids, keyed by the bits of the content mask they clip to.
+    clip_paths: FxHashMap<[u32; 4], String>,
+    next_id: usize,
+}
+
+impl SvgWriter<'_> {
+    fn write_shadow(&mut self, shadow: &Shadow) {
+        let blur_radius = shadow.blur_radius.0 / self.scale_factor;
+        let visible_bounds = shadow
+            .bounds
+            .dilate(ScaledPixels(shadow.blur_radius.0 * 3.));
+        let clip = self.clip_attribute(visible_bounds, &shadow.content_mask);
+        let rect = self.rect(shadow.bounds);
+        let d = rounded_rect_path(rect, self.corners(&shadow.corner_radii));
+
+        let mut filter = String::new();
+        if blur_radius > 0. {
+            let id = self.next_id("shadow");
+            let [x, y, width, height] = self.rect(visible_bounds).map(Num);
+            self.defs.push_str(&format!(
+                "<filter id=\"{id}\" filterUnits=\"userSpaceOnUse\" x=\"{x}\" y=\"{y}\" \
+                width=\"{width}\" height=\"{height}\">\
+                <feGaussianBlur stdDeviation=\"{}\"/></filter>\n",
+                Num(blur_radius)
+            ));
+            filter = format!(" filter=\"url(#{id})\"");
+        }
+
+        self.body.push_str(&format!(
+            "<path d=\"{d}\"{}{filter}{clip}/>\n",
+            color_attributes("fill", shadow.color)
+        ));
+    }
+
+    fn write_quad(&mut self, quad: &Quad) {
+        let clip = self.clip_attribute(quad.bounds, &quad.content_mask);
+        let rect = self.rect(quad.bounds);
+        let [top_left, top_right, bottom_right, bottom_left] = self.corners(&quad.corner_radii);
+        let outer = rounded_rect_path(rect, [top_left, top_right, bottom_right, bottom_left]);
+
+        if let Some(fill) = self.background_attributes(&quad.background, rect) {
+            self.body
+                .push_str(&format!("<path d=\"{outer}\"{fill}{clip}/>\n"));
+        }
+
+        let [top, right, bottom, left] = self.edges(&quad.border_widths);
+        if quad.border_color.a > 0. && (top > 0. || right > 0. || bottom > 0. || left > 0.) {
+            let [x, y, width, height] = rect;
+            let inner = rounded_rect_path(
+                [
+                    x + left,
+                    y + top,
+                    (width - left - right).max(0.),
+                    (height - top - bottom).max(0.),
+                ],
+                [
+                    (top_left - top.max(left)).max(0.),
+                    (top_right - top.max(right)).max(0.),
+                    (bottom_right - bottom.max(right)).max(0.),
+                    (bottom_left - bottom.max(left)).max(0.),
+                ],
+            );
+            self.body.push_str(&format!(
+                "<path d=\"{outer}{inner}\" fill-rule=\"evenodd\"{}{clip}/>\n",
+                color_attributes("fill", quad.border_color)
+            ));
+        }
+    }
+
+    fn write_path(&mut self, path: &Path<ScaledPixels>) {
+        let clip = self.clip_attribute(path.bounds, &path.content_mask);
+        let Some(fill) = self.background_attributes(&path.color, self.rect(path.bounds)) else {
+            return;
+        };
+
+        let mut d = String::new();
+        for triangle in path.vertices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|ix| {
+                let position = triangle[ix].xy_position;
+                [
+                    position.x.0 / self.scale_factor,
+                    position.y.0 / self.scale_factor,
+                ]
+            });
+            // Orient every triangle the same way, so that overlapping triangles are unioned
+            // under the nonzero fill rule, like the additive coverage of the path rasterizer.
+            let clockwise = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]) >= 0.;
+            let (a, c) = if clockwise { (a, c) } else { (c, a) };
+            // Curve triangles fill the area between the chord and a quadratic curve,
+            // and use the middle vertex as the control point.
+            let is_curve = triangle[1].st_position.x == 0.5;
+            let command = if is_curve { 'Q' } else { 'L' };
+            d.push_str(&format!(
+                "M{} {}{command}{} {} {} {}Z",
+                Num(a[0]),
+                Num(a[1]),
+                Num(b[0]),
+                Num(b[1]),
+                Num(c[0]),
+                Num(c[1])
+            ));
+        }
+
+        self.body
+            .push_str(&format!("<path d=\"{d}\"{fill}{clip}/>\n"));
+    }
+
+    fn write_underline(&mut self, underline: &Underline) {
+        let clip = self.clip_attribute(underline.bounds, &underline.content_mask);
+        let [x, y, width, height] = self.rect(underline.bounds);
+        if !underline.wavy {
+            self.body.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}{clip}/>\n",
+                Num(x),
+                Num(y),
+                Num(width),
+                Num(height),
+                color_attributes("fill", underline.color)
+            ));
+            return;
+        }
+
+        // Sample the same sine wave the shaders draw and stroke it.
+        let frequency = std::f32::consts::PI * underline.thickness.0;
+        let amplitude = 1. / (4. * underline.thickness.0);
+        let samples = ((width / height.max(1.)) * 8.).ceil().max(1.) as usize;
+        let mut d = String::new();
+        for sample in 0..=samples {
+            let dx = width * sample as f32 / samples as f32;
+            let dy = height * (0.5 + (dx / height * frequency).sin() * amplitude);
+            let command = if sample == 0 { 'M' } else { 'L' };
+            d.push_str(&format!("{command}{} {}", Num(x + dx), Num(y + dy)));
+        }
+        self.body.push_str(&format!(
+            "<path d=\"{d}\" fill=\"none\"{} stroke-width=\"{}\"{clip}/>\n",
+            color_attributes("stroke", underline.color),
+            Num(underline.thickness.0 / self.scale_factor)
+        ));
+    }
+
+    fn write_monochrome_sprite(&mut self, operation_ix: usize, sprite: &MonochromeSprite) {
+        let clip = self.clip_attribute(sprite.bounds, &sprite.content_mask);
+        let element = match self.sprite_sources.get(&operation_ix) {
+            Some(SpriteSource::Glyph {
+                font_id,
+                glyph_id,
+                font_size,
+                origin,
+            }) => {
+                let Some(outline) = self
+                    .text_system
+                    .glyph_outline(*font_id, *glyph_id, *font_size)
+                    .log_err()
+                else {
+                    return;
+                };
+                format!(
+                    "<path transform=\"translate({} {})\" d=\"{}\"{}/>",
+                    Num(origin.x.0),
+                    Num(origin.y.0),
+                    outline_path(&outline),
+                    color_attributes("fill", sprite.color)
+                )
+            }
+            Some(SpriteSource::Bitmap { size, bytes }) => {
+                let color = Rgba::from(sprite.color);
+                let rgba = bytes
+                    .iter()
+                    .flat_map(|alpha| {
+                        [color.r, color.g, color.b, color.a * *alpha as f32 / 255.]
+                            .map(|channel| (channel * 255.).round() as u8)
+                    })
+                    .collect();
+                let Some(image) = self.image_element(sprite.bounds, *size, rgba, "") else {
+                    return;
+                };
+                format!(
+                    "<g{}>{image}</g>",
+                    transform_attribute(&sprite.transformation, self.scale_factor)
+                )
+            }
+            // The sprite was painted before the export started recording sprite sources.
+            None => return,
+        };
+        self.body.push_str(&format!("<g{clip}>{element}</g>\n"));
+    }
+
+    fn write_polychrome_sprite(&mut self, operation_ix: usize, sprite: &PolychromeSprite) {
+        let clip = self.clip_attribute(sprite.bounds, &sprite.content_mask);
+        let sprite_sources = self.sprite_sources;
+        let Some(SpriteSource::Bitmap { size, bytes }) = sprite_sources.get(&operation_ix) else {
+            return;
+        };
+
+        let rgba = bytes
+            .chunks_exact(4)
+            .flat_map(|bgra| {
+                let [b, g, r, a] = [bgra[0], bgra[1], bgra[2], bgra[3]];
+                if sprite.grayscale {
+                    let luminance =
+                        (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32).round() as u8;
+                    [luminance, luminance, luminance, a]
+                } else {
+                    [r, g, b, a]
+                }
+            })
+            .collect();
+
+        let rect = self.rect(sprite.bounds);
+        let radii = self.corners(&sprite.corner_radii);
+        let mut attributes = String::new();
+        if radii.iter().any(|radius| *radius > 0.) {
+            let id = self.next_id("corners");
+            self.defs.push_str(&format!(
+                "<clipPath id=\"{id}\"><path d=\"{}\"/></clipPath>\n",
+                rounded_rect_path(rect, radii)
+            ));
+            attributes.push_str(&format!(" clip-path=\"url(#{id})\""));
+        }
+        if sprite.opacity < 1. {
+            attributes.push_str(&format!(" opacity=\"{}\"", Num(sprite.opacity)));
+        }
+
+        if let Some(image) = self.image_element(sprite.bounds, *size, rgba, &attributes) {
+            self.body.push_str(&format!("<g{clip}>{image}</g>\n"));
+        }
+    }
+
+    /// Returns fill attributes for a background, or `None` if it is invisible.
+    fn background_attributes(&mut self, background: &Background, rect: [f32; 4]) -> Option<String> {
+        match background.tag {
+            BackgroundTag::Solid => {
+                (background.solid.a > 0.).then(|| color_attributes("fill", background.solid))
+            }
+            BackgroundTag::LinearGradient => {
+                let [x, y, width, height] = rect;
+                // Mirror `gradient_color` in the shaders. SVG always interpolates in sRGB,
+                // so Oklab gradients come out slightly different.
+                let radians =
+                    (background.gradient_angle_or_pattern_height % 360. - 90.).to_radians();
+                let mut direction = [radians.cos(), radians.sin()];
+                if width > height {
+                    direction[1] *= height / width;
+                } else {
+                    direction[0] *= width / height;
+                }
+                let length = direction[0].hypot(direction[1]);
+                let direction = direction.map(|component| component / length);
+                let span = if direction[0].abs() > direction[1].abs() {
+                    width
+                } else {
+                    height
+                };
+                let center = [x + width / 2., y + height / 2.];
+                let start = [
+                    center[0] - direction[0] * span / 2.,
+                    center[1] - direction[1] * span / 2.,
+                ];
+                let end = [
+                    center[0] + direction[0] * span / 2.,
+                    center[1] + direction[1] * span / 2.,
+                ];
+
+                let id = self.next_id("gradient");
+                let mut gradient = format!(
+                    "<linearGradient id=\"{id}\" gradientUnits=\"userSpaceOnUse\" \
+                    x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">",
+                    Num(start[0]),
+                    Num(start[1]),
+                    Num(end[0]),
+                    Num(end[1])
+                );
+                for stop in background.colors {
+                    gradient.push_str(&format!(
+                        "<stop offset=\"{}\"{}/>",
+                        Num(stop.percentage.clamp(0., 1.)),
+                        color_attributes("stop-color", stop.color)
+                    ));
+                }
+                gradient.push_str("</linearGradient>\n");
+                self.defs.push_str(&gradient);
+                Some(format!(" fill=\"url(#{id})\""))
+            }
+            BackgroundTag::PatternSlash => {
+                let [x, y, ..] = rect;
+                // Stripes half a period wide, perpendicular to the diagonal.
+                let period = background.gradient_angle_or_pattern_height / self.scale_factor
+                    * std::f32::consts::FRAC_1_SQRT_2;
+                let fill = color_attributes("fill", background.solid);
+                let id = self.next_id("pattern");
+                self.defs.push_str(&format!(
+                    "<pattern id=\"{id}\" patternUnits=\"userSpaceOnUse\" width=\"{period}\" \
+                    height=\"{period}\" patternTransform=\"translate({} {}) rotate(45)\">\
+                    <rect width=\"{quarter}\" height=\"{period}\"{fill}/>\
+                    <rect x=\"{three_quarters}\" width=\"{quarter}\" height=\"{period}\"{fill}/>\
+                    </pattern>\n",
+                    Num(x),
+                    Num(y),
+                    period = Num(period),
+                    quarter = Num(period / 4.),
+                    three_quarters = Num(period * 0.75),
+                ));
+                Some(format!(" fill=\"url(#{id})\""))
+            }
+        }
+    }
+
+    /// Returns a `clip-path` attribute for the content mask, unless it doesn't clip `bounds`.
+    fn clip_attribute(
+        &mut self,
+        bounds: Bounds<ScaledPixels>,
+        content_mask: &ContentMask<ScaledPixels>,
+    ) -> String {
+        if content_mask.bounds.intersect(&bounds) == bounds {
+            return String::new();
+        }
+
+        let rect = self.rect(content_mask.bounds);
+        let key = rect.map(f32::to_bits);
+        let id = match self.clip_paths.get(&key) {
+            Some(id) => id.clone(),
+            None => {
+                let id = self.next_id("clip");
+                let [x, y, width, height] = rect.map(Num);
+                self.defs.push_str(&format!(
+                    "<clipPath id=\"{id}\"><rect x=\"{x}\" y=\"{y}\" width=\"{width}\" \
+                    height=\"{height}\"/></clipPath>\n"
+                ));
+                self.clip_paths.insert(key, id.clone());
+                id
+            }
+        };
+        format!(" clip-path=\"url(#{id})\"")
+    }
+
+    fn image_element(
+        &self,
+        bounds: Bounds<ScaledPixels>,
+        size: Size<DevicePixels>,
+        rgba: Vec<u8>,
+        attributes: &str,
+    ) -> Option<String> {
+        let image = image::RgbaImage::from_raw(size.width.0 as u32, size.height.0 as u32, rgba)?;
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .log_err()?;
+
+        let [x, y, width, height] = self.rect(bounds).map(Num);
+        Some(format!(
+            "<image x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" \
+            preserveAspectRatio=\"none\"{attributes} xlink:href=\"data:image/png;base64,{}\"/>",
+            base64(&png)
+        ))
+    }
+
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{prefix}{}", self.next_id)
+    }
+
+    /// Converts bounds to `[x, y, width, height]` in logical pixels.
+    fn rect(&self, bounds: Bounds<ScaledPixels>) -> [f32; 4] {
+        [
+            bounds.origin.x.0,
+            bounds.origin.y.0,
+            bounds.size.width.0,
+            bounds.size.height.0,
+        ]
+        .map(|value| value / self.scale_factor)
+    }
+
+    fn corners(&self, corners: &Corners<ScaledPixels>) -> [f32; 4] {
+        [
+            corners.top_left.0,
+            corners.top_right.0,
+            corners.bottom_right.0,
+            corners.bottom_left.0,
+        ]
+        .map(|value| value / self.scale_factor)
+    }
+
+    fn edges(&self, edges: &Edges<ScaledPixels>) -> [f32; 4] {
+        [edges.top.0, edges.right.0, edges.bottom.0, edges.left.0]
+            .map(|value| value / self.scale_factor)
+    }
+}
+
+pub(super) fn draw_order(primitive: &Primitive) -> (DrawOrder, u8) {
+    match primitive {
+        Primitive::Shadow(shadow) => (shadow.order, 0),
+        Primitive::Quad(quad) => (quad.order, 1),
+        Primitive::Path(path) => (path.order, 2),
+        Primitive::Underline(underline) => (underline.order, 3),
+        Primitive::MonochromeSprite(sprite) => (sprite.order, 4),
+        Primitive::PolychromeSprite(sprite) => (sprite.order, 5),
+        Primitive::Surface(surface) => (surface.order, 6),
+    }
+}
+
+/// Builds path data for a rectangle with the given top left, top right,
+/// bottom right and bottom left corner radii.
+fn rounded_rect_path([x, y, width, height]: [f32; 4], radii: [f32; 4]) -> String {
+    let max_radius = width.min(height) / 2.;
+    let [top_left, top_right, bottom_right, bottom_left] =
+        radii.map(|radius| radius.clamp(0., max_radius));
+    let arc = |radius: f32, x: f32, y: f32| {
+        if radius > 0. {
+            format!(
+                "A{} {} 0 0 1 {} {}",
+                Num(radius),
+                Num(radius),
+                Num(x),
+                Num(y)
+            )
+        } else {
+            String::new()
+        }
+    };
+
+    format!(
+        "M{} {}H{}{}V{}{}H{}{}V{}{}Z",
+        Num(x + top_left),
+        Num(y),
+        Num(x + width - top_right),
+        arc(top_right, x + width, y + top_right),
+        Num(y + height - bottom_right),
+        arc(bottom_right, x + width - bottom_right, y + height),
+        Num(x + bottom_left),
+        arc(bottom_left, x, y + height - bottom_left),
+        Num(y + top_left),
+        arc(top_left, x + top_left, y),
+    )
+}
+
+fn outline_path(outline: &[GlyphOutlineCommand]) -> String {
+    let point = |point: &Point<Pixels>| format!("{} {}", Num(point.x.0), Num(point.y.0));
+    let mut d = String::new();
+    for command in outline {
+        match command {
+            GlyphOutlineCommand::MoveTo(to) => d.push_str(&format!("M{}", point(to))),
+            GlyphOutlineCommand::LineTo(to) => d.push_str(&format!("L{}", point(to))),
+            GlyphOutlineCommand::QuadTo { control, to } => {
+                d.push_str(&format!("Q{} {}", point(control), point(to)))
+            }
+            GlyphOutlineCommand::CurveTo {
+                control_a,
+                control_b,
+                to,
+            } => d.push_str(&format!(
+                "C{} {} {}",
+                point(control_a),
+                point(control_b),
+                point(to)
+            )),
+            GlyphOutlineCommand::Close => d.push('Z'),
+        }
+    }
+    d
+}
+
+fn transform_attribute(transformation: &TransformationMatrix, scale_factor: f32) -> String {
+    if *transformation == TransformationMatrix::unit() {
+        return String::new();
+    }
+    // Transformations apply to device pixels, so only the translation needs to be scaled.
+    let [[a, b], [c, d]] = transformation.rotation_scale;
+    let [x, y] = transformation.translation;
+    format!(
+        " transform=\"matrix({} {} {} {} {} {})\"",
+        Num(a),
+        Num(c),
+        Num(b),
+        Num(d),
+        Num(x / scale_factor),
+        Num(y / scale_factor)
+    )
+}
+
+/// Formats a color as an attribute and, if it is translucent, a matching opacity attribute.
+fn color_attributes(attribute: &str, color: Hsla) -> String {
+    let color = Rgba::from(color);
+    let [r, g, b] = [color.r, color.g, color.b].map(|channel| (channel * 255.).round() as u8);
+    let mut attributes = format!(" {attribute}=\"#{r:02x}{g:02x}{b:02x}\"");
+    if color.a < 1. {
+        let opacity_attribute = match attribute {
+            "stop-color" => "stop-opacity",
+            "stroke" => "stroke-opacity",
+            _ => "fill-opacity",
+        };
+        attributes.push_str(&format!(" {opacity_attribute}=\"{}\"", Num(color.a)));
+    }
+    attributes
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (ix, byte)| {
+            group | (*byte as u32) << (16 - 8 * ix)
+        });
+        for ix in 0..4 {
+            if ix <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * ix)) as usize & 63] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Formats a coordinate with at most three decimals, without trailing zeros.
+pub(super) struct Num(pub(super) f32);
+
+impl fmt::Display for Num {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formatted = format!("{:.3}", self.0);
+        let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+        match trimmed {
+            "-0" | "" => f.write_str("0"),
+            trimmed => f.write_str(trimmed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hsla, linear_color_stop, linear_gradient, point, px, size, NoopTextSystem};
+    use std::sync::Arc;
+
+    fn scaled_bounds(x: f32, y: f32, width: f32, height: f32) -> Bounds<ScaledPixels> {
+        Bounds {
+            origin: point(ScaledPixels(x), ScaledPixels(y)),
+            size: size(ScaledPixels(width), ScaledPixels(height)),
+        }
+    }
+
+    fn export(scene: &mut Scene, scale_factor: f32) -> String {
+        scene.finish();
+        let text_system = TextSystem::new(Arc::new(NoopTextSystem::new()));
+        scene_to_svg(
+            scene,
+            &FxHashMap::default(),
+            &text_system,
+            scale_factor,
+            Bounds {
+                origin: point(px(0.), px(0.)),
+                size: size(px(100.), px(100.)),
+            },
+        )
+    }
+
+    #[test]
+    fn test_quads_become_paths_in_logical_pixels() {
+        let mut scene = Scene::default();
+        let bounds = scaled_bounds(20., 20., 40., 40.);
+        scene.insert_primitive(Quad {
+            bounds,
+            content_mask: ContentMask {
+                bounds: scaled_bounds(0., 0., 200., 40.),
+            },
+            background: hsla(0., 1., 0.5, 0.5).into(),
+            ..Default::default()
+        });
+        let svg = export(&mut scene, 2.);
+
+        assert!(svg.contains("viewBox=\"0 0 100 100\""), "{svg}");
+        assert!(
+            svg.contains("<path d=\"M10 10H30V30H10V10Z\" fill=\"#ff0000\" fill-opacity=\"0.5\""),
+            "{svg}"
+        );
+        assert!(
+            svg.contains(
+                "<clipPath id=\"clip1\"><rect x=\"0\" y=\"0\" width=\"100\" height=\"20\"/>"
+            ),
+            "{svg}"
+        );
+    }
+
+    #[test]
+    fn test_gradients_become_linear_gradients() {
+        let mut scene = Scene::default();
+        let bounds = scaled_bounds(0., 0., 100., 50.);
+        scene.insert_primitive(Quad {
+            bounds,
+            content_mask: ContentMask { bounds },
+            background: linear_gradient(
+                90.,
+                linear_color_stop(hsla(0., 0., 0., 1.), 0.),
+                linear_color_stop(hsla(0., 0., 1., 1.), 1.),
+            ),
+            ..Default::default()
+        });
+        let svg = export(&mut scene, 1.);
+
+        assert!(
+            svg.contains("x1=\"0\" y1=\"25\" x2=\"100\" y2=\"25\""),
+            "{svg}"
+        );
+        assert!(
+            svg.contains("<stop offset=\"1\" stop-color=\"#ffffff\"/>"),
+            "{svg}"
+        );
+        assert!(svg.contains("fill=\"url(#gradient1)\""), "{svg}");
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"h"), "aA==");
+        assert_eq!(base64(b"he"), "aGU=");
+        assert_eq!(base64(b"hello"), "aGVsbG8=");
+    }
+}
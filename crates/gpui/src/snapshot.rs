@@ -0,0 +1,322 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    point, px, AnyWindowHandle, App, AssetSource, Bounds, Entity, HeadlessClient, NullHttpClient,
+    Pixels, Render, Size, Window, WindowBackgroundAppearance, WindowBounds, WindowOptions,
+};
+
+/// Setting this environment variable to anything but `0` makes [`Snapshot::assert_matches`]
+/// overwrite the reference images instead of comparing against them.
+pub const UPDATE_SNAPSHOTS_ENV_VAR: &str = "GPUI_UPDATE_SNAPSHOTS";
+
+/// Renders a view offscreen and compares the result against a reference PNG.
+///
+/// ```ignore
+/// #[test]
+/// fn test_hello_world() {
+///     Snapshot::new("hello_world")
+///         .size(size(px(500.), px(500.)))
+///         .scale_factor(2.)
+///         .assert_matches(|_, cx| cx.new(|_| HelloWorld::new()));
+/// }
+/// ```
+///
+/// Reference images live in `snapshots/` next to the crate's `Cargo.toml` by default. When
+/// a comparison fails, the rendered image and an image highlighting the differing pixels are
+/// written next to the reference, with `.actual.png` and `.diff.png` extensions.
+pub struct Snapshot {
+    name: String,
+    size: Size<Pixels>,
+    scale_factor: f32,
+    tolerance: u8,
+    directory: Option<PathBuf>,
+    asset_source: Arc<dyn AssetSource>,
+}
+
+impl Snapshot {
+    /// Creates a snapshot with the given name, which is used as the file name of the reference image.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            size: crate::DEFAULT_WINDOW_SIZE,
+            scale_factor: 1.,
+            tolerance: 2,
+            directory: None,
+            asset_source: Arc::new(()),
+        }
+    }
+
+    /// Sets the viewport size the view is rendered at.
+    pub fn size(mut self, size: Size<Pixels>) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Sets the scale factor the view is rendered at.
+    pub fn scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Sets how much each color channel of a pixel may differ from the reference before
+    /// the pixel counts as different.
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the directory reference images are read from and written to.
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Sets the asset source used while rendering.
+    pub fn with_assets(mut self, asset_source: impl AssetSource) -> Self {
+        self.asset_source = Arc::new(asset_source);
+        self
+    }
+
+    /// Renders the view returned by `build_root_view` into an image.
+    pub fn render<V: 'static + Render>(
+        &self,
+        build_root_view: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
+    ) -> Result<RgbaImage> {
+        let platform = Rc::new(HeadlessClient::with_scale_factor(self.scale_factor));
+        let app = App::new_app(
+            platform,
+            self.asset_source.clone(),
+            Arc::new(NullHttpClient),
+        );
+        let mut cx = app.borrow_mut();
+        let image = self.render_in(&mut cx, build_root_view);
+        // Release the windows and run the quit observers, so that nothing outlives the app.
+        cx.shutdown();
+        image
+    }
+
+    fn render_in<V: 'static + Render>(
+        &self,
+        cx: &mut App,
+        build_root_view: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
+    ) -> Result<RgbaImage> {
+        let window = cx.open_window(
+            WindowOptions {
+                window_bounds: Some(WindowBounds::Windowed(Bounds {
+                    origin: point(px(0.), px(0.)),
+                    size: self.size,
+                })),
+                window_background: WindowBackgroundAppearance::Opaque,
+                ..Default::default()
+            },
+            build_root_view,
+        )?;
+
+        let window: AnyWindowHandle = window.into();
+        let image = window.update(cx, |_, window, cx| {
+            window.draw(cx);
+            window.present();
+            window.platform_window.read_frame()
+        })?;
+        window.update(cx, |_, window, _| window.remove_window())?;
+
+        image.ok_or_else(|| anyhow!("the headless window did not produce a frame"))
+    }
+
+    /// Renders the view returned by `build_root_view` and compares it against the reference image.
+    pub fn compare<V: 'static + Render>(
+        &self,
+        build_root_view: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
+    ) -> Result<()> {
+        let actual = self.render(build_root_view)?;
+        self.compare_image(&actual)
+    }
+
+    /// Like [`Snapshot::compare`], but panics if the rendered view doesn't match the reference image.
+    #[track_caller]
+    pub fn assert_matches<V: 'static + Render>(
+        &self,
+        build_root_view: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
+    ) {
+        if let Err(error) = self.compare(build_root_view) {
+            panic!("snapshot {:?} does not match: {error:#}", self.name);
+        }
+    }
+
+    /// Compares an already rendered image against the reference image.
+    pub fn compare_image(&self, actual: &RgbaImage) -> Result<()> {
+        let reference_path = self.path("png");
+        if should_update_snapshots() {
+            write_image(&reference_path, actual)?;
+            return Ok(());
+        }
+
+        if !reference_path.exists() {
+            write_image(&self.path("actual.png"), actual)?;
+            bail!(
+                "no reference image at {}, rerun with {UPDATE_SNAPSHOTS_ENV_VAR}=1 to create it",
+                reference_path.display()
+            );
+        }
+
+        let expected = image::open(&reference_path)
+            .with_context(|| format!("reading {}", reference_path.display()))?
+            .to_rgba8();
+        if expected.dimensions() != actual.dimensions() {
+            write_image(&self.path("actual.png"), actual)?;
+            bail!(
+                "expected a {}x{} image, but rendered {}x{}",
+                expected.width(),
+                expected.height(),
+                actual.width(),
+                actual.height()
+            );
+        }
+
+        let diff = diff_images(&expected, actual, self.tolerance);
+        if diff.mismatched_pixels > 0 {
+            write_image(&self.path("actual.png"), actual)?;
+            write_image(&self.path("diff.png"), &diff.image)?;
+            bail!(
+                "{} of {} pixels differ by more than {}, see {}",
+                diff.mismatched_pixels,
+                expected.width() * expected.height(),
+                self.tolerance,
+                self.path("diff.png").display()
+            );
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, extension: &str) -> PathBuf {
+        let directory = self.directory.clone().unwrap_or_else(|| {
+            env::var_os("CARGO_MANIFEST_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_default()
+                .join("snapshots")
+        });
+        directory.join(format!("{}.{extension}", self.name))
+    }
+}
+
+struct ImageDiff {
+    mismatched_pixels: u32,
+    /// The reference image washed out, with differing pixels drawn in red.
+    image: RgbaImage,
+}
+
+fn diff_images(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> ImageDiff {
+    let mut mismatched_pixels = 0;
+    let image = RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let expected = expected.get_pixel(x, y);
+        let actual = actual.get_pixel(x, y);
+        let matches = expected
+            .0
+            .iter()
+            .zip(actual.0.iter())
+            .all(|(expected, actual)| expected.abs_diff(*actual) <= tolerance);
+        if matches {
+            let [r, g, b, a] = expected.0.map(|channel| channel as f32);
+            let luminance = (0.2126 * r + 0.7152 * g + 0.0722 * b) * a / 255.;
+            let washed_out = 255 - ((255. - luminance) / 4.) as u8;
+            Rgba([washed_out, washed_out, washed_out, 255])
+        } else {
+            mismatched_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        }
+    });
+    ImageDiff {
+        mismatched_pixels,
+        image,
+    }
+}
+
+fn should_update_snapshots() -> bool {
+    env::var(UPDATE_SNAPSHOTS_ENV_VAR).map_or(false, |value| !value.is_empty() && value != "0")
+}
+
+fn write_image(path: &Path, image: &RgbaImage) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    image
+        .save(path)
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        div, red, size, white, AppContext as _, Context, IntoElement, ParentElement as _,
+        Styled as _,
+    };
+
+    struct Square;
+
+    impl Render for Square {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            div()
+                .size_full()
+                .p(px(8.))
+                .bg(white())
+                .child(div().size(px(16.)).bg(red()))
+        }
+    }
+
+    #[test]
+    fn test_render_matches_reference() {
+        // The reference is 64x64 pixels: a white background with a red square from 16 to 48.
+        Snapshot::new("red_square")
+            .size(size(px(32.), px(32.)))
+            .scale_factor(2.)
+            .assert_matches(|_, cx| cx.new(|_| Square));
+    }
+
+    #[test]
+    fn test_diff_within_tolerance() {
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 150, 200, 255]));
+        let actual = RgbaImage::from_pixel(4, 4, Rgba([102, 148, 200, 255]));
+
+        assert_eq!(diff_images(&expected, &actual, 2).mismatched_pixels, 0);
+        assert_eq!(diff_images(&expected, &actual, 1).mismatched_pixels, 16);
+    }
+
+    #[test]
+    fn test_diff_highlights_mismatched_pixels() {
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(1, 2, Rgba([0, 0, 0, 255]));
+
+        let diff = diff_images(&expected, &actual, 0);
+        assert_eq!(diff.mismatched_pixels, 1);
+        assert_eq!(diff.image.get_pixel(1, 2), &Rgba([255, 0, 0, 255]));
+        assert_eq!(diff.image.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_compare_image_against_reference() {
+        let directory = env::temp_dir().join(format!("gpui-snapshot-{}", std::process::id()));
+        let snapshot = Snapshot::new("square").directory(&directory);
+        let image = RgbaImage::from_pixel(8, 8, Rgba([10, 20, 30, 255]));
+        write_image(&snapshot.path("png"), &image).unwrap();
+
+        assert!(snapshot.compare_image(&image).is_ok());
+
+        let mut changed = image.clone();
+        changed.put_pixel(0, 0, Rgba([200, 20, 30, 255]));
+        assert!(snapshot.compare_image(&changed).is_err());
+        assert!(snapshot.path("diff.png").exists());
+
+        std::fs::remove_dir_all(directory).ok();
+    }
+}
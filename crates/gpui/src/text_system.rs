@@ -302,6 +302,17 @@ impl TextSystem {
         self.platform_text_system
             .rasterize_glyph(params, raster_bounds)
     }
+
+    /// Returns the outline of a glyph at the given font size, for exporting text to vector formats.
+    pub(crate) fn glyph_outline(
+        &self,
+        font_id: FontId,
+        glyph_id: GlyphId,
+        font_size: Pixels,
+    ) -> Result<Vec<GlyphOutlineCommand>> {
+        self.platform_text_system
+            .glyph_outline(font_id, glyph_id, font_size)
+    }
 }
 
 /// The GPUI text layout subsystem.
@@ -687,6 +698,24 @@ pub(crate) struct RenderGlyphParams {
 
 impl Eq for RenderGlyphParams {}
 
+/// A drawing command in the outline of a glyph. Points are relative to the
+/// glyph's origin on the baseline, with y growing downwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum GlyphOutlineCommand {
+    MoveTo(Point<Pixels>),
+    LineTo(Point<Pixels>),
+    QuadTo {
+        control: Point<Pixels>,
+        to: Point<Pixels>,
+    },
+    CurveTo {
+        control_a: Point<Pixels>,
+        control_b: Point<Pixels>,
+        to: Point<Pixels>,
+    },
+    Close,
+}
+
 impl Hash for RenderGlyphParams {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.font_id.0.hash(state);
@@ -1,19 +1,21 @@
 use crate::{
     point, prelude::*, px, size, transparent_black, Action, AnyDrag, AnyElement, AnyTooltip,
     AnyView, App, AppContext, Arena, Asset, AsyncWindowContext, AvailableSpace, Background, Bounds,
-    BoxShadow, Context, Corners, CursorStyle, Decorations, DevicePixels, DispatchActionListener,
-    DispatchNodeId, DispatchTree, DisplayId, Edges, Effect, Entity, EntityId, EventEmitter,
-    FileDropEvent, FontId, Global, GlobalElementId, GlyphId, GpuSpecs, Hsla, InputHandler, IsZero,
-    KeyBinding, KeyContext, KeyDownEvent, KeyEvent, Keystroke, KeystrokeEvent, LayoutId,
-    LineLayoutIndex, Modifiers, ModifiersChangedEvent, MonochromeSprite, MouseButton, MouseEvent,
-    MouseMoveEvent, MouseUpEvent, Path, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
-    PlatformInputHandler, PlatformWindow, Point, PolychromeSprite, PromptLevel, Quad, Render,
-    RenderGlyphParams, RenderImage, RenderImageParams, RenderSvgParams, Replay, ResizeEdge,
-    ScaledPixels, Scene, Shadow, SharedString, Size, StrikethroughStyle, Style, SubscriberSet,
-    Subscription, TaffyLayoutEngine, Task, TextStyle, TextStyleRefinement, TransformationMatrix,
-    Underline, UnderlineStyle, WindowAppearance, WindowBackgroundAppearance, WindowBounds,
-    WindowControls, WindowDecorations, WindowOptions, WindowParams, WindowTextSystem,
-    SMOOTH_SVG_SCALE_FACTOR, SUBPIXEL_VARIANTS,
+    BoxShadow, Context, Corners, CursorStyle, Damage, Decorations, DevicePixels,
+    DispatchActionListener, DispatchNodeId, DispatchTree, DisplayId, Edges, Effect, Entity,
+    EntityId, EventEmitter, ExternalDrag, ExternalDragEvent, FileDropEvent, FontId, Global,
+    GlobalElementId, GlyphId, GpuSpecs, Hsla, InputHandler, IsZero, KeyBinding, KeyContext,
+    KeyDownEvent, KeyEvent, Keystroke, KeystrokeEvent, LayoutId, LineLayoutIndex, Modifiers,
+    ModifiersChangedEvent, MonochromeSprite, MouseButton, MouseDownEvent, MouseEvent,
+    MouseExitEvent, MouseMoveEvent, MouseUpEvent, PageSetup, Path, Pixels, PlatformAtlas,
+    PlatformDisplay, PlatformInput, PlatformInputHandler, PlatformWindow, Point, PointerConstraint,
+    PolychromeSprite, PromptLevel, Quad, Render, RenderGlyphParams, RenderImage, RenderImageParams,
+    RenderSvgParams, Replay, ResizeEdge, ScaledPixels, Scene, SceneDump, Shadow, SharedString,
+    Size, StrikethroughStyle, Style, StylusEvent, StylusPhase, SubscriberSet, Subscription,
+    TaffyLayoutEngine, Task, TextStyle, TextStyleRefinement, TouchEvent, TouchPhase,
+    TransformationMatrix, Underline, UnderlineStyle, WindowAppearance, WindowBackgroundAppearance,
+    WindowBounds, WindowControls, WindowDecorations, WindowIcon, WindowKind, WindowOptions,
+    WindowParams, WindowTextSystem, SMOOTH_SVG_SCALE_FACTOR, SUBPIXEL_VARIANTS,
 };
 use anyhow::{anyhow, Context as _, Result};
 use collections::{FxHashMap, FxHashSet};
@@ -55,6 +57,14 @@ pub use prompts::*;
 
 pub(crate) const DEFAULT_WINDOW_SIZE: Size<Pixels> = size(px(1024.), px(700.));
 
+/// How far a touch can move from where it started and still be treated as a tap.
+const TAP_SLOP: f64 = 8.;
+
+/// How soon and how close to the previous one the stylus has to touch the screen again to be
+/// counted as a double click, matching the Linux platforms' mouse clicks.
+const STYLUS_DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+const STYLUS_DOUBLE_CLICK_DISTANCE: Pixels = px(5.);
+
 /// Represents the two different phases when dispatching events.
 #[derive(Default, Copy, Clone, Debug, Eq, PartialEq)]
 pub enum DispatchPhase {
@@ -476,6 +486,15 @@ pub(crate) struct TooltipRequest {
     tooltip: AnyTooltip,
 }
 
+/// The root view of the window that renders the icon of an [`ExternalDrag`].
+struct ExternalDragIcon(AnyView);
+
+impl Render for ExternalDragIcon {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        self.0.clone()
+    }
+}
+
 pub(crate) struct DeferredDraw {
     current_view: EntityId,
     priority: usize,
@@ -488,6 +507,36 @@ pub(crate) struct DeferredDraw {
     paint_range: Range<PaintIndex>,
 }
 
+/// What a sprite in the scene was painted from. Sprites only reference atlas tiles, so this
+/// is recorded while a frame is drawn for export to a vector format.
+#[derive(Clone)]
+pub(crate) enum SpriteSource {
+    /// A glyph, whose outline can be drawn at any resolution.
+    Glyph {
+        font_id: FontId,
+        glyph_id: GlyphId,
+        font_size: Pixels,
+        /// The origin of the glyph on the baseline.
+        origin: Point<Pixels>,
+    },
+    /// A bitmap, with one alpha byte per pixel for monochrome sprites and BGRA pixels for
+    /// polychrome sprites.
+    Bitmap {
+        size: Size<DevicePixels>,
+        bytes: Vec<u8>,
+    },
+}
+
+/// The areas of the window that accept pointer input.
+#[derive(Default)]
+enum InputRegion {
+    #[default]
+    Window,
+    Bounds,
+    /// Follows the opaque hitboxes of the last frame.
+    Hitboxes(Vec<Bounds<Pixels>>),
+}
+
 pub(crate) struct Frame {
     pub(crate) focus: Option<FocusId>,
     pub(crate) window_active: bool,
@@ -632,10 +681,16 @@ pub struct Window {
     mouse_position: Point<Pixels>,
     mouse_hit_test: HitTest,
     modifiers: Modifiers,
+    active_touches: FxHashSet<u64>,
+    pending_tap: Option<PendingTap>,
+    stylus_click: StylusClickState,
     scale_factor: f32,
     pub(crate) bounds_observers: SubscriberSet<(), AnyObserver>,
     appearance: WindowAppearance,
     pub(crate) appearance_observers: SubscriberSet<(), AnyObserver>,
+    input_region: InputRegion,
+    keyboard_shortcuts_inhibited: bool,
+    pub(crate) keyboard_shortcuts_inhibit_observers: SubscriberSet<(), AnyObserver>,
     active: Rc<Cell<bool>>,
     hovered: Rc<Cell<bool>>,
     pub(crate) needs_present: Rc<Cell<bool>>,
@@ -648,6 +703,11 @@ pub struct Window {
     pending_modifier: ModifierState,
     pub(crate) pending_input_observers: SubscriberSet<(), AnyObserver>,
     prompt: Option<RenderablePromptHandle>,
+    /// What each sprite of the next frame was painted from, keyed by paint operation index.
+    /// Only recorded while drawing a frame for export.
+    sprite_sources: Option<FxHashMap<usize, SpriteSource>>,
+    /// The regions that changed since the platform window last presented a frame.
+    pending_damage: Damage,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -664,6 +724,36 @@ pub(crate) enum DrawPhase {
     Focus,
 }
 
+/// A touch that will be turned into a click if it ends before moving too far.
+#[derive(Clone, Copy, Debug)]
+struct PendingTap {
+    id: u64,
+    position: Point<Pixels>,
+}
+
+/// Counts the clicks of a stylus that's emulating the mouse.
+#[derive(Default, Debug)]
+struct StylusClickState {
+    last_down: Option<(Instant, Point<Pixels>)>,
+    count: usize,
+}
+
+impl StylusClickState {
+    /// Registers the stylus touching the screen at `position`, and returns the click count.
+    fn down(&mut self, position: Point<Pixels>) -> usize {
+        let now = Instant::now();
+        let is_repeated_click = self.last_down.is_some_and(|(time, last_position)| {
+            let distance = position - last_position;
+            now - time < STYLUS_DOUBLE_CLICK_INTERVAL
+                && distance.x.abs() <= STYLUS_DOUBLE_CLICK_DISTANCE
+                && distance.y.abs() <= STYLUS_DOUBLE_CLICK_DISTANCE
+        });
+        self.count = if is_repeated_click { self.count + 1 } else { 1 };
+        self.last_down = Some((now, position));
+        self.count
+    }
+}
+
 #[derive(Default, Debug)]
 struct PendingInput {
     keystrokes: SmallVec<[Keystroke; 1]>,
@@ -712,10 +802,12 @@ impl Window {
             focus,
             show,
             kind,
+            layer_shell,
             is_movable,
             display_id,
             window_background,
             app_id,
+            icon,
             window_min_size,
             window_decorations,
         } = options;
@@ -729,6 +821,7 @@ impl Window {
                 bounds,
                 titlebar,
                 kind,
+                layer_shell,
                 is_movable,
                 focus,
                 show,
@@ -841,6 +934,20 @@ impl Window {
                     .log_err();
             }
         }));
+        platform_window.on_keyboard_shortcuts_inhibit_change(Box::new({
+            let mut cx = cx.to_async();
+            move |inhibited| {
+                handle
+                    .update(&mut cx, |_, window, cx| {
+                        window.keyboard_shortcuts_inhibited = inhibited;
+                        window
+                            .keyboard_shortcuts_inhibit_observers
+                            .clone()
+                            .retain(&(), |callback| callback(window, cx));
+                    })
+                    .log_err();
+            }
+        }));
         platform_window.on_active_status_change(Box::new({
             let mut cx = cx.to_async();
             move |active| {
@@ -880,6 +987,9 @@ impl Window {
         if let Some(app_id) = app_id {
             platform_window.set_app_id(&app_id);
         }
+        if let Some(icon) = icon {
+            platform_window.set_icon(Some(&icon));
+        }
 
         platform_window.map_window().unwrap();
 
@@ -916,10 +1026,16 @@ impl Window {
             mouse_position,
             mouse_hit_test: HitTest::default(),
             modifiers,
+            active_touches: FxHashSet::default(),
+            pending_tap: None,
+            stylus_click: StylusClickState::default(),
             scale_factor,
             bounds_observers: SubscriberSet::new(),
             appearance,
             appearance_observers: SubscriberSet::new(),
+            input_region: InputRegion::default(),
+            keyboard_shortcuts_inhibited: false,
+            keyboard_shortcuts_inhibit_observers: SubscriberSet::new(),
             active,
             hovered,
             needs_present,
@@ -932,6 +1048,8 @@ impl Window {
             pending_modifier: ModifierState::default(),
             pending_input_observers: SubscriberSet::new(),
             prompt: None,
+            sprite_sources: None,
+            pending_damage: Damage::Full,
         })
     }
 
@@ -1303,6 +1421,7 @@ impl Window {
         self.scale_factor = self.platform_window.scale_factor();
         self.viewport_size = self.platform_window.content_size();
         self.display_id = self.platform_window.display().map(|display| display.id());
+        self.pending_damage = Damage::Full;
 
         self.refresh();
 
@@ -1339,6 +1458,140 @@ impl Window {
         self.viewport_size
     }
 
+    /// Returns a serializable copy of the most recently drawn frame, for debugging
+    /// rendering issues or comparing two frames with [`crate::diff_scene_dumps`].
+    pub fn dump_scene(&self) -> SceneDump {
+        SceneDump::new(&self.rendered_frame.scene)
+    }
+
+    /// Redraws the window and translates the frame into an SVG document, for
+    /// resolution-independent screenshots. Text is exported as glyph outlines, while
+    /// images, emoji and icons are embedded as PNGs.
+    ///
+    /// Pass `bounds` to only export part of the window, such as the bounds of a single element.
+    /// This must not be called while the window is being drawn.
+    pub fn export_svg(&mut self, bounds: Option<Bounds<Pixels>>, cx: &mut App) -> String {
+        let sprite_sources = self.draw_for_export(cx);
+        crate::scene_to_svg(
+            &self.rendered_frame.scene,
+            &sprite_sources,
+            self.text_system(),
+            self.scale_factor,
+            bounds.unwrap_or_else(|| Bounds {
+                origin: Point::default(),
+                size: self.viewport_size,
+            }),
+        )
+    }
+
+    /// Lays out `view` at the width of a page, splits it into pages and writes them as a PDF
+    /// document, for printing. Text is drawn from glyph outlines, while images, emoji and
+    /// icons are embedded as bitmaps.
+    ///
+    /// The view is drawn separately from the window's own frames, so it doesn't need to be
+    /// shown in the window. This must not be called while the window is being drawn.
+    pub fn export_pdf(
+        &mut self,
+        view: impl Into<AnyView>,
+        page_setup: &PageSetup,
+        cx: &mut App,
+    ) -> Vec<u8> {
+        let width = page_setup.content_size().width;
+        let (scene, height, sprite_sources) = self.draw_offscreen(view.into(), width, cx);
+        crate::scene_to_pdf(
+            &scene,
+            &sprite_sources,
+            self.text_system(),
+            self.scale_factor,
+            height,
+            page_setup,
+        )
+    }
+
+    /// Draws a view at the given width into a frame of its own, leaving the window's frames
+    /// untouched. Returns the finished scene, the height of the view, and what each sprite
+    /// was painted from.
+    fn draw_offscreen(
+        &mut self,
+        view: AnyView,
+        width: Pixels,
+        cx: &mut App,
+    ) -> (Scene, Pixels, FxHashMap<usize, SpriteSource>) {
+        let dispatch_tree = DispatchTree::new(cx.keymap.clone(), cx.actions.clone());
+        let mut frame = mem::replace(&mut self.next_frame, Frame::new(dispatch_tree));
+        let refreshing = mem::replace(&mut self.refreshing, true);
+        self.sprite_sources = Some(FxHashMap::default());
+
+        let view_size = {
+            self.invalidator.set_phase(DrawPhase::Prepaint);
+            let mut element = view.into_any();
+            let view_size = element.layout_as_root(
+                size(AvailableSpace::Definite(width), AvailableSpace::MinContent),
+                self,
+                cx,
+            );
+            element.prepaint_at(Point::default(), self, cx);
+            let mut sorted_deferred_draws =
+                (0..self.next_frame.deferred_draws.len()).collect::<SmallVec<[_; 8]>>();
+            sorted_deferred_draws.sort_by_key(|ix| self.next_frame.deferred_draws[*ix].priority);
+            self.prepaint_deferred_draws(&sorted_deferred_draws, cx);
+
+            self.invalidator.set_phase(DrawPhase::Paint);
+            element.paint(self, cx);
+            self.paint_deferred_draws(&sorted_deferred_draws, cx);
+            self.invalidator.set_phase(DrawPhase::None);
+            view_size
+        };
+
+        // The element arena is cleared once the frame is finished, so the element must be
+        // dropped by now.
+        self.finish_next_frame();
+        self.refreshing = refreshing;
+        mem::swap(&mut self.next_frame, &mut frame);
+        // Hand back the element state the view took over from the rendered frame.
+        self.rendered_frame
+            .element_states
+            .extend(frame.element_states.drain());
+        // Finishing the text system's frame dropped the rendered frame's line layouts, so
+        // the window's views can't reuse them on the next draw.
+        self.refresh();
+
+        let sprite_sources = self.sprite_sources.take().unwrap_or_default();
+        (
+            mem::take(&mut frame.scene),
+            view_size.height,
+            sprite_sources,
+        )
+    }
+
+    /// Redraws every view of the window, recording what each sprite was painted from so
+    /// the resulting frame can be translated into a vector format.
+    fn draw_for_export(&mut self, cx: &mut App) -> FxHashMap<usize, SpriteSource> {
+        self.sprite_sources = Some(FxHashMap::default());
+        self.refresh();
+        self.draw(cx);
+        self.sprite_sources.take().unwrap_or_default()
+    }
+
+    /// Records what the sprite painted at `operation_ix` came from, if a frame is being
+    /// drawn for export and the sprite wasn't clipped away.
+    fn record_sprite_source(
+        &mut self,
+        operation_ix: usize,
+        source: impl FnOnce(&Self) -> Result<SpriteSource>,
+    ) -> Result<()> {
+        if self.sprite_sources.is_none()
+            || self.next_frame.scene.paint_operations.len() <= operation_ix
+        {
+            return Ok(());
+        }
+        let source = source(self)?;
+        if let Some(sprite_sources) = self.sprite_sources.as_mut() {
+            sprite_sources.insert(operation_ix, source);
+        }
+        Ok(())
+    }
+
     /// Returns whether this window is focused by the operating system (receiving key events).
     pub fn is_window_active(&self) -> bool {
         self.active.get()
@@ -1376,6 +1629,138 @@ impl Window {
         self.platform_window.start_window_move()
     }
 
+    /// Locks the pointer in place while it's over this window, and hides it, so that it can be
+    /// moved indefinitely, e.g. to orbit a camera. Its movements are reported as
+    /// [`RelativeMouseMoveEvent`](crate::RelativeMouseMoveEvent)s instead. (Wayland)
+    ///
+    /// The pointer is released with [`Window::release_pointer`], or when the window loses focus.
+    pub fn lock_pointer(&self) {
+        self.platform_window
+            .set_pointer_constraint(Some(PointerConstraint::Locked));
+    }
+
+    /// Keeps the pointer within this window while it's over it. Its movements are also reported as
+    /// [`RelativeMouseMoveEvent`](crate::RelativeMouseMoveEvent)s, which continue when it reaches
+    /// the edge of the window. (Wayland)
+    ///
+    /// The pointer is released with [`Window::release_pointer`], or when the window loses focus.
+    pub fn confine_pointer(&self) {
+        self.platform_window
+            .set_pointer_constraint(Some(PointerConstraint::Confined));
+    }
+
+    /// Releases the pointer after [`Window::lock_pointer`] or [`Window::confine_pointer`]. (Wayland)
+    pub fn release_pointer(&self) {
+        self.platform_window.set_pointer_constraint(None);
+    }
+
+    /// Keeps the screen from dimming or locking while `inhibit` is true, e.g. while a video or a
+    /// presentation is playing. (Linux)
+    ///
+    /// On Wayland this only applies while the window is visible.
+    pub fn inhibit_idle(&self, inhibit: bool) {
+        self.platform_window.set_idle_inhibited(inhibit);
+    }
+
+    /// Only accepts mouse, touch and tablet input within the given areas of the window, letting it
+    /// reach the windows underneath everywhere else. An empty region makes the window entirely
+    /// click-through, e.g. for overlays drawn with a transparent background. (Wayland)
+    pub fn set_input_region(&mut self, region: impl IntoIterator<Item = Bounds<Pixels>>) {
+        let region = region.into_iter().collect::<Vec<_>>();
+        self.platform_window.set_input_region(Some(&region));
+        self.input_region = InputRegion::Bounds;
+    }
+
+    /// Only accepts mouse, touch and tablet input over hitboxes that block the mouse, as updated
+    /// every frame. Input over other areas reaches the windows underneath. (Wayland)
+    pub fn set_input_region_from_hitboxes(&mut self) {
+        if !matches!(self.input_region, InputRegion::Hitboxes(_)) {
+            let region = self.hitbox_input_region();
+            self.platform_window.set_input_region(Some(&region));
+            self.input_region = InputRegion::Hitboxes(region);
+        }
+    }
+
+    /// Accepts input anywhere in the window again, after [`Window::set_input_region`] or
+    /// [`Window::set_input_region_from_hitboxes`].
+    pub fn reset_input_region(&mut self) {
+        self.platform_window.set_input_region(None);
+        self.input_region = InputRegion::Window;
+    }
+
+    /// Asks the compositor to pass all key combinations to this window while it's focused,
+    /// including the ones it would usually handle itself, such as Super or Alt+Tab. (Wayland)
+    ///
+    /// The compositor may ask the user first, or revoke it at any time (e.g. through an escape
+    /// shortcut). Use [`Window::keyboard_shortcuts_inhibited`] and
+    /// [`Context::observe_keyboard_shortcuts_inhibit`] to find out whether it's in effect.
+    pub fn inhibit_keyboard_shortcuts(&self, inhibit: bool) {
+        self.platform_window
+            .set_keyboard_shortcuts_inhibited(inhibit);
+    }
+
+    /// Returns whether the compositor currently passes all key combinations to this window.
+    pub fn keyboard_shortcuts_inhibited(&self) -> bool {
+        self.keyboard_shortcuts_inhibited
+    }
+
+    /// Starts a drag that can leave the window and be dropped onto other applications. (Wayland)
+    ///
+    /// This has to be called while a mouse button is held down, typically once the mouse has moved
+    /// far enough from where it was pressed. Elements can use
+    /// [`StatefulInteractiveElement::on_external_drag`](crate::StatefulInteractiveElement::on_external_drag) for this.
+    pub fn start_external_drag(&mut self, mut drag: ExternalDrag, cx: &mut App) -> Result<()> {
+        let icon = drag.icon.take().and_then(|(size, view)| {
+            cx.open_window(
+                WindowOptions {
+                    window_bounds: Some(WindowBounds::Windowed(Bounds {
+                        origin: Point::default(),
+                        size,
+                    })),
+                    titlebar: None,
+                    focus: false,
+                    kind: WindowKind::DragIcon,
+                    is_movable: false,
+                    window_background: WindowBackgroundAppearance::Transparent,
+                    ..Default::default()
+                },
+                |_, cx| cx.new(|_| ExternalDragIcon(view)),
+            )
+            .log_err()
+            .map(AnyWindowHandle::from)
+        });
+
+        let handle = self.handle;
+        let mut listener = drag.listener.take();
+        let on_event = Box::new({
+            let mut cx = cx.to_async();
+            move |event: ExternalDragEvent| {
+                if let Some(listener) = listener.as_mut() {
+                    handle
+                        .update(&mut cx, |_, window, cx| listener(&event, window, cx))
+                        .log_err();
+                }
+                let ended = matches!(
+                    event,
+                    ExternalDragEvent::Finished { .. } | ExternalDragEvent::Cancelled
+                );
+                if let Some(icon) = icon.filter(|_| ended) {
+                    icon.update(&mut cx, |_, window, _| window.remove_window())
+                        .log_err();
+                }
+            }
+        });
+
+        let result = self.platform_window.start_drag(drag, icon, on_event);
+        if result.is_err() {
+            if let Some(icon) = icon {
+                icon.update(cx, |_, window, _| window.remove_window())
+                    .log_err();
+            }
+        }
+        result
+    }
+
     /// When using client side decorations, set this to the width of the invisible decorations (Wayland and X11)
     pub fn set_client_inset(&self, inset: Pixels) {
         self.platform_window.set_client_inset(inset);
@@ -1401,6 +1786,11 @@ impl Window {
         self.platform_window.set_app_id(app_id);
     }
 
+    /// Sets the window's icon, or reverts to the one the desktop environment picks when `None`.
+    pub fn set_icon(&mut self, icon: Option<WindowIcon>) {
+        self.platform_window.set_icon(icon.as_ref());
+    }
+
     /// Sets the window background appearance.
     pub fn set_background_appearance(&self, background_appearance: WindowBackgroundAppearance) {
         self.platform_window
@@ -1535,16 +1925,12 @@ impl Window {
                 .set_input_handler(input_handler.unwrap());
         }
 
-        self.layout_engine.as_mut().unwrap().clear();
-        self.text_system().finish_frame();
-        self.next_frame.finish(&mut self.rendered_frame);
-        ELEMENT_ARENA.with_borrow_mut(|element_arena| {
-            let percentage = (element_arena.len() as f32 / element_arena.capacity() as f32) * 100.;
-            if percentage >= 80. {
-                log::warn!("elevated element arena occupation: {}.", percentage);
-            }
-            element_arena.clear();
-        });
+        self.finish_next_frame();
+        let damage = self
+            .next_frame
+            .scene
+            .damage_since(&self.rendered_frame.scene);
+        self.pending_damage.union(damage);
 
         self.invalidator.set_phase(DrawPhase::Focus);
         let previous_focus_path = self.rendered_frame.focus_path();
@@ -1583,11 +1969,49 @@ impl Window {
         debug_assert!(self.rendered_entity_stack.is_empty());
         self.record_entities_accessed(cx);
         self.reset_cursor_style(cx);
+        self.update_input_region();
         self.refreshing = false;
         self.invalidator.set_phase(DrawPhase::None);
         self.needs_present.set(true);
     }
 
+    /// Finishes the frame painted into `next_frame`, taking over the element state it
+    /// accessed from the rendered frame, and releases what was only needed while drawing.
+    fn finish_next_frame(&mut self) {
+        self.layout_engine.as_mut().unwrap().clear();
+        self.text_system().finish_frame();
+        self.next_frame.finish(&mut self.rendered_frame);
+        ELEMENT_ARENA.with_borrow_mut(|element_arena| {
+            let percentage = (element_arena.len() as f32 / element_arena.capacity() as f32) * 100.;
+            if percentage >= 80. {
+                log::warn!("elevated element arena occupation: {}.", percentage);
+            }
+            element_arena.clear();
+        });
+    }
+
+    fn update_input_region(&mut self) {
+        let InputRegion::Hitboxes(current_region) = &self.input_region else {
+            return;
+        };
+        let region = self.hitbox_input_region();
+        if *current_region != region {
+            self.platform_window.set_input_region(Some(&region));
+            self.input_region = InputRegion::Hitboxes(region);
+        }
+    }
+
+    /// The visible parts of the opaque hitboxes of the last frame.
+    fn hitbox_input_region(&self) -> Vec<Bounds<Pixels>> {
+        self.rendered_frame
+            .hitboxes
+            .iter()
+            .filter(|hitbox| hitbox.opaque)
+            .map(|hitbox| hitbox.bounds.intersect(&hitbox.content_mask.bounds))
+            .filter(|bounds| !bounds.is_empty())
+            .collect()
+    }
+
     fn record_entities_accessed(&mut self, cx: &mut App) {
         let mut entities_ref = cx.entities.accessed_entities.borrow_mut();
         let mut entities = mem::take(entities_ref.deref_mut());
@@ -1612,8 +2036,10 @@ impl Window {
     }
 
     #[profiling::function]
-    fn present(&self) {
-        self.platform_window.draw(&self.rendered_frame.scene);
+    pub(crate) fn present(&mut self) {
+        let damage = mem::take(&mut self.pending_damage);
+        self.platform_window
+            .draw(&self.rendered_frame.scene, &damage);
         self.needs_present.set(false);
         profiling::finish_frame!();
     }
@@ -2472,6 +2898,7 @@ impl Window {
                 size: tile.bounds.size.map(Into::into),
             };
             let content_mask = self.content_mask().scale(scale_factor);
+            let operation_ix = self.next_frame.scene.paint_operations.len();
             self.next_frame.scene.insert_primitive(MonochromeSprite {
                 order: 0,
                 pad: 0,
@@ -2481,6 +2908,14 @@ impl Window {
                 tile,
                 transformation: TransformationMatrix::unit(),
             });
+            self.record_sprite_source(operation_ix, |_| {
+                Ok(SpriteSource::Glyph {
+                    font_id,
+                    glyph_id,
+                    font_size,
+                    origin,
+                })
+            })?;
         }
         Ok(())
     }
@@ -2531,6 +2966,7 @@ impl Window {
             let content_mask = self.content_mask().scale(scale_factor);
             let opacity = self.element_opacity();
 
+            let operation_ix = self.next_frame.scene.paint_operations.len();
             self.next_frame.scene.insert_primitive(PolychromeSprite {
                 order: 0,
                 pad: 0,
@@ -2541,6 +2977,10 @@ impl Window {
                 tile,
                 opacity,
             });
+            self.record_sprite_source(operation_ix, |window| {
+                let (size, bytes) = window.text_system().rasterize_glyph(&params)?;
+                Ok(SpriteSource::Bitmap { size, bytes })
+            })?;
         }
         Ok(())
     }
@@ -2581,6 +3021,7 @@ impl Window {
         };
         let content_mask = self.content_mask().scale(scale_factor);
 
+        let operation_ix = self.next_frame.scene.paint_operations.len();
         self.next_frame.scene.insert_primitive(MonochromeSprite {
             order: 0,
             pad: 0,
@@ -2592,6 +3033,16 @@ impl Window {
             tile,
             transformation,
         });
+        self.record_sprite_source(operation_ix, |_| {
+            let bytes = cx
+                .svg_renderer
+                .render(&params)?
+                .ok_or_else(|| anyhow!("failed to render {:?}", params.path))?;
+            Ok(SpriteSource::Bitmap {
+                size: params.size,
+                bytes,
+            })
+        })?;
 
         Ok(())
     }
@@ -2633,6 +3084,7 @@ impl Window {
         let corner_radii = corner_radii.scale(scale_factor);
         let opacity = self.element_opacity();
 
+        let operation_ix = self.next_frame.scene.paint_operations.len();
         self.next_frame.scene.insert_primitive(PolychromeSprite {
             order: 0,
             pad: 0,
@@ -2643,6 +3095,15 @@ impl Window {
             tile,
             opacity,
         });
+        self.record_sprite_source(operation_ix, |_| {
+            Ok(SpriteSource::Bitmap {
+                size: data.size(frame_index),
+                bytes: data
+                    .as_bytes(frame_index)
+                    .expect("It's the caller's job to pass a valid frame index")
+                    .to_vec(),
+            })
+        })?;
         Ok(())
     }
 
@@ -2977,7 +3438,7 @@ impl Window {
                 .iter()
                 .rev()
                 .find(|request| request.hitbox_id.is_hovered(self))
-                .map(|request| request.style)
+                .map(|request| request.style.clone())
                 .unwrap_or(CursorStyle::Arrow);
             cx.platform.set_cursor_style(style);
         }
@@ -3025,6 +3486,23 @@ impl Window {
             .unwrap_or_else(|| action.name().to_string())
     }
 
+    /// Queue a mouse or keyboard event on the platform window, as if it came from the
+    /// operating system. Unlike [`Window::dispatch_event`], the event is delivered on a later
+    /// turn of the event loop and typed text reaches the focused input handler.
+    ///
+    /// This is only supported by headless windows, other platforms ignore the event.
+    pub fn inject_input(&self, event: PlatformInput) {
+        self.platform_window.inject_input(event);
+    }
+
+    /// Queue a request to close the window, as if the user clicked its close button. The
+    /// callback given to [`Window::on_window_should_close`] can keep the window open.
+    ///
+    /// This is only supported by headless windows, other platforms ignore the request.
+    pub fn inject_close_request(&self) {
+        self.platform_window.inject_close_request();
+    }
+
     /// Dispatch a mouse or keyboard event on the window.
     #[profiling::function]
     pub fn dispatch_event(&mut self, event: PlatformInput, cx: &mut App) -> DispatchEventResult {
@@ -3065,6 +3543,37 @@ impl Window {
                 self.modifiers = scroll_wheel.modifiers;
                 PlatformInput::ScrollWheel(scroll_wheel)
             }
+            // Touches are hit tested like the mouse, at the position of each touch.
+            PlatformInput::Touch(touch) => {
+                self.mouse_position = touch.position;
+                self.modifiers = touch.modifiers;
+                PlatformInput::Touch(touch)
+            }
+            PlatformInput::Pinch(pinch) => {
+                self.mouse_position = pinch.position;
+                self.modifiers = pinch.modifiers;
+                PlatformInput::Pinch(pinch)
+            }
+            PlatformInput::Swipe(swipe) => {
+                self.mouse_position = swipe.position;
+                self.modifiers = swipe.modifiers;
+                PlatformInput::Swipe(swipe)
+            }
+            PlatformInput::Hold(hold) => {
+                self.mouse_position = hold.position;
+                self.modifiers = hold.modifiers;
+                PlatformInput::Hold(hold)
+            }
+            PlatformInput::RelativeMouseMove(relative_move) => {
+                self.mouse_position = relative_move.position;
+                self.modifiers = relative_move.modifiers;
+                PlatformInput::RelativeMouseMove(relative_move)
+            }
+            PlatformInput::Stylus(stylus) => {
+                self.mouse_position = stylus.position;
+                self.modifiers = stylus.modifiers;
+                PlatformInput::Stylus(stylus)
+            }
             // Translate dragging and dropping of external files from the operating system
             // to internal drag and drop events.
             PlatformInput::FileDrop(file_drop) => match file_drop {
@@ -3115,10 +3624,128 @@ impl Window {
             self.dispatch_key_event(any_key_event, cx);
         }
 
-        DispatchEventResult {
+        let result = DispatchEventResult {
             propagate: cx.propagate_event,
             default_prevented: self.default_prevented,
+        };
+
+        match &event {
+            PlatformInput::Touch(touch) => self.track_tap(touch, cx),
+            PlatformInput::Stylus(stylus) if !result.default_prevented => {
+                self.emulate_mouse_for_stylus(stylus, cx)
+            }
+            _ => {}
         }
+
+        result
+    }
+
+    /// Platforms stop emulating the mouse for a stylus once we listen to it directly,
+    /// so dispatch the stylus as the left mouse button ourselves.
+    fn emulate_mouse_for_stylus(&mut self, stylus: &StylusEvent, cx: &mut App) {
+        let event = match stylus.phase {
+            StylusPhase::ProximityIn | StylusPhase::Moved => {
+                PlatformInput::MouseMove(MouseMoveEvent {
+                    position: stylus.position,
+                    pressed_button: stylus.in_contact.then_some(MouseButton::Left),
+                    modifiers: stylus.modifiers,
+                })
+            }
+            StylusPhase::Down => PlatformInput::MouseDown(MouseDownEvent {
+                button: MouseButton::Left,
+                position: stylus.position,
+                modifiers: stylus.modifiers,
+                click_count: self.stylus_click.down(stylus.position),
+                first_mouse: false,
+            }),
+            StylusPhase::Up => PlatformInput::MouseUp(MouseUpEvent {
+                button: MouseButton::Left,
+                position: stylus.position,
+                modifiers: stylus.modifiers,
+                click_count: self.stylus_click.count,
+            }),
+            StylusPhase::ProximityOut => PlatformInput::MouseExited(MouseExitEvent {
+                position: stylus.position,
+                pressed_button: None,
+                modifiers: stylus.modifiers,
+            }),
+            StylusPhase::ButtonDown(_) | StylusPhase::ButtonUp(_) => return,
+        };
+        self.dispatch_event(event, cx);
+    }
+
+    /// Turns a tap, a single touch that ends close to where it started, into a left click,
+    /// so that elements which only listen to the mouse can be used on a touch screen.
+    /// Touch listeners can opt out of this by calling [`Window::prevent_default`].
+    fn track_tap(&mut self, touch: &TouchEvent, cx: &mut App) {
+        let is_pending_tap = self.pending_tap.is_some_and(|tap| tap.id == touch.id);
+        match touch.phase {
+            TouchPhase::Started => {
+                let is_first_touch = self.active_touches.is_empty();
+                self.active_touches.insert(touch.id);
+                // A second finger turns the touch into a gesture.
+                self.pending_tap =
+                    (is_first_touch && !self.default_prevented).then_some(PendingTap {
+                        id: touch.id,
+                        position: touch.position,
+                    });
+            }
+            TouchPhase::Moved => {
+                if is_pending_tap {
+                    let tap = self.pending_tap.unwrap();
+                    if self.default_prevented
+                        || (touch.position - tap.position).magnitude() > TAP_SLOP
+                    {
+                        self.pending_tap = None;
+                    }
+                }
+            }
+            TouchPhase::Ended => {
+                self.active_touches.remove(&touch.id);
+                if is_pending_tap {
+                    let tap = self.pending_tap.take().unwrap();
+                    if !self.default_prevented {
+                        self.dispatch_tap(tap, touch, cx);
+                    }
+                }
+            }
+            TouchPhase::Cancelled => {
+                self.active_touches.remove(&touch.id);
+                if is_pending_tap {
+                    self.pending_tap = None;
+                }
+            }
+        }
+    }
+
+    fn dispatch_tap(&mut self, tap: PendingTap, touch: &TouchEvent, cx: &mut App) {
+        self.dispatch_event(
+            PlatformInput::MouseMove(MouseMoveEvent {
+                position: tap.position,
+                pressed_button: None,
+                modifiers: touch.modifiers,
+            }),
+            cx,
+        );
+        self.dispatch_event(
+            PlatformInput::MouseDown(MouseDownEvent {
+                button: MouseButton::Left,
+                position: tap.position,
+                modifiers: touch.modifiers,
+                click_count: 1,
+                first_mouse: false,
+            }),
+            cx,
+        );
+        self.dispatch_event(
+            PlatformInput::MouseUp(MouseUpEvent {
+                button: MouseButton::Left,
+                position: touch.position,
+                modifiers: touch.modifiers,
+                click_count: 1,
+            }),
+            cx,
+        );
     }
 
     fn dispatch_mouse_event(&mut self, event: &dyn Any, cx: &mut App) {
@@ -3908,7 +4535,7 @@ unsafe impl<V> Send for WindowHandle<V> {}
 unsafe impl<V> Sync for WindowHandle<V> {}
 
 /// A handle to a window with any root view type, which can be downcast to a window with a specific root view type.
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct AnyWindowHandle {
     pub(crate) id: WindowId,
     state_type: TypeId,
@@ -4179,3 +4806,397 @@ pub fn outline(bounds: impl Into<Bounds<Pixels>>, border_color: impl Into<Hsla>)
         border_color: border_color.into(),
     }
 }
+
+#[cfg(all(test, any(target_os = "linux", target_os = "freebsd")))]
+mod tests {
+    use super::*;
+    use crate::{div, AppCell, HeadlessClient};
+
+    struct TapView {
+        events: Rc<RefCell<Vec<&'static str>>>,
+        prevent_default: bool,
+    }
+
+    impl Render for TapView {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            let prevent_default = self.prevent_default;
+            let end_events = self.events.clone();
+            let down_events = self.events.clone();
+            let up_events = self.events.clone();
+            div()
+                .size_full()
+                .on_touch_start(move |_, window, _| {
+                    if prevent_default {
+                        window.prevent_default();
+                    }
+                })
+                .on_touch_end(move |_, _, _| end_events.borrow_mut().push("end"))
+                .on_mouse_down(MouseButton::Left, move |_, _, _| {
+                    down_events.borrow_mut().push("down")
+                })
+                .on_mouse_up(MouseButton::Left, move |_, _, _| {
+                    up_events.borrow_mut().push("up")
+                })
+        }
+    }
+
+    fn open_window(
+        app: &Rc<AppCell>,
+        events: Rc<RefCell<Vec<&'static str>>>,
+        prevent_default: bool,
+    ) -> AnyWindowHandle {
+        let window: AnyWindowHandle = app
+            .borrow_mut()
+            .open_window(
+                WindowOptions {
+                    window_bounds: Some(WindowBounds::Windowed(Bounds::new(
+                        point(px(0.), px(0.)),
+                        size(px(200.), px(100.)),
+                    ))),
+                    ..Default::default()
+                },
+                |_, cx| {
+                    cx.new(|_| TapView {
+                        events,
+                        prevent_default,
+                    })
+                },
+            )
+            .unwrap()
+            .into();
+        window
+            .update(&mut *app.borrow_mut(), |_, window, cx| window.draw(cx))
+            .unwrap();
+        window
+    }
+
+    /// Opens a drawn window and returns the events it received once all of `touches` were
+    /// dispatched to it.
+    fn tap(
+        prevent_default: bool,
+        touches: &[(u64, TouchPhase, Point<Pixels>)],
+    ) -> Vec<&'static str> {
+        let client = HeadlessClient::with_scale_factor(1.);
+        let app = client.new_app();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let window = open_window(&app, events.clone(), prevent_default);
+
+        window
+            .update(&mut *app.borrow_mut(), |_, window, _| {
+                for &(id, phase, position) in touches {
+                    window.inject_input(PlatformInput::Touch(TouchEvent {
+                        id,
+                        phase,
+                        position,
+                        modifiers: Modifiers::default(),
+                    }));
+                }
+            })
+            .unwrap();
+        // Taps are clicked while the touch that ends them is dispatched.
+        let touch_ends = touches
+            .iter()
+            .filter(|(_, phase, _)| *phase == TouchPhase::Ended)
+            .count();
+        client.run_until(|| {
+            events
+                .borrow()
+                .iter()
+                .filter(|event| **event == "end")
+                .count()
+                == touch_ends
+        });
+
+        app.borrow_mut().shutdown();
+        events.take()
+    }
+
+    #[test]
+    fn test_tap_clicks() {
+        let position = point(px(50.), px(50.));
+        let events = tap(
+            false,
+            &[
+                (0, TouchPhase::Started, position),
+                (0, TouchPhase::Moved, position + point(px(4.), px(4.))),
+                (0, TouchPhase::Ended, position + point(px(4.), px(4.))),
+            ],
+        );
+        assert_eq!(events, ["end", "down", "up"]);
+    }
+
+    #[test]
+    fn test_tap_cancelled_by_moving() {
+        let position = point(px(50.), px(50.));
+        let moved = position + point(px(TAP_SLOP as f32 + 1.), px(0.));
+        let events = tap(
+            false,
+            &[
+                (0, TouchPhase::Started, position),
+                (0, TouchPhase::Moved, moved),
+                (0, TouchPhase::Ended, moved),
+            ],
+        );
+        assert_eq!(events, ["end"]);
+    }
+
+    #[test]
+    fn test_tap_cancelled_by_second_finger() {
+        let position = point(px(50.), px(50.));
+        let second_position = point(px(100.), px(50.));
+        let events = tap(
+            false,
+            &[
+                (0, TouchPhase::Started, position),
+                (1, TouchPhase::Started, second_position),
+                (1, TouchPhase::Ended, second_position),
+                (0, TouchPhase::Ended, position),
+            ],
+        );
+        assert_eq!(events, ["end", "end"]);
+    }
+
+    #[test]
+    fn test_tap_prevented_on_touch_start() {
+        let position = point(px(50.), px(50.));
+        let events = tap(
+            true,
+            &[
+                (0, TouchPhase::Started, position),
+                (0, TouchPhase::Ended, position),
+            ],
+        );
+        assert_eq!(events, ["end"]);
+    }
+
+    #[test]
+    fn test_external_drag_icon_closes_when_drag_ends() {
+        let client = HeadlessClient::with_scale_factor(1.);
+        let app = client.new_app();
+        let window = open_window(&app, Rc::default(), false);
+
+        let drag_events = Rc::new(RefCell::new(Vec::new()));
+        window
+            .update(&mut *app.borrow_mut(), |_, window, cx| {
+                let icon = cx.new(|_| TapView {
+                    events: Rc::default(),
+                    prevent_default: false,
+                });
+                let drag_events = drag_events.clone();
+                let drag = ExternalDrag::new()
+                    .text("dragged")
+                    .icon(size(px(16.), px(16.)), icon)
+                    .on_event(move |event, _, _| drag_events.borrow_mut().push(event.clone()));
+                window.start_external_drag(drag, cx).unwrap();
+            })
+            .unwrap();
+
+        // The icon is shown in a window of its own while dragging.
+        let windows = app.borrow().windows();
+        assert_eq!(windows.len(), 2);
+        let icon_window = windows.into_iter().find(|w| *w != window).unwrap();
+        icon_window
+            .update(&mut *app.borrow_mut(), |_, window, _| {
+                assert_eq!(window.viewport_size(), size(px(16.), px(16.)))
+            })
+            .unwrap();
+
+        client.run_until(|| !drag_events.borrow().is_empty());
+        assert_eq!(*drag_events.borrow(), [ExternalDragEvent::Cancelled]);
+        client.run_until(|| app.borrow().windows() == [window]);
+
+        app.borrow_mut().shutdown();
+    }
+
+    struct InhibitView {
+        inhibited: Vec<bool>,
+        _subscription: Subscription,
+    }
+
+    impl Render for InhibitView {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            div()
+        }
+    }
+
+    #[test]
+    fn test_observe_keyboard_shortcuts_inhibit() {
+        let client = HeadlessClient::with_scale_factor(1.);
+        let app = client.new_app();
+        let window = app
+            .borrow_mut()
+            .open_window(WindowOptions::default(), |window, cx| {
+                cx.new(|cx| InhibitView {
+                    inhibited: Vec::new(),
+                    _subscription: cx.observe_keyboard_shortcuts_inhibit(
+                        window,
+                        |view, window, _| {
+                            view.inhibited.push(window.keyboard_shortcuts_inhibited())
+                        },
+                    ),
+                })
+            })
+            .unwrap();
+        let inhibited = || {
+            window
+                .update(&mut *app.borrow_mut(), |view, _, _| view.inhibited.clone())
+                .unwrap()
+        };
+
+        for inhibit in [true, false] {
+            window
+                .update(&mut *app.borrow_mut(), |_, window, _| {
+                    window.inhibit_keyboard_shortcuts(inhibit)
+                })
+                .unwrap();
+            // The state is only updated once the compositor granted or revoked the request.
+            window
+                .update(&mut *app.borrow_mut(), |_, window, _| {
+                    assert_eq!(window.keyboard_shortcuts_inhibited(), !inhibit)
+                })
+                .unwrap();
+            client.run_until(|| inhibited().last() == Some(&inhibit));
+        }
+        assert_eq!(inhibited(), [true, false]);
+
+        app.borrow_mut().shutdown();
+    }
+
+    struct ClickCountView {
+        click_counts: Rc<RefCell<Vec<(&'static str, usize)>>>,
+    }
+
+    impl Render for ClickCountView {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            let down_counts = self.click_counts.clone();
+            let up_counts = self.click_counts.clone();
+            div()
+                .size_full()
+                .on_mouse_down(MouseButton::Left, move |event, _, _| {
+                    down_counts.borrow_mut().push(("down", event.click_count))
+                })
+                .on_mouse_up(MouseButton::Left, move |event, _, _| {
+                    up_counts.borrow_mut().push(("up", event.click_count))
+                })
+        }
+    }
+
+    #[test]
+    fn test_stylus_click_count() {
+        let client = HeadlessClient::with_scale_factor(1.);
+        let app = client.new_app();
+        let click_counts = Rc::new(RefCell::new(Vec::new()));
+        let window: AnyWindowHandle = app
+            .borrow_mut()
+            .open_window(WindowOptions::default(), |_, cx| {
+                cx.new(|_| ClickCountView {
+                    click_counts: click_counts.clone(),
+                })
+            })
+            .unwrap()
+            .into();
+
+        let position = point(px(50.), px(50.));
+        let far_position = point(px(100.), px(50.));
+        window
+            .update(&mut *app.borrow_mut(), |_, window, cx| {
+                window.draw(cx);
+                // Two quick taps at the same position are a double click, while a tap
+                // somewhere else starts counting again.
+                for position in [position, position, far_position] {
+                    for phase in [StylusPhase::Down, StylusPhase::Up] {
+                        window.inject_input(PlatformInput::Stylus(StylusEvent {
+                            phase,
+                            position,
+                            in_contact: phase == StylusPhase::Down,
+                            ..Default::default()
+                        }));
+                    }
+                }
+            })
+            .unwrap();
+        client.run_until(|| click_counts.borrow().len() == 6);
+        assert_eq!(
+            *click_counts.borrow(),
+            [
+                ("down", 1),
+                ("up", 1),
+                ("down", 2),
+                ("up", 2),
+                ("down", 1),
+                ("up", 1)
+            ]
+        );
+
+        app.borrow_mut().shutdown();
+    }
+
+    struct InputRegionView {
+        origin: Point<Pixels>,
+    }
+
+    impl Render for InputRegionView {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            div().size_full().child(
+                div()
+                    .absolute()
+                    .left(self.origin.x)
+                    .top(self.origin.y)
+                    .size(px(30.))
+                    .occlude(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_update_input_region() {
+        let client = HeadlessClient::with_scale_factor(1.);
+        let app = client.new_app();
+        let window = app
+            .borrow_mut()
+            .open_window(WindowOptions::default(), |_, cx| {
+                cx.new(|_| InputRegionView {
+                    origin: point(px(20.), px(10.)),
+                })
+            })
+            .unwrap();
+        let any_window: AnyWindowHandle = window.into();
+        let input_region = |window: &Window| match &window.input_region {
+            InputRegion::Hitboxes(region) => region.clone(),
+            _ => panic!("the input region doesn't follow the hitboxes"),
+        };
+
+        any_window
+            .update(&mut *app.borrow_mut(), |_, window, cx| {
+                window.draw(cx);
+                // The region is taken from the last frame right away.
+                window.set_input_region_from_hitboxes();
+                assert_eq!(
+                    input_region(window),
+                    [Bounds::new(point(px(20.), px(10.)), size(px(30.), px(30.)))]
+                );
+            })
+            .unwrap();
+
+        window
+            .update(&mut *app.borrow_mut(), |view, _, cx| {
+                view.origin = point(px(40.), px(50.));
+                cx.notify();
+            })
+            .unwrap();
+        any_window
+            .update(&mut *app.borrow_mut(), |_, window, cx| {
+                window.draw(cx);
+                assert_eq!(
+                    input_region(window),
+                    [Bounds::new(point(px(40.), px(50.)), size(px(30.), px(30.)))]
+                );
+
+                window.reset_input_region();
+                assert!(matches!(window.input_region, InputRegion::Window));
+            })
+            .unwrap();
+
+        app.borrow_mut().shutdown();
+    }
+}